@@ -18,8 +18,23 @@ _dog()
             return
             ;;
 
+        --opcode)
+            COMPREPLY=( $( compgen -W 'QUERY IQUERY STATUS NOTIFY UPDATE DSO' -- "$cur" ) )
+            return
+            ;;
+
+        --prereq)
+            COMPREPLY=( $( compgen -W 'yxdomain nxdomain yxrrset nxrrset' -- "$cur" ) )
+            return
+            ;;
+
         -Z)
-            COMPREPLY=( $( compgen -W 'aa ad bufsize= cd' -- "$cur" ) )
+            COMPREPLY=( $( compgen -W 'aa ad bufsize= cd cookie cookie= do nsid subnet=' -- "$cur" ) )
+            return
+            ;;
+
+        --tsig-file)
+            _filedir
             return
             ;;
 
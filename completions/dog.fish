@@ -8,6 +8,7 @@ complete -c dog -s 'q' -l 'query'      -d "Host name or domain name to query" -x
 complete -c dog -s 't' -l 'type'       -d "Type of the DNS record being queried" -x -a "A AAAA CAA CNAME HINFO MX NS PTR SOA SRV TXT"
 complete -c dog -s 'n' -l 'nameserver' -d "Address of the nameserver to send packets to" -x -a "(__fish_print_hostnames)"
 complete -c dog        -l 'class'      -d "Network class of the DNS record being queried" -x -a "IN CH HS"
+complete -c dog        -l 'serial'     -d "Serial number of the zone already held, for IXFR queries" -x
 
 # Sending options
 complete -c dog        -l 'edns'       -d "Whether to OPT in to EDNS" -x -a "
@@ -16,12 +17,37 @@ complete -c dog        -l 'edns'       -d "Whether to OPT in to EDNS" -x -a "
     show\t'Send an OPT query, and show the result'
 "
 complete -c dog        -l 'txid'       -d "Set the transaction ID to a specific value" -x
+complete -c dog        -l 'opcode'     -d "Set the opcode of the request" -x -a "
+    QUERY\t'Standard query'
+    IQUERY\t'Inverse query (obsolete)'
+    STATUS\t'Server status request'
+    NOTIFY\t'Notify secondaries of a zone change'
+    UPDATE\t'Dynamic update'
+    DSO\t'DNS Stateful Operations'
+"
+complete -c dog        -l 'update'     -d "Send a dynamic update to a zone instead of querying" -x
+complete -c dog        -l 'prereq'     -d "Require a condition to hold for the update to happen" -x -a "
+    yxdomain\t'Require a name to be in use'
+    nxdomain\t'Require a name not to be in use'
+    yxrrset\t'Require a record type to exist at a name'
+    nxrrset\t'Require a record type not to exist at a name'
+"
+complete -c dog        -l 'add'        -d "Add a record, written as in a zone file" -x
+complete -c dog        -l 'delete'     -d "Delete a name, an RRset, or a record" -x
 complete -c dog -s 'Z'                 -d "Configure uncommon protocol-level tweaks" -x -a "
     aa\t'Set the AA (Authoritative Answers) query bit'
     ad\t'Set the AD (Authentic Data) query bit'
     bufsize=\t'Set the UDP payload size'
     cd\t'Set the CD (Checking Disabled) query bit'
+    cookie\t'Send a DNS Cookie option with a random client cookie'
+    cookie=\t'Send a DNS Cookie option with the given client cookie'
+    do\t'Set the DO (DNSSEC OK) bit in the OPT record'
+    nsid\t'Ask the server to identify itself with NSID'
+    subnet=\t'Send an EDNS Client Subnet option'
 "
+complete -c dog        -l 'validate'   -d "Validate DNSSEC signatures from the root zone down"
+complete -c dog        -l 'tsig'       -d "Sign requests with a TSIG key" -x
+complete -c dog        -l 'tsig-file'  -d "Sign requests with the TSIG key in a BIND key file" -r -F
 
 # Protocol options
 complete -c dog -s 'U' -l 'udp'        -d "Use the DNS protocol over UDP"
@@ -32,6 +58,7 @@ complete -c dog -s 'H' -l 'https'      -d "Use the DNS-over-HTTPS protocol"
 # Output options
 complete -c dog -s '1' -l 'short'      -d "Display nothing but the first result"
 complete -c dog -s 'J' -l 'json'       -d "Display the output as JSON"
+complete -c dog        -l 'zone'       -d "Display the answers in zone file format"
 complete -c dog        -l 'color'      -d "When to colorise the output" -x -a "
     always\t'Always use colors'
     automatic\t'Use colors when printing to a terminal'
@@ -43,4 +70,6 @@ complete -c dog        -l 'colour'     -d "When to colourise the output" -x -a "
     never\t'Never use colours'
 "
 complete -c dog        -l 'seconds'    -d "Do not format durations, display them as seconds"
+complete -c dog        -l 'unicode'    -d "Display internationalised domain names in Unicode"
 complete -c dog        -l 'time'       -d "Print how long the response took to arrive"
+complete -c dog        -l 'lenient'    -d "Show the records that parsed when others are malformed"
@@ -43,7 +43,13 @@ Register-ArgumentCompleter -Native -CommandName 'dog' -ScriptBlock {
         '^(--class)'          { $isOptionValue = $true; $completions += @('IN', 'CH', 'HS') }
         '^(--edns)'           { $isOptionValue = $true; $completions += @('disable', 'hide', 'show') }
         '^(--txid)'           { $isOptionValue = $true }
-        '^(-Z)'               { $isOptionValue = $true; $completions += @('aa', 'ad', 'bufsize=', 'cd') }
+        '^(--serial)'         { $isOptionValue = $true }
+        '^(--tsig)'           { $isOptionValue = $true }
+        '^(--update)'         { $isOptionValue = $true }
+        '^(--prereq)'         { $isOptionValue = $true; $completions += @('yxdomain', 'nxdomain', 'yxrrset', 'nxrrset') }
+        '^(--add|--delete)'   { $isOptionValue = $true }
+        '^(--opcode)'         { $isOptionValue = $true; $completions += @('QUERY', 'IQUERY', 'STATUS', 'NOTIFY', 'UPDATE', 'DSO') }
+        '^(-Z)'               { $isOptionValue = $true; $completions += @('aa', 'ad', 'bufsize=', 'cd', 'cookie', 'cookie=', 'do', 'nsid', 'subnet=') }
         '^(--color|--colour)' { $isOptionValue = $true; $completions += @('always', 'automatic', 'never') }
     }
 
@@ -64,18 +70,30 @@ Register-ArgumentCompleter -Native -CommandName 'dog' -ScriptBlock {
             '-t', '--type',
             '-n', '--nameserver',
             '--class',
+            '--serial',
             '--edns',
             '--txid',
+            '--opcode',
             '-Z',
+            '--validate',
+            '--tsig',
+            '--tsig-file',
+            '--update',
+            '--prereq',
+            '--add',
+            '--delete',
             '-U', '--udp',
             '-T', '--tcp',
             '-S', '--tls',
             '-H', '--https',
             '-1', '--short',
             '-J', '--json',
+            '--zone',
             '--color', '--colour',
             '--seconds',
+            '--unicode',
             '--time',
+            '--lenient',
             '-?', '--help',
             '-v', '--version'
         ) | Sort-Object
@@ -11,20 +11,28 @@ use super::{Transport, Error, UdpTransport, TcpTransport};
 /// This is the default behaviour for many DNS clients.
 pub struct AutoTransport {
     addr: String,
+    lenient: bool,
 }
 
 impl AutoTransport {
 
     /// Creates a new automatic transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self { addr, lenient: false }
+    }
+
+    /// Sets whether responses should be parsed leniently.
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
     }
 }
 
 
 impl Transport for AutoTransport {
     fn send(&self, request: &Request) -> Result<Response, Error> {
-        let udp_transport = UdpTransport::new(self.addr.clone());
+        let udp_transport = UdpTransport::new(self.addr.clone()).lenient(self.lenient);
         let udp_response = udp_transport.send(&request)?;
 
         if ! udp_response.flags.truncated {
@@ -33,7 +41,7 @@ impl Transport for AutoTransport {
 
         debug!("Truncated flag set, so switching to TCP");
 
-        let tcp_transport = TcpTransport::new(self.addr.clone());
+        let tcp_transport = TcpTransport::new(self.addr.clone()).lenient(self.lenient);
         let tcp_response = tcp_transport.send(&request)?;
         Ok(tcp_response)
     }
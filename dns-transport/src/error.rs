@@ -13,6 +13,9 @@ pub enum Error {
     /// call returned zero bytes.
     TruncatedResponse,
 
+    /// The stream of messages in a zone transfer was not valid.
+    TransferError(dns::TransferError),
+
     /// There was a problem making a TLS request.
     #[cfg(feature = "with_nativetls")]
     TlsError(native_tls::Error),
@@ -44,6 +47,12 @@ impl From<dns::WireError> for Error {
     }
 }
 
+impl From<dns::TransferError> for Error {
+    fn from(inner: dns::TransferError) -> Self {
+        Self::TransferError(inner)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(inner: std::io::Error) -> Self {
         Self::NetworkError(inner)
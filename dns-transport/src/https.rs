@@ -6,7 +6,7 @@ use std::net::TcpStream;
 use log::*;
 
 use dns::{Request, Response, WireError};
-use super::{Transport, Error};
+use super::{Transport, Error, parse_response};
 
 use super::tls_stream;
 
@@ -14,13 +14,21 @@ use super::tls_stream;
 /// encrypted with TLS, using TCP.
 pub struct HttpsTransport {
     url: String,
+    lenient: bool,
 }
 
 impl HttpsTransport {
 
     /// Creates a new HTTPS transport that connects to the given URL.
     pub fn new(url: String) -> Self {
-        Self { url }
+        Self { url, lenient: false }
+    }
+
+    /// Sets whether responses should be parsed leniently.
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
     }
 }
 
@@ -100,8 +108,7 @@ impl Transport for HttpsTransport {
 
         let body = &buf[index .. read_len];
         debug!("HTTP body has {} bytes", body.len());
-        let response = Response::from_bytes(&body)?;
-        Ok(response)
+        parse_response(&body, self.lenient)
     }
 
     #[cfg(not(feature = "with_https"))]
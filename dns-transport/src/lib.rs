@@ -47,6 +47,10 @@ mod tls_stream;
 pub use self::error::Error;
 
 /// The trait implemented by all transport types.
+///
+/// Every transport can be made *lenient* with its `lenient` method. A lenient
+/// transport returns responses with records that fail to parse, listing them
+/// as malformed, instead of returning an error.
 pub trait Transport {
 
     /// Convert the request to bytes, send it over the network, wait for a
@@ -60,3 +64,16 @@ pub trait Transport {
     /// the TLS and HTTPS transports.
     fn send(&self, request: &dns::Request) -> Result<dns::Response, Error>;
 }
+
+/// Parses the bytes of a response, leniently if the transport is lenient.
+fn parse_response(bytes: &[u8], lenient: bool) -> Result<dns::Response, Error> {
+    let response =
+        if lenient {
+            dns::Response::from_bytes_lenient(bytes)?
+        }
+        else {
+            dns::Response::from_bytes(bytes)?
+        };
+
+    Ok(response)
+}
@@ -4,8 +4,9 @@ use std::io::{Read, Write};
 
 use log::*;
 
-use dns::{Request, Response};
-use super::{Transport, Error};
+use dns::{Request, Response, Update, Transfer};
+use dns::record::RecordType;
+use super::{Transport, Error, parse_response};
 
 
 /// The **TCP transport**, which sends DNS wire data over a TCP stream.
@@ -18,19 +19,102 @@ use super::{Transport, Error};
 ///   TCP, Implementation Requirements (March 2016)
 pub struct TcpTransport {
     addr: String,
+    lenient: bool,
 }
 
 impl TcpTransport {
 
     /// Creates a new TCP transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self { addr, lenient: false }
+    }
+
+    /// Sets whether responses should be parsed leniently.
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
     }
 }
 
 
 impl Transport for TcpTransport {
     fn send(&self, request: &Request) -> Result<Response, Error> {
+        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        self.exchange(bytes_to_send)
+    }
+}
+
+impl TcpTransport {
+
+    /// Sends a dynamic update to the server, waits for its response, and
+    /// returns it. Updates are always sent over TCP, as they can be too
+    /// large to fit in a UDP packet, and are not safe to retry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` error if there’s an I/O error sending or
+    /// receiving data, or the DNS packet in the response contained invalid
+    /// bytes and failed to parse.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the update has too many records to be written out.
+    pub fn send_update(&self, update: &Update) -> Result<Response, Error> {
+        let bytes_to_send = update.to_bytes().expect("failed to serialise update");
+        self.exchange(bytes_to_send)
+    }
+
+    /// Sends a full or incremental zone transfer request to the server, and
+    /// reads the stream of messages it sends back until the transfer is
+    /// complete. The messages are always parsed strictly, even by a lenient
+    /// transport, as a zone with records missing from it is not complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` error if there’s an I/O error sending or
+    /// receiving data, any of the DNS packets in the response contained
+    /// invalid bytes and failed to parse, or the messages did not form a
+    /// valid transfer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request has too many records to be written out.
+    pub fn transfer(&self, request: &Request) -> Result<Transfer, Error> {
+        let bytes_to_send = request.to_bytes().expect("failed to serialise request");
+        let mut stream = self.connect_and_send(bytes_to_send)?;
+
+        let mut transfer =
+            if request.query.qtype == RecordType::IXFR {
+                Transfer::incremental()
+            }
+            else {
+                Transfer::new()
+            };
+
+        loop {
+            let read_bytes = Self::read_message(&mut stream)?;
+            let response = Response::from_bytes(&read_bytes)?;
+
+            if transfer.add(response)? {
+                info!("Transfer complete after {} messages", transfer.messages().len());
+                return Ok(transfer);
+            }
+        }
+    }
+
+    /// Opens a connection to the server, sends it the given message, and
+    /// reads back the response.
+    fn exchange(&self, bytes_to_send: Vec<u8>) -> Result<Response, Error> {
+        let mut stream = self.connect_and_send(bytes_to_send)?;
+
+        let read_bytes = Self::length_prefixed_read(&mut stream)?;
+        parse_response(&read_bytes, self.lenient)
+    }
+
+    /// Opens a connection to the server and sends it the given message,
+    /// returning the stream to read the response from.
+    fn connect_and_send(&self, mut bytes_to_send: Vec<u8>) -> Result<TcpStream, Error> {
         info!("Opening TCP stream");
         let mut stream =
             if self.addr.contains(':') {
@@ -43,20 +127,14 @@ impl Transport for TcpTransport {
 
         // The message is prepended with the length when sent over TCP,
         // so the server knows how long it is (RFC 1035 §4.2.2)
-        let mut bytes_to_send = request.to_bytes().expect("failed to serialise request");
         Self::prefix_with_length(&mut bytes_to_send);
 
         info!("Sending {} bytes of data to {:?} over TCP", bytes_to_send.len(), self.addr);
         let written_len = stream.write(&bytes_to_send)?;
         debug!("Wrote {} bytes", written_len);
 
-        let read_bytes = Self::length_prefixed_read(&mut stream)?;
-        let response = Response::from_bytes(&read_bytes)?;
-        Ok(response)
+        Ok(stream)
     }
-}
-
-impl TcpTransport {
 
     /// Mutate the given byte buffer, prefixing it with its own length as a
     /// big-endian `u16`.
@@ -125,4 +203,38 @@ impl TcpTransport {
 
         Ok(combined_buffer)
     }
+
+    /// Reads exactly one length-prefixed message from the given I/O source,
+    /// leaving any bytes after it unread. This is needed when the server
+    /// sends a stream of messages, as one read could otherwise return the
+    /// end of one message along with the start of the next.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there’s a network error during reading, or the
+    /// source ends before the whole message has been read.
+    pub(crate) fn read_message(stream: &mut impl Read) -> Result<Vec<u8>, Error> {
+        let mut len_bytes = [0; 2];
+        stream.read_exact(&mut len_bytes).map_err(truncated)?;
+
+        let total_len = u16::from_be_bytes(len_bytes);
+        debug!("Reading message of {} bytes", total_len);
+
+        let mut buf = vec![0; usize::from(total_len)];
+        stream.read_exact(&mut buf).map_err(truncated)?;
+        Ok(buf)
+    }
 }
+
+/// Treats the stream ending early as a truncated response, rather than as
+/// any other I/O error.
+fn truncated(error: std::io::Error) -> Error {
+    if error.kind() == std::io::ErrorKind::UnexpectedEof {
+        warn!("Stream ended before the message did");
+        Error::TruncatedResponse
+    }
+    else {
+        Error::from(error)
+    }
+}
+
@@ -6,7 +6,7 @@ use std::io::Write;
 use log::*;
 
 use dns::{Request, Response};
-use super::{Transport, Error, TcpTransport};
+use super::{Transport, Error, TcpTransport, parse_response};
 use super::tls_stream::TlsStream;
 
 
@@ -14,13 +14,21 @@ use super::tls_stream::TlsStream;
 /// encrypted TLS connection.
 pub struct TlsTransport {
     addr: String,
+    lenient: bool,
 }
 
 impl TlsTransport {
 
     /// Creates a new TLS transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self { addr, lenient: false }
+    }
+
+    /// Sets whether responses should be parsed leniently.
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
     }
 }
 
@@ -59,8 +67,7 @@ impl Transport for TlsTransport {
         debug!("Wrote all bytes");
 
         let read_bytes = TcpTransport::length_prefixed_read(&mut stream)?;
-        let response = Response::from_bytes(&read_bytes)?;
-        Ok(response)
+        parse_response(&read_bytes, self.lenient)
     }
 
     #[cfg(not(feature = "with_tls"))]
@@ -3,7 +3,7 @@ use std::net::{Ipv4Addr, UdpSocket};
 use log::*;
 
 use dns::{Request, Response};
-use super::{Transport, Error};
+use super::{Transport, Error, parse_response};
 
 
 /// The **UDP transport**, which sends DNS wire data inside a UDP datagram.
@@ -14,13 +14,21 @@ use super::{Transport, Error};
 ///   Implementation and Specification (November 1987)
 pub struct UdpTransport {
     addr: String,
+    lenient: bool,
 }
 
 impl UdpTransport {
 
     /// Creates a new UDP transport that connects to the given host.
     pub fn new(addr: String) -> Self {
-        Self { addr }
+        Self { addr, lenient: false }
+    }
+
+    /// Sets whether responses should be parsed leniently.
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
     }
 }
 
@@ -50,7 +58,6 @@ impl Transport for UdpTransport {
         let received_len = socket.recv(&mut buf)?;
 
         info!("Received {} bytes of data", received_len);
-        let response = Response::from_bytes(&buf[.. received_len])?;
-        Ok(response)
+        parse_response(&buf[.. received_len], self.lenient)
     }
 }
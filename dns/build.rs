@@ -0,0 +1,108 @@
+//! This build script gets run during every build. Its purpose is to turn
+//! the list of DNS parameters in `src/registry.txt` into Rust tables, so
+//! that the names and numbers of record types, classes, rcodes, EDNS
+//! options, and DNSSEC algorithms all come from the same place.
+//!
+//! The tables get written to a file in the Cargo output directory, which
+//! the `registry` module includes during compilation.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+
+/// The sections that the registry file can contain: their names in the
+/// file, the names of the tables they become, and the type of number that
+/// each table holds.
+const SECTIONS: &[(&str, &str, &str)] = &[
+    ("types",            "TYPES",            "u16"),
+    ("classes",          "CLASSES",          "u16"),
+    ("rcodes",           "RCODES",           "u16"),
+    ("options",          "OPTIONS",          "u16"),
+    ("algorithms",       "ALGORITHMS",       "u8"),
+    ("extended errors",  "EXTENDED_ERRORS",  "u16"),
+];
+
+
+/// The build script entry point.
+fn main() -> io::Result<()> {
+    let registry = include_str!("src/registry.txt");
+    let sections = parse_registry(registry);
+
+    // We need to create this file in the Cargo output directory.
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let mut f = File::create(out.join("registry.rs"))?;
+
+    for (section, entries) in sections {
+        let (name, table, number_type) = SECTIONS.iter().find(|s| s.0 == section).unwrap();
+
+        writeln!(f, "/// The mnemonics of the {} in the IANA registry, with their numbers.", name)?;
+        writeln!(f, "pub(crate) static {}: &[(&str, {})] = &[", table, number_type)?;
+        for (number, mnemonic) in entries {
+            writeln!(f, "    ({:?}, {}),", mnemonic, number)?;
+        }
+        writeln!(f, "];")?;
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
+/// Splits the registry file into its sections, checking that each section
+/// is one we know about, and that each of its entries has a number that
+/// fits the table and a mnemonic, neither of which has been seen before in
+/// that section. Panics with the line number if it finds a problem, which
+/// stops the build.
+fn parse_registry(registry: &str) -> Vec<(&str, Vec<(u32, &str)>)> {
+    let mut sections: Vec<(&str, Vec<(u32, &str)>)> = Vec::new();
+
+    for (index, line) in registry.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            let section = &line[1 .. line.len() - 1];
+            if ! SECTIONS.iter().any(|s| s.0 == section) {
+                panic!("registry.txt:{}: unknown section {:?}", line_number, section);
+            }
+            if sections.iter().any(|s| s.0 == section) {
+                panic!("registry.txt:{}: section {:?} appears twice", line_number, section);
+            }
+
+            sections.push((section, Vec::new()));
+            continue;
+        }
+
+        let (section, entries) = match sections.last_mut() {
+            Some(s) => s,
+            None    => panic!("registry.txt:{}: entry before the first section", line_number),
+        };
+
+        let (number, mnemonic) = match line.find(char::is_whitespace) {
+            Some(space) => (&line[.. space], line[space ..].trim()),
+            None        => panic!("registry.txt:{}: entry has no mnemonic", line_number),
+        };
+
+        let number_type = SECTIONS.iter().find(|s| s.0 == *section).unwrap().2;
+        let maximum = if number_type == "u8" { u32::from(u8::MAX) } else { u32::from(u16::MAX) };
+        let number = match number.parse::<u32>() {
+            Ok(n) if n <= maximum => n,
+            _ => panic!("registry.txt:{}: invalid number {:?}", line_number, number),
+        };
+
+        if entries.iter().any(|e| e.0 == number) {
+            panic!("registry.txt:{}: number {} appears twice", line_number, number);
+        }
+        if entries.iter().any(|e| e.1.eq_ignore_ascii_case(mnemonic)) {
+            panic!("registry.txt:{}: mnemonic {:?} appears twice", line_number, mnemonic);
+        }
+
+        entries.push((number, mnemonic));
+    }
+
+    sections
+}
@@ -0,0 +1,665 @@
+//! Borrowed responses, which decode their contents from the bytes of the
+//! message only when they are asked for.
+
+use std::fmt;
+
+use log::*;
+
+use crate::record::{Record, RecordType, OPT};
+use crate::strings::{Labels, RECURSION_LIMIT, write_escaped_segment};
+use crate::types::*;
+use crate::wire::*;
+
+
+/// A response that borrows the bytes of the message it was read from.
+///
+/// Reading a `Response` copies every name and record out of the message,
+/// which means a lot of allocation when there are millions of messages to
+/// get through. A `ResponseRef` instead checks the structure of the message
+/// up-front without allocating anything, and then only decodes the names
+/// and record data that get asked for. The owned types can still be had
+/// from it, for one answer at a time or for the whole response.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct ResponseRef<'a> {
+
+    /// The transaction ID, which should match the ID of the request.
+    pub transaction_id: u16,
+
+    /// The flags that accompany every DNS packet. As with a `Response`, the
+    /// error code includes the higher bits held in the OPT record.
+    pub flags: Flags,
+
+    /// The bytes of the whole message.
+    bytes: &'a [u8],
+
+    /// Where each section starts in the message, in order.
+    queries: Range,
+    answers: Range,
+    authorities: Range,
+    additionals: Range,
+}
+
+/// The position of the first entry in a section, and the number of entries.
+#[derive(PartialEq, Debug, Copy, Clone)]
+struct Range {
+    start: usize,
+    count: u16,
+}
+
+
+/// A query in a borrowed response.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct QueryRef<'a> {
+
+    /// The domain name being queried.
+    pub qname: NameRef<'a>,
+
+    /// The class number.
+    pub qclass: QClass,
+
+    /// The type number.
+    pub qtype: RecordType,
+}
+
+
+/// An answer, authority, or additional record in a borrowed response. Its
+/// record data stays undecoded until it’s asked for.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct AnswerRef<'a> {
+
+    /// The domain name being answered for.
+    pub qname: NameRef<'a>,
+
+    /// The type of the record.
+    pub record_type: RecordType,
+
+    /// This answer’s class. In an OPT pseudo-record, this field holds the
+    /// UDP payload size instead.
+    pub qclass: QClass,
+
+    /// The time-to-live duration, in seconds. In an OPT pseudo-record, this
+    /// field holds the higher bits of the error code, the EDNS version, and
+    /// the flags instead.
+    pub ttl: u32,
+
+    /// The record data, as undecoded bytes. Any domain names in it may be
+    /// pointers to elsewhere in the message.
+    pub data: &'a [u8],
+
+    /// The bytes of the whole message, which any pointers are relative to.
+    message: &'a [u8],
+
+    /// The position of the class field in the message.
+    fields_start: usize,
+}
+
+
+/// A domain name in a borrowed response, which gets decoded by following
+/// its labels and pointers through the message whenever it is used.
+#[derive(Debug, Copy, Clone)]
+pub struct NameRef<'a> {
+    message: &'a [u8],
+    start: usize,
+}
+
+
+/// The length of the header that comes before the first section.
+const HEADER_LENGTH: usize = 12;
+
+
+impl<'a> ResponseRef<'a> {
+
+    /// Reads the header of the message in the given buffer, and checks that
+    /// every entry in its four sections is complete without decoding any of
+    /// them. Returns an error if the message is malformed.
+    ///
+    /// Names are checked all the way through, so iterating over their
+    /// segments later cannot fail. Record data is only checked to be the
+    /// stated length, so decoding it later can.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, WireError> {
+        let transaction_id   = read_u16(bytes, 0)?;
+        let mut flags        = Flags::from_u16(read_u16(bytes, 2)?);
+        let query_count      = read_u16(bytes, 4)?;
+        let answer_count     = read_u16(bytes, 6)?;
+        let authority_count  = read_u16(bytes, 8)?;
+        let additional_count = read_u16(bytes, 10)?;
+
+        let mut position = HEADER_LENGTH;
+
+        let queries = Range { start: position, count: query_count };
+        debug!("Checking {}x query in response", query_count);
+        for _ in 0 .. query_count {
+            position = read_query(bytes, position)?.1;
+        }
+
+        let answers = Range { start: position, count: answer_count };
+        debug!("Checking {}x answer in response", answer_count);
+        for _ in 0 .. answer_count {
+            position = read_answer(bytes, position)?.1;
+        }
+
+        let authorities = Range { start: position, count: authority_count };
+        debug!("Checking {}x authority in response", authority_count);
+        for _ in 0 .. authority_count {
+            position = read_answer(bytes, position)?.1;
+        }
+
+        let additionals = Range { start: position, count: additional_count };
+        debug!("Checking {}x additional answer in response", additional_count);
+        let mut higher_bits = None;
+        for _ in 0 .. additional_count {
+            let (answer, next) = read_answer(bytes, position)?;
+            if higher_bits.is_none() && answer.is_pseudo() {
+                higher_bits = Some(answer.ttl.to_be_bytes()[0]);
+            }
+
+            position = next;
+        }
+
+        if let Some(higher_bits) = higher_bits {
+            flags.error_code = ErrorCode::extend(flags.error_code, higher_bits);
+            trace!("Combined extended rcode -> {:?}", flags.error_code);
+        }
+
+        Ok(Self { transaction_id, flags, bytes, queries, answers, authorities, additionals })
+    }
+
+    /// Returns an iterator over the queries section.
+    pub fn queries(&self) -> Queries<'a> {
+        Queries { message: self.bytes, position: self.queries.start, remaining: self.queries.count }
+    }
+
+    /// Returns an iterator over the answers section.
+    pub fn answers(&self) -> Answers<'a> {
+        Answers::new(self.bytes, self.answers)
+    }
+
+    /// Returns an iterator over the authoritative nameservers section.
+    pub fn authorities(&self) -> Answers<'a> {
+        Answers::new(self.bytes, self.authorities)
+    }
+
+    /// Returns an iterator over the additional records section. Unlike in a
+    /// `Response`, a TSIG record at the end of it is not taken out.
+    pub fn additionals(&self) -> Answers<'a> {
+        Answers::new(self.bytes, self.additionals)
+    }
+
+    /// Returns the bytes of the message that this response borrows.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decodes the whole message into an owned `Response`, the same as
+    /// reading it with `Response::from_bytes` in the first place.
+    pub fn to_response(&self) -> Result<Response, WireError> {
+        Response::from_bytes(self.bytes)
+    }
+}
+
+
+impl QueryRef<'_> {
+
+    /// Decodes this query into an owned `Query`.
+    pub fn to_query(&self) -> Query {
+        Query { qname: self.qname.to_labels(), qclass: self.qclass, qtype: self.qtype }
+    }
+}
+
+
+impl AnswerRef<'_> {
+
+    /// Whether this is an OPT pseudo-record, which has no record data as
+    /// such, and uses its class and TTL fields for other things.
+    pub fn is_pseudo(&self) -> bool {
+        self.record_type.type_number() == OPT::RR_TYPE
+    }
+
+    /// Decodes the record data into an owned `Record`, depending on the
+    /// type of the record. An OPT pseudo-record has to be decoded with
+    /// `to_answer` instead.
+    pub fn record(&self) -> Result<Record, WireError> {
+        let data_length = read_u16(self.message, self.fields_start + 6)?;
+
+        let mut c = Cursor::new(self.message);
+        c.set_position((self.fields_start + 8) as u64);
+        Record::from_bytes(self.record_type, data_length, &mut c)
+    }
+
+    /// Decodes this whole answer into an owned `Answer`.
+    pub fn to_answer(&self) -> Result<Answer, WireError> {
+        let qname = self.qname.to_labels();
+
+        if self.is_pseudo() {
+            let mut c = Cursor::new(self.message);
+            c.set_position(self.fields_start as u64);
+            let opt = OPT::read(&mut c)?;
+            Ok(Answer::Pseudo { qname, opt })
+        }
+        else {
+            let record = self.record()?;
+            Ok(Answer::Standard { qname, qclass: self.qclass, ttl: self.ttl, record })
+        }
+    }
+}
+
+
+impl<'a> NameRef<'a> {
+
+    /// Returns an iterator over each segment of this name as raw bytes, from
+    /// the leftmost (the most specific) to the rightmost.
+    pub fn segments(&self) -> Segments<'a> {
+        Segments { walk: Walk::new(self.message, self.start) }
+    }
+
+    /// Decodes this name into owned `Labels`.
+    pub fn to_labels(&self) -> Labels {
+        let mut labels = Labels::root();
+        for segment in self.segments() {
+            labels.push_wire_segment(segment);
+        }
+
+        labels
+    }
+}
+
+impl PartialEq for NameRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        same_segments(self.segments(), other.segments())
+    }
+}
+
+impl PartialEq<Labels> for NameRef<'_> {
+    fn eq(&self, other: &Labels) -> bool {
+        same_segments(self.segments(), other.iter())
+    }
+}
+
+/// Whether two names have the same segments, ignoring the case of ASCII
+/// letters.
+fn same_segments<'s>(mut a: impl Iterator<Item=&'s [u8]>, mut b: impl Iterator<Item=&'s [u8]>) -> bool {
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) if x.eq_ignore_ascii_case(y) => {}
+            (None, None)  => return true,
+            _             => return false,
+        }
+    }
+}
+
+impl fmt::Display for NameRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.segments() {
+            write_escaped_segment(f, segment)?;
+            f.write_str(".")?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// An iterator over the queries in a borrowed response.
+#[derive(Debug, Clone)]
+pub struct Queries<'a> {
+    message: &'a [u8],
+    position: usize,
+    remaining: u16,
+}
+
+impl<'a> Iterator for Queries<'a> {
+    type Item = QueryRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        // The message has already been checked, so this cannot fail
+        let (query, next) = read_query(self.message, self.position).ok()?;
+        self.position = next;
+        self.remaining -= 1;
+        Some(query)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::from(self.remaining), Some(usize::from(self.remaining)))
+    }
+}
+
+impl ExactSizeIterator for Queries<'_> {}
+
+
+/// An iterator over the records in one section of a borrowed response.
+#[derive(Debug, Clone)]
+pub struct Answers<'a> {
+    message: &'a [u8],
+    position: usize,
+    remaining: u16,
+}
+
+impl<'a> Answers<'a> {
+    fn new(message: &'a [u8], range: Range) -> Self {
+        Self { message, position: range.start, remaining: range.count }
+    }
+}
+
+impl<'a> Iterator for Answers<'a> {
+    type Item = AnswerRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        // The message has already been checked, so this cannot fail
+        let (answer, next) = read_answer(self.message, self.position).ok()?;
+        self.position = next;
+        self.remaining -= 1;
+        Some(answer)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::from(self.remaining), Some(usize::from(self.remaining)))
+    }
+}
+
+impl ExactSizeIterator for Answers<'_> {}
+
+
+/// An iterator over the segments of a borrowed domain name.
+#[derive(Debug, Clone)]
+pub struct Segments<'a> {
+    walk: Walk<'a>,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The name has already been checked, so this cannot fail
+        self.walk.next_segment().ok().flatten()
+    }
+}
+
+
+/// The state of following a domain name through a message, which keeps
+/// track of the pointers followed so far in the same way as when reading
+/// labels.
+#[derive(Debug, Clone)]
+struct Walk<'a> {
+    message: &'a [u8],
+    position: usize,
+    end: Option<usize>,
+    recursions: [u16; RECURSION_LIMIT],
+    recursion_count: usize,
+}
+
+impl<'a> Walk<'a> {
+    fn new(message: &'a [u8], start: usize) -> Self {
+        Self { message, position: start, end: None, recursions: [0; RECURSION_LIMIT], recursion_count: 0 }
+    }
+
+    /// Returns the next segment of the name, following any pointers, or
+    /// `None` if the end of the name has been reached.
+    fn next_segment(&mut self) -> Result<Option<&'a [u8]>, WireError> {
+        loop {
+            let byte = *self.message.get(self.position).ok_or(WireError::IO)?;
+
+            if byte == 0 {
+                self.end.get_or_insert(self.position + 1);
+                return Ok(None);
+            }
+
+            else if byte >= 0b_1100_0000 {
+                let name_one = byte - 0b1100_0000;
+                let name_two = *self.message.get(self.position + 1).ok_or(WireError::IO)?;
+                let offset = u16::from_be_bytes([name_one, name_two]);
+                self.end.get_or_insert(self.position + 2);
+
+                let previous = &self.recursions[.. self.recursion_count];
+                if previous.contains(&offset) {
+                    warn!("Hit previous offset ({}) decoding string", offset);
+                    return Err(WireError::TooMuchRecursion(previous.into()));
+                }
+
+                self.recursions[self.recursion_count] = offset;
+                self.recursion_count += 1;
+
+                if self.recursion_count >= RECURSION_LIMIT {
+                    warn!("Hit recursion limit ({}) decoding string", RECURSION_LIMIT);
+                    return Err(WireError::TooMuchRecursion(self.recursions[.. self.recursion_count].into()));
+                }
+
+                self.position = usize::from(offset);
+            }
+
+            else {
+                let start = self.position + 1;
+                let segment = self.message.get(start .. start + usize::from(byte)).ok_or(WireError::IO)?;
+                self.position = start + segment.len();
+                return Ok(Some(segment));
+            }
+        }
+    }
+}
+
+
+/// Checks the domain name at the given position in the message, and returns
+/// the position of whatever comes after it.
+fn skip_name(message: &[u8], start: usize) -> Result<usize, WireError> {
+    let mut walk = Walk::new(message, start);
+    while walk.next_segment()?.is_some() {}
+    walk.end.ok_or(WireError::IO)
+}
+
+/// Reads the query at the given position in the message, returning it along
+/// with the position of the next entry.
+pub(crate) fn read_query(message: &[u8], start: usize) -> Result<(QueryRef<'_>, usize), WireError> {
+    let qname = NameRef { message, start };
+    let position = skip_name(message, start)?;
+
+    let qtype = RecordType::from(read_u16(message, position)?);
+    let qclass = QClass::from_u16(read_u16(message, position + 2)?);
+
+    Ok((QueryRef { qname, qclass, qtype }, position + 4))
+}
+
+/// Reads the record at the given position in the message, returning it along
+/// with the position of the next entry.
+pub(crate) fn read_answer(message: &[u8], start: usize) -> Result<(AnswerRef<'_>, usize), WireError> {
+    let qname = NameRef { message, start };
+    let position = skip_name(message, start)?;
+
+    let record_type = RecordType::from(read_u16(message, position)?);
+    let fields_start = position + 2;
+    let qclass = QClass::from_u16(read_u16(message, fields_start)?);
+    let ttl = read_u32(message, fields_start + 2)?;
+    let data_length = read_u16(message, fields_start + 6)?;
+
+    let data_start = fields_start + 8;
+    let data = message.get(data_start .. data_start + usize::from(data_length)).ok_or(WireError::IO)?;
+
+    Ok((AnswerRef { qname, record_type, qclass, ttl, data, message, fields_start }, data_start + data.len()))
+}
+
+/// Reads a big-endian `u16` from the given position in the message.
+fn read_u16(message: &[u8], position: usize) -> Result<u16, WireError> {
+    match message.get(position .. position + 2) {
+        Some(&[a, b])  => Ok(u16::from_be_bytes([a, b])),
+        _              => Err(WireError::IO),
+    }
+}
+
+/// Reads a big-endian `u32` from the given position in the message.
+fn read_u32(message: &[u8], position: usize) -> Result<u32, WireError> {
+    match message.get(position .. position + 4) {
+        Some(&[a, b, c, d])  => Ok(u32::from_be_bytes([a, b, c, d])),
+        _                    => Err(WireError::IO),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::{A, CNAME};
+    use std::net::Ipv4Addr;
+    use pretty_assertions::assert_eq;
+
+    fn buf() -> Vec<u8> {
+        vec![
+            0x0d, 0xcd,  // transaction ID
+            0x81, 0x80,  // flags (standard query, response, no error)
+            0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01,  // counts (1, 2, 0, 1)
+
+            // the query:
+            0x03, 0x77, 0x77, 0x77, 0x03, 0x64, 0x6f, 0x67, 0x00,  // "www.dog."
+            0x00, 0x01,  // type A
+            0x00, 0x01,  // class IN
+
+            // the first answer:
+            0xc0, 0x0c,  // to find the name, backtrack to position 0x0c (12)
+            0x00, 0x05,  // type CNAME
+            0x00, 0x01,  // class IN
+            0x00, 0x00, 0x01, 0x2c,  // TTL (300)
+            0x00, 0x06,  // data length 6
+            0x03, 0x61, 0x70, 0x70,  // "app"...
+            0xc0, 0x10,  // ...then backtrack to "dog." at position 0x10 (16)
+
+            // the second answer:
+            0xc0, 0x25,  // backtrack to "app.dog." at position 0x25 (37)
+            0x00, 0x01,  // type A
+            0x00, 0x01,  // class IN
+            0x00, 0x00, 0x00, 0x3c,  // TTL (60)
+            0x00, 0x04,  // data length 4
+            0xc0, 0x00, 0x02, 0x01,  // IP address
+
+            // the additional:
+            0x00,  // name
+            0x00, 0x29,  // type OPT
+            0x04, 0xd0,  // UDP payload size (1232)
+            0x00,  // higher bits
+            0x00,  // EDNS(0) version
+            0x00, 0x00,  // more flags
+            0x00, 0x00,  // no data
+        ]
+    }
+
+    #[test]
+    fn header() {
+        let buf = buf();
+        let response = ResponseRef::from_bytes(&buf).unwrap();
+
+        assert_eq!(response.transaction_id, 0x0dcd);
+        assert_eq!(response.flags, Flags::standard_response());
+        assert_eq!(response.queries().len(), 1);
+        assert_eq!(response.answers().len(), 2);
+        assert_eq!(response.authorities().len(), 0);
+        assert_eq!(response.additionals().len(), 1);
+    }
+
+    #[test]
+    fn names() {
+        let buf = buf();
+        let response = ResponseRef::from_bytes(&buf).unwrap();
+
+        let names = response.answers()
+            .map(|answer| answer.qname.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec![ "www.dog.".to_string(), "app.dog.".to_string() ]);
+        assert_eq!(response.queries().next().unwrap().qname, Labels::encode("www.dog").unwrap());
+    }
+
+    #[test]
+    fn records() {
+        let buf = buf();
+        let response = ResponseRef::from_bytes(&buf).unwrap();
+        let mut answers = response.answers();
+
+        let cname = answers.next().unwrap();
+        assert_eq!(cname.record_type, RecordType::CNAME);
+        assert_eq!(cname.ttl, 300);
+        assert_eq!(cname.record(),
+                   Ok(Record::CNAME(CNAME { domain: Labels::encode("app.dog").unwrap() })));
+
+        let a = answers.next().unwrap();
+        assert_eq!(a.data, &[ 0xc0, 0x00, 0x02, 0x01 ]);
+        assert_eq!(a.record(),
+                   Ok(Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) })));
+
+        assert_eq!(answers.next(), None);
+    }
+
+    #[test]
+    fn same_as_owned() {
+        let buf = buf();
+        let response = ResponseRef::from_bytes(&buf).unwrap();
+        let owned = Response::from_bytes(&buf).unwrap();
+
+        let queries = response.queries().map(|q| q.to_query()).collect::<Vec<_>>();
+        assert_eq!(queries, owned.queries);
+
+        let answers = response.answers().map(|a| a.to_answer().unwrap()).collect::<Vec<_>>();
+        assert_eq!(answers, owned.answers);
+
+        let additionals = response.additionals().map(|a| a.to_answer().unwrap()).collect::<Vec<_>>();
+        assert_eq!(additionals, owned.additionals);
+
+        assert_eq!(response.to_response(), Ok(owned));
+    }
+
+    #[test]
+    fn extended_error_code() {
+        let buf = &[
+            0x4a, 0x1b,  // transaction ID
+            0x81, 0x87,  // flags (standard query, response, YXRRset)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (0, 0, 0, 1)
+
+            // OPT record:
+            0x00,  // name
+            0x00, 0x29,  // type OPT
+            0x04, 0xd0,  // UDP payload size (1232)
+            0x01,  // higher bits
+            0x00,  // EDNS(0) version
+            0x00, 0x00,  // more flags
+            0x00, 0x00,  // no data
+        ];
+
+        let response = ResponseRef::from_bytes(buf).unwrap();
+        assert_eq!(response.flags.error_code, Some(ErrorCode::BadCookie));
+    }
+
+    #[test]
+    fn record_ends_abruptly() {
+        let mut buf = buf();
+        buf.truncate(buf.len() - 3);
+
+        assert_eq!(ResponseRef::from_bytes(&buf),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn name_loops() {
+        let buf = &[
+            0x00, 0x00,  // transaction ID
+            0x81, 0x80,  // flags (standard query, response, no error)
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (1, 0, 0, 0)
+
+            // the query:
+            0xc0, 0x0c,  // backtrack to position 0x0c (12), which is here
+            0x00, 0x01,  // type A
+            0x00, 0x01,  // class IN
+        ];
+
+        assert_eq!(ResponseRef::from_bytes(buf),
+                   Err(WireError::TooMuchRecursion(Box::new([ 0x0c ]))));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(ResponseRef::from_bytes(&[]),
+                   Err(WireError::IO));
+    }
+}
@@ -30,15 +30,64 @@
 
 //! The DNS crate is the ‘library’ part of dog. It implements the DNS
 //! protocol: creating and decoding packets from their byte structure.
+//!
+//! # Serialisation
+//!
+//! With the `with_serde` feature enabled, the requests, responses, and
+//! records can be serialised and deserialised with serde. Their layout only
+//! changes between major versions.
+//!
+//! Most types use serde’s derived layout, which is documented here so that
+//! it can be relied on:
+//!
+//! - Structs are maps with the same field names as their Rust fields, such
+//!   as `{"qname": "lookup.dog.", "qclass": 1, "qtype": 1}` for a `Query`.
+//! - Enums are externally tagged, so a record is a map from its type name
+//!   to its fields, such as `{"A": {"address": "192.0.2.1"}}`, and the
+//!   variants with no fields are plain strings.
+//! - Byte strings are sequences of numbers, and IP addresses are strings in
+//!   their usual notation.
+//!
+//! The types that stand for numbers registered with IANA are serialised as
+//! those numbers, so that a number that a later version of this crate gives
+//! a name to still reads the same way:
+//!
+//! - `RecordType` and `UnknownQtype` as the record type number;
+//! - `QClass` as the class number;
+//! - `Opcode` as the opcode number, from 0 to 15;
+//! - `ErrorCode` as the rcode number, including the extended bits;
+//! - `Algorithm` as the DNSSEC algorithm number.
+//!
+//! `Labels` are serialised as a string in presentation format, with a
+//! trailing dot, and with any dots, backslashes, or unprintable bytes in a
+//! label escaped with a backslash. The root is written as a single dot.
 
 
 mod types;
 pub use self::types::*;
 
 mod strings;
-pub use self::strings::Labels;
+pub use self::strings::{Labels, Compression};
 
 mod wire;
 pub use self::wire::{Wire, WireError, MandatedLength};
 
+mod zone;
+pub use self::zone::{parse_zone, read_zone_file, parse_relative_name, ZoneError, ZoneErrorKind};
+
+mod update;
+pub use self::update::{Update, Prerequisite, Operation};
+
+mod transfer;
+pub use self::transfer::{Transfer, Difference, TransferError};
+
+mod borrowed;
+pub use self::borrowed::{ResponseRef, QueryRef, AnswerRef, NameRef, Queries, Answers, Segments};
+
 pub mod record;
+
+mod registry;
+pub use self::registry::UnknownMnemonic;
+
+#[cfg(feature = "with_serde")]
+mod serialise;
@@ -12,6 +12,7 @@ use crate::wire::*;
 /// - [RFC 3596](https://tools.ietf.org/html/rfc3596) — DNS Extensions to
 ///   Support IP Version 6 (October 2003)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AAAA {
 
     /// The IPv6 address contained in the packet.
@@ -38,6 +39,11 @@ impl Wire for AAAA {
 
         Ok(Self { address })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.extend_from_slice(&self.address.octets());
+        Ok(())
+    }
 }
 
 
@@ -57,6 +63,19 @@ mod test {
                    AAAA { address: Ipv6Addr::new(0,0,0,0,0,0,0,0) });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // IPv6 address
+        ];
+
+        let record = AAAA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn record_too_long() {
         let buf = &[
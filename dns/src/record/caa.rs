@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::strings::write_character_string;
 use crate::wire::*;
 
 
@@ -12,6 +13,7 @@ use crate::wire::*;
 /// - [RFC 6844](https://tools.ietf.org/html/rfc6844) — DNS Certification
 ///   Authority Authorization Resource Record (January 2013)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CAA {
 
     /// Whether this record is marked as “critical” or not.
@@ -57,6 +59,13 @@ impl Wire for CAA {
 
         Ok(Self { critical, tag, value })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u8(if self.critical { 0b_1000_0000 } else { 0 })?;
+        write_character_string(bytes, &self.tag)?;
+        bytes.extend_from_slice(&self.value);
+        Ok(())
+    }
 }
 
 
@@ -82,6 +91,21 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00,  // flags (all unset)
+            0x09,  // tag length
+            0x69, 0x73, 0x73, 0x75, 0x65, 0x77, 0x69, 0x6c, 0x64,  // tag
+            0x65, 0x6e, 0x74, 0x72, 0x75, 0x73, 0x74, 0x2e, 0x6e, 0x65, 0x74,  // value
+        ];
+
+        let record = CAA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn parses_critical() {
         let buf = &[
@@ -11,6 +11,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.3.1](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CNAME {
 
     /// The domain name that this CNAME record is responding with.
@@ -35,6 +36,10 @@ impl Wire for CNAME {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: domain_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()> {
+        bytes.write_labels_compressed(&self.domain, compression)
+    }
 }
 
 
@@ -56,6 +61,19 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // domain
+            0x00,  // domain terminator
+        ];
+
+        let record = CNAME::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_record_length() {
         let buf = &[
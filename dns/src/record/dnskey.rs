@@ -0,0 +1,214 @@
+use log::*;
+
+use crate::record::Algorithm;
+use crate::wire::*;
+
+
+/// A **DNSKEY** _(DNS public key)_ record, which holds a public key that
+/// resolvers use to verify the signatures in a DNSSEC-signed zone.
+///
+/// # References
+///
+/// - [RFC 4034 §2](https://tools.ietf.org/html/rfc4034) — Resource Records
+///   for the DNS Security Extensions (March 2005)
+/// - [RFC 5011 §7](https://tools.ietf.org/html/rfc5011) — Automated Updates
+///   of DNS Security (DNSSEC) Trust Anchors (September 2007)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DNSKEY {
+
+    /// Sixteen bits worth of flags, of which three have defined meanings.
+    pub flags: u16,
+
+    /// The protocol number, which must always be 3.
+    pub protocol: u8,
+
+    /// The algorithm of the public key.
+    pub algorithm: Algorithm,
+
+    /// The public key, as unencoded bytes.
+    pub public_key: Vec<u8>,
+}
+
+impl Wire for DNSKEY {
+    const NAME: &'static str = "DNSKEY";
+    const RR_TYPE: u16 = 48;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let flags = c.read_u16::<BigEndian>()?;
+        trace!("Parsed flags -> {:#018b}", flags);
+
+        let protocol = c.read_u8()?;
+        trace!("Parsed protocol -> {:?}", protocol);
+
+        let algorithm = Algorithm::from(c.read_u8()?);
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        if stated_length <= 4 {
+            let mandated_length = MandatedLength::AtLeast(5);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let public_key_length = stated_length - 2 - 1 - 1;
+        let mut public_key = vec![0_u8; usize::from(public_key_length)];
+        c.read_exact(&mut public_key)?;
+        trace!("Parsed public key -> {:#x?}", public_key);
+
+        Ok(Self { flags, protocol, algorithm, public_key })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.flags)?;
+        bytes.write_u8(self.protocol)?;
+        bytes.write_u8(self.algorithm.number())?;
+        bytes.extend_from_slice(&self.public_key);
+        Ok(())
+    }
+}
+
+impl DNSKEY {
+
+    /// Whether the Zone Key flag is set, meaning this key can be used to
+    /// verify signatures over records in the zone.
+    pub fn is_zone_key(&self) -> bool {
+        self.flags & 0b_0000_0001_0000_0000 != 0
+    }
+
+    /// Whether the Secure Entry Point flag is set, which conventionally marks
+    /// a key-signing key.
+    pub fn is_secure_entry_point(&self) -> bool {
+        self.flags & 0b_0000_0000_0000_0001 != 0
+    }
+
+    /// Whether the Revoke flag is set.
+    pub fn is_revoked(&self) -> bool {
+        self.flags & 0b_0000_0000_1000_0000 != 0
+    }
+
+    /// The base64-encoded public key.
+    pub fn base64_public_key(&self) -> String {
+        base64::encode(&self.public_key)
+    }
+
+    /// Computes the key tag for this key, which DS and RRSIG records use to
+    /// refer to it. This is a checksum of the record data, as described in
+    /// RFC 4034 Appendix B.
+    pub fn key_tag(&self) -> u16 {
+        let mut rdata = Vec::with_capacity(self.public_key.len() + 4);
+        rdata.extend_from_slice(&self.flags.to_be_bytes());
+        rdata.push(self.protocol);
+        rdata.push(self.algorithm.number());
+        rdata.extend_from_slice(&self.public_key);
+
+        let mut accumulator = 0_u32;
+        for (index, byte) in rdata.iter().enumerate() {
+            if index % 2 == 0 {
+                accumulator += u32::from(*byte) << 8;
+            }
+            else {
+                accumulator += u32::from(*byte);
+            }
+        }
+
+        accumulator += (accumulator >> 16) & 0xFFFF;
+        let [_, _, high, low] = accumulator.to_be_bytes();
+        u16::from_be_bytes([high, low])
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x01, 0x01,  // flags
+            0x03,  // protocol
+            0x0d,  // algorithm
+            0x12, 0x34, 0x56, 0x78,  // public key
+        ];
+
+        assert_eq!(DNSKEY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   DNSKEY {
+                       flags: 257,
+                       protocol: 3,
+                       algorithm: Algorithm::from(13),
+                       public_key: vec![ 0x12, 0x34, 0x56, 0x78 ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01, 0x01,  // flags
+            0x03,  // protocol
+            0x0d,  // algorithm
+            0x12, 0x34, 0x56, 0x78,  // public key
+        ];
+
+        let record = DNSKEY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0x01, 0x01,  // flags
+            0x03,  // protocol
+            0x0d,  // algorithm
+        ];
+
+        assert_eq!(DNSKEY::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 4, mandated_length: MandatedLength::AtLeast(5) }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(DNSKEY::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x01, 0x01,  // flags
+            0x03,  // protocol
+        ];
+
+        assert_eq!(DNSKEY::read(8, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn flags() {
+        let dnskey = DNSKEY {
+            flags: 257,
+            protocol: 3,
+            algorithm: Algorithm::from(13),
+            public_key: vec![],
+        };
+
+        assert!(dnskey.is_zone_key());
+        assert!(dnskey.is_secure_entry_point());
+        assert!(! dnskey.is_revoked());
+    }
+
+    #[test]
+    fn key_tag() {
+        // The example key from RFC 4034 §5.4
+        let dnskey = DNSKEY {
+            flags: 256,
+            protocol: 3,
+            algorithm: Algorithm::from(5),
+            public_key: base64::decode("AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvxegXd/M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9XzcnOf+EPbtG9DMBmADjFDc2w/rljwvFw==").unwrap(),
+        };
+
+        assert_eq!(dnskey.key_tag(), 60485);
+    }
+}
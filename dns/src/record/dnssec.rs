@@ -0,0 +1,92 @@
+//! Types shared between the DNSSEC record types.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::registry::{self, ALGORITHMS, UnknownMnemonic};
+
+
+/// A number representing the cryptographic algorithm used to create a key or
+/// signature. The same numbers are used in DNSKEY, DS, and RRSIG records.
+///
+/// # References
+///
+/// - [RFC 8624 §3.1](https://tools.ietf.org/html/rfc8624) — Algorithm
+///   Implementation Requirements and Usage Guidance for DNSSEC (June 2019)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Algorithm {
+
+    /// An algorithm number that dog knows the mnemonic for.
+    Known(&'static str, u8),
+
+    /// An algorithm number that has no known mnemonic.
+    Unknown(u8),
+}
+
+impl Algorithm {
+
+    /// Returns the number behind this algorithm.
+    pub fn number(self) -> u8 {
+        match self {
+            Self::Known(_, num) |
+            Self::Unknown(num)   => num,
+        }
+    }
+
+    /// Returns the algorithm with the given mnemonic, or `None` if no
+    /// algorithm has it. Matches names case-insensitively.
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        let (name, number) = registry::number(ALGORITHMS, mnemonic)?;
+        Some(Self::Known(name, number))
+    }
+}
+
+impl From<u8> for Algorithm {
+    fn from(number: u8) -> Self {
+        match registry::mnemonic(ALGORITHMS, number) {
+            Some(name)  => Self::Known(name, number),
+            None        => Self::Unknown(number),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Known(name, _)  => write!(f, "{}", name),
+            Self::Unknown(num)    => write!(f, "{}", num),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = UnknownMnemonic;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::from_mnemonic(input).ok_or_else(|| UnknownMnemonic(input.into()))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn known() {
+        assert_eq!(Algorithm::from(13).to_string(),
+                   String::from("ECDSAP256SHA256"));
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(Algorithm::from(99).to_string(),
+                   String::from("99"));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("ed25519".parse(),
+                   Ok(Algorithm::Known("ED25519", 15)));
+    }
+}
@@ -0,0 +1,159 @@
+use log::*;
+
+use crate::record::Algorithm;
+use crate::wire::*;
+
+
+/// A **DS** _(delegation signer)_ record, which is placed in a parent zone
+/// and holds a digest of one of the child zone’s DNSKEY records, linking the
+/// two zones together in the chain of trust.
+///
+/// # References
+///
+/// - [RFC 4034 §5](https://tools.ietf.org/html/rfc4034) — Resource Records
+///   for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DS {
+
+    /// The key tag of the DNSKEY record that this digest refers to.
+    pub key_tag: u16,
+
+    /// The algorithm of the DNSKEY record that this digest refers to.
+    pub algorithm: Algorithm,
+
+    /// The type of the digest, which specifies the hashing algorithm used to
+    /// produce it. This is a number with several defined mappings.
+    pub digest_type: u8,
+
+    /// The digest of the DNSKEY record.
+    pub digest: Vec<u8>,
+}
+
+impl Wire for DS {
+    const NAME: &'static str = "DS";
+    const RR_TYPE: u16 = 43;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let key_tag = c.read_u16::<BigEndian>()?;
+        trace!("Parsed key tag -> {:?}", key_tag);
+
+        let algorithm = Algorithm::from(c.read_u8()?);
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        let digest_type = c.read_u8()?;
+        trace!("Parsed digest type -> {:?}", digest_type);
+
+        if stated_length <= 4 {
+            let mandated_length = MandatedLength::AtLeast(5);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let digest_length = stated_length - 2 - 1 - 1;
+        let mut digest = vec![0_u8; usize::from(digest_length)];
+        c.read_exact(&mut digest)?;
+        trace!("Parsed digest -> {:#x?}", digest);
+
+        Ok(Self { key_tag, algorithm, digest_type, digest })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.key_tag)?;
+        bytes.write_u8(self.algorithm.number())?;
+        bytes.write_u8(self.digest_type)?;
+        bytes.extend_from_slice(&self.digest);
+        Ok(())
+    }
+}
+
+impl DS {
+
+    /// Returns the hexadecimal representation of the digest.
+    pub fn hex_digest(&self) -> String {
+        self.digest.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0xec, 0x45,  // key tag
+            0x05,  // algorithm
+            0x01,  // digest type
+            0x2b, 0xb1, 0x83, 0xaf,  // a short digest
+        ];
+
+        assert_eq!(DS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   DS {
+                       key_tag: 60485,
+                       algorithm: Algorithm::from(5),
+                       digest_type: 1,
+                       digest: vec![ 0x2b, 0xb1, 0x83, 0xaf ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0xec, 0x45,  // key tag
+            0x05,  // algorithm
+            0x01,  // digest type
+            0x2b, 0xb1, 0x83, 0xaf,  // a short digest
+        ];
+
+        let record = DS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn record_too_short() {
+        let buf = &[
+            0xec, 0x45,  // key tag
+            0x05,  // algorithm
+            0x01,  // digest type
+        ];
+
+        assert_eq!(DS::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 4, mandated_length: MandatedLength::AtLeast(5) }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(DS::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0xec, 0x45,  // key tag
+        ];
+
+        assert_eq!(DS::read(8, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn hex_rep() {
+        let ds = DS {
+            key_tag: 60485,
+            algorithm: Algorithm::from(5),
+            digest_type: 1,
+            digest: vec![ 0x2b, 0xb1, 0x83, 0xaf ],
+        };
+
+        assert_eq!(ds.hex_digest(),
+                   String::from("2bb183af"));
+    }
+}
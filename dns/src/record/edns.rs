@@ -0,0 +1,581 @@
+use std::convert::TryFrom;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use log::*;
+
+use crate::registry;
+use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::wire::*;
+
+
+/// One option in the payload of an OPT pseudo-record, made up of an option
+/// code and a value whose format depends on the code.
+///
+/// Options with a known code but a value that cannot be decoded are kept as
+/// `Other`, so that one malformed option does not prevent the rest of the
+/// response from being read.
+///
+/// # References
+///
+/// - [RFC 6891 §6.1.2](https://tools.ietf.org/html/rfc6891#section-6.1.2) —
+///   Extension Mechanisms for DNS (April 2013)
+/// - [IANA DNS EDNS0 Option Codes](https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-11)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EDNSOption {
+
+    /// A **NSID** _(name server identifier)_ option. Clients send it empty,
+    /// and servers fill it in with an identifier for themselves.
+    ///
+    /// - [RFC 5001](https://tools.ietf.org/html/rfc5001) — DNS Name Server
+    ///   Identifier (NSID) Option (August 2007)
+    NSID(Vec<u8>),
+
+    /// A **Client Subnet** option, which tells a resolver which network a
+    /// query came from, so it can give a geographically appropriate answer.
+    ///
+    /// - [RFC 7871](https://tools.ietf.org/html/rfc7871) — Client Subnet in
+    ///   DNS Queries (May 2016)
+    ClientSubnet(ClientSubnet),
+
+    /// An **Expire** option, which holds a zone’s remaining expiry time in
+    /// a response, and is empty in a query.
+    ///
+    /// - [RFC 7314](https://tools.ietf.org/html/rfc7314) — Extension
+    ///   Mechanisms for DNS (EDNS) EXPIRE Option (July 2014)
+    Expire(Option<u32>),
+
+    /// A **Cookie** option, which protects against off-path attackers.
+    ///
+    /// - [RFC 7873](https://tools.ietf.org/html/rfc7873) — Domain Name System
+    ///   (DNS) Cookies (May 2016)
+    Cookie(Cookie),
+
+    /// A **TCP Keepalive** option, which holds how long the server will keep
+    /// an idle TCP connection open, in units of 100 milliseconds. It is empty
+    /// in queries.
+    ///
+    /// - [RFC 7828](https://tools.ietf.org/html/rfc7828) — The
+    ///   edns-tcp-keepalive EDNS0 Option (April 2016)
+    TcpKeepalive(Option<u16>),
+
+    /// A **Padding** option, which contains this many zero bytes, to hide
+    /// the length of an encrypted message.
+    ///
+    /// - [RFC 7830](https://tools.ietf.org/html/rfc7830) — The EDNS(0)
+    ///   Padding Option (May 2016)
+    Padding(u16),
+
+    /// A **Chain** option, which asks for the records needed to validate an
+    /// answer all the way down from the given trust point.
+    ///
+    /// - [RFC 7901](https://tools.ietf.org/html/rfc7901) — CHAIN Query
+    ///   Requests in DNS (June 2016)
+    Chain(Labels),
+
+    /// An **Extended DNS Error** option, which gives more detail about why a
+    /// response has the error code it does.
+    ///
+    /// - [RFC 8914](https://tools.ietf.org/html/rfc8914) — Extended DNS Errors
+    ///   (October 2020)
+    ExtendedError(ExtendedError),
+
+    /// An option with a code we don’t recognise, or with a value that could
+    /// not be decoded.
+    Other {
+
+        /// The number that’s meant to represent the option.
+        code: u16,
+
+        /// The undecoded bytes of the option’s value.
+        data: Vec<u8>,
+    },
+}
+
+/// The contents of a Client Subnet option.
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientSubnet {
+
+    /// The number of leading bits of the address that are significant, as
+    /// sent by the client.
+    pub source_prefix_length: u8,
+
+    /// The number of leading bits of the address that the answer applies to,
+    /// as returned by the server. This is zero in queries.
+    pub scope_prefix_length: u8,
+
+    /// The address of the client’s network. Only the bytes covered by the
+    /// source prefix are sent; the rest are zero.
+    pub address: IpAddr,
+}
+
+/// The contents of a Cookie option.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cookie {
+
+    /// The cookie generated by the client.
+    pub client: [u8; 8],
+
+    /// The cookie generated by the server, which is between 8 and 32 bytes
+    /// long when present.
+    pub server: Option<Vec<u8>>,
+}
+
+/// The contents of an Extended DNS Error option.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedError {
+
+    /// The number that identifies the error.
+    pub info_code: u16,
+
+    /// Additional text about the error, intended for humans, which may be
+    /// empty.
+    pub extra_text: String,
+}
+
+
+impl EDNSOption {
+
+    /// The option code for NSID.
+    pub const NSID_CODE: u16 = 3;
+
+    /// The option code for Client Subnet.
+    pub const CLIENT_SUBNET_CODE: u16 = 8;
+
+    /// The option code for Expire.
+    pub const EXPIRE_CODE: u16 = 9;
+
+    /// The option code for Cookie.
+    pub const COOKIE_CODE: u16 = 10;
+
+    /// The option code for TCP Keepalive.
+    pub const TCP_KEEPALIVE_CODE: u16 = 11;
+
+    /// The option code for Padding.
+    pub const PADDING_CODE: u16 = 12;
+
+    /// The option code for Chain.
+    pub const CHAIN_CODE: u16 = 13;
+
+    /// The option code for Extended DNS Error.
+    pub const EXTENDED_ERROR_CODE: u16 = 15;
+
+    /// Reads every option in the payload of an OPT record, until the payload
+    /// runs out.
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    pub fn read_all(payload: &[u8]) -> Result<Vec<Self>, WireError> {
+        let mut options = Vec::new();
+        let mut rest = payload;
+
+        while ! rest.is_empty() {
+            let mut c = Cursor::new(rest);
+
+            let code = c.read_u16::<BigEndian>()?;
+            trace!("Parsed option code -> {:?}", code);
+
+            let length = c.read_u16::<BigEndian>()?;
+            trace!("Parsed option length -> {:?}", length);
+
+            let mut data = vec![0_u8; usize::from(length)];
+            c.read_exact(&mut data)?;
+            trace!("Parsed option data -> {:#x?}", data);
+
+            rest = &rest[4 + data.len() ..];
+
+            let option = Self::decode(code, data);
+            trace!("Parsed option -> {:?}", option);
+            options.push(option);
+        }
+
+        Ok(options)
+    }
+
+    /// Decodes the value of an option with the given code, falling back to
+    /// `Other` if it is malformed.
+    fn decode(code: u16, data: Vec<u8>) -> Self {
+        let option = match code {
+            Self::NSID_CODE            => return Self::NSID(data),
+            Self::CLIENT_SUBNET_CODE   => ClientSubnet::decode(&data).map(Self::ClientSubnet),
+            Self::EXPIRE_CODE          => decode_optional_u32(&data).map(Self::Expire),
+            Self::COOKIE_CODE          => Cookie::decode(&data).map(Self::Cookie),
+            Self::TCP_KEEPALIVE_CODE   => decode_optional_u16(&data).map(Self::TcpKeepalive),
+            Self::PADDING_CODE         => u16::try_from(data.len()).ok().map(Self::Padding),
+            Self::CHAIN_CODE           => decode_name(&data).map(Self::Chain),
+            Self::EXTENDED_ERROR_CODE  => ExtendedError::decode(&data).map(Self::ExtendedError),
+            _                          => return Self::Other { code, data },
+        };
+
+        option.unwrap_or_else(|| {
+            warn!("Option with code {:?} is malformed", code);
+            Self::Other { code, data }
+        })
+    }
+
+    /// Returns the option code of this option.
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::NSID(_)           => Self::NSID_CODE,
+            Self::ClientSubnet(_)   => Self::CLIENT_SUBNET_CODE,
+            Self::Expire(_)         => Self::EXPIRE_CODE,
+            Self::Cookie(_)         => Self::COOKIE_CODE,
+            Self::TcpKeepalive(_)   => Self::TCP_KEEPALIVE_CODE,
+            Self::Padding(_)        => Self::PADDING_CODE,
+            Self::Chain(_)          => Self::CHAIN_CODE,
+            Self::ExtendedError(_)  => Self::EXTENDED_ERROR_CODE,
+            Self::Other { code, .. }  => *code,
+        }
+    }
+
+    /// Returns the name that this option’s code is registered under, or
+    /// `None` if the code has not been assigned.
+    ///
+    /// # References
+    ///
+    /// - [IANA DNS EDNS0 Option Codes](https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-11)
+    pub fn name(&self) -> Option<&'static str> {
+        registry::mnemonic(registry::OPTIONS, self.code())
+    }
+
+    /// Serialises this option into a vector of bytes, including its code
+    /// and length.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+
+        match self {
+            Self::NSID(nsid) => {
+                data.extend_from_slice(nsid);
+            }
+            Self::ClientSubnet(ecs) => {
+                ecs.write(&mut data)?;
+            }
+            Self::Expire(expire) => {
+                if let Some(expire) = expire {
+                    data.write_u32::<BigEndian>(*expire)?;
+                }
+            }
+            Self::Cookie(cookie) => {
+                data.extend_from_slice(&cookie.client);
+                if let Some(server) = &cookie.server {
+                    data.extend_from_slice(server);
+                }
+            }
+            Self::TcpKeepalive(timeout) => {
+                if let Some(timeout) = timeout {
+                    data.write_u16::<BigEndian>(*timeout)?;
+                }
+            }
+            Self::Padding(length) => {
+                data.resize(usize::from(*length), 0);
+            }
+            Self::Chain(trust_point) => {
+                data.write_labels(trust_point)?;
+            }
+            Self::ExtendedError(ede) => {
+                data.write_u16::<BigEndian>(ede.info_code)?;
+                data.extend_from_slice(ede.extra_text.as_bytes());
+            }
+            Self::Other { data: other_data, .. } => {
+                data.extend_from_slice(other_data);
+            }
+        }
+
+        let length = u16::try_from(data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Option too long"))?;
+
+        let mut bytes = Vec::with_capacity(4 + data.len());
+        bytes.write_u16::<BigEndian>(self.code())?;
+        bytes.write_u16::<BigEndian>(length)?;
+        bytes.extend(data);
+        Ok(bytes)
+    }
+}
+
+impl ExtendedError {
+
+    /// Returns the name of this error’s info code, or `None` if the code has
+    /// not been assigned.
+    ///
+    /// # References
+    ///
+    /// - [IANA Extended DNS Error Codes](https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#extended-dns-error-codes)
+    pub fn info_code_name(&self) -> Option<&'static str> {
+        registry::mnemonic(registry::EXTENDED_ERRORS, self.info_code)
+    }
+}
+
+impl ClientSubnet {
+
+    /// The address family number for IPv4.
+    const FAMILY_IPV4: u16 = 1;
+
+    /// The address family number for IPv6.
+    const FAMILY_IPV6: u16 = 2;
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let (family, source_prefix_length, scope_prefix_length, address_bytes) = match data {
+            [f1, f2, source, scope, rest @ ..]  => (u16::from_be_bytes([*f1, *f2]), *source, *scope, rest),
+            _                                   => return None,
+        };
+
+        // Only as many bytes as are needed to hold the prefix are sent
+        if address_bytes.len() != prefix_bytes(source_prefix_length) {
+            return None;
+        }
+
+        let address = match family {
+            Self::FAMILY_IPV4 if source_prefix_length <= 32 => {
+                let mut octets = [0_u8; 4];
+                octets[.. address_bytes.len()].copy_from_slice(address_bytes);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            Self::FAMILY_IPV6 if source_prefix_length <= 128 => {
+                let mut octets = [0_u8; 16];
+                octets[.. address_bytes.len()].copy_from_slice(address_bytes);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => {
+                return None;
+            }
+        };
+
+        Some(Self { source_prefix_length, scope_prefix_length, address })
+    }
+
+    fn write(&self, data: &mut Vec<u8>) -> io::Result<()> {
+        let (family, octets) = match self.address {
+            IpAddr::V4(address)  => (Self::FAMILY_IPV4, address.octets().to_vec()),
+            IpAddr::V6(address)  => (Self::FAMILY_IPV6, address.octets().to_vec()),
+        };
+
+        let length = prefix_bytes(self.source_prefix_length).min(octets.len());
+
+        data.write_u16::<BigEndian>(family)?;
+        data.write_u8(self.source_prefix_length)?;
+        data.write_u8(self.scope_prefix_length)?;
+        data.extend_from_slice(&octets[.. length]);
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes needed to hold an address prefix of the given
+/// length in bits.
+fn prefix_bytes(prefix_length: u8) -> usize {
+    (usize::from(prefix_length) + 7) / 8
+}
+
+impl Cookie {
+    fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+
+        let (client_bytes, server) = data.split_at(8);
+        let mut client = [0_u8; 8];
+        client.copy_from_slice(client_bytes);
+
+        match server.len() {
+            0        => Some(Self { client, server: None }),
+            8 ..= 32 => Some(Self { client, server: Some(server.to_vec()) }),
+            _        => None,
+        }
+    }
+}
+
+impl ExtendedError {
+    fn decode(data: &[u8]) -> Option<Self> {
+        match data {
+            [high, low, text @ ..] => {
+                let info_code = u16::from_be_bytes([*high, *low]);
+                let extra_text = String::from_utf8_lossy(text).to_string();
+                Some(Self { info_code, extra_text })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn decode_optional_u16(data: &[u8]) -> Option<Option<u16>> {
+    match data {
+        []           => Some(None),
+        [high, low]  => Some(Some(u16::from_be_bytes([*high, *low]))),
+        _            => None,
+    }
+}
+
+fn decode_optional_u32(data: &[u8]) -> Option<Option<u32>> {
+    match data {
+        []              => Some(None),
+        [a, b, c, d]    => Some(Some(u32::from_be_bytes([*a, *b, *c, *d]))),
+        _               => None,
+    }
+}
+
+fn decode_name(data: &[u8]) -> Option<Labels> {
+    let (labels, length) = Cursor::new(data).read_labels().ok()?;
+
+    if usize::from(length) == data.len() { Some(labels) } else { None }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_nsid() {
+        let buf = &[
+            0x00, 0x03,  // code
+            0x00, 0x03,  // length
+            0x61, 0x62, 0x63,  // “abc”
+        ];
+
+        assert_eq!(EDNSOption::read_all(buf),
+                   Ok(vec![ EDNSOption::NSID(b"abc".to_vec()) ]));
+    }
+
+    #[test]
+    fn parses_client_subnet() {
+        let buf = &[
+            0x00, 0x08,  // code
+            0x00, 0x07,  // length
+            0x00, 0x01,  // family (IPv4)
+            0x18,  // source prefix length
+            0x10,  // scope prefix length
+            0xcb, 0x00, 0x71,  // address
+        ];
+
+        assert_eq!(EDNSOption::read_all(buf),
+                   Ok(vec![ EDNSOption::ClientSubnet(ClientSubnet {
+                       source_prefix_length: 24,
+                       scope_prefix_length: 16,
+                       address: IpAddr::V4(Ipv4Addr::new(203, 0, 113, 0)),
+                   }) ]));
+    }
+
+    #[test]
+    fn parses_several() {
+        let buf = &[
+            0x00, 0x0a,  // code (cookie)
+            0x00, 0x10,  // length
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,  // client cookie
+            0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,  // server cookie
+            0x00, 0x0b,  // code (tcp keepalive)
+            0x00, 0x02,  // length
+            0x01, 0x2c,  // timeout
+            0x00, 0x0f,  // code (extended error)
+            0x00, 0x04,  // length
+            0x00, 0x06,  // info code
+            0x68, 0x69,  // extra text
+            0x00, 0x0c,  // code (padding)
+            0x00, 0x03,  // length
+            0x00, 0x00, 0x00,  // padding
+            0x00, 0x0d,  // code (chain)
+            0x00, 0x05,  // length
+            0x03, 0x63, 0x6f, 0x6d, 0x00,  // trust point
+        ];
+
+        assert_eq!(EDNSOption::read_all(buf),
+                   Ok(vec![
+                       EDNSOption::Cookie(Cookie {
+                           client: [ 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08 ],
+                           server: Some(vec![ 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18 ]),
+                       }),
+                       EDNSOption::TcpKeepalive(Some(300)),
+                       EDNSOption::ExtendedError(ExtendedError { info_code: 6, extra_text: "hi".into() }),
+                       EDNSOption::Padding(3),
+                       EDNSOption::Chain(Labels::encode("com").unwrap()),
+                   ]));
+    }
+
+    #[test]
+    fn malformed_becomes_other() {
+        let buf = &[
+            0x00, 0x09,  // code (expire)
+            0x00, 0x02,  // length
+            0x01, 0x02,  // half an expiry time
+            0xfd, 0xe9,  // code (unassigned)
+            0x00, 0x01,  // length
+            0x01,  // data
+        ];
+
+        assert_eq!(EDNSOption::read_all(buf),
+                   Ok(vec![
+                       EDNSOption::Other { code: 9, data: vec![ 0x01, 0x02 ] },
+                       EDNSOption::Other { code: 65001, data: vec![ 0x01 ] },
+                   ]));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00, 0x03,  // code
+            0x00, 0x03,  // length
+            0x61,  // one byte of three
+        ];
+
+        assert_eq!(EDNSOption::read_all(buf),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn info_code_names() {
+        let ede = ExtendedError { info_code: 6, extra_text: String::new() };
+        assert_eq!(ede.info_code_name(), Some("DNSSEC Bogus"));
+
+        let ede = ExtendedError { info_code: 512, extra_text: String::new() };
+        assert_eq!(ede.info_code_name(), None);
+    }
+
+    #[test]
+    fn option_names() {
+        assert_eq!(EDNSOption::Padding(12).name(), Some("Padding"));
+        assert_eq!(EDNSOption::Other { code: 14, data: Vec::new() }.name(), Some("edns-key-tag"));
+        assert_eq!(EDNSOption::Other { code: 65001, data: Vec::new() }.name(), None);
+    }
+
+    #[test]
+    fn client_subnet_round_trip() {
+        let option = EDNSOption::ClientSubnet(ClientSubnet {
+            source_prefix_length: 56,
+            scope_prefix_length: 0,
+            address: IpAddr::V6("2001:db8:1::".parse().unwrap()),
+        });
+
+        let bytes = option.to_bytes().unwrap();
+        assert_eq!(bytes, vec![
+            0x00, 0x08,  // code
+            0x00, 0x0b,  // length
+            0x00, 0x02,  // family (IPv6)
+            0x38,  // source prefix length
+            0x00,  // scope prefix length
+            0x20, 0x01, 0x0d, 0xb8, 0x00, 0x01, 0x00,  // address
+        ]);
+
+        assert_eq!(EDNSOption::read_all(&bytes), Ok(vec![ option ]));
+    }
+
+    #[test]
+    fn round_trips() {
+        let options = vec![
+            EDNSOption::NSID(vec![]),
+            EDNSOption::Expire(None),
+            EDNSOption::Cookie(Cookie { client: [ 7; 8 ], server: None }),
+            EDNSOption::TcpKeepalive(None),
+            EDNSOption::Padding(5),
+            EDNSOption::Chain(Labels::root()),
+            EDNSOption::ExtendedError(ExtendedError { info_code: 18, extra_text: String::new() }),
+            EDNSOption::Other { code: 65001, data: vec![ 1, 2, 3 ] },
+        ];
+
+        let bytes = options.iter()
+            .flat_map(|o| o.to_bytes().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(EDNSOption::read_all(&bytes), Ok(options));
+    }
+}
@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::strings::write_character_string;
 use crate::wire::*;
 
 
@@ -15,6 +16,7 @@ use crate::wire::*;
 /// - [RFC 8482 §6](https://tools.ietf.org/html/rfc8482#section-6) — Providing
 ///   Minimal-Sized Responses to DNS Queries That Have QTYPE=ANY (January 2019)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HINFO {
 
     /// The CPU field, specifying the CPU type.
@@ -55,6 +57,11 @@ impl Wire for HINFO {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        write_character_string(bytes, &self.cpu)?;
+        write_character_string(bytes, &self.os)
+    }
 }
 
 
@@ -81,6 +88,23 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x0e,  // cpu length
+            0x73, 0x6f, 0x6d, 0x65, 0x2d, 0x6b, 0x69, 0x6e, 0x64, 0x61, 0x2d,
+            0x63, 0x70, 0x75,  // cpu
+            0x0d,  // os length
+            0x73, 0x6f, 0x6d, 0x65, 0x2d, 0x6b, 0x69, 0x6e, 0x64, 0x61, 0x2d,
+            0x6f, 0x73,  // os
+        ];
+
+        let record = HINFO::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_record_length() {
         let buf = &[
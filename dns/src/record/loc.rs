@@ -1,4 +1,7 @@
+use std::convert::TryFrom;
 use std::fmt;
+use std::iter::{Copied, Peekable};
+use std::slice;
 
 use log::*;
 
@@ -13,6 +16,7 @@ use crate::wire::*;
 /// - [RFC 1876](https://tools.ietf.org/html/rfc1876) — A Means for Expressing
 ///   Location Information in the Domain Name System (January 1996)
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LOC {
 
     /// The diameter of a sphere enclosing the entity at the location, as a
@@ -42,6 +46,7 @@ pub struct LOC {
 
 /// A measure of size, in centimetres, represented by a base and an exponent.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Size {
     base: u8,
     power_of_ten: u8,
@@ -49,6 +54,7 @@ pub struct Size {
 
 /// A position on one of the world’s axes.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     degrees: u32,
     arcminutes: u32,
@@ -59,6 +65,7 @@ pub struct Position {
 
 /// A position on the vertical axis.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Altitude {
     metres: i64,
     centimetres: i64,
@@ -67,6 +74,7 @@ pub struct Altitude {
 /// One of the directions a position could be in, relative to the equator or
 /// prime meridian.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     North,
     East,
@@ -121,6 +129,82 @@ impl Wire for LOC {
             size, horizontal_precision, vertical_precision, latitude, longitude, altitude,
         })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        // Positions that were out of range when read cannot be written back
+        let out_of_range = || io::Error::new(io::ErrorKind::InvalidInput, "LOC position is out of range");
+        let latitude = self.latitude.ok_or_else(out_of_range)?;
+        let longitude = self.longitude.ok_or_else(out_of_range)?;
+
+        bytes.write_u8(0)?;  // version
+        bytes.write_u8(self.size.to_u8())?;
+        bytes.write_u8(self.horizontal_precision)?;
+        bytes.write_u8(self.vertical_precision)?;
+        bytes.write_u32::<BigEndian>(latitude.to_u32())?;
+        bytes.write_u32::<BigEndian>(longitude.to_u32())?;
+        bytes.write_u32::<BigEndian>(self.altitude.to_u32().ok_or_else(out_of_range)?)?;
+        Ok(())
+    }
+}
+
+impl LOC {
+
+    /// Returns this location in the textual format used in zone files, with
+    /// the latitude and longitude as degrees, minutes, and seconds, and the
+    /// altitude, size, and precisions in metres. Returns `None` if either
+    /// position was out of range.
+    ///
+    /// # References
+    ///
+    /// - [RFC 1876 §3](https://tools.ietf.org/html/rfc1876#section-3) — A
+    ///   Means for Expressing Location Information in the Domain Name System
+    ///   (January 1996)
+    pub fn formatted_location(&self) -> Option<String> {
+        let latitude = self.latitude?;
+        let longitude = self.longitude?;
+
+        Some(format!("{} {} {} {} {} {}",
+            latitude.master_file_format(),
+            longitude.master_file_format(),
+            self.altitude.master_file_format(),
+            self.size.master_file_format(),
+            Size::from_u8(self.horizontal_precision).master_file_format(),
+            Size::from_u8(self.vertical_precision).master_file_format(),
+        ))
+    }
+
+    /// Parses a location from the fields of its textual format, as used in
+    /// zone files. The minutes and seconds of each position can be left out,
+    /// as can the size and precisions, which have defaults. Returns `None` if
+    /// any field is invalid or out of range.
+    pub(crate) fn from_fields(fields: &[&str]) -> Option<Self> {
+        let mut fields = fields.iter().copied().peekable();
+
+        let latitude = Position::from_fields(&mut fields, true)?;
+        let longitude = Position::from_fields(&mut fields, false)?;
+
+        let altitude = parse_centimetres(fields.next()?)?;
+        let altitude = Altitude { metres: altitude / 100, centimetres: altitude % 100 };
+        altitude.to_u32()?;
+
+        let mut size = || fields.next().map_or(Some(None), |f| Size::from_centimetres(parse_centimetres(f)?).map(Some));
+        let size_value = size()?.unwrap_or(Size { base: 1, power_of_ten: 2 });
+        let horizontal_precision = size()?.unwrap_or(Size { base: 1, power_of_ten: 6 });
+        let vertical_precision = size()?.unwrap_or(Size { base: 1, power_of_ten: 3 });
+
+        if fields.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            size: size_value,
+            horizontal_precision: horizontal_precision.to_u8(),
+            vertical_precision: vertical_precision.to_u8(),
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            altitude,
+        })
+    }
 }
 
 impl Size {
@@ -133,6 +217,33 @@ impl Size {
         let power_of_ten = input & 0b_0000_1111;
         Self { base, power_of_ten }
     }
+
+    fn to_u8(self) -> u8 {
+        (self.base << 4) | (self.power_of_ten & 0b_0000_1111)
+    }
+
+    fn master_file_format(self) -> String {
+        let centimetres = u64::from(self.base) * 10_u64.pow(u32::from(self.power_of_ten));
+        format!("{}.{:02}m", centimetres / 100, centimetres % 100)
+    }
+
+    /// Converts a number of centimetres into the nearest size that can be
+    /// represented, rounding down. Returns `None` if it is too big.
+    fn from_centimetres(centimetres: i64) -> Option<Self> {
+        let mut base = u64::try_from(centimetres).ok()?;
+        let mut power_of_ten = 0;
+
+        while base > 9 {
+            base /= 10;
+            power_of_ten += 1;
+        }
+
+        if power_of_ten > 9 {
+            return None;
+        }
+
+        Some(Self { base: u8::try_from(base).ok()?, power_of_ten })
+    }
 }
 
 impl Position {
@@ -177,6 +288,72 @@ impl Position {
             Some(pos)
         }
     }
+
+    fn to_u32(self) -> u32 {
+        let offset = self.to_total();
+
+        match self.direction {
+            Direction::North | Direction::East  => 0x_8000_0000 + offset,
+            Direction::South | Direction::West  => 0x_8000_0000 - offset,
+        }
+    }
+
+    /// Reads a position from the fields of its textual format: the degrees,
+    /// optionally followed by minutes and seconds, then the direction.
+    fn from_fields(fields: &mut Peekable<Copied<slice::Iter<'_, &str>>>, vertical: bool) -> Option<Self> {
+        let (positive, negative, max_degrees) = if vertical { ("N", "S", 90) }
+                                                       else { ("E", "W", 180) };
+
+        let degrees: u32 = fields.next()?.parse().ok()?;
+        let mut arcminutes = 0;
+        let mut arcseconds = 0;
+        let mut milliarcseconds = 0;
+
+        if fields.peek()?.bytes().all(|b| b.is_ascii_digit()) {
+            arcminutes = fields.next()?.parse().ok()?;
+
+            let field = fields.peek()?;
+            if field.starts_with(|c: char| c.is_ascii_digit()) {
+                let (whole, fraction) = split_decimal(field);
+                if fraction.len() > 3 || ! fraction.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+
+                arcseconds = whole.parse().ok()?;
+                milliarcseconds = format!("{:0<3}", fraction).parse().ok()?;
+                fields.next();
+            }
+        }
+
+        let direction = match fields.next()? {
+            d if d.eq_ignore_ascii_case(positive)  => if vertical { Direction::North } else { Direction::East },
+            d if d.eq_ignore_ascii_case(negative)  => if vertical { Direction::South } else { Direction::West },
+            _                                      => return None,
+        };
+
+        let position = Self { degrees, arcminutes, arcseconds, milliarcseconds, direction };
+        if degrees > max_degrees || arcminutes >= 60 || arcseconds >= 60 || position.to_total() > max_degrees * 60 * 60 * 1000 {
+            return None;
+        }
+
+        Some(position)
+    }
+
+    /// Returns the distance from the equator or prime meridian, in
+    /// milliarcseconds.
+    fn to_total(self) -> u32 {
+        ((self.degrees * 60 + self.arcminutes) * 60 + self.arcseconds) * 1000 + self.milliarcseconds
+    }
+
+    fn master_file_format(self) -> String {
+        format!("{} {} {}.{:03} {}",
+            self.degrees,
+            self.arcminutes,
+            self.arcseconds,
+            self.milliarcseconds,
+            self.direction,
+        )
+    }
 }
 
 impl Altitude {
@@ -187,6 +364,44 @@ impl Altitude {
         let centimetres = input % 100;
         Self { metres, centimetres }
     }
+
+    fn to_u32(self) -> Option<u32> {
+        u32::try_from(self.metres * 100 + self.centimetres + 10_000_000).ok()
+    }
+
+    fn master_file_format(self) -> String {
+        let centimetres = self.metres * 100 + self.centimetres;
+        let sign = if centimetres < 0 { "-" } else { "" };
+        format!("{}{}.{:02}m", sign, centimetres.abs() / 100, centimetres.abs() % 100)
+    }
+}
+
+
+/// Parses a distance in metres, with up to two decimal places and an
+/// optional unit, into a number of centimetres.
+fn parse_centimetres(input: &str) -> Option<i64> {
+    let input = input.strip_suffix('m').unwrap_or(input);
+    let (negative, input) = match input.strip_prefix('-') {
+        Some(rest)  => (true, rest),
+        None        => (false, input),
+    };
+
+    let (whole, fraction) = split_decimal(input);
+    if whole.is_empty() || fraction.len() > 2 || ! whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let centimetres = whole.parse::<i64>().ok()?.checked_mul(100)? + format!("{:0<2}", fraction).parse::<i64>().ok()?;
+    Some(if negative { -centimetres } else { centimetres })
+}
+
+/// Splits a decimal number at its point, returning the whole part and the
+/// fractional part, which is empty if there is no point.
+fn split_decimal(input: &str) -> (&str, &str) {
+    match input.find('.') {
+        Some(point)  => (&input[.. point], &input[point + 1 ..]),
+        None         => (input, ""),
+    }
 }
 
 
@@ -265,6 +480,78 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00,  // version
+            0x32,  // size,
+            0x00,  // horizontal precision
+            0x00,  // vertical precision
+            0x8b, 0x0d, 0x2c, 0x8c,  // latitude
+            0x7f, 0xf8, 0xfc, 0xa5,  // longitude
+            0x00, 0x98, 0x96, 0x80,  // altitude
+        ];
+
+        let record = LOC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn formats_location() {
+        let buf = &[
+            0x00,  // version
+            0x32,  // size,
+            0x16,  // horizontal precision
+            0x13,  // vertical precision
+            0x8b, 0x0d, 0x2c, 0x8c,  // latitude
+            0x7f, 0xf8, 0xfc, 0xa5,  // longitude
+            0x00, 0x98, 0x96, 0x7f,  // altitude
+        ];
+
+        let record = LOC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        assert_eq!(record.formatted_location(),
+                   Some(String::from("51 30 12.748 N 0 7 39.611 W -0.01m 3.00m 10000.00m 10.00m")));
+    }
+
+    #[test]
+    fn formats_out_of_range_location() {
+        let record = LOC {
+            size: Size::from_u8(0x12),
+            horizontal_precision: 0x16,
+            vertical_precision: 0x13,
+            latitude: None,
+            longitude: Position::from_u32(0x_8000_0000, false),
+            altitude: Altitude::from_u32(0x_0098_9680),
+        };
+
+        assert_eq!(record.formatted_location(), None);
+    }
+
+    #[test]
+    fn parses_location() {
+        let text = "51 30 12.748 N 0 7 39.611 W -0.01m 3.00m 10000.00m 10.00m";
+        let fields = text.split(' ').collect::<Vec<_>>();
+        assert_eq!(LOC::from_fields(&fields).and_then(|loc| loc.formatted_location()),
+                   Some(String::from(text)));
+    }
+
+    #[test]
+    fn parses_short_location() {
+        let fields = [ "42", "N", "71", "6", "W", "-24m" ];
+        assert_eq!(LOC::from_fields(&fields).and_then(|loc| loc.formatted_location()),
+                   Some(String::from("42 0 0.000 N 71 6 0.000 W -24.00m 1.00m 10000.00m 10.00m")));
+    }
+
+    #[test]
+    fn parses_invalid_location() {
+        assert_eq!(LOC::from_fields(&[ "91", "N", "0", "E", "0m" ]), None);
+        assert_eq!(LOC::from_fields(&[ "42", "E", "0", "N", "0m" ]), None);
+        assert_eq!(LOC::from_fields(&[ "42", "N", "0", "E" ]), None);
+        assert_eq!(LOC::from_fields(&[ "42", "N", "0", "E", "0m", "1m", "1m", "1m", "1m" ]), None);
+    }
+
     #[test]
     fn record_too_short() {
         let buf = &[
@@ -1,5 +1,9 @@
 //! All the DNS record types, as well as how to parse each type.
 
+use std::fmt;
+use std::str::FromStr;
+
+use crate::registry::{self, UnknownMnemonic};
 use crate::wire::*;
 
 
@@ -15,6 +19,12 @@ pub use self::caa::CAA;
 mod cname;
 pub use self::cname::CNAME;
 
+mod dnskey;
+pub use self::dnskey::DNSKEY;
+
+mod ds;
+pub use self::ds::DS;
+
 mod eui48;
 pub use self::eui48::EUI48;
 
@@ -36,15 +46,31 @@ pub use self::naptr::NAPTR;
 mod ns;
 pub use self::ns::NS;
 
+mod nsec;
+pub use self::nsec::NSEC;
+
+mod nsec3;
+pub use self::nsec3::NSEC3;
+
+mod nsec3param;
+pub use self::nsec3param::NSEC3PARAM;
+
 mod openpgpkey;
 pub use self::openpgpkey::OPENPGPKEY;
 
 mod opt;
 pub use self::opt::OPT;
 
+mod edns;
+pub use self::edns::{EDNSOption, ClientSubnet, Cookie, ExtendedError};
+
 mod ptr;
 pub use self::ptr::PTR;
 
+mod rrsig;
+pub use self::rrsig::RRSIG;
+pub(crate) use self::rrsig::parse_timestamp;
+
 mod sshfp;
 pub use self::sshfp::SSHFP;
 
@@ -54,9 +80,15 @@ pub use self::soa::SOA;
 mod srv;
 pub use self::srv::SRV;
 
+mod svcb;
+pub use self::svcb::{SVCB, HTTPS, SvcParam};
+
 mod tlsa;
 pub use self::tlsa::TLSA;
 
+mod tsig;
+pub use self::tsig::TSIG;
+
 mod txt;
 pub use self::txt::TXT;
 
@@ -64,31 +96,43 @@ mod uri;
 pub use self::uri::URI;
 
 
+mod dnssec;
+pub use self::dnssec::Algorithm;
+
 mod others;
 pub use self::others::UnknownQtype;
 
 
 /// A record that’s been parsed from a byte buffer.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum Record {
     A(A),
     AAAA(AAAA),
     CAA(CAA),
     CNAME(CNAME),
+    DNSKEY(DNSKEY),
+    DS(DS),
     EUI48(EUI48),
     EUI64(EUI64),
     HINFO(HINFO),
+    HTTPS(HTTPS),
     LOC(LOC),
     MX(MX),
     NAPTR(NAPTR),
     NS(NS),
+    NSEC(NSEC),
+    NSEC3(NSEC3),
+    NSEC3PARAM(NSEC3PARAM),
     OPENPGPKEY(OPENPGPKEY),
     // OPT is not included here.
     PTR(PTR),
+    RRSIG(RRSIG),
     SSHFP(SSHFP),
     SOA(SOA),
     SRV(SRV),
+    SVCB(SVCB),
     TLSA(TLSA),
     TXT(TXT),
     URI(URI),
@@ -105,6 +149,44 @@ pub enum Record {
 }
 
 
+impl Record {
+
+    /// Returns the type of this record.
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            Self::A(_)             => RecordType::A,
+            Self::AAAA(_)          => RecordType::AAAA,
+            Self::CAA(_)           => RecordType::CAA,
+            Self::CNAME(_)         => RecordType::CNAME,
+            Self::DNSKEY(_)        => RecordType::DNSKEY,
+            Self::DS(_)            => RecordType::DS,
+            Self::EUI48(_)         => RecordType::EUI48,
+            Self::EUI64(_)         => RecordType::EUI64,
+            Self::HINFO(_)         => RecordType::HINFO,
+            Self::HTTPS(_)         => RecordType::HTTPS,
+            Self::LOC(_)           => RecordType::LOC,
+            Self::MX(_)            => RecordType::MX,
+            Self::NAPTR(_)         => RecordType::NAPTR,
+            Self::NS(_)            => RecordType::NS,
+            Self::NSEC(_)          => RecordType::NSEC,
+            Self::NSEC3(_)         => RecordType::NSEC3,
+            Self::NSEC3PARAM(_)    => RecordType::NSEC3PARAM,
+            Self::OPENPGPKEY(_)    => RecordType::OPENPGPKEY,
+            Self::PTR(_)           => RecordType::PTR,
+            Self::RRSIG(_)         => RecordType::RRSIG,
+            Self::SSHFP(_)         => RecordType::SSHFP,
+            Self::SOA(_)           => RecordType::SOA,
+            Self::SRV(_)           => RecordType::SRV,
+            Self::SVCB(_)          => RecordType::SVCB,
+            Self::TLSA(_)          => RecordType::TLSA,
+            Self::TXT(_)           => RecordType::TXT,
+            Self::URI(_)           => RecordType::URI,
+            Self::Other { type_number, .. }  => RecordType::Other(*type_number),
+        }
+    }
+}
+
+
 /// The type of a record that may or may not be one of the known ones. Has no
 /// data associated with it other than what type of record it is.
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -114,18 +196,26 @@ pub enum RecordType {
     AAAA,
     CAA,
     CNAME,
+    DNSKEY,
+    DS,
     EUI48,
     EUI64,
     HINFO,
+    HTTPS,
     LOC,
     MX,
     NAPTR,
     NS,
+    NSEC,
+    NSEC3,
+    NSEC3PARAM,
     OPENPGPKEY,
     PTR,
+    RRSIG,
     SSHFP,
     SOA,
     SRV,
+    SVCB,
     TLSA,
     TXT,
     URI,
@@ -148,19 +238,27 @@ impl From<u16> for RecordType {
         try_record!(AAAA);
         try_record!(CAA);
         try_record!(CNAME);
+        try_record!(DNSKEY);
+        try_record!(DS);
         try_record!(EUI48);
         try_record!(EUI64);
         try_record!(HINFO);
+        try_record!(HTTPS);
         try_record!(LOC);
         try_record!(MX);
         try_record!(NAPTR);
         try_record!(NS);
+        try_record!(NSEC);
+        try_record!(NSEC3);
+        try_record!(NSEC3PARAM);
         try_record!(OPENPGPKEY);
         // OPT is handled separately
         try_record!(PTR);
+        try_record!(RRSIG);
         try_record!(SSHFP);
         try_record!(SOA);
         try_record!(SRV);
+        try_record!(SVCB);
         try_record!(TLSA);
         try_record!(TXT);
         try_record!(URI);
@@ -173,38 +271,20 @@ impl From<u16> for RecordType {
 impl RecordType {
 
     /// Determines the record type with a given name, or `None` if none is
-    /// known. Matches names case-insensitively.
+    /// known. Matches names case-insensitively. Any type can also be given
+    /// by its number in the generic form, such as `TYPE1234`.
+    ///
+    /// # References
+    ///
+    /// - [RFC 3597 §5](https://tools.ietf.org/html/rfc3597#section-5) — Handling
+    ///   of Unknown DNS Resource Record (RR) Types (September 2003)
     pub fn from_type_name(type_name: &str) -> Option<Self> {
-        macro_rules! try_record {
-            ($record:tt) => {
-                if $record::NAME.eq_ignore_ascii_case(type_name) {
-                    return Some(Self::$record);
-                }
-            }
+        if let Some(type_number) = generic_number(type_name, "TYPE") {
+            return Some(Self::from(type_number));
         }
 
-        try_record!(A);
-        try_record!(AAAA);
-        try_record!(CAA);
-        try_record!(CNAME);
-        try_record!(EUI48);
-        try_record!(EUI64);
-        try_record!(HINFO);
-        try_record!(LOC);
-        try_record!(MX);
-        try_record!(NAPTR);
-        try_record!(NS);
-        try_record!(OPENPGPKEY);
-        // OPT is elsewhere
-        try_record!(PTR);
-        try_record!(SSHFP);
-        try_record!(SOA);
-        try_record!(SRV);
-        try_record!(TLSA);
-        try_record!(TXT);
-        try_record!(URI);
-
-        UnknownQtype::from_type_name(type_name).map(Self::Other)
+        let (_, type_number) = registry::number(registry::TYPES, type_name)?;
+        Some(Self::from(type_number))
     }
 
     /// Returns the record type number associated with this record type.
@@ -214,19 +294,27 @@ impl RecordType {
             Self::AAAA        => AAAA::RR_TYPE,
             Self::CAA         => CAA::RR_TYPE,
             Self::CNAME       => CNAME::RR_TYPE,
+            Self::DNSKEY      => DNSKEY::RR_TYPE,
+            Self::DS          => DS::RR_TYPE,
             Self::EUI48       => EUI48::RR_TYPE,
             Self::EUI64       => EUI64::RR_TYPE,
             Self::HINFO       => HINFO::RR_TYPE,
+            Self::HTTPS       => HTTPS::RR_TYPE,
             Self::LOC         => LOC::RR_TYPE,
             Self::MX          => MX::RR_TYPE,
             Self::NAPTR       => NAPTR::RR_TYPE,
             Self::NS          => NS::RR_TYPE,
+            Self::NSEC        => NSEC::RR_TYPE,
+            Self::NSEC3       => NSEC3::RR_TYPE,
+            Self::NSEC3PARAM  => NSEC3PARAM::RR_TYPE,
             Self::OPENPGPKEY  => OPENPGPKEY::RR_TYPE,
             // Wherefore art thou, OPT
             Self::PTR         => PTR::RR_TYPE,
+            Self::RRSIG       => RRSIG::RR_TYPE,
             Self::SSHFP       => SSHFP::RR_TYPE,
             Self::SOA         => SOA::RR_TYPE,
             Self::SRV         => SRV::RR_TYPE,
+            Self::SVCB        => SVCB::RR_TYPE,
             Self::TLSA        => TLSA::RR_TYPE,
             Self::TXT         => TXT::RR_TYPE,
             Self::URI         => URI::RR_TYPE,
@@ -235,4 +323,39 @@ impl RecordType {
     }
 }
 
-// This code is really repetitive, I know, I know
+/// Parses a number written in the generic form of RFC 3597, which is a
+/// prefix such as `TYPE` or `CLASS` followed by the number in decimal, such
+/// as `TYPE1234`. Matches the prefix case-insensitively.
+pub(crate) fn generic_number(text: &str, prefix: &str) -> Option<u16> {
+    if text.len() <= prefix.len() || ! text.is_char_boundary(prefix.len()) || ! text[.. prefix.len()].eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    let digits = &text[prefix.len() ..];
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    }
+    else {
+        None
+    }
+}
+
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let type_number = self.type_number();
+
+        match registry::mnemonic(registry::TYPES, type_number) {
+            Some(name)  => write!(f, "{}", name),
+            None        => write!(f, "TYPE{}", type_number),
+        }
+    }
+}
+
+impl FromStr for RecordType {
+    type Err = UnknownMnemonic;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::from_type_name(input).ok_or_else(|| UnknownMnemonic(input.into()))
+    }
+}
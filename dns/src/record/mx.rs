@@ -12,6 +12,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.3.9](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MX {
 
     /// The preference that clients should give to this MX record amongst all
@@ -44,6 +45,11 @@ impl Wire for MX {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.preference)?;
+        bytes.write_labels_compressed(&self.exchange, compression)
+    }
 }
 
 
@@ -67,6 +73,20 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x0A,  // preference
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // exchange
+            0x00,  // exchange terminator
+        ];
+
+        let record = MX::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_record_length() {
         let buf = &[
@@ -1,6 +1,6 @@
 use log::*;
 
-use crate::strings::{Labels, ReadLabels};
+use crate::strings::{Labels, ReadLabels, write_character_string};
 use crate::wire::*;
 
 
@@ -13,6 +13,7 @@ use crate::wire::*;
 ///   Discovery System (DDDS) Part Three: The Domain Name System (DNS) Database
 ///   (October 2002)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NAPTR {
 
     /// The order in which NAPTR records must be processed.
@@ -88,6 +89,17 @@ impl Wire for NAPTR {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.order)?;
+        bytes.write_u16::<BigEndian>(self.preference)?;
+        write_character_string(bytes, &self.flags)?;
+        write_character_string(bytes, &self.service)?;
+        write_character_string(bytes, &self.regex)?;
+
+        // The replacement name must not be compressed (RFC 3597 §4)
+        bytes.write_labels(&self.replacement)
+    }
 }
 
 
@@ -124,6 +136,29 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x05,  // order
+            0x00, 0x0a,  // preference
+            0x01,  // flags length
+            0x73,  // flags
+            0x03,  // service length
+            0x53, 0x52, 0x56,  // service
+            0x0e,  // regex length
+            0x5c, 0x64, 0x5c, 0x64, 0x3a, 0x5c, 0x64, 0x5c, 0x64, 0x3a, 0x5c,
+            0x64, 0x5c, 0x64,  // regex
+            0x0b, 0x73, 0x72, 0x76, 0x2d, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c,
+            0x65, 0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f,
+            0x67, 0x00,  // replacement
+        ];
+
+        let record = NAPTR::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_length() {
         let buf = &[
@@ -12,6 +12,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.3.11](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NS {
 
     /// The address of a nameserver that provides this DNS response.
@@ -36,6 +37,10 @@ impl Wire for NS {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: nameserver_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()> {
+        bytes.write_labels_compressed(&self.nameserver, compression)
+    }
 }
 
 
@@ -58,6 +63,20 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01, 0x61, 0x0c, 0x67, 0x74, 0x6c, 0x64, 0x2d, 0x73, 0x65, 0x72,
+            0x76, 0x65, 0x72, 0x73, 0x03, 0x6e, 0x65, 0x74,  // nameserver
+            0x00,  // nameserver terminator
+        ];
+
+        let record = NS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_record_length() {
         let buf = &[
@@ -0,0 +1,265 @@
+use log::*;
+
+use crate::record::RecordType;
+use crate::strings::{Labels, ReadLabels};
+use crate::wire::*;
+
+
+/// A **NSEC** _(next secure)_ record, which proves that a name does not
+/// exist by naming the next name that does exist in the zone’s canonical
+/// order, and listing the record types present at the owner name.
+///
+/// # References
+///
+/// - [RFC 4034 §4](https://tools.ietf.org/html/rfc4034) — Resource Records
+///   for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NSEC {
+
+    /// The next owner name in the canonical ordering of the zone.
+    pub next_domain: Labels,
+
+    /// The record types that exist at the owner name.
+    pub types: Vec<RecordType>,
+}
+
+impl Wire for NSEC {
+    const NAME: &'static str = "NSEC";
+    const RR_TYPE: u16 = 47;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let (next_domain, next_domain_length) = c.read_labels()?;
+        trace!("Parsed next domain -> {:?}", next_domain);
+
+        if stated_length < next_domain_length {
+            warn!("Length is incorrect (stated length {:?}, next domain length {:?})", stated_length, next_domain_length);
+            return Err(WireError::WrongLabelLength { stated_length, length_after_labels: next_domain_length });
+        }
+
+        let types = read_type_bitmaps(stated_length, next_domain_length, c)?;
+        trace!("Parsed types -> {:?}", types);
+
+        Ok(Self { next_domain, types })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        // The next domain name must not be compressed (RFC 4034 §4.1.1)
+        bytes.write_labels(&self.next_domain)?;
+        write_type_bitmaps(bytes, &self.types)
+    }
+}
+
+
+/// Reads the type bitmaps that fill the rest of a record from the given
+/// cursor, returning the list of record types that have their bits set. The
+/// record has the given stated length, of which `length_before_bitmaps` bytes
+/// have already been read. The same encoding is used by both NSEC and NSEC3
+/// records.
+///
+/// The types are split into windows of 256 types each. Every window is
+/// written as its window number, the length of its bitmap (from 1 to 32),
+/// then the bitmap itself, with the most significant bit of the first byte
+/// corresponding to the first type in the window.
+pub(crate) fn read_type_bitmaps(stated_length: u16, length_before_bitmaps: u16, c: &mut Cursor<&[u8]>) -> Result<Vec<RecordType>, WireError> {
+    let mut types = Vec::new();
+    let mut length_read = length_before_bitmaps;
+
+    while length_read < stated_length {
+        if stated_length - length_read < 2 {
+            warn!("Window header runs past the end of the record (stated length {:?}, length read {:?})", stated_length, length_read);
+            return Err(WireError::WrongBitmapLength { stated_length, length_after_bitmaps: length_read.saturating_add(2) });
+        }
+
+        let window = c.read_u8()?;
+        let bitmap_length = c.read_u8()?;
+        trace!("Parsed window -> {:?} (bitmap length {:?})", window, bitmap_length);
+
+        if bitmap_length == 0 || bitmap_length > 32 {
+            warn!("Bitmap length {:?} is out of range", bitmap_length);
+            let mandated_length = MandatedLength::AtLeast(length_read + 3);
+            return Err(WireError::WrongRecordLength { stated_length, mandated_length });
+        }
+
+        let window_length = 2 + u16::from(bitmap_length);
+        if window_length > stated_length - length_read {
+            warn!("Bitmap runs past the end of the record (stated length {:?}, length read {:?})", stated_length, length_read);
+            return Err(WireError::WrongBitmapLength { stated_length, length_after_bitmaps: length_read.saturating_add(window_length) });
+        }
+
+        for byte_index in 0 .. bitmap_length {
+            let byte = c.read_u8()?;
+
+            for bit_index in 0 .. 8 {
+                if byte & (0b_1000_0000 >> bit_index) != 0 {
+                    let type_number = u16::from_be_bytes([window, byte_index * 8 + bit_index]);
+                    types.push(RecordType::from(type_number));
+                }
+            }
+        }
+
+        length_read += window_length;
+    }
+
+    if length_read == stated_length {
+        Ok(types)
+    }
+    else {
+        warn!("Length is incorrect (stated length {:?}, length read {:?})", stated_length, length_read);
+        Err(WireError::WrongBitmapLength { stated_length, length_after_bitmaps: length_read })
+    }
+}
+
+/// Writes a list of record types as type bitmaps, with one window for each
+/// block of 256 type numbers that contains any types.
+pub(crate) fn write_type_bitmaps(bytes: &mut Vec<u8>, types: &[RecordType]) -> io::Result<()> {
+    let mut numbers = types.iter().map(|t| t.type_number()).collect::<Vec<_>>();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let mut index = 0;
+    while index < numbers.len() {
+        let [window, _] = numbers[index].to_be_bytes();
+        let mut bitmap = [0_u8; 32];
+        let mut bitmap_length = 0;
+
+        while let Some([this_window, low]) = numbers.get(index).map(|n| n.to_be_bytes()) {
+            if this_window != window {
+                break;
+            }
+
+            bitmap[usize::from(low / 8)] |= 0b_1000_0000 >> (low % 8);
+            bitmap_length = low / 8 + 1;
+            index += 1;
+        }
+
+        bytes.write_u8(window)?;
+        bytes.write_u8(bitmap_length)?;
+        bytes.extend_from_slice(&bitmap[.. usize::from(bitmap_length)]);
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // next domain
+            0x00,  // next domain terminator
+            0x00, 0x06,  // window 0, bitmap length 6
+            0x40, 0x01, 0x00, 0x00, 0x00, 0x03,  // A, MX, RRSIG, NSEC
+            0x01, 0x01,  // window 1, bitmap length 1
+            0x40,  // CAA
+        ];
+
+        assert_eq!(NSEC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC {
+                       next_domain: Labels::encode("bsago.me").unwrap(),
+                       types: vec![ RecordType::A, RecordType::MX, RecordType::RRSIG,
+                                    RecordType::NSEC, RecordType::CAA ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // next domain
+            0x00,  // next domain terminator
+            0x00, 0x06,  // window 0, bitmap length 6
+            0x40, 0x01, 0x00, 0x00, 0x00, 0x03,  // A, MX, RRSIG, NSEC
+            0x01, 0x01,  // window 1, bitmap length 1
+            0x40,  // CAA
+        ];
+
+        let record = NSEC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn no_types() {
+        let buf = &[
+            0x00,  // next domain (the root)
+        ];
+
+        assert_eq!(NSEC::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC {
+                       next_domain: Labels::root(),
+                       types: vec![],
+                   });
+    }
+
+    #[test]
+    fn bitmap_too_long() {
+        let buf = &[
+            0x00,  // next domain (the root)
+            0x00, 0x21,  // window 0, bitmap length 33
+        ];
+
+        assert_eq!(NSEC::read(40, &mut Cursor::new(buf)),
+                   Err(WireError::WrongRecordLength { stated_length: 40, mandated_length: MandatedLength::AtLeast(4) }));
+    }
+
+    #[test]
+    fn incorrect_record_length() {
+        let buf = &[
+            0x00,  // next domain (the root)
+            0x00, 0x01,  // window 0, bitmap length 1
+            0x40,  // A
+        ];
+
+        assert_eq!(NSEC::read(3, &mut Cursor::new(buf)),
+                   Err(WireError::WrongBitmapLength { stated_length: 3, length_after_bitmaps: 4 }));
+    }
+
+    #[test]
+    fn bitmap_past_record() {
+        let buf = &[
+            0x00,  // next domain (the root)
+            0x00, 0x02,  // window 0, bitmap length 2
+            0x40, 0x00,  // A, but the record ends after the first byte
+            0x00, 0x01, 0x00, 0x01,  // the next record
+        ];
+
+        assert_eq!(NSEC::read(4, &mut Cursor::new(buf)),
+                   Err(WireError::WrongBitmapLength { stated_length: 4, length_after_bitmaps: 5 }));
+    }
+
+    #[test]
+    fn window_header_past_record() {
+        let buf = &[
+            0x00,  // next domain (the root)
+            0x00,  // window 0, but the record ends before the bitmap length
+            0x01, 0x40,  // the next record
+        ];
+
+        assert_eq!(NSEC::read(2, &mut Cursor::new(buf)),
+                   Err(WireError::WrongBitmapLength { stated_length: 2, length_after_bitmaps: 3 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(NSEC::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00,  // next domain (the root)
+            0x00, 0x06,  // window 0, bitmap length 6
+            0x40,  // the start of a bitmap
+        ];
+
+        assert_eq!(NSEC::read(9, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+}
@@ -0,0 +1,263 @@
+use log::*;
+
+use crate::record::RecordType;
+use crate::record::nsec::{read_type_bitmaps, write_type_bitmaps};
+use crate::strings::write_character_string;
+use crate::wire::*;
+
+
+/// A **NSEC3** _(next secure, version 3)_ record, which proves that a name
+/// does not exist like NSEC does, but uses hashed owner names so the zone’s
+/// contents cannot be enumerated.
+///
+/// # References
+///
+/// - [RFC 5155 §3](https://tools.ietf.org/html/rfc5155) — DNS Security
+///   (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NSEC3 {
+
+    /// The hash algorithm used to hash the owner names. This is a number
+    /// with several defined mappings.
+    pub hash_algorithm: u8,
+
+    /// Eight bits worth of flags, of which only the Opt-Out flag is defined.
+    pub flags: u8,
+
+    /// The number of additional times the hash function has been applied.
+    pub iterations: u16,
+
+    /// The salt appended to names before hashing them.
+    pub salt: Vec<u8>,
+
+    /// The next hashed owner name in the hash order of the zone, as
+    /// unencoded bytes.
+    pub next_hashed_owner: Vec<u8>,
+
+    /// The record types that exist at the original owner name.
+    pub types: Vec<RecordType>,
+}
+
+impl Wire for NSEC3 {
+    const NAME: &'static str = "NSEC3";
+    const RR_TYPE: u16 = 50;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let hash_algorithm = c.read_u8()?;
+        trace!("Parsed hash algorithm -> {:?}", hash_algorithm);
+
+        let flags = c.read_u8()?;
+        trace!("Parsed flags -> {:#010b}", flags);
+
+        let iterations = c.read_u16::<BigEndian>()?;
+        trace!("Parsed iterations -> {:?}", iterations);
+
+        let salt_length = c.read_u8()?;
+        trace!("Parsed salt length -> {:?}", salt_length);
+
+        let mut salt = vec![0_u8; usize::from(salt_length)];
+        c.read_exact(&mut salt)?;
+        trace!("Parsed salt -> {:#x?}", salt);
+
+        let hash_length = c.read_u8()?;
+        trace!("Parsed hash length -> {:?}", hash_length);
+
+        let mut next_hashed_owner = vec![0_u8; usize::from(hash_length)];
+        c.read_exact(&mut next_hashed_owner)?;
+        trace!("Parsed next hashed owner -> {:#x?}", next_hashed_owner);
+
+        let length_after_labels = 1 + 1 + 2 + 1 + u16::from(salt_length) + 1 + u16::from(hash_length);
+        if stated_length < length_after_labels {
+            warn!("Length is incorrect (stated length {:?}, fields plus salt plus hash length {:?})", stated_length, length_after_labels);
+            return Err(WireError::WrongLabelLength { stated_length, length_after_labels });
+        }
+
+        let types = read_type_bitmaps(stated_length, length_after_labels, c)?;
+        trace!("Parsed types -> {:?}", types);
+
+        Ok(Self { hash_algorithm, flags, iterations, salt, next_hashed_owner, types })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u8(self.hash_algorithm)?;
+        bytes.write_u8(self.flags)?;
+        bytes.write_u16::<BigEndian>(self.iterations)?;
+        write_character_string(bytes, &self.salt)?;
+        write_character_string(bytes, &self.next_hashed_owner)?;
+        write_type_bitmaps(bytes, &self.types)
+    }
+}
+
+impl NSEC3 {
+
+    /// Whether the Opt-Out flag is set, meaning this record may cover
+    /// unsigned delegations.
+    pub fn is_opt_out(&self) -> bool {
+        self.flags & 0b_0000_0001 != 0
+    }
+
+    /// Returns the hexadecimal representation of the salt, or a single dash
+    /// if there is no salt.
+    pub fn hex_salt(&self) -> String {
+        hex_salt(&self.salt)
+    }
+
+    /// Returns the next hashed owner name encoded in Base32 with the
+    /// extended hex alphabet, which is how it appears as a label.
+    pub fn base32_next_hashed_owner(&self) -> String {
+        const ALPHABET: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+        let mut output = String::new();
+        let mut buffer = 0_u16;
+        let mut bits = 0;
+
+        for byte in &self.next_hashed_owner {
+            buffer = (buffer << 8) | u16::from(*byte);
+            bits += 8;
+
+            while bits >= 5 {
+                bits -= 5;
+                output.push(char::from(ALPHABET[usize::from((buffer >> bits) & 0b_0001_1111)]));
+            }
+        }
+
+        if bits > 0 {
+            output.push(char::from(ALPHABET[usize::from((buffer << (5 - bits)) & 0b_0001_1111)]));
+        }
+
+        output
+    }
+}
+
+/// Formats a salt as hexadecimal, using a single dash for an empty salt as
+/// the presentation format requires.
+pub(crate) fn hex_salt(salt: &[u8]) -> String {
+    if salt.is_empty() {
+        String::from("-")
+    }
+    else {
+        salt.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x01,  // flags
+            0x00, 0x0a,  // iterations
+            0x02,  // salt length
+            0xab, 0xcd,  // salt
+            0x05,  // hash length
+            0x01, 0x02, 0x03, 0x04, 0x05,  // next hashed owner
+            0x00, 0x01,  // window 0, bitmap length 1
+            0x40,  // A
+        ];
+
+        assert_eq!(NSEC3::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC3 {
+                       hash_algorithm: 1,
+                       flags: 1,
+                       iterations: 10,
+                       salt: vec![ 0xab, 0xcd ],
+                       next_hashed_owner: vec![ 0x01, 0x02, 0x03, 0x04, 0x05 ],
+                       types: vec![ RecordType::A ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x01,  // flags
+            0x00, 0x0a,  // iterations
+            0x02,  // salt length
+            0xab, 0xcd,  // salt
+            0x05,  // hash length
+            0x01, 0x02, 0x03, 0x04, 0x05,  // next hashed owner
+            0x00, 0x01,  // window 0, bitmap length 1
+            0x40,  // A
+        ];
+
+        let record = NSEC3::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn incorrect_record_length() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x01,  // flags
+            0x00, 0x0a,  // iterations
+            0x00,  // salt length
+            0x01,  // hash length
+            0x01,  // next hashed owner
+        ];
+
+        assert_eq!(NSEC3::read(6, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 6, length_after_labels: 7 }));
+    }
+
+    #[test]
+    fn bitmap_past_record() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x01,  // flags
+            0x00, 0x0a,  // iterations
+            0x00,  // salt length
+            0x01,  // hash length
+            0x01,  // next hashed owner
+            0x00, 0x02,  // window 0, bitmap length 2
+            0x40, 0x00,  // A, but the record ends after the first byte
+        ];
+
+        assert_eq!(NSEC3::read(10, &mut Cursor::new(buf)),
+                   Err(WireError::WrongBitmapLength { stated_length: 10, length_after_bitmaps: 11 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(NSEC3::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x01,  // flags
+        ];
+
+        assert_eq!(NSEC3::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn base32_rep() {
+        let nsec3 = NSEC3 {
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 0,
+            salt: vec![],
+            next_hashed_owner: b"foobar".to_vec(),
+            types: vec![],
+        };
+
+        assert_eq!(nsec3.base32_next_hashed_owner(),
+                   String::from("cpnmuoj1e8"));
+        assert_eq!(nsec3.hex_salt(),
+                   String::from("-"));
+    }
+}
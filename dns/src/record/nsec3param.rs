@@ -0,0 +1,168 @@
+use log::*;
+
+use crate::record::nsec3::hex_salt;
+use crate::strings::write_character_string;
+use crate::wire::*;
+
+
+/// A **NSEC3PARAM** _(NSEC3 parameters)_ record, which holds the parameters
+/// an authoritative server needs to calculate hashed owner names for a zone
+/// signed with NSEC3.
+///
+/// # References
+///
+/// - [RFC 5155 §4](https://tools.ietf.org/html/rfc5155) — DNS Security
+///   (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NSEC3PARAM {
+
+    /// The hash algorithm used to hash the owner names. This is a number
+    /// with several defined mappings.
+    pub hash_algorithm: u8,
+
+    /// Eight bits worth of flags, none of which are defined.
+    pub flags: u8,
+
+    /// The number of additional times the hash function is applied.
+    pub iterations: u16,
+
+    /// The salt appended to names before hashing them.
+    pub salt: Vec<u8>,
+}
+
+impl Wire for NSEC3PARAM {
+    const NAME: &'static str = "NSEC3PARAM";
+    const RR_TYPE: u16 = 51;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let hash_algorithm = c.read_u8()?;
+        trace!("Parsed hash algorithm -> {:?}", hash_algorithm);
+
+        let flags = c.read_u8()?;
+        trace!("Parsed flags -> {:#010b}", flags);
+
+        let iterations = c.read_u16::<BigEndian>()?;
+        trace!("Parsed iterations -> {:?}", iterations);
+
+        let salt_length = c.read_u8()?;
+        trace!("Parsed salt length -> {:?}", salt_length);
+
+        let mut salt = vec![0_u8; usize::from(salt_length)];
+        c.read_exact(&mut salt)?;
+        trace!("Parsed salt -> {:#x?}", salt);
+
+        let length_after_labels = 1 + 1 + 2 + 1 + u16::from(salt_length);
+        if stated_length == length_after_labels {
+            trace!("Length is correct");
+            Ok(Self { hash_algorithm, flags, iterations, salt })
+        }
+        else {
+            warn!("Length is incorrect (stated length {:?}, fields plus salt length {:?})", stated_length, length_after_labels);
+            Err(WireError::WrongLabelLength { stated_length, length_after_labels })
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u8(self.hash_algorithm)?;
+        bytes.write_u8(self.flags)?;
+        bytes.write_u16::<BigEndian>(self.iterations)?;
+        write_character_string(bytes, &self.salt)
+    }
+}
+
+impl NSEC3PARAM {
+
+    /// Returns the hexadecimal representation of the salt, or a single dash
+    /// if there is no salt.
+    pub fn hex_salt(&self) -> String {
+        hex_salt(&self.salt)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x0a,  // iterations
+            0x02,  // salt length
+            0xab, 0xcd,  // salt
+        ];
+
+        assert_eq!(NSEC3PARAM::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   NSEC3PARAM {
+                       hash_algorithm: 1,
+                       flags: 0,
+                       iterations: 10,
+                       salt: vec![ 0xab, 0xcd ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x0a,  // iterations
+            0x02,  // salt length
+            0xab, 0xcd,  // salt
+        ];
+
+        let record = NSEC3PARAM::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn incorrect_record_length() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00, 0x0a,  // iterations
+            0x00,  // salt length
+        ];
+
+        assert_eq!(NSEC3PARAM::read(6, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 6, length_after_labels: 5 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(NSEC3PARAM::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x01,  // hash algorithm
+            0x00,  // flags
+            0x00,  // half the iterations
+        ];
+
+        assert_eq!(NSEC3PARAM::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn hex_rep() {
+        let nsec3param = NSEC3PARAM {
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 10,
+            salt: vec![ 0xab, 0xcd ],
+        };
+
+        assert_eq!(nsec3param.hex_salt(),
+                   String::from("abcd"));
+    }
+}
@@ -10,6 +10,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.3.14](https://tools.ietf.org/html/rfc7929) — DNS-Based
 ///   Authentication of Named Entities Bindings for OpenPGP (August 2016)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OPENPGPKEY {
 
     /// The PGP key, as unencoded bytes.
@@ -33,6 +34,11 @@ impl Wire for OPENPGPKEY {
 
         Ok(Self { key })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.extend_from_slice(&self.key);
+        Ok(())
+    }
 }
 
 impl OPENPGPKEY {
@@ -61,6 +67,18 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x12, 0x34, 0x56, 0x78,  // key
+        ];
+
+        let record = OPENPGPKEY::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn one_byte_of_uri() {
         let buf = &[
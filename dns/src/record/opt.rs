@@ -3,6 +3,7 @@ use std::io;
 
 use log::*;
 
+use crate::record::EDNSOption;
 use crate::wire::*;
 
 
@@ -31,6 +32,7 @@ use crate::wire::*;
 /// - [RFC 6891](https://tools.ietf.org/html/rfc6891) — Extension Mechanisms
 ///   for DNS (April 2013)
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OPT {
 
     /// The maximum size of a UDP packet that the client supports.
@@ -45,8 +47,8 @@ pub struct OPT {
     /// Sixteen bits worth of flags.
     pub flags: u16,
 
-    /// The payload of the OPT record.
-    pub data: Vec<u8>,
+    /// The options in the payload of the OPT record.
+    pub options: Vec<EDNSOption>,
 }
 
 impl OPT {
@@ -54,6 +56,10 @@ impl OPT {
     /// The record type number associated with OPT.
     pub const RR_TYPE: u16 = 41;
 
+    /// The bit in the flags field that signals that DNSSEC records should be
+    /// included in the response, known as the `DO` (DNSSEC OK) bit.
+    pub const DNSSEC_OK: u16 = 0b_1000_0000_0000_0000;
+
     /// Reads from the given cursor to parse an OPT record.
     ///
     /// The buffer will have slightly more bytes to read for an OPT record
@@ -83,7 +89,10 @@ impl OPT {
         c.read_exact(&mut data)?;
         trace!("Parsed data -> {:#x?}", data);
 
-        Ok(Self { udp_payload_size, higher_bits, edns0_version, flags, data })
+        let options = EDNSOption::read_all(&data)?;
+        trace!("Parsed options -> {:?}", options);
+
+        Ok(Self { udp_payload_size, higher_bits, edns0_version, flags, options })
     }
 
     /// Serialises this OPT record into a vector of bytes.
@@ -98,15 +107,17 @@ impl OPT {
         bytes.write_u8(self.edns0_version)?;
         bytes.write_u16::<BigEndian>(self.flags)?;
 
-        // We should not be sending any data at all in the request, really,
-        // so sending too much data is downright nonsensical
-        let data_len = u16::try_from(self.data.len()).expect("Sending too much data");
-        bytes.write_u16::<BigEndian>(data_len)?;
-
-        for b in &self.data {
-            bytes.write_u8(*b)?;
+        let mut data = Vec::new();
+        for option in &self.options {
+            data.extend(option.to_bytes()?);
         }
 
+        // Requests only ever contain a few small options, so sending too much
+        // data is downright nonsensical
+        let data_len = u16::try_from(data.len()).expect("Sending too much data");
+        bytes.write_u16::<BigEndian>(data_len)?;
+        bytes.extend(data);
+
         Ok(bytes)
     }
 }
@@ -133,7 +144,7 @@ mod test {
                        higher_bits: 0,
                        edns0_version: 0,
                        flags: 0,
-                       data: vec![],
+                       options: vec![],
                    });
     }
 
@@ -144,8 +155,10 @@ mod test {
             0x00,        // higher bits
             0x00, 0x00,  // EDNS(0) version
             0x00, 0x00,  // flags
-            0x04,        // data length
-            0x01, 0x02, 0x03, 0x04,  // data
+            0x08,        // data length
+            0x00, 0x03,  // option code (NSID)
+            0x00, 0x04,  // option length
+            0x01, 0x02, 0x03, 0x04,  // option data
         ];
 
         assert_eq!(OPT::read(&mut Cursor::new(buf)).unwrap(),
@@ -154,10 +167,31 @@ mod test {
                        higher_bits: 0,
                        edns0_version: 0,
                        flags: 0,
-                       data: vec![1, 2, 3, 4],
+                       options: vec![ EDNSOption::NSID(vec![1, 2, 3, 4]) ],
                    });
     }
 
+    #[test]
+    fn serialises_options() {
+        let opt = OPT {
+            udp_payload_size: 1232,
+            higher_bits: 0,
+            edns0_version: 0,
+            flags: OPT::DNSSEC_OK,
+            options: vec![ EDNSOption::NSID(vec![]), EDNSOption::Padding(2) ],
+        };
+
+        assert_eq!(opt.to_bytes().unwrap(), vec![
+            0x04, 0xd0,  // UDP payload size
+            0x00,        // higher bits
+            0x00,        // EDNS(0) version
+            0x80, 0x00,  // flags
+            0x00, 0x0a,  // data length
+            0x00, 0x03, 0x00, 0x00,  // NSID
+            0x00, 0x0c, 0x00, 0x02, 0x00, 0x00,  // padding
+        ]);
+    }
+
     #[test]
     fn record_empty() {
         assert_eq!(OPT::read(&mut Cursor::new(&[])),
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::registry::{self, TYPES};
+
 
 /// A number representing a record type dog can’t deal with.
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -14,11 +16,11 @@ pub enum UnknownQtype {
 
 impl UnknownQtype {
 
-    /// Searches the list for an unknown type with the given name, returning a
+    /// Searches the registry for a type with the given name, returning a
     /// `HeardOf` variant if one is found, and `None` otherwise.
     pub fn from_type_name(type_name: &str) -> Option<Self> {
-        let (name, num) = TYPES.iter().find(|t| t.0.eq_ignore_ascii_case(type_name))?;
-        Some(Self::HeardOf(name, *num))
+        let (name, num) = registry::number(TYPES, type_name)?;
+        Some(Self::HeardOf(name, num))
     }
 
     /// Returns the type number behind this unknown type.
@@ -32,9 +34,9 @@ impl UnknownQtype {
 
 impl From<u16> for UnknownQtype {
     fn from(qtype: u16) -> Self {
-        match TYPES.iter().find(|t| t.1 == qtype) {
-            Some(tuple)  => Self::HeardOf(tuple.0, qtype),
-            None         => Self::UnheardOf(qtype),
+        match registry::mnemonic(TYPES, qtype) {
+            Some(name)  => Self::HeardOf(name, qtype),
+            None        => Self::UnheardOf(qtype),
         }
     }
 }
@@ -43,60 +45,79 @@ impl fmt::Display for UnknownQtype {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::HeardOf(name, _)  => write!(f, "{}", name),
-            Self::UnheardOf(num)    => write!(f, "{}", num),
+            Self::UnheardOf(num)    => write!(f, "TYPE{}", num),
         }
     }
 }
 
 
-/// Mapping of record type names to their assigned numbers.
-static TYPES: &[(&str, u16)] = &[
-    ("AFSDB",      18),
-    ("ANY",       255),
-    ("APL",        42),
-    ("AXFR",      252),
-    ("CDNSKEY",    60),
-    ("CDS",        59),
-    ("CERT",       37),
-    ("CSYNC",      62),
-    ("DHCID",      49),
-    ("DLV",     32769),
-    ("DNAME",      39),
-    ("DNSKEEYE",   48),
-    ("DS",         43),
-    ("HIP",        55),
-    ("IPSECKEY",   45),
-    ("IXFR",      251),
-    ("KEY",        25),
-    ("KX",         36),
-    ("NSEC",       47),
-    ("NSEC3",      50),
-    ("NSEC3PARAM", 51),
-    ("OPENPGPKEY", 61),
-    ("RRSIG",      46),
-    ("RP",         17),
-    ("SIG",        24),
-    ("SMIMEA",     53),
-    ("TA",      32768),
-    ("TKEY",      249),
-    ("TSIG",      250),
-    ("URI",       256),
-];
-
-
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::record::RecordType;
+    use crate::registry::UnknownMnemonic;
 
     #[test]
     fn known() {
-        assert_eq!(UnknownQtype::from(46).to_string(),
-                   String::from("RRSIG"));
+        assert_eq!(UnknownQtype::from(18).to_string(),
+                   String::from("AFSDB"));
+    }
+
+    #[test]
+    fn registered_but_unparsed() {
+        assert_eq!(UnknownQtype::from(41).to_string(),
+                   String::from("OPT"));
     }
 
     #[test]
     fn unknown() {
         assert_eq!(UnknownQtype::from(4444).to_string(),
-                   String::from("4444"));
+                   String::from("TYPE4444"));
+    }
+
+    #[test]
+    fn generic_name() {
+        assert_eq!(RecordType::from_type_name("TYPE4444"),
+                   Some(RecordType::Other(UnknownQtype::UnheardOf(4444))));
+    }
+
+    #[test]
+    fn generic_name_of_known_type() {
+        assert_eq!(RecordType::from_type_name("type1"),
+                   Some(RecordType::A));
+    }
+
+    #[test]
+    fn generic_name_without_number() {
+        assert_eq!(RecordType::from_type_name("TYPE"), None);
+        assert_eq!(RecordType::from_type_name("TYPE99999"), None);
+    }
+
+    #[test]
+    fn parsed_types_by_name() {
+        assert_eq!(RecordType::from_type_name("openpgpkey"),
+                   Some(RecordType::OPENPGPKEY));
+        assert_eq!(RecordType::from_type_name("URI"),
+                   Some(RecordType::URI));
+    }
+
+    #[test]
+    fn parsed_types_are_registered() {
+        for type_number in 0 ..= u16::MAX {
+            let record_type = RecordType::from(type_number);
+            if let RecordType::Other(_) = record_type {
+                continue;
+            }
+
+            assert_eq!(record_type.type_number(), type_number);
+            assert_ne!(record_type.to_string(), format!("TYPE{}", type_number));
+            assert_eq!(record_type.to_string().parse(), Ok(record_type));
+        }
+    }
+
+    #[test]
+    fn parse_unknown_name() {
+        assert_eq!("BOGUS".parse::<RecordType>(),
+                   Err(UnknownMnemonic(String::from("BOGUS"))));
     }
 }
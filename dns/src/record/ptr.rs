@@ -17,6 +17,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.3.14](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PTR {
 
     /// The CNAME contained in the record.
@@ -41,6 +42,10 @@ impl Wire for PTR {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: cname_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()> {
+        bytes.write_labels_compressed(&self.cname, compression)
+    }
 }
 
 
@@ -62,6 +67,19 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x03, 0x64, 0x6e, 0x73, 0x06, 0x67, 0x6f, 0x6f, 0x67, 0x6c, 0x65,  // cname
+            0x00,  // cname terminator
+        ];
+
+        let record = PTR::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_record_length() {
         let buf = &[
@@ -0,0 +1,301 @@
+use std::convert::TryFrom;
+
+use log::*;
+
+use crate::record::{Algorithm, RecordType};
+use crate::strings::{Labels, ReadLabels};
+use crate::wire::*;
+
+
+/// A **RRSIG** _(resource record signature)_ record, which holds the
+/// signature over a set of records with the same name, class, and type.
+///
+/// # References
+///
+/// - [RFC 4034 §3](https://tools.ietf.org/html/rfc4034) — Resource Records
+///   for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RRSIG {
+
+    /// The type of the records that this signature covers.
+    pub type_covered: RecordType,
+
+    /// The algorithm used to create the signature.
+    pub algorithm: Algorithm,
+
+    /// The number of labels in the original owner name of the signed
+    /// records, which is used to detect wildcard expansion.
+    pub labels: u8,
+
+    /// The TTL of the signed records as it appears in the authoritative zone.
+    pub original_ttl: u32,
+
+    /// The time after which this signature must not be used, as a number of
+    /// seconds since the Unix epoch.
+    pub signature_expiration: u32,
+
+    /// The time before which this signature must not be used, as a number of
+    /// seconds since the Unix epoch.
+    pub signature_inception: u32,
+
+    /// The key tag of the DNSKEY record that can verify this signature.
+    pub key_tag: u16,
+
+    /// The name of the zone containing the signed records.
+    pub signer_name: Labels,
+
+    /// The cryptographic signature, as unencoded bytes.
+    pub signature: Vec<u8>,
+}
+
+impl Wire for RRSIG {
+    const NAME: &'static str = "RRSIG";
+    const RR_TYPE: u16 = 46;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let type_covered = RecordType::from(c.read_u16::<BigEndian>()?);
+        trace!("Parsed type covered -> {:?}", type_covered);
+
+        let algorithm = Algorithm::from(c.read_u8()?);
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        let labels = c.read_u8()?;
+        trace!("Parsed labels -> {:?}", labels);
+
+        let original_ttl = c.read_u32::<BigEndian>()?;
+        trace!("Parsed original TTL -> {:?}", original_ttl);
+
+        let signature_expiration = c.read_u32::<BigEndian>()?;
+        trace!("Parsed signature expiration -> {:?}", signature_expiration);
+
+        let signature_inception = c.read_u32::<BigEndian>()?;
+        trace!("Parsed signature inception -> {:?}", signature_inception);
+
+        let key_tag = c.read_u16::<BigEndian>()?;
+        trace!("Parsed key tag -> {:?}", key_tag);
+
+        let (signer_name, signer_name_length) = c.read_labels()?;
+        trace!("Parsed signer name -> {:?}", signer_name);
+
+        let length_after_labels = 2 + 1 + 1 + 4 + 4 + 4 + 2 + signer_name_length;
+        if stated_length <= length_after_labels {
+            warn!("Length is incorrect (stated length {:?}, fields plus signer name length {:?})", stated_length, length_after_labels);
+            return Err(WireError::WrongLabelLength { stated_length, length_after_labels });
+        }
+
+        let signature_length = stated_length - length_after_labels;
+        let mut signature = vec![0_u8; usize::from(signature_length)];
+        c.read_exact(&mut signature)?;
+        trace!("Parsed signature -> {:#x?}", signature);
+
+        Ok(Self {
+            type_covered, algorithm, labels, original_ttl, signature_expiration,
+            signature_inception, key_tag, signer_name, signature,
+        })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.type_covered.type_number())?;
+        bytes.write_u8(self.algorithm.number())?;
+        bytes.write_u8(self.labels)?;
+        bytes.write_u32::<BigEndian>(self.original_ttl)?;
+        bytes.write_u32::<BigEndian>(self.signature_expiration)?;
+        bytes.write_u32::<BigEndian>(self.signature_inception)?;
+        bytes.write_u16::<BigEndian>(self.key_tag)?;
+
+        // The signer’s name must not be compressed (RFC 4034 §3.1.7)
+        bytes.write_labels(&self.signer_name)?;
+        bytes.extend_from_slice(&self.signature);
+        Ok(())
+    }
+}
+
+impl RRSIG {
+
+    /// The base64-encoded signature.
+    pub fn base64_signature(&self) -> String {
+        base64::encode(&self.signature)
+    }
+
+    /// The expiration time, formatted as `YYYYMMDDHHmmSS` in UTC.
+    pub fn formatted_expiration(&self) -> String {
+        format_timestamp(self.signature_expiration)
+    }
+
+    /// The inception time, formatted as `YYYYMMDDHHmmSS` in UTC.
+    pub fn formatted_inception(&self) -> String {
+        format_timestamp(self.signature_inception)
+    }
+}
+
+/// Formats a number of seconds since the Unix epoch as a date and time in
+/// the form used by the RRSIG presentation format.
+fn format_timestamp(timestamp: u32) -> String {
+    let days = i64::from(timestamp / 86400);
+    let seconds = timestamp % 86400;
+
+    // Convert the day count into a civil date, using the algorithm from
+    // <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}{:02}{:02}{:02}{:02}{:02}",
+        year, month, day,
+        seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
+
+/// Parses a date and time in the form `YYYYMMDDHHmmSS` in UTC, or a plain
+/// number of seconds since the Unix epoch, into a timestamp. Returns `None`
+/// if the input is not in either form, or is out of range.
+pub(crate) fn parse_timestamp(input: &str) -> Option<u32> {
+    if ! input.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    else if input.len() != 14 {
+        return input.parse().ok();
+    }
+
+    let field = |range: std::ops::Range<usize>| input[range].parse::<i64>().ok();
+    let (year, month, day) = (field(0 .. 4)?, field(4 .. 6)?, field(6 .. 8)?);
+    let (hour, minute, second) = (field(8 .. 10)?, field(10 .. 12)?, field(12 .. 14)?);
+
+    if ! (1 ..= 12).contains(&month) || ! (1 ..= 31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    // The inverse of the conversion above, from
+    // <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u32::try_from(days * 86400 + hour * 3600 + minute * 60 + second).ok()
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+            0x02,  // labels
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x5f, 0xf3, 0xc5, 0x00,  // signature expiration
+            0x5f, 0xcc, 0x38, 0x00,  // signature inception
+            0x30, 0x39,  // key tag
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // signer name
+            0x00,  // signer name terminator
+            0x01, 0x02, 0x03, 0x04,  // signature
+        ];
+
+        assert_eq!(RRSIG::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   RRSIG {
+                       type_covered: RecordType::A,
+                       algorithm: Algorithm::from(13),
+                       labels: 2,
+                       original_ttl: 3600,
+                       signature_expiration: 1609811200,
+                       signature_inception: 1607219200,
+                       key_tag: 12345,
+                       signer_name: Labels::encode("bsago.me").unwrap(),
+                       signature: vec![ 0x01, 0x02, 0x03, 0x04 ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+            0x02,  // labels
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x5f, 0xf3, 0xc5, 0x00,  // signature expiration
+            0x5f, 0xcc, 0x38, 0x00,  // signature inception
+            0x30, 0x39,  // key tag
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // signer name
+            0x00,  // signer name terminator
+            0x01, 0x02, 0x03, 0x04,  // signature
+        ];
+
+        let record = RRSIG::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn no_signature() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+            0x02,  // labels
+            0x00, 0x00, 0x0e, 0x10,  // original TTL
+            0x5f, 0xf3, 0xc5, 0x00,  // signature expiration
+            0x5f, 0xcc, 0x38, 0x00,  // signature inception
+            0x30, 0x39,  // key tag
+            0x00,  // signer name (the root)
+        ];
+
+        assert_eq!(RRSIG::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 19, length_after_labels: 19 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(RRSIG::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00, 0x01,  // type covered
+            0x0d,  // algorithm
+        ];
+
+        assert_eq!(RRSIG::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0),          String::from("19700101000000"));
+        assert_eq!(format_timestamp(951_782_400), String::from("20000229000000"));
+        assert_eq!(format_timestamp(1609811200), String::from("20210105014640"));
+        assert_eq!(format_timestamp(u32::MAX),   String::from("21060207062815"));
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("19700101000000"), Some(0));
+        assert_eq!(parse_timestamp("20000229000000"), Some(951_782_400));
+        assert_eq!(parse_timestamp("20210105014640"), Some(1_609_811_200));
+        assert_eq!(parse_timestamp("21060207062815"), Some(u32::MAX));
+        assert_eq!(parse_timestamp("1609811200"),     Some(1_609_811_200));
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        assert_eq!(parse_timestamp("20211305014640"), None);
+        assert_eq!(parse_timestamp("21060207062816"), None);
+        assert_eq!(parse_timestamp("2021-01-05"),     None);
+    }
+}
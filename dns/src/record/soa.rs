@@ -13,6 +13,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.3.13](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SOA {
 
     /// The primary master name for this server.
@@ -83,6 +84,17 @@ impl Wire for SOA {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()> {
+        bytes.write_labels_compressed(&self.mname, compression)?;
+        bytes.write_labels_compressed(&self.rname, compression)?;
+        bytes.write_u32::<BigEndian>(self.serial)?;
+        bytes.write_u32::<BigEndian>(self.refresh_interval)?;
+        bytes.write_u32::<BigEndian>(self.retry_interval)?;
+        bytes.write_u32::<BigEndian>(self.expire_limit)?;
+        bytes.write_u32::<BigEndian>(self.minimum_ttl)?;
+        Ok(())
+    }
 }
 
 
@@ -117,6 +129,25 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x05, 0x62, 0x73, 0x61, 0x67, 0x6f, 0x02, 0x6d, 0x65,  // mname
+            0x00,  // mname terminator
+            0xc0, 0x00,  // rname, which is the same as mname
+            0x5d, 0x3c, 0xef, 0x02,  // Serial
+            0x00, 0x01, 0x51, 0x80,  // Refresh interval
+            0x00, 0x00, 0x1c, 0x20,  // Retry interval
+            0x00, 0x09, 0x3a, 0x80,  // Expire limit
+            0x00, 0x00, 0x01, 0x2c,  // Minimum TTL
+        ];
+
+        let record = SOA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_record_length() {
         let buf = &[
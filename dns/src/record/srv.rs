@@ -12,6 +12,7 @@ use crate::wire::*;
 /// - [RFC 2782](https://tools.ietf.org/html/rfc2782) — A DNS RR for
 ///   specifying the location of services (February 2000)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SRV {
 
     /// The priority of this host among all that get returned. Lower values
@@ -57,6 +58,15 @@ impl Wire for SRV {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.priority)?;
+        bytes.write_u16::<BigEndian>(self.weight)?;
+        bytes.write_u16::<BigEndian>(self.port)?;
+
+        // The target must not be compressed (RFC 2782)
+        bytes.write_labels(&self.target)
+    }
 }
 
 
@@ -86,6 +96,24 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00, 0x01,  // weight
+            0x92, 0x7c,  // port
+            0x03, 0x61, 0x74, 0x61, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x04,
+            0x6e, 0x6f, 0x64, 0x65, 0x03, 0x64, 0x63, 0x31, 0x06, 0x63, 0x6f,
+            0x6e, 0x73, 0x75, 0x6c,  // target
+            0x00,  // target terminator
+        ];
+
+        let record = SRV::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn incorrect_record_length() {
         let buf = &[
@@ -11,6 +11,7 @@ use crate::wire::*;
 /// - [RFC 4255](https://tools.ietf.org/html/rfc4255) — Using DNS to Securely
 ///   Publish Secure Shell (SSH) Key Fingerprints (January 2006)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SSHFP {
 
     /// The algorithm of the public key. This is a number with several defined
@@ -50,6 +51,13 @@ impl Wire for SSHFP {
 
         Ok(Self { algorithm, fingerprint_type, fingerprint })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u8(self.algorithm)?;
+        bytes.write_u8(self.fingerprint_type)?;
+        bytes.extend_from_slice(&self.fingerprint);
+        Ok(())
+    }
 }
 
 impl SSHFP {
@@ -83,6 +91,20 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x01,  // algorithm
+            0x01,  // fingerprint type
+            0x21, 0x22, 0x23, 0x24, 0x25, 0x26,  // a short fingerprint
+        ];
+
+        let record = SSHFP::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn one_byte_fingerprint() {
         let buf = &[
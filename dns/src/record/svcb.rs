@@ -0,0 +1,745 @@
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use log::*;
+
+use crate::strings::{Labels, ReadLabels, write_character_string};
+use crate::wire::*;
+
+
+/// A **SVCB** _(service binding)_ record, which lists the alternative
+/// endpoints for a service, along with the parameters needed to connect to
+/// each of them.
+///
+/// # References
+///
+/// - [RFC 9460](https://tools.ietf.org/html/rfc9460) — Service Binding and
+///   Parameter Specification via the DNS (SVCB and HTTPS Resource Records)
+///   (November 2023)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SVCB {
+
+    /// The priority of this endpoint. Clients should use the endpoint with
+    /// the lowest priority they can. A priority of zero means the record is
+    /// in _alias mode_, and only gives another name for the service.
+    pub priority: u16,
+
+    /// The domain name of the endpoint, or of the alias. The root name means
+    /// the owner name of the record itself.
+    pub target: Labels,
+
+    /// The parameters used to connect to the endpoint, in increasing order
+    /// of their key numbers.
+    pub parameters: Vec<SvcParam>,
+}
+
+/// A **HTTPS** record, which is a SVCB record with the same format that is
+/// specifically for HTTPS origins, so it can be looked up without an
+/// underscore-prefixed name.
+///
+/// # References
+///
+/// - [RFC 9460 §9](https://tools.ietf.org/html/rfc9460#section-9) — Service
+///   Binding and Parameter Specification via the DNS (SVCB and HTTPS Resource
+///   Records) (November 2023)
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HTTPS {
+
+    /// The priority of this endpoint, with zero meaning alias mode.
+    pub priority: u16,
+
+    /// The domain name of the endpoint, or of the alias.
+    pub target: Labels,
+
+    /// The parameters used to connect to the endpoint.
+    pub parameters: Vec<SvcParam>,
+}
+
+/// One key-value pair in the parameter list of a SVCB or HTTPS record.
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SvcParam {
+
+    /// The keys of the parameters that a client must understand in order to
+    /// use this endpoint.
+    Mandatory(Vec<u16>),
+
+    /// The identifiers of the application protocols supported by the
+    /// endpoint, such as `h2` or `h3`.
+    Alpn(Vec<Box<[u8]>>),
+
+    /// Indicates that the default protocol for the scheme is _not_ supported,
+    /// leaving only the ones in the `alpn` parameter.
+    NoDefaultAlpn,
+
+    /// The port to connect to, instead of the default for the scheme.
+    Port(u16),
+
+    /// IPv4 addresses that clients may use to reach the endpoint.
+    Ipv4Hint(Vec<Ipv4Addr>),
+
+    /// An Encrypted ClientHello configuration list.
+    Ech(Vec<u8>),
+
+    /// IPv6 addresses that clients may use to reach the endpoint.
+    Ipv6Hint(Vec<Ipv6Addr>),
+
+    /// A parameter with a key that we don’t recognise, along with its
+    /// undecoded value.
+    Other(u16, Vec<u8>),
+}
+
+
+impl Wire for SVCB {
+    const NAME: &'static str = "SVCB";
+    const RR_TYPE: u16 = 64;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let (priority, target, parameters) = read_service_binding(stated_length, c)?;
+        Ok(Self { priority, target, parameters })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        write_service_binding(bytes, self.priority, &self.target, &self.parameters)
+    }
+}
+
+impl Wire for HTTPS {
+    const NAME: &'static str = "HTTPS";
+    const RR_TYPE: u16 = 65;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let (priority, target, parameters) = read_service_binding(stated_length, c)?;
+        Ok(Self { priority, target, parameters })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        write_service_binding(bytes, self.priority, &self.target, &self.parameters)
+    }
+}
+
+/// Reads the fields shared by SVCB and HTTPS records.
+fn read_service_binding(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<(u16, Labels, Vec<SvcParam>), WireError> {
+    let priority = c.read_u16::<BigEndian>()?;
+    trace!("Parsed priority -> {:?}", priority);
+
+    let (target, target_length) = c.read_labels()?;
+    trace!("Parsed target -> {:?}", target);
+
+    let mut length_after_labels = 2 + target_length;
+    if stated_length < length_after_labels {
+        warn!("Length is incorrect (stated length {:?}, priority plus target length {:?})", stated_length, length_after_labels);
+        return Err(WireError::WrongLabelLength { stated_length, length_after_labels });
+    }
+
+    let mut parameters = Vec::new();
+    let mut previous_key = None;
+
+    while length_after_labels < stated_length {
+        let key = c.read_u16::<BigEndian>()?;
+        trace!("Parsed parameter key -> {:?}", key);
+
+        if previous_key.map_or(false, |previous| key <= previous) {
+            warn!("Parameter key {:?} is out of order", key);
+            return Err(WireError::InvalidSvcParam(key));
+        }
+
+        let value_length = c.read_u16::<BigEndian>()?;
+        trace!("Parsed parameter length -> {:?}", value_length);
+
+        length_after_labels = length_after_labels.saturating_add(4).saturating_add(value_length);
+        if length_after_labels > stated_length {
+            warn!("Length is incorrect (stated length {:?}, parameters overflow to {:?})", stated_length, length_after_labels);
+            return Err(WireError::WrongLabelLength { stated_length, length_after_labels });
+        }
+
+        let mut value = vec![0_u8; usize::from(value_length)];
+        c.read_exact(&mut value)?;
+
+        let parameter = SvcParam::decode(key, value).ok_or(WireError::InvalidSvcParam(key))?;
+        trace!("Parsed parameter -> {:?}", parameter);
+
+        parameters.push(parameter);
+        previous_key = Some(key);
+    }
+
+    Ok((priority, target, parameters))
+}
+
+/// Writes the fields shared by SVCB and HTTPS records.
+fn write_service_binding(bytes: &mut Vec<u8>, priority: u16, target: &Labels, parameters: &[SvcParam]) -> io::Result<()> {
+    bytes.write_u16::<BigEndian>(priority)?;
+
+    // The target name must not be compressed (RFC 9460 §2.2)
+    bytes.write_labels(target)?;
+
+    for parameter in parameters {
+        bytes.write_u16::<BigEndian>(parameter.key())?;
+        write_with_length(bytes, |bytes| parameter.encode(bytes))?;
+    }
+
+    Ok(())
+}
+
+
+impl SVCB {
+
+    /// Whether this record is in alias mode, rather than service mode.
+    pub fn is_alias(&self) -> bool {
+        self.priority == 0
+    }
+}
+
+impl HTTPS {
+
+    /// Whether this record is in alias mode, rather than service mode.
+    pub fn is_alias(&self) -> bool {
+        self.priority == 0
+    }
+}
+
+
+impl SvcParam {
+
+    /// Decodes the value of a parameter with the given key, returning `None`
+    /// if the value is malformed.
+    fn decode(key: u16, value: Vec<u8>) -> Option<Self> {
+        match key {
+            0 => {
+                if value.is_empty() || value.len() % 2 != 0 {
+                    return None;
+                }
+
+                let keys = value.chunks(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+
+                Some(Self::Mandatory(keys))
+            }
+            1 => {
+                let mut ids = Vec::new();
+                let mut rest = &value[..];
+
+                while let Some((&length, after)) = rest.split_first() {
+                    let length = usize::from(length);
+                    if length == 0 || after.len() < length {
+                        return None;
+                    }
+
+                    ids.push(after[.. length].into());
+                    rest = &after[length ..];
+                }
+
+                if ids.is_empty() { None } else { Some(Self::Alpn(ids)) }
+            }
+            2 => {
+                if value.is_empty() { Some(Self::NoDefaultAlpn) } else { None }
+            }
+            3 => {
+                match value[..] {
+                    [high, low]  => Some(Self::Port(u16::from_be_bytes([high, low]))),
+                    _            => None,
+                }
+            }
+            4 => {
+                if value.is_empty() || value.len() % 4 != 0 {
+                    return None;
+                }
+
+                let addresses = value.chunks(4)
+                    .map(|octets| Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+                    .collect();
+
+                Some(Self::Ipv4Hint(addresses))
+            }
+            5 => {
+                Some(Self::Ech(value))
+            }
+            6 => {
+                if value.is_empty() || value.len() % 16 != 0 {
+                    return None;
+                }
+
+                let addresses = value.chunks(16)
+                    .map(|octets| {
+                        let mut buf = [0_u8; 16];
+                        buf.copy_from_slice(octets);
+                        Ipv6Addr::from(buf)
+                    })
+                    .collect();
+
+                Some(Self::Ipv6Hint(addresses))
+            }
+            _ => {
+                Some(Self::Other(key, value))
+            }
+        }
+    }
+
+    /// Writes the value of this parameter, without its key or length.
+    fn encode(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        match self {
+            Self::Mandatory(keys) => {
+                for key in keys {
+                    bytes.write_u16::<BigEndian>(*key)?;
+                }
+            }
+            Self::Alpn(ids) => {
+                for id in ids {
+                    write_character_string(bytes, id)?;
+                }
+            }
+            Self::NoDefaultAlpn => {}
+            Self::Port(port) => {
+                bytes.write_u16::<BigEndian>(*port)?;
+            }
+            Self::Ipv4Hint(addresses) => {
+                for address in addresses {
+                    bytes.extend_from_slice(&address.octets());
+                }
+            }
+            Self::Ipv6Hint(addresses) => {
+                for address in addresses {
+                    bytes.extend_from_slice(&address.octets());
+                }
+            }
+            Self::Ech(value) | Self::Other(_, value) => {
+                bytes.extend_from_slice(value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the key number of this parameter.
+    pub fn key(&self) -> u16 {
+        match self {
+            Self::Mandatory(_)     => 0,
+            Self::Alpn(_)          => 1,
+            Self::NoDefaultAlpn    => 2,
+            Self::Port(_)          => 3,
+            Self::Ipv4Hint(_)      => 4,
+            Self::Ech(_)           => 5,
+            Self::Ipv6Hint(_)      => 6,
+            Self::Other(key, _)    => *key,
+        }
+    }
+
+    /// Returns the name of the parameter with the given key number, which
+    /// is in the form `keyNNNNN` for keys without a name.
+    pub fn key_name(key: u16) -> String {
+        match key {
+            0  => "mandatory".into(),
+            1  => "alpn".into(),
+            2  => "no-default-alpn".into(),
+            3  => "port".into(),
+            4  => "ipv4hint".into(),
+            5  => "ech".into(),
+            6  => "ipv6hint".into(),
+            _  => format!("key{}", key),
+        }
+    }
+
+    /// Returns the key number of the parameter with the given name, which
+    /// can also be in the form `keyNNNNN`.
+    pub fn key_number(name: &str) -> Option<u16> {
+        match name {
+            "mandatory"        => Some(0),
+            "alpn"             => Some(1),
+            "no-default-alpn"  => Some(2),
+            "port"             => Some(3),
+            "ipv4hint"         => Some(4),
+            "ech"              => Some(5),
+            "ipv6hint"         => Some(6),
+            _ => {
+                let digits = name.strip_prefix("key")?;
+                if digits.is_empty() || ! digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+
+                digits.parse().ok()
+            }
+        }
+    }
+
+    /// Parses a parameter from its presentation form, given its key name and
+    /// its value, if it has one, with any character-string escapes already
+    /// decoded. Returns `None` if the key is unknown or the value is invalid.
+    pub(crate) fn from_presentation(key: &str, value: Option<&[u8]>) -> Option<Self> {
+        let list = || std::str::from_utf8(value?).ok().map(|v| v.split(','));
+
+        match Self::key_number(key)? {
+            0 => {
+                let keys = list()?.map(Self::key_number).collect::<Option<Vec<_>>>()?;
+                Some(Self::Mandatory(keys))
+            }
+            1 => {
+                Some(Self::Alpn(split_escaped_list(value?)?))
+            }
+            2 => {
+                if matches!(value, Some(v) if ! v.is_empty()) {
+                    return None;
+                }
+
+                Some(Self::NoDefaultAlpn)
+            }
+            3 => {
+                let port = std::str::from_utf8(value?).ok()?.parse().ok()?;
+                Some(Self::Port(port))
+            }
+            4 => {
+                let addresses = list()?.map(|a| a.parse().ok()).collect::<Option<Vec<_>>>()?;
+                Some(Self::Ipv4Hint(addresses))
+            }
+            5 => {
+                Some(Self::Ech(base64::decode(value?).ok()?))
+            }
+            6 => {
+                let addresses = list()?.map(|a| a.parse().ok()).collect::<Option<Vec<_>>>()?;
+                Some(Self::Ipv6Hint(addresses))
+            }
+            key => {
+                Some(Self::Other(key, value.unwrap_or_default().to_vec()))
+            }
+        }
+    }
+
+    /// Returns the base64-encoded ECH configuration, if this is an `ech`
+    /// parameter.
+    pub fn base64_ech(&self) -> Option<String> {
+        match self {
+            Self::Ech(config)  => Some(base64::encode(config)),
+            _                  => None,
+        }
+    }
+}
+
+/// Formats the parameter in its presentation form, such as `port=8443` or
+/// `alpn=h2,h3`.
+impl fmt::Display for SvcParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Self::key_name(self.key()))?;
+
+        match self {
+            Self::Mandatory(keys) => {
+                let names = keys.iter().map(|k| Self::key_name(*k)).collect::<Vec<_>>();
+                write!(f, "={}", names.join(","))
+            }
+            Self::Alpn(ids) => {
+                write!(f, "=")?;
+                for (i, id) in ids.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write_escaped(f, id, true)?;
+                }
+                Ok(())
+            }
+            Self::NoDefaultAlpn => {
+                Ok(())
+            }
+            Self::Port(port) => {
+                write!(f, "={}", port)
+            }
+            Self::Ipv4Hint(addresses) => {
+                let addresses = addresses.iter().map(Ipv4Addr::to_string).collect::<Vec<_>>();
+                write!(f, "={}", addresses.join(","))
+            }
+            Self::Ech(config) => {
+                write!(f, "={}", base64::encode(config))
+            }
+            Self::Ipv6Hint(addresses) => {
+                let addresses = addresses.iter().map(Ipv6Addr::to_string).collect::<Vec<_>>();
+                write!(f, "={}", addresses.join(","))
+            }
+            Self::Other(_, value) if value.is_empty() => {
+                Ok(())
+            }
+            Self::Other(_, value) => {
+                write!(f, "=")?;
+                write_escaped(f, value, false)
+            }
+        }
+    }
+}
+
+/// Writes a parameter value as an unquoted character string, escaping
+/// anything that is not printable, and also escaping commas if the value is
+/// one item in a comma-separated list.
+fn write_escaped(f: &mut fmt::Formatter<'_>, bytes: &[u8], in_list: bool) -> fmt::Result {
+    for byte in bytes.iter().copied() {
+        match byte {
+            b',' if in_list         => write!(f, "\\\\,")?,
+            b'\\' if in_list        => write!(f, "\\\\\\\\")?,
+            b'"' | b'\\' | b';'     => write!(f, "\\{}", char::from(byte))?,
+            0x21 ..= 0x7E           => write!(f, "{}", char::from(byte))?,
+            _                       => write!(f, "\\{:03}", byte)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a comma-separated list of values, in which commas and backslashes
+/// that are part of a value are escaped with a backslash. Returns `None` if
+/// any value is empty.
+fn split_escaped_list(bytes: &[u8]) -> Option<Vec<Box<[u8]>>> {
+    let mut values = Vec::new();
+    let mut value = Vec::new();
+    let mut iter = bytes.iter().copied();
+
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'\\' => {
+                value.push(iter.next()?);
+            }
+            b',' => {
+                if value.is_empty() {
+                    return None;
+                }
+
+                values.push(std::mem::take(&mut value).into_boxed_slice());
+            }
+            _ => {
+                value.push(byte);
+            }
+        }
+    }
+
+    if value.is_empty() {
+        return None;
+    }
+
+    values.push(value.into_boxed_slice());
+    Some(values)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_service_mode() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target (the root, meaning the owner name)
+            0x00, 0x01,  // key: alpn
+            0x00, 0x06,  // length
+            0x02, 0x68, 0x32, 0x02, 0x68, 0x33,  // “h2”, “h3”
+            0x00, 0x03,  // key: port
+            0x00, 0x02,  // length
+            0x01, 0xbb,  // 443
+            0x00, 0x04,  // key: ipv4hint
+            0x00, 0x08,  // length
+            0xc0, 0x00, 0x02, 0x01, 0xc0, 0x00, 0x02, 0x02,  // addresses
+        ];
+
+        assert_eq!(HTTPS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   HTTPS {
+                       priority: 1,
+                       target: Labels::root(),
+                       parameters: vec![
+                           SvcParam::Alpn(vec![ Box::new(*b"h2"), Box::new(*b"h3") ]),
+                           SvcParam::Port(443),
+                           SvcParam::Ipv4Hint(vec![ Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2) ]),
+                       ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target (the root, meaning the owner name)
+            0x00, 0x01,  // key: alpn
+            0x00, 0x06,  // length
+            0x02, 0x68, 0x32, 0x02, 0x68, 0x33,  // “h2”, “h3”
+            0x00, 0x03,  // key: port
+            0x00, 0x02,  // length
+            0x01, 0xbb,  // 443
+            0x00, 0x04,  // key: ipv4hint
+            0x00, 0x08,  // length
+            0xc0, 0x00, 0x02, 0x01, 0xc0, 0x00, 0x02, 0x02,  // addresses
+        ];
+
+        let record = HTTPS::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn parses_alias_mode() {
+        let buf = &[
+            0x00, 0x00,  // priority
+            0x03, 0x66, 0x6f, 0x6f, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c,
+            0x65, 0x00,  // target
+        ];
+
+        let svcb = SVCB::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        assert_eq!(svcb,
+                   SVCB {
+                       priority: 0,
+                       target: Labels::encode("foo.example").unwrap(),
+                       parameters: vec![],
+                   });
+        assert!(svcb.is_alias());
+    }
+
+    #[test]
+    fn parses_other_parameters() {
+        let buf = &[
+            0x00, 0x10,  // priority
+            0x00,  // target
+            0x00, 0x00,  // key: mandatory
+            0x00, 0x02,  // length
+            0x00, 0x01,  // alpn
+            0x00, 0x02,  // key: no-default-alpn
+            0x00, 0x00,  // length
+            0x00, 0x06,  // key: ipv6hint
+            0x00, 0x10,  // length
+            0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x01,  // address
+            0x02, 0x9b,  // key: 667
+            0x00, 0x02,  // length
+            0x68, 0x69,  // value
+        ];
+
+        assert_eq!(SVCB::read(buf.len() as _, &mut Cursor::new(buf)).unwrap(),
+                   SVCB {
+                       priority: 16,
+                       target: Labels::root(),
+                       parameters: vec![
+                           SvcParam::Mandatory(vec![ 1 ]),
+                           SvcParam::NoDefaultAlpn,
+                           SvcParam::Ipv6Hint(vec![ "2001:db8::1".parse().unwrap() ]),
+                           SvcParam::Other(667, b"hi".to_vec()),
+                       ],
+                   });
+    }
+
+    #[test]
+    fn keys_out_of_order() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x03,  // key: port
+            0x00, 0x02,  // length
+            0x01, 0xbb,  // 443
+            0x00, 0x01,  // key: alpn
+            0x00, 0x03,  // length
+            0x02, 0x68, 0x32,  // “h2”
+        ];
+
+        assert_eq!(SVCB::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::InvalidSvcParam(1)));
+    }
+
+    #[test]
+    fn wrong_port_length() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x03,  // key: port
+            0x00, 0x01,  // length
+            0x01,  // half a port
+        ];
+
+        assert_eq!(SVCB::read(buf.len() as _, &mut Cursor::new(buf)),
+                   Err(WireError::InvalidSvcParam(3)));
+    }
+
+    #[test]
+    fn parameter_overflows_record() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x03,  // key: port
+            0x00, 0x02,  // length
+            0x01, 0xbb,  // 443
+        ];
+
+        assert_eq!(SVCB::read(8, &mut Cursor::new(buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 8, length_after_labels: 9 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(SVCB::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00, 0x01,  // priority
+            0x00,  // target
+            0x00, 0x01,  // key: alpn
+        ];
+
+        assert_eq!(SVCB::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn presentation_form() {
+        let parameters = vec![
+            SvcParam::Mandatory(vec![ 1, 4 ]),
+            SvcParam::Alpn(vec![ Box::new(*b"h2"), Box::new(*b"a,b") ]),
+            SvcParam::NoDefaultAlpn,
+            SvcParam::Port(8443),
+            SvcParam::Ipv4Hint(vec![ Ipv4Addr::new(192, 0, 2, 1) ]),
+            SvcParam::Ech(vec![ 0x01, 0x02, 0x03 ]),
+            SvcParam::Ipv6Hint(vec![ "2001:db8::1".parse().unwrap(), "2001:db8::2".parse().unwrap() ]),
+            SvcParam::Other(667, b"hi there".to_vec()),
+            SvcParam::Other(668, vec![]),
+        ];
+
+        let strings = parameters.iter().map(SvcParam::to_string).collect::<Vec<_>>();
+        assert_eq!(strings, vec![
+            "mandatory=alpn,ipv4hint",
+            "alpn=h2,a\\\\,b",
+            "no-default-alpn",
+            "port=8443",
+            "ipv4hint=192.0.2.1",
+            "ech=AQID",
+            "ipv6hint=2001:db8::1,2001:db8::2",
+            "key667=hi\\032there",
+            "key668",
+        ]);
+    }
+
+    #[test]
+    fn from_presentation_form() {
+        assert_eq!(SvcParam::from_presentation("mandatory", Some(b"alpn,key65")),
+                   Some(SvcParam::Mandatory(vec![ 1, 65 ])));
+        assert_eq!(SvcParam::from_presentation("alpn", Some(b"h2,a\\,b")),
+                   Some(SvcParam::Alpn(vec![ Box::new(*b"h2"), Box::new(*b"a,b") ])));
+        assert_eq!(SvcParam::from_presentation("no-default-alpn", None),
+                   Some(SvcParam::NoDefaultAlpn));
+        assert_eq!(SvcParam::from_presentation("port", Some(b"8443")),
+                   Some(SvcParam::Port(8443)));
+        assert_eq!(SvcParam::from_presentation("ipv4hint", Some(b"192.0.2.1,192.0.2.2")),
+                   Some(SvcParam::Ipv4Hint(vec![ Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2) ])));
+        assert_eq!(SvcParam::from_presentation("ech", Some(b"AQID")),
+                   Some(SvcParam::Ech(vec![ 0x01, 0x02, 0x03 ])));
+        assert_eq!(SvcParam::from_presentation("key667", Some(b"hi there")),
+                   Some(SvcParam::Other(667, b"hi there".to_vec())));
+    }
+
+    #[test]
+    fn from_invalid_presentation_form() {
+        assert_eq!(SvcParam::from_presentation("port", None), None);
+        assert_eq!(SvcParam::from_presentation("port", Some(b"99999")), None);
+        assert_eq!(SvcParam::from_presentation("alpn", Some(b"h2,,h3")), None);
+        assert_eq!(SvcParam::from_presentation("no-default-alpn", Some(b"yes")), None);
+        assert_eq!(SvcParam::from_presentation("frobnicate", Some(b"1")), None);
+    }
+}
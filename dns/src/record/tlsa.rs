@@ -12,6 +12,7 @@ use crate::wire::*;
 ///   Authentication of Named Entities (DANE) Transport Layer Security
 ///   Protocol: TLSA (August 2012)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TLSA {
 
     /// A number representing the purpose of the certificate.
@@ -58,6 +59,14 @@ impl Wire for TLSA {
 
         Ok(Self { certificate_usage, selector, matching_type, certificate_data })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u8(self.certificate_usage)?;
+        bytes.write_u8(self.selector)?;
+        bytes.write_u8(self.matching_type)?;
+        bytes.extend_from_slice(&self.certificate_data);
+        Ok(())
+    }
 }
 
 impl TLSA {
@@ -93,6 +102,21 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x03,  // certificate usage
+            0x01,  // selector
+            0x01,  // matching type
+            0x05, 0x95, 0x98, 0x11, 0x22, 0x33 // data
+        ];
+
+        let record = TLSA::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn one_byte_certificate() {
         let buf = &[
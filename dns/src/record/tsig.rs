@@ -0,0 +1,280 @@
+use std::convert::TryFrom;
+
+use log::*;
+
+use crate::strings::{Labels, ReadLabels};
+use crate::wire::*;
+
+
+/// A **TSIG** _(transaction signature)_ pseudo-record, which authenticates
+/// a whole message using a secret key shared between the client and the
+/// server.
+///
+/// # Pseudo-record?
+///
+/// Like the OPT record, a TSIG record does not hold data about the domain
+/// name it’s attached to: its owner name is the name of the key, and it must
+/// be the very last record in the Additional section. It’s added to a
+/// message after the rest of it has been written, as the MAC inside it covers
+/// all the bytes that come before it. This means the parser takes it out of
+/// the message and stores it in a `Signature` rather than with the other
+/// records.
+///
+/// # References
+///
+/// - [RFC 8945](https://tools.ietf.org/html/rfc8945) — Secret Key Transaction
+///   Authentication for DNS (TSIG) (November 2020)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TSIG {
+
+    /// The name of the MAC algorithm, such as `hmac-sha256`.
+    pub algorithm: Labels,
+
+    /// The time the message was signed, as a number of seconds since the
+    /// Unix epoch. This is a 48-bit field on the wire.
+    pub time_signed: u64,
+
+    /// The number of seconds of difference from `time_signed` that is
+    /// allowed when checking the signature.
+    pub fudge: u16,
+
+    /// The message authentication code, as unencoded bytes.
+    pub mac: Vec<u8>,
+
+    /// The transaction ID of the message when it was first signed.
+    pub original_id: u16,
+
+    /// The extended error code, which is non-zero when a server could not
+    /// verify the signature on the request.
+    pub error: u16,
+
+    /// Extra data sent with some errors, such as the server’s clock time
+    /// with `BADTIME`.
+    pub other_data: Vec<u8>,
+}
+
+impl TSIG {
+
+    /// The error code for when the MAC did not match.
+    pub const BADSIG: u16 = 16;
+
+    /// The error code for when the server does not know the key or the
+    /// algorithm.
+    pub const BADKEY: u16 = 17;
+
+    /// The error code for when the time signed is too far from the server’s
+    /// own clock.
+    pub const BADTIME: u16 = 18;
+
+    /// The error code for when the MAC was truncated too much.
+    pub const BADTRUNC: u16 = 22;
+
+    /// The class that a TSIG record always has, which is `ANY`.
+    pub const CLASS: u16 = 255;
+
+    /// Writes out the TSIG variables, which get added to the end of the
+    /// message when computing its MAC. These are the fields of the record,
+    /// apart from the MAC and the original ID, with the names written in
+    /// their canonical form.
+    ///
+    /// # References
+    ///
+    /// - [RFC 8945 §4.3.3](https://tools.ietf.org/html/rfc8945#section-4.3.3)
+    ///   — Secret Key Transaction Authentication for DNS (TSIG) (November 2020)
+    pub fn variables(&self, key_name: &Labels) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(64);
+
+        bytes.write_labels(&key_name.to_lowercase())?;
+        bytes.write_u16::<BigEndian>(Self::CLASS)?;
+        bytes.write_u32::<BigEndian>(0)?;  // TTL
+        bytes.write_labels(&self.algorithm.to_lowercase())?;
+        bytes.write_u48::<BigEndian>(self.time_signed)?;
+        bytes.write_u16::<BigEndian>(self.fudge)?;
+        bytes.write_u16::<BigEndian>(self.error)?;
+        write_with_length(&mut bytes, |bytes| {
+            bytes.extend_from_slice(&self.other_data);
+            Ok(())
+        })?;
+
+        Ok(bytes)
+    }
+
+    /// Writes out just the timer fields of the TSIG variables. In a stream
+    /// of messages, such as a zone transfer, these are all that get added
+    /// to the end of every message after the first when computing its MAC.
+    ///
+    /// # References
+    ///
+    /// - [RFC 8945 §5.3.1](https://tools.ietf.org/html/rfc8945#section-5.3.1)
+    ///   — Secret Key Transaction Authentication for DNS (TSIG) (November 2020)
+    pub fn timers(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(8);
+        bytes.write_u48::<BigEndian>(self.time_signed)?;
+        bytes.write_u16::<BigEndian>(self.fudge)?;
+        Ok(bytes)
+    }
+}
+
+impl Wire for TSIG {
+    const NAME: &'static str = "TSIG";
+    const RR_TYPE: u16 = 250;
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(stated_length: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+        let (algorithm, algorithm_length) = c.read_labels()?;
+        trace!("Parsed algorithm -> {:?}", algorithm);
+
+        let time_signed = c.read_u48::<BigEndian>()?;
+        trace!("Parsed time signed -> {:?}", time_signed);
+
+        let fudge = c.read_u16::<BigEndian>()?;
+        trace!("Parsed fudge -> {:?}", fudge);
+
+        let mac_length = c.read_u16::<BigEndian>()?;
+        let mut mac = vec![0_u8; usize::from(mac_length)];
+        c.read_exact(&mut mac)?;
+        trace!("Parsed MAC -> {:#x?}", mac);
+
+        let original_id = c.read_u16::<BigEndian>()?;
+        trace!("Parsed original ID -> {:?}", original_id);
+
+        let error = c.read_u16::<BigEndian>()?;
+        trace!("Parsed error -> {:?}", error);
+
+        let other_length = c.read_u16::<BigEndian>()?;
+        let mut other_data = vec![0_u8; usize::from(other_length)];
+        c.read_exact(&mut other_data)?;
+        trace!("Parsed other data -> {:#x?}", other_data);
+
+        let length_after_labels = u16::try_from(usize::from(algorithm_length) + 6 + 2 + 2 + mac.len() + 2 + 2 + 2 + other_data.len())
+            .unwrap_or(u16::MAX);
+
+        if stated_length == length_after_labels {
+            Ok(Self { algorithm, time_signed, fudge, mac, original_id, error, other_data })
+        }
+        else {
+            warn!("Length is incorrect (stated length {:?}, fields plus algorithm length {:?})", stated_length, length_after_labels);
+            Err(WireError::WrongLabelLength { stated_length, length_after_labels })
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        // The algorithm name must not be compressed (RFC 8945 §4.2)
+        bytes.write_labels(&self.algorithm)?;
+        bytes.write_u48::<BigEndian>(self.time_signed)?;
+        bytes.write_u16::<BigEndian>(self.fudge)?;
+        write_with_length(bytes, |bytes| {
+            bytes.extend_from_slice(&self.mac);
+            Ok(())
+        })?;
+        bytes.write_u16::<BigEndian>(self.original_id)?;
+        bytes.write_u16::<BigEndian>(self.error)?;
+        write_with_length(bytes, |bytes| {
+            bytes.extend_from_slice(&self.other_data);
+            Ok(())
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn buf() -> Vec<u8> {
+        vec![
+            0x0b, 0x68, 0x6d, 0x61, 0x63, 0x2d, 0x73, 0x68, 0x61, 0x32, 0x35, 0x36,  // algorithm
+            0x00,  // algorithm terminator
+            0x00, 0x00, 0x60, 0x00, 0x00, 0x00,  // time signed
+            0x01, 0x2c,  // fudge
+            0x00, 0x04,  // MAC length
+            0x01, 0x02, 0x03, 0x04,  // MAC
+            0xab, 0xcd,  // original ID
+            0x00, 0x12,  // error
+            0x00, 0x02,  // other length
+            0x05, 0x06,  // other data
+        ]
+    }
+
+    #[test]
+    fn parses() {
+        let buf = buf();
+
+        assert_eq!(TSIG::read(35, &mut Cursor::new(&buf)).unwrap(),
+                   TSIG {
+                       algorithm: Labels::encode("hmac-sha256").unwrap(),
+                       time_signed: 0x6000_0000,
+                       fudge: 300,
+                       mac: vec![ 0x01, 0x02, 0x03, 0x04 ],
+                       original_id: 0xabcd,
+                       error: TSIG::BADTIME,
+                       other_data: vec![ 0x05, 0x06 ],
+                   });
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = buf();
+
+        let record = TSIG::read(35, &mut Cursor::new(&buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn variables() {
+        let record = TSIG::read(35, &mut Cursor::new(&buf())).unwrap();
+
+        assert_eq!(record.variables(&Labels::encode("Key").unwrap()).unwrap(),
+                   vec![
+                       0x03, 0x6b, 0x65, 0x79, 0x00,  // key name, in lowercase
+                       0x00, 0xff,  // class
+                       0x00, 0x00, 0x00, 0x00,  // TTL
+                       0x0b, 0x68, 0x6d, 0x61, 0x63, 0x2d, 0x73, 0x68, 0x61, 0x32, 0x35, 0x36, 0x00,  // algorithm
+                       0x00, 0x00, 0x60, 0x00, 0x00, 0x00,  // time signed
+                       0x01, 0x2c,  // fudge
+                       0x00, 0x12,  // error
+                       0x00, 0x02,  // other length
+                       0x05, 0x06,  // other data
+                   ]);
+    }
+
+    #[test]
+    fn timers() {
+        let record = TSIG::read(35, &mut Cursor::new(&buf())).unwrap();
+
+        assert_eq!(record.timers().unwrap(),
+                   vec![
+                       0x00, 0x00, 0x60, 0x00, 0x00, 0x00,  // time signed
+                       0x01, 0x2c,  // fudge
+                   ]);
+    }
+
+    #[test]
+    fn incorrect_record_length() {
+        let buf = buf();
+
+        assert_eq!(TSIG::read(40, &mut Cursor::new(&buf)),
+                   Err(WireError::WrongLabelLength { stated_length: 40, length_after_labels: 35 }));
+    }
+
+    #[test]
+    fn record_empty() {
+        assert_eq!(TSIG::read(0, &mut Cursor::new(&[])),
+                   Err(WireError::IO));
+    }
+
+    #[test]
+    fn buffer_ends_abruptly() {
+        let buf = &[
+            0x00,  // algorithm
+            0x00, 0x00, 0x60,  // half a time signed
+        ];
+
+        assert_eq!(TSIG::read(23, &mut Cursor::new(buf)),
+                   Err(WireError::IO));
+    }
+}
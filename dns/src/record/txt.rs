@@ -1,5 +1,6 @@
 use log::*;
 
+use crate::strings::write_character_string;
 use crate::wire::*;
 
 
@@ -15,6 +16,7 @@ use crate::wire::*;
 /// - [RFC 1035 §3.3.14](https://tools.ietf.org/html/rfc1035) — Domain Names,
 ///   Implementation and Specification (November 1987)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TXT {
 
     /// The messages contained in the record.
@@ -68,6 +70,26 @@ impl Wire for TXT {
             Err(WireError::WrongLabelLength { stated_length, length_after_labels: total_length })
         }
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        for message in &self.messages {
+            // Messages longer than 255 bytes are split into slices, and a
+            // slice of exactly 255 bytes means another one follows it, so a
+            // message that fills its last slice gets an empty one after it
+            let mut last_length = 0;
+
+            for slice in message.chunks(255) {
+                write_character_string(bytes, slice)?;
+                last_length = slice.len();
+            }
+
+            if last_length == 0 || last_length == 255 {
+                bytes.write_u8(0)?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 
@@ -89,6 +111,19 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x06,  // message chunk length
+            0x74, 0x78, 0x74, 0x20, 0x6d, 0x65,  // message chunk
+        ];
+
+        let record = TXT::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn parses_two_iterations() {
         let buf = &[
@@ -202,6 +237,32 @@ mod test {
                    });
     }
 
+    #[test]
+    fn writes_full_slice() {
+        let record = TXT {
+            messages: vec![ vec![ b'C'; 255 ].into_boxed_slice() ],
+        };
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+
+        let mut expected = vec![ 0xFF ];  // message chunk length
+        expected.extend(vec![ b'C'; 255 ]);  // message chunk
+        expected.push(0x00);  // empty chunk to end the message
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn writes_empty_message() {
+        let record = TXT {
+            messages: vec![ Box::new(*b"") ],
+        };
+
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, vec![ 0x00 ]);
+    }
+
     #[test]
     fn length_too_short() {
         let buf = &[
@@ -13,6 +13,7 @@ use crate::wire::*;
 /// - [RFC 3986](https://tools.ietf.org/html/rfc3986) — Uniform Resource
 ///   Identifier (URI): Generic Syntax (January 2005)
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct URI {
 
     /// The priority of the URI. Clients are supposed to contact the URI with
@@ -53,6 +54,13 @@ impl Wire for URI {
 
         Ok(Self { priority, weight, target })
     }
+
+    fn write(&self, bytes: &mut Vec<u8>, _compression: &mut Compression) -> io::Result<()> {
+        bytes.write_u16::<BigEndian>(self.priority)?;
+        bytes.write_u16::<BigEndian>(self.weight)?;
+        bytes.extend_from_slice(&self.target);
+        Ok(())
+    }
 }
 
 
@@ -78,6 +86,21 @@ mod test {
                    });
     }
 
+    #[test]
+    fn round_trip() {
+        let buf = &[
+            0x00, 0x0A,  // priority
+            0x00, 0x10,  // weight
+            0x68, 0x74, 0x74, 0x70, 0x73, 0x3a, 0x2f, 0x2f, 0x72, 0x66, 0x63,
+            0x73, 0x2e, 0x69, 0x6f, 0x2f,  // uri
+        ];
+
+        let record = URI::read(buf.len() as _, &mut Cursor::new(buf)).unwrap();
+        let mut bytes = Vec::new();
+        record.write(&mut bytes, &mut Compression::new()).unwrap();
+        assert_eq!(bytes, buf);
+    }
+
     #[test]
     fn one_byte_of_uri() {
         let buf = &[
@@ -0,0 +1,84 @@
+//! The names of the numbers used in DNS packets, which get generated at
+//! build time from the IANA registries listed in `registry.txt`.
+
+use std::fmt;
+
+
+include!(concat!(env!("OUT_DIR"), "/registry.rs"));
+
+
+/// Returns the mnemonic of the given number in a registry table, or `None`
+/// if the number has not been given one.
+pub(crate) fn mnemonic<N: PartialEq + Copy>(table: &[(&'static str, N)], number: N) -> Option<&'static str> {
+    table.iter().find(|entry| entry.1 == number).map(|entry| entry.0)
+}
+
+/// Returns the mnemonic and number of the entry in a registry table with
+/// the given mnemonic, or `None` if no entry has it. Matches mnemonics
+/// case-insensitively.
+pub(crate) fn number<N: Copy>(table: &[(&'static str, N)], mnemonic: &str) -> Option<(&'static str, N)> {
+    table.iter().find(|entry| entry.0.eq_ignore_ascii_case(mnemonic)).copied()
+}
+
+
+/// The error returned when text does not name anything in a registry.
+#[derive(PartialEq, Debug, Clone)]
+pub struct UnknownMnemonic(pub String);
+
+impl fmt::Display for UnknownMnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown mnemonic {:?}", self.0)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{QClass, ErrorCode};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn type_mnemonic() {
+        assert_eq!(mnemonic(TYPES, 29), Some("LOC"));
+        assert_eq!(mnemonic(TYPES, 54), None);
+    }
+
+    #[test]
+    fn type_number() {
+        assert_eq!(number(TYPES, "nsap-ptr"), Some(("NSAP-PTR", 23)));
+        assert_eq!(number(TYPES, "TYPE23"), None);
+    }
+
+    #[test]
+    fn algorithm_table() {
+        assert_eq!(mnemonic(ALGORITHMS, 15), Some("ED25519"));
+    }
+
+    #[test]
+    fn mnemonics_with_spaces() {
+        assert_eq!(mnemonic(EXTENDED_ERRORS, 6), Some("DNSSEC Bogus"));
+        assert_eq!(number(OPTIONS, "extended dns error"), Some(("Extended DNS Error", 15)));
+    }
+
+    #[test]
+    fn class_names() {
+        assert_eq!(QClass::CH.to_string(), "CH");
+        assert_eq!(QClass::Other(254).to_string(), "NONE");
+        assert_eq!(QClass::Other(99).to_string(), "CLASS99");
+    }
+
+    #[test]
+    fn parse_classes() {
+        assert_eq!("none".parse(), Ok(QClass::Other(254)));
+        assert_eq!("CLASS4".parse(), Ok(QClass::HS));
+        assert_eq!("CLASS".parse::<QClass>(), Err(UnknownMnemonic(String::from("CLASS"))));
+    }
+
+    #[test]
+    fn rcode_names() {
+        assert_eq!(ErrorCode::NXDomain.to_string(), "NXDOMAIN");
+        assert_eq!(ErrorCode::Other(11).to_string(), "DSOTYPENI");
+        assert_eq!(ErrorCode::Private(3841).to_string(), "3841");
+    }
+}
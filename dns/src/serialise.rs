@@ -0,0 +1,58 @@
+//! Serialising and deserialising DNS types with serde, for when the
+//! `with_serde` feature is enabled. The layout is documented in the
+//! crate’s own documentation.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::record::{RecordType, UnknownQtype, Algorithm};
+use crate::strings::Labels;
+use crate::types::{QClass, Opcode, ErrorCode};
+
+
+impl Serialize for Labels {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.len() == 0 {
+            serializer.serialize_str(".")
+        }
+        else {
+            serializer.collect_str(self)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Labels {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        Self::encode(&input).map_err(|label| de::Error::custom(format_args!("invalid label {:?}", label)))
+    }
+}
+
+
+/// Implements serialisation for a type that gets written as the number that
+/// it stands for, using the given functions to convert it to and from that
+/// number. Numbers that do not convert into a value fail to deserialise.
+macro_rules! as_number {
+    ($type:ty, $number:ty, $to_number:expr, $from_number:expr) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let to_number: fn($type) -> $number = $to_number;
+                to_number(*self).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let from_number: fn($number) -> Option<$type> = $from_number;
+                let number = <$number>::deserialize(deserializer)?;
+                from_number(number).ok_or_else(|| de::Error::custom(format_args!("invalid {} {}", stringify!($type), number)))
+            }
+        }
+    };
+}
+
+as_number!(RecordType,    u16,  RecordType::type_number,    |number| Some(RecordType::from(number)));
+as_number!(UnknownQtype,  u16,  UnknownQtype::type_number,  |number| Some(UnknownQtype::from(number)));
+as_number!(QClass,        u16,  QClass::to_u16,             |number| Some(QClass::from_u16(number)));
+as_number!(Opcode,        u8,   Opcode::to_bits,            |number| Some(number).filter(|n| *n <= 15).map(Opcode::from_bits));
+as_number!(ErrorCode,     u16,  ErrorCode::to_bits,         ErrorCode::from_bits);
+as_number!(Algorithm,     u8,   Algorithm::number,          |number| Some(Algorithm::from(number)));
@@ -1,23 +1,37 @@
-//! Reading strings from the DNS wire protocol.
+//! Reading and writing strings from the DNS wire protocol.
 
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fmt;
-use std::io::{self, Write};
+use std::io;
 
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use log::*;
 
 use crate::wire::*;
 
 
 /// Domain names in the DNS protocol are encoded as **Labels**, which are
-/// segments of ASCII characters prefixed by their length. When written out,
-/// each segment is followed by a dot.
+/// segments of bytes prefixed by their length. When written out, each
+/// segment is followed by a dot.
 ///
-/// The maximum length of a segment is 255 characters.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
+/// The bytes of each segment are kept exactly as they were given or read,
+/// including their case and any bytes that are not printable, so a name
+/// always gets written back out the way it came in. Comparing two names
+/// ignores the case of ASCII letters, though.
+///
+/// The maximum length of a segment is 63 bytes.
+///
+/// # References
+///
+/// - [RFC 1035 §5.1](https://tools.ietf.org/html/rfc1035) — Domain Names,
+///   Implementation and Specification (November 1987)
+/// - [RFC 4343](https://tools.ietf.org/html/rfc4343) — Domain Name System
+///   (DNS) Case Insensitivity Clarification (January 2006)
+#[derive(Clone)]
 pub struct Labels {
-    segments: Vec<(u8, String)>,
+    segments: Vec<Box<[u8]>>,
 }
 
 #[cfg(feature = "with_idna")]
@@ -31,6 +45,34 @@ fn label_to_ascii(label: &str) -> Result<String, ()> {
     Ok(label.to_owned())
 }
 
+#[cfg(feature = "with_idna")]
+fn label_to_unicode(segment: &[u8]) -> Option<String> {
+    // A dot would make the IDNA library treat the segment as two labels
+    if ! segment.get(.. 4)?.eq_ignore_ascii_case(b"xn--") || segment.contains(&b'.') {
+        return None;
+    }
+
+    let label = std::str::from_utf8(segment).ok()?;
+    let flags = unic_idna::Flags{use_std3_ascii_rules: false, transitional_processing: false, verify_dns_length: false};
+    match unic_idna::to_unicode(label, flags) {
+        (unicode, Ok(())) => {
+            Some(unicode)
+        }
+        (_, Err(e)) => {
+            warn!("Could not decode label {:?}: {:?}", label, e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "with_idna"))]
+fn label_to_unicode(_segment: &[u8]) -> Option<String> {
+    None
+}
+
+/// The maximum length of one segment of a domain name.
+const MAX_SEGMENT_LENGTH: usize = 63;
+
 impl Labels {
 
     /// Creates a new empty set of labels, which represent the root of the DNS
@@ -39,36 +81,71 @@ impl Labels {
         Self { segments: Vec::new() }
     }
 
-    /// Encodes the given input string as labels. If any segment is too long,
-    /// returns that segment as an error.
+    /// Encodes the given input string as labels. The input is in
+    /// presentation format, so a dot or a backslash can be escaped with a
+    /// backslash to make it part of a segment, and any byte can be written
+    /// as a backslash followed by three decimal digits. Segments containing
+    /// characters outside of ASCII are converted with IDNA; the rest are
+    /// kept byte-for-byte. If any segment is invalid or too long, returns
+    /// that segment as an error.
     pub fn encode(input: &str) -> Result<Self, &str> {
         let mut segments = Vec::new();
 
-        for label in input.split('.') {
+        for label in split_unescaped(input) {
             if label.is_empty() {
                 continue;
             }
 
-            let label_idn = label_to_ascii(label)
+            let segment = if label.is_ascii() {
+                unescape(label).ok_or_else(|| {
+                    warn!("Could not encode label {:?}: invalid escape", label);
+                    label
+                })?
+            }
+            else {
+                label_to_ascii(label)
                     .map_err(|e| {
                         warn!("Could not encode label {:?}: {:?}", label, e);
                         label
-                    })?;
+                    })?
+                    .into_bytes()
+            };
 
-            match u8::try_from(label_idn.len()) {
-                Ok(length) => {
-                    segments.push((length, label_idn));
-                }
-                Err(e) => {
-                    warn!("Could not encode label {:?}: {}", label, e);
-                    return Err(label);
-                }
+            if segment.len() > MAX_SEGMENT_LENGTH {
+                warn!("Could not encode label {:?}: longer than {} bytes", label, MAX_SEGMENT_LENGTH);
+                return Err(label);
+            }
+
+            segments.push(segment.into_boxed_slice());
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Creates labels from segments that have already been split apart and
+    /// unescaped, without encoding them any further. If any segment is empty
+    /// or longer than 63 bytes, returns that segment as an error.
+    pub(crate) fn from_segments(input: Vec<Vec<u8>>) -> Result<Self, Vec<u8>> {
+        let mut segments = Vec::with_capacity(input.len());
+
+        for segment in input {
+            if segment.is_empty() || segment.len() > MAX_SEGMENT_LENGTH {
+                warn!("Invalid label {:?}", String::from_utf8_lossy(&segment));
+                return Err(segment);
             }
+
+            segments.push(segment.into_boxed_slice());
         }
 
         Ok(Self { segments })
     }
 
+    /// Adds a segment that has been read out of a message as raw bytes to
+    /// the end.
+    pub(crate) fn push_wire_segment(&mut self, segment: &[u8]) {
+        self.segments.push(segment.into());
+    }
+
     /// Returns the number of segments.
     pub fn len(&self) -> usize {
         self.segments.len()
@@ -80,18 +157,210 @@ impl Labels {
         segments.extend_from_slice(&other.segments);
         Self { segments }
     }
+
+    /// Returns an iterator over the bytes of each segment, from the leftmost
+    /// (the most specific) to the rightmost.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item=&[u8]> + ExactSizeIterator {
+        self.segments.iter().map(|segment| &**segment)
+    }
+
+    /// Returns the name of the parent domain, or `None` if this is the root.
+    pub fn parent(&self) -> Option<Self> {
+        if self.segments.is_empty() {
+            None
+        }
+        else {
+            Some(Self { segments: self.segments[1..].to_vec() })
+        }
+    }
+
+    /// Returns the rightmost `count` segments of this name. If there are
+    /// fewer segments than that, the whole name is returned.
+    pub fn suffix(&self, count: usize) -> Self {
+        let start = self.segments.len().saturating_sub(count);
+        Self { segments: self.segments[start..].to_vec() }
+    }
+
+    /// Whether this name is equal to, or a subdomain of, the given name,
+    /// ignoring the case of ASCII letters.
+    pub fn is_within(&self, other: &Self) -> bool {
+        self.segments.len() >= other.segments.len()
+            && self.segments.iter().rev()
+                   .zip(other.segments.iter().rev())
+                   .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+
+    /// Formats this name the same way as `Display`, except that segments
+    /// that are IDNA A-labels, starting with `xn--`, get decoded back into
+    /// the Unicode U-labels they stand for. Segments that cannot be decoded
+    /// are left as they are.
+    pub fn to_unicode(&self) -> String {
+        let mut output = String::new();
+
+        for label in self.unicode_segments() {
+            output.push_str(&label);
+            output.push('.');
+        }
+
+        output
+    }
+
+    /// Returns each segment of this name formatted the same way as in
+    /// `to_unicode`. A dot inside a segment stays escaped, so the segments
+    /// can be told apart without having to split the name on its dots.
+    pub fn unicode_segments(&self) -> impl Iterator<Item=String> + '_ {
+        self.segments.iter().map(|segment| {
+            if let Some(label) = label_to_unicode(segment) {
+                label
+            }
+            else {
+                let mut output = String::new();
+
+                // Writing to a string cannot fail
+                let _ = write_escaped_segment(&mut output, segment);
+                output
+            }
+        })
+    }
+
+    /// Returns a copy of this name with every ASCII letter converted to
+    /// lowercase, which is the canonical form used by DNSSEC.
+    pub fn to_lowercase(&self) -> Self {
+        let segments = self.segments.iter()
+            .map(|segment| segment.to_ascii_lowercase().into_boxed_slice())
+            .collect();
+
+        Self { segments }
+    }
+}
+
+impl PartialEq for Labels {
+    fn eq(&self, other: &Self) -> bool {
+        self.segments.len() == other.segments.len()
+            && self.segments.iter()
+                   .zip(other.segments.iter())
+                   .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+}
+
+impl Eq for Labels {}
+
+impl PartialOrd for Labels {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Labels {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (a, b) in self.segments.iter().zip(other.segments.iter()) {
+            let lowercase = |segment: &[u8]| segment.iter().map(u8::to_ascii_lowercase).collect::<Vec<_>>();
+
+            let ordering = lowercase(a).cmp(&lowercase(b));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+
+        self.segments.len().cmp(&other.segments.len())
+    }
 }
 
 impl fmt::Display for Labels {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (_, segment) in &self.segments {
-            write!(f, "{}.", segment)?;
+        for segment in &self.segments {
+            write_escaped_segment(f, segment)?;
+            f.write_str(".")?;
         }
 
         Ok(())
     }
 }
 
+impl fmt::Debug for Labels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Labels({:?})", self.to_string())
+    }
+}
+
+/// Writes one segment of a domain name in presentation format, escaping the
+/// characters that would otherwise be read as syntax with a backslash, and
+/// writing any bytes that are not printable ASCII as three decimal digits.
+pub(crate) fn write_escaped_segment(f: &mut impl fmt::Write, segment: &[u8]) -> fmt::Result {
+    for &byte in segment {
+        match byte {
+            b'.' | b'\\' | b'"' | b'(' | b')' | b';' | b'@' | b'$' => {
+                write!(f, "\\{}", char::from(byte))?;
+            }
+            0x21 ..= 0x7E => {
+                f.write_char(char::from(byte))?;
+            }
+            _ => {
+                write!(f, "\\{:03}", byte)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a name in presentation format at each dot that has not been
+/// escaped with a backslash.
+fn split_unescaped(input: &str) -> Vec<&str> {
+    let mut labels = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (index, byte) in input.bytes().enumerate() {
+        if escaped {
+            escaped = false;
+        }
+        else if byte == b'\\' {
+            escaped = true;
+        }
+        else if byte == b'.' {
+            labels.push(&input[start .. index]);
+            start = index + 1;
+        }
+    }
+
+    labels.push(&input[start ..]);
+    labels
+}
+
+/// Decodes the backslash escapes in a name segment or a character-string
+/// in presentation format, returning `None` if any of them is invalid.
+pub(crate) fn unescape(text: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes();
+
+    while let Some(byte) = bytes.next() {
+        if byte == b'\\' {
+            output.push(unescape_one(&mut bytes)?);
+        }
+        else {
+            output.push(byte);
+        }
+    }
+
+    Some(output)
+}
+
+/// Decodes the character after a backslash, which is either a single
+/// character standing for itself, or three decimal digits giving the value
+/// of a byte.
+pub(crate) fn unescape_one(bytes: &mut std::str::Bytes<'_>) -> Option<u8> {
+    let first = bytes.next()?;
+    if ! first.is_ascii_digit() {
+        return Some(first);
+    }
+
+    let second = bytes.next().filter(u8::is_ascii_digit)?;
+    let third = bytes.next().filter(u8::is_ascii_digit)?;
+    let value = u16::from(first - b'0') * 100 + u16::from(second - b'0') * 10 + u16::from(third - b'0');
+    u8::try_from(value).ok()
+}
+
 /// An extension for `Cursor` that enables reading compressed domain names
 /// from DNS packets.
 pub(crate) trait ReadLabels {
@@ -109,7 +378,7 @@ impl ReadLabels for Cursor<&[u8]> {
 }
 
 
-/// An extension for `Write` that enables writing domain names.
+/// An extension for `Vec<u8>` that enables writing domain names.
 pub(crate) trait WriteLabels {
 
     /// Write a domain name.
@@ -121,16 +390,52 @@ pub(crate) trait WriteLabels {
     /// So “dns.lookup.dog” would be encoded as:
     /// “3, dns, 6, lookup, 3, dog, 0”.
     fn write_labels(&mut self, input: &Labels) -> io::Result<()>;
+
+    /// Write a domain name, replacing the longest suffix of it that has
+    /// already been written with a pointer back to that suffix, and
+    /// remembering where its other suffixes were written so later names can
+    /// point back to them.
+    ///
+    /// The vector must hold the message from its very first byte, as the
+    /// pointers are offsets from the start of the message.
+    fn write_labels_compressed(&mut self, input: &Labels, compression: &mut Compression) -> io::Result<()>;
 }
 
-impl<W: Write> WriteLabels for W {
+impl WriteLabels for Vec<u8> {
     fn write_labels(&mut self, input: &Labels) -> io::Result<()> {
-        for (length, label) in &input.segments {
-            self.write_u8(*length)?;
+        for segment in &input.segments {
+            self.write_u8(segment_length(segment))?;
+            self.extend_from_slice(segment);
+        }
 
-            for b in label.as_bytes() {
-                self.write_u8(*b)?;
+        self.write_u8(0)?;  // terminate the string
+        Ok(())
+    }
+
+    fn write_labels_compressed(&mut self, input: &Labels, compression: &mut Compression) -> io::Result<()> {
+        if ! compression.enabled {
+            return self.write_labels(input);
+        }
+
+        for (index, segment) in input.segments.iter().enumerate() {
+            let suffix = &input.segments[index ..];
+
+            if let Some(offset) = compression.offsets.get(suffix) {
+                trace!("Pointing {:?} back to offset {}", Labels { segments: suffix.to_vec() }, offset);
+                self.write_u16::<BigEndian>(0b_1100_0000_0000_0000 | offset)?;
+                return Ok(());
             }
+
+            // Pointers only have fourteen bits for the offset
+            match u16::try_from(self.len()) {
+                Ok(offset) if offset < 0b_0100_0000_0000_0000 => {
+                    compression.offsets.insert(suffix.to_vec(), offset);
+                }
+                _ => {}
+            }
+
+            self.write_u8(segment_length(segment))?;
+            self.extend_from_slice(segment);
         }
 
         self.write_u8(0)?;  // terminate the string
@@ -139,7 +444,66 @@ impl<W: Write> WriteLabels for W {
 }
 
 
-const RECURSION_LIMIT: usize = 8;
+/// The positions in a message where domain names have already been written,
+/// so that names written later can point back to them instead of being
+/// written out in full.
+///
+/// # References
+///
+/// - [RFC 1035 §4.1.4](https://tools.ietf.org/html/rfc1035) — Domain Names,
+///   Implementation and Specification (November 1987)
+#[derive(PartialEq, Debug)]
+pub struct Compression {
+    enabled: bool,
+
+    // Keyed by the exact bytes of each suffix rather than by `Labels`, which
+    // would ignore case, so a name never points back to a suffix that was
+    // written with different letters in upper or lower case.
+    offsets: BTreeMap<Vec<Box<[u8]>>, u16>,
+}
+
+impl Compression {
+
+    /// Creates a new table with no names written yet.
+    pub fn new() -> Self {
+        Self { enabled: true, offsets: BTreeMap::new() }
+    }
+
+    /// Creates a table that never compresses names, for when the bytes being
+    /// written are not going to be at the start of a message.
+    pub fn disabled() -> Self {
+        Self { enabled: false, offsets: BTreeMap::new() }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// Returns the length byte for one segment of a domain name. Segments can
+/// only be created with a length that fits in one.
+fn segment_length(segment: &[u8]) -> u8 {
+    u8::try_from(segment.len()).unwrap_or(u8::MAX)
+}
+
+/// Writes a character string: a length byte followed by that many bytes.
+/// Returns an error if the string is too long for its length to fit.
+pub(crate) fn write_character_string(bytes: &mut Vec<u8>, string: &[u8]) -> io::Result<()> {
+    let length = u8::try_from(string.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "character string is longer than 255 bytes"))?;
+
+    bytes.write_u8(length)?;
+    bytes.extend_from_slice(string);
+    Ok(())
+}
+
+
+/// The maximum number of pointers that can be followed when reading one
+/// domain name.
+pub(crate) const RECURSION_LIMIT: usize = 8;
 
 /// Reads bytes from the given cursor into the given buffer, using the list of
 /// recursions to track backtracking positions. Returns the count of bytes
@@ -197,8 +561,7 @@ fn read_string_recursive(labels: &mut Labels, c: &mut Cursor<&[u8]>, recursions:
                 name_buf.push(c);
             }
 
-            let string = String::from_utf8_lossy(&*name_buf).to_string();
-            labels.segments.push((byte, string));
+            labels.segments.push(name_buf.into_boxed_slice());
         }
     }
 
@@ -322,4 +685,168 @@ mod test {
         assert_eq!(cursor.read_labels(),
                    Err(WireError::TooMuchRecursion(Box::new([ 2, 4, 6, 8, 10, 12, 14, 16 ]))));
     }
+
+    #[test]
+    fn compresses_suffix() {
+        let mut compression = Compression::new();
+        let mut bytes = vec![ 0xAA, 0xBB ];  // something before the names
+
+        bytes.write_labels_compressed(&Labels::encode("dns.lookup.dog").unwrap(), &mut compression).unwrap();
+        bytes.write_labels_compressed(&Labels::encode("www.lookup.dog").unwrap(), &mut compression).unwrap();
+        bytes.write_labels_compressed(&Labels::encode("dns.lookup.dog").unwrap(), &mut compression).unwrap();
+
+        assert_eq!(bytes, vec![
+            0xAA, 0xBB,
+            0x03, b'd', b'n', b's', 0x06, b'l', b'o', b'o', b'k', b'u', b'p', 0x03, b'd', b'o', b'g', 0x00,
+            0x03, b'w', b'w', b'w', 0xc0, 0x06,  // skip to “lookup.dog”
+            0xc0, 0x02,  // skip to “dns.lookup.dog”
+        ]);
+    }
+
+    #[test]
+    fn compression_disabled() {
+        let mut compression = Compression::disabled();
+        let mut bytes = Vec::new();
+
+        bytes.write_labels_compressed(&Labels::encode("one").unwrap(), &mut compression).unwrap();
+        bytes.write_labels_compressed(&Labels::encode("one").unwrap(), &mut compression).unwrap();
+
+        assert_eq!(bytes, vec![
+            0x03, b'o', b'n', b'e', 0x00,
+            0x03, b'o', b'n', b'e', 0x00,
+        ]);
+    }
+
+    #[test]
+    fn parents() {
+        let labels = Labels::encode("dns.lookup.dog").unwrap();
+
+        assert_eq!(labels.parent(), Some(Labels::encode("lookup.dog").unwrap()));
+        assert_eq!(Labels::root().parent(), None);
+    }
+
+    #[test]
+    fn suffixes() {
+        let labels = Labels::encode("dns.lookup.dog").unwrap();
+
+        assert_eq!(labels.suffix(1), Labels::encode("dog").unwrap());
+        assert_eq!(labels.suffix(0), Labels::root());
+        assert_eq!(labels.suffix(4), labels);
+    }
+
+    #[test]
+    fn within() {
+        let labels = Labels::encode("DNS.lookup.dog").unwrap();
+
+        assert!(labels.is_within(&Labels::encode("Lookup.DOG").unwrap()));
+        assert!(labels.is_within(&Labels::root()));
+        assert!(! labels.is_within(&Labels::encode("bsago.me").unwrap()));
+        assert!(! Labels::root().is_within(&labels));
+    }
+
+    #[test]
+    fn lowercase() {
+        assert_eq!(Labels::encode("DNS.Lookup.dog").unwrap().to_lowercase(),
+                   Labels::encode("dns.lookup.dog").unwrap());
+    }
+
+    #[test]
+    fn binary_label() {
+        let buf: &[u8] = &[
+            0x04,  // label of length 4
+            b'a', b'.', 0x00, 0xFF,  // label, with a dot and unprintable bytes
+            0x00,  // end reading
+        ];
+
+        let (labels, _) = Cursor::new(buf).read_labels().unwrap();
+        assert_eq!(labels.iter().collect::<Vec<_>>(), vec![ &[ b'a', b'.', 0x00, 0xFF ][..] ]);
+        assert_eq!(labels.to_string(), "a\\.\\000\\255.");
+    }
+
+    #[test]
+    fn encode_escapes() {
+        let labels = Labels::encode("My\\ Printer\\.local\\032two._ipp._tcp.local.").unwrap();
+
+        assert_eq!(labels.iter().collect::<Vec<_>>(), vec![
+            &b"My Printer.local two"[..], b"_ipp", b"_tcp", b"local",
+        ]);
+        assert_eq!(labels.to_string(), "My\\032Printer\\.local\\032two._ipp._tcp.local.");
+    }
+
+    #[test]
+    fn encode_invalid_escape() {
+        assert_eq!(Labels::encode("one\\256.two"),
+                   Err("one\\256"));
+    }
+
+    #[test]
+    fn encode_overlong_label() {
+        let label = "a".repeat(64);
+        assert_eq!(Labels::encode(&label),
+                   Err(&*label));
+    }
+
+    #[test]
+    fn escaped_round_trip() {
+        let labels = Labels::encode("\\\\\\\"\\(\\);\\@\\$\\007.dog").unwrap();
+        assert_eq!(Labels::encode(&labels.to_string()).unwrap().iter().collect::<Vec<_>>(),
+                   labels.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn case_preserved() {
+        let mut bytes = Vec::new();
+        bytes.write_labels(&Labels::encode("DNS.Lookup.dog").unwrap()).unwrap();
+
+        assert_eq!(bytes, vec![
+            0x03, b'D', b'N', b'S', 0x06, b'L', b'o', b'o', b'k', b'u', b'p', 0x03, b'd', b'o', b'g', 0x00,
+        ]);
+        assert_eq!(Labels::encode("DNS.Lookup.dog").unwrap().to_string(), "DNS.Lookup.dog.");
+    }
+
+    #[test]
+    fn case_insensitive_comparison() {
+        assert_eq!(Labels::encode("DNS.Lookup.DOG").unwrap(),
+                   Labels::encode("dns.lookup.dog").unwrap());
+        assert_ne!(Labels::encode("dns.lookup.dog").unwrap(),
+                   Labels::encode("dns.lookup.cat").unwrap());
+        assert_eq!(Labels::encode("A.dog").unwrap().cmp(&Labels::encode("b.DOG").unwrap()),
+                   Ordering::Less);
+    }
+
+    #[test]
+    fn unicode_leaves_ascii() {
+        let labels = Labels::encode("My\\032Printer.lookup.dog").unwrap();
+        assert_eq!(labels.to_unicode(), labels.to_string());
+    }
+
+    #[test]
+    fn unicode_segments_keep_dots() {
+        let labels = Labels::encode("one\\.two.lookup.dog").unwrap();
+        assert_eq!(labels.unicode_segments().collect::<Vec<_>>(),
+                   vec![ "one\\.two", "lookup", "dog" ]);
+    }
+
+    #[cfg(feature = "with_idna")]
+    #[test]
+    fn unicode_decodes_a_labels() {
+        let labels = Labels::encode("bücher.lookup.dog").unwrap();
+
+        assert_eq!(labels.to_string(), "xn--bcher-kva.lookup.dog.");
+        assert_eq!(labels.to_unicode(), "bücher.lookup.dog.");
+    }
+
+    #[test]
+    fn compression_keeps_case() {
+        let mut compression = Compression::new();
+        let mut bytes = Vec::new();
+
+        bytes.write_labels_compressed(&Labels::encode("dog").unwrap(), &mut compression).unwrap();
+        bytes.write_labels_compressed(&Labels::encode("DOG").unwrap(), &mut compression).unwrap();
+
+        assert_eq!(bytes, vec![
+            0x03, b'd', b'o', b'g', 0x00,
+            0x03, b'D', b'O', b'G', 0x00,
+        ]);
+    }
 }
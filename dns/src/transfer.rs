@@ -0,0 +1,501 @@
+//! Zone transfers, which send every record in a zone as a stream of
+//! response messages.
+
+use std::fmt;
+
+use log::*;
+
+use crate::record::{Record, RecordType, UnknownQtype, SOA};
+use crate::types::{Answer, Response};
+
+
+impl RecordType {
+
+    /// The query type that asks for a full transfer of a zone. It’s not a
+    /// record type that can appear in a response, so it’s one of the
+    /// unknown types.
+    pub const AXFR: Self = Self::Other(UnknownQtype::HeardOf("AXFR", 252));
+
+    /// The query type that asks for the changes to a zone since a given
+    /// version of it.
+    pub const IXFR: Self = Self::Other(UnknownQtype::HeardOf("IXFR", 251));
+}
+
+
+/// A zone transfer that is in progress, holding the messages that have been
+/// received so far.
+///
+/// The records in a zone are sent as a stream of messages. The first record
+/// of the first message is the SOA record for the newest version of the
+/// zone, and the stream ends with the same SOA record again.
+///
+/// In a full transfer, every record in the zone is sent in between. In an
+/// incremental transfer, what gets sent instead is a sequence of
+/// differences, each of which is the SOA record of an older version, the
+/// records removed from it, the SOA record of the version after it, and the
+/// records added to make that version. A server can also answer an
+/// incremental transfer request with a full transfer, or with only the SOA
+/// record if the client already has the newest version.
+///
+/// # References
+///
+/// - [RFC 5936](https://tools.ietf.org/html/rfc5936) — DNS Zone Transfer
+///   Protocol (AXFR) (June 2010)
+/// - [RFC 1995 §4](https://tools.ietf.org/html/rfc1995#section-4) —
+///   Incremental Zone Transfer in DNS (August 1996)
+#[derive(PartialEq, Debug)]
+pub struct Transfer {
+    incremental: bool,
+    state: State,
+    messages: Vec<Response>,
+}
+
+/// Where the stream of records in a transfer is up to.
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum State {
+
+    /// No records have been read yet.
+    Opening,
+
+    /// Only the opening SOA record has been read, so it’s not yet known
+    /// whether this is a full or incremental transfer.
+    Opened,
+
+    /// The records of a full transfer are being read.
+    Full,
+
+    /// The records removed from the version with the given serial are
+    /// being read.
+    Removing(u32),
+
+    /// The records added to make the version with the given serial are
+    /// being read.
+    Adding(u32),
+
+    /// The closing SOA record has been read.
+    Complete,
+}
+
+/// The changes that were made to a zone to go from one version to the next,
+/// as sent in an incremental transfer.
+#[derive(PartialEq, Debug)]
+pub struct Difference {
+
+    /// The serial number of the older version.
+    pub from_serial: u32,
+
+    /// The serial number of the newer version.
+    pub to_serial: u32,
+
+    /// The records that were in the older version, but not the newer one.
+    pub removed: Vec<Answer>,
+
+    /// The records that are in the newer version, but were not in the
+    /// older one.
+    pub added: Vec<Answer>,
+}
+
+/// Something that can go wrong with the stream of messages in a zone
+/// transfer.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum TransferError {
+
+    /// The first message did not start with a SOA record.
+    NoOpeningSOA,
+
+    /// The SOA record that closed the transfer differed from the one that
+    /// opened it, meaning the zone changed while it was being sent.
+    MismatchedSOA {
+
+        /// The serial number of the opening SOA record.
+        opening_serial: u32,
+
+        /// The serial number of the closing SOA record.
+        closing_serial: u32,
+    },
+
+    /// A difference in an incremental transfer did not start from the
+    /// version that the difference before it ended at.
+    UnexpectedSerial {
+
+        /// The serial number that the previous difference ended at.
+        expected: u32,
+
+        /// The serial number that the next difference started from.
+        found: u32,
+    },
+
+    /// There were more records in the message after the closing SOA record.
+    RecordsAfterClosingSOA,
+}
+
+
+impl Transfer {
+
+    /// Creates a new full transfer that has not received any messages yet.
+    pub fn new() -> Self {
+        Self { incremental: false, state: State::Opening, messages: Vec::new() }
+    }
+
+    /// Creates a new incremental transfer that has not received any
+    /// messages yet.
+    pub fn incremental() -> Self {
+        Self { incremental: true, .. Self::new() }
+    }
+
+    /// Adds the next message in the stream to the transfer, returning
+    /// whether it was the last one. A response with an error code ends the
+    /// transfer straight away.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the first message does not open with a SOA
+    /// record, the closing SOA record does not match the opening one, or
+    /// the differences in an incremental transfer do not follow on from
+    /// one another.
+    pub fn add(&mut self, response: Response) -> Result<bool, TransferError> {
+        if self.messages.is_empty() && response.flags.error_code.is_some() {
+            debug!("Transfer refused with error -> {:?}", response.flags.error_code);
+            self.messages.push(response);
+            return Ok(true);
+        }
+
+        let opening = self.messages.first().and_then(opening_soa)
+            .or_else(|| opening_soa(&response))
+            .ok_or(TransferError::NoOpeningSOA)?;
+
+        let mut state = self.state;
+        for answer in &response.answers {
+            state = self.next_state(state, opening, answer)?;
+        }
+
+        // A server answers an incremental transfer request with just the
+        // SOA record if the client already has the newest version.
+        if state == State::Opened && self.incremental && self.messages.is_empty() {
+            debug!("Zone is up to date");
+            state = State::Complete;
+        }
+
+        debug!("Received transfer message with {} answers (state {:?})", response.answers.len(), state);
+        self.state = state;
+        self.messages.push(response);
+        Ok(state == State::Complete)
+    }
+
+    /// Works out the state after reading one more record.
+    fn next_state(&self, state: State, opening: &SOA, answer: &Answer) -> Result<State, TransferError> {
+        let soa = match answer {
+            Answer::Standard { record: Record::SOA(soa), .. }  => Some(soa),
+            _                                                  => None,
+        };
+
+        match (state, soa) {
+            (State::Opening, _) => {
+                Ok(State::Opened)
+            }
+            (State::Opened, Some(soa)) if self.incremental && soa.serial != opening.serial => {
+                Ok(State::Removing(soa.serial))
+            }
+            (State::Opened, Some(closing)) |
+            (State::Full, Some(closing)) => {
+                closing_state(opening, closing)
+            }
+            (State::Opened, None) |
+            (State::Full, None) => {
+                Ok(State::Full)
+            }
+            (State::Removing(_), Some(soa)) => {
+                Ok(State::Adding(soa.serial))
+            }
+            (State::Adding(serial), Some(soa)) if serial == opening.serial => {
+                closing_state(opening, soa)
+            }
+            (State::Adding(serial), Some(soa)) if serial == soa.serial => {
+                Ok(State::Removing(soa.serial))
+            }
+            (State::Adding(serial), Some(soa)) => {
+                warn!("Difference starts from serial {}, expected {}", soa.serial, serial);
+                Err(TransferError::UnexpectedSerial { expected: serial, found: soa.serial })
+            }
+            (State::Removing(_), None) |
+            (State::Adding(_), None) => {
+                Ok(state)
+            }
+            (State::Complete, _) => {
+                Err(TransferError::RecordsAfterClosingSOA)
+            }
+        }
+    }
+
+    /// Returns the messages that have been received so far.
+    pub fn messages(&self) -> &[Response] {
+        &self.messages
+    }
+
+    /// Merges all the messages into one response, which has the header and
+    /// sections of the first message, with the answers of every message. The
+    /// closing SOA record is left out, so the answers hold every record in
+    /// the zone exactly once.
+    ///
+    /// For an incremental transfer, the answers hold only the SOA record of
+    /// the newest version, and the changes are returned as a list of
+    /// differences, from oldest to newest. Otherwise, the list is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no messages have been added to the transfer.
+    pub fn into_response(self) -> (Response, Vec<Difference>) {
+        let mut messages = self.messages.into_iter();
+        let mut response = messages.next().expect("no messages in transfer");
+
+        for message in messages {
+            response.answers.extend(message.answers);
+        }
+
+        if response.flags.error_code.is_none() && response.answers.len() > 1 {
+            response.answers.pop();
+        }
+
+        let is_incremental = matches!(response.answers.get(1), Some(Answer::Standard { record: Record::SOA(_), .. }));
+        if ! self.incremental || ! is_incremental {
+            return (response, Vec::new());
+        }
+
+        let mut differences = Vec::<Difference>::new();
+        let mut adding = true;
+        for answer in response.answers.split_off(1) {
+            let serial = match &answer {
+                Answer::Standard { record: Record::SOA(soa), .. }  => Some(soa.serial),
+                _                                                  => None,
+            };
+
+            match (serial, differences.last_mut()) {
+                (Some(serial), _) if adding => {
+                    differences.push(Difference { from_serial: serial, to_serial: serial, removed: Vec::new(), added: Vec::new() });
+                    adding = false;
+                }
+                (Some(serial), Some(difference)) => {
+                    difference.to_serial = serial;
+                    adding = true;
+                }
+                (None, Some(difference)) if adding => {
+                    difference.added.push(answer);
+                }
+                (None, Some(difference)) => {
+                    difference.removed.push(answer);
+                }
+                (_, None) => {
+                    unreachable!("records before the first difference");
+                }
+            }
+        }
+
+        (response, differences)
+    }
+}
+
+impl Default for Transfer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the SOA record at the very start of a message, if there is one.
+fn opening_soa(response: &Response) -> Option<&SOA> {
+    match response.answers.first() {
+        Some(Answer::Standard { record: Record::SOA(soa), .. })  => Some(soa),
+        _                                                        => None,
+    }
+}
+
+/// Checks that the closing SOA record matches the opening one, which
+/// completes the transfer.
+fn closing_state(opening: &SOA, closing: &SOA) -> Result<State, TransferError> {
+    if closing == opening {
+        Ok(State::Complete)
+    }
+    else {
+        warn!("Closing SOA {:?} does not match opening SOA {:?}", closing, opening);
+        Err(TransferError::MismatchedSOA { opening_serial: opening.serial, closing_serial: closing.serial })
+    }
+}
+
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoOpeningSOA            => write!(f, "Transfer did not start with a SOA record"),
+            Self::MismatchedSOA { opening_serial, closing_serial } => {
+                write!(f, "Transfer opened with SOA serial {} but closed with serial {}", opening_serial, closing_serial)
+            }
+            Self::UnexpectedSerial { expected, found } => {
+                write!(f, "Transfer has a difference from serial {} after one ending at serial {}", found, expected)
+            }
+            Self::RecordsAfterClosingSOA  => write!(f, "Transfer has records after the closing SOA record"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::{A, NS};
+    use crate::strings::Labels;
+    use crate::types::{Flags, QClass, ErrorCode};
+    use pretty_assertions::assert_eq;
+    use std::net::Ipv4Addr;
+
+    fn soa(serial: u32) -> Answer {
+        answer("dom.ain", Record::SOA(SOA {
+            mname: Labels::encode("ns1.dom.ain").unwrap(),
+            rname: Labels::encode("hostmaster.dom.ain").unwrap(),
+            serial,
+            refresh_interval: 7200,
+            retry_interval: 1800,
+            expire_limit: 1_209_600,
+            minimum_ttl: 300,
+        }))
+    }
+
+    fn ns() -> Answer {
+        answer("dom.ain", Record::NS(NS { nameserver: Labels::encode("ns1.dom.ain").unwrap() }))
+    }
+
+    fn a() -> Answer {
+        answer("ns1.dom.ain", Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) }))
+    }
+
+    fn answer(qname: &str, record: Record) -> Answer {
+        Answer::Standard { qname: Labels::encode(qname).unwrap(), qclass: QClass::IN, ttl: 300, record }
+    }
+
+    fn message(answers: Vec<Answer>) -> Response {
+        Response {
+            transaction_id: 0x1234,
+            flags: Flags::standard_response(),
+            queries: Vec::new(),
+            answers,
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+            signature: None,
+            malformed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn one_message() {
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(message(vec![ soa(1), ns(), a(), soa(1) ])),
+                   Ok(true));
+
+        assert_eq!(transfer.into_response(),
+                   (message(vec![ soa(1), ns(), a() ]), Vec::new()));
+    }
+
+    #[test]
+    fn several_messages() {
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(message(vec![ soa(1), ns() ])), Ok(false));
+        assert_eq!(transfer.add(message(vec![ a() ])),           Ok(false));
+        assert_eq!(transfer.add(message(vec![ soa(1) ])),        Ok(true));
+        assert_eq!(transfer.messages().len(), 3);
+
+        assert_eq!(transfer.into_response(),
+                   (message(vec![ soa(1), ns(), a() ]), Vec::new()));
+    }
+
+    #[test]
+    fn empty_zone() {
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(message(vec![ soa(1), soa(1) ])),
+                   Ok(true));
+    }
+
+    #[test]
+    fn mismatched_soa() {
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(message(vec![ soa(1), ns() ])), Ok(false));
+        assert_eq!(transfer.add(message(vec![ a(), soa(2) ])),
+                   Err(TransferError::MismatchedSOA { opening_serial: 1, closing_serial: 2 }));
+    }
+
+    #[test]
+    fn no_opening_soa() {
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(message(vec![ ns(), soa(1) ])),
+                   Err(TransferError::NoOpeningSOA));
+    }
+
+    #[test]
+    fn records_after_closing_soa() {
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(message(vec![ soa(1), ns(), soa(1), a() ])),
+                   Err(TransferError::RecordsAfterClosingSOA));
+    }
+
+    #[test]
+    fn refused() {
+        let mut refused = message(Vec::new());
+        refused.flags.error_code = Some(ErrorCode::QueryRefused);
+
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(refused), Ok(true));
+
+        let (response, _) = transfer.into_response();
+        assert_eq!(response.flags.error_code, Some(ErrorCode::QueryRefused));
+    }
+
+    // incremental transfer tests
+
+    fn www(last: u8) -> Answer {
+        answer("www.dom.ain", Record::A(A { address: Ipv4Addr::new(192, 0, 2, last) }))
+    }
+
+    #[test]
+    fn incremental() {
+        let mut transfer = Transfer::incremental();
+        assert_eq!(transfer.add(message(vec![ soa(3), soa(1), www(1), soa(2), www(2) ])), Ok(false));
+        assert_eq!(transfer.add(message(vec![ soa(2), www(2), soa(3), www(3), a() ])),    Ok(false));
+        assert_eq!(transfer.add(message(vec![ soa(3) ])),                                 Ok(true));
+
+        assert_eq!(transfer.into_response(), (message(vec![ soa(3) ]), vec![
+            Difference { from_serial: 1, to_serial: 2, removed: vec![ www(1) ], added: vec![ www(2) ] },
+            Difference { from_serial: 2, to_serial: 3, removed: vec![ www(2) ], added: vec![ www(3), a() ] },
+        ]));
+    }
+
+    #[test]
+    fn incremental_falls_back_to_full() {
+        let mut transfer = Transfer::incremental();
+        assert_eq!(transfer.add(message(vec![ soa(3), ns(), a(), soa(3) ])),
+                   Ok(true));
+
+        assert_eq!(transfer.into_response(),
+                   (message(vec![ soa(3), ns(), a() ]), Vec::new()));
+    }
+
+    #[test]
+    fn incremental_up_to_date() {
+        let mut transfer = Transfer::incremental();
+        assert_eq!(transfer.add(message(vec![ soa(3) ])),
+                   Ok(true));
+
+        assert_eq!(transfer.into_response(),
+                   (message(vec![ soa(3) ]), Vec::new()));
+    }
+
+    #[test]
+    fn incremental_with_gap() {
+        let mut transfer = Transfer::incremental();
+        assert_eq!(transfer.add(message(vec![ soa(3), soa(1), www(1), soa(2), www(2), soa(4) ])),
+                   Err(TransferError::UnexpectedSerial { expected: 2, found: 4 }));
+    }
+
+    #[test]
+    fn full_with_two_messages_of_one_soa() {
+        let mut transfer = Transfer::new();
+        assert_eq!(transfer.add(message(vec![ soa(1) ])), Ok(false));
+        assert_eq!(transfer.add(message(vec![ soa(1) ])), Ok(true));
+    }
+}
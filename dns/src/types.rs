@@ -3,12 +3,14 @@
 //! with the request packet having zero answer fields, and the response packet
 //! having at least one record in its answer fields.
 
-use crate::record::{Record, RecordType, OPT};
+use crate::record::{Record, RecordType, OPT, SOA, TSIG, EDNSOption, ExtendedError, ClientSubnet, Cookie};
 use crate::strings::Labels;
+use crate::wire::WireError;
 
 
 /// A request that gets sent out over a transport.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Request {
 
     /// The transaction ID of this request. This is used to make sure
@@ -23,13 +25,23 @@ pub struct Request {
     /// in a packet.
     pub query: Query,
 
+    /// The SOA record for the version of the zone that the client already
+    /// has, which an IXFR request sends in the Authority section so the
+    /// server knows which changes to send back.
+    pub authority: Option<SOA>,
+
     /// An additional record that may be sent as part of the query.
     pub additional: Option<OPT>,
+
+    /// The TSIG signature on this request, which gets written after every
+    /// other record.
+    pub signature: Option<Signature>,
 }
 
 
 /// A response obtained from a DNS server.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Response {
 
     /// The transaction ID, which should match the ID of the request.
@@ -49,11 +61,62 @@ pub struct Response {
 
     /// The additional records section.
     pub additionals: Vec<Answer>,
+
+    /// The TSIG signature on this response, which is taken out of the
+    /// additional records section.
+    pub signature: Option<Signature>,
+
+    /// The records that failed to parse, which are left out of their
+    /// sections. This is always empty unless the response was read
+    /// leniently.
+    pub malformed: Vec<MalformedRecord>,
+}
+
+
+/// A record that failed to parse when a response was read leniently.
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MalformedRecord {
+
+    /// The section that the record was in.
+    pub section: Section,
+
+    /// The position of the record within its section, counting every record
+    /// before it whether it parsed or not.
+    pub index: usize,
+
+    /// The position in the message where the record starts, in bytes.
+    pub offset: usize,
+
+    /// The reason the record failed to parse.
+    pub error: WireError,
+}
+
+
+/// A TSIG record at the end of a message, which authenticates it using a
+/// key that the client and server share.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Signature {
+
+    /// The name of the key, which is the owner name of the TSIG record.
+    pub key_name: Labels,
+
+    /// The TSIG record, which holds the MAC.
+    pub tsig: TSIG,
+
+    /// The bytes of the message that the MAC covers. For a message that has
+    /// been parsed, these are the bytes that came before the TSIG record,
+    /// with the transaction ID changed back to the original ID and the
+    /// record left out of the additional count. They are not used when a
+    /// message is written.
+    pub signed_bytes: Vec<u8>,
 }
 
 
 /// A DNS query section.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query {
 
     /// The domain name being queried, in human-readable dotted notation.
@@ -69,6 +132,7 @@ pub struct Query {
 
 /// A DNS answer section.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Answer {
 
     /// This is a standard answer with every field.
@@ -100,6 +164,27 @@ pub enum Answer {
 }
 
 
+/// One of the four sections of a DNS packet.
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Section {
+
+    /// The **Question** section, which holds the queries.
+    Question,
+
+    /// The **Answer** section.
+    Answer,
+
+    /// The **Authority** section, which holds the records of the zone’s
+    /// authoritative nameservers.
+    Authority,
+
+    /// The **Additional** section, which holds extra records and the OPT
+    /// pseudo-record.
+    Additional,
+}
+
+
 /// A DNS record class. Of these, the only one that’s in regular use anymore
 /// is the Internet class.
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -121,6 +206,7 @@ pub enum QClass {
 
 /// The flags that accompany every DNS packet.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Flags {
 
     /// Whether this packet is a response packet.
@@ -149,12 +235,26 @@ pub struct Flags {
     /// checking for the request’s queries.
     pub checking_disabled: bool,
 
-    /// In a response, a code indicating an error if one occurred.
+    /// In a response, a code indicating an error if one occurred. Only the
+    /// lower four bits of it are in the header: when a response is parsed,
+    /// the higher bits in its OPT record get combined with them to make the
+    /// full extended error code.
     pub error_code: Option<ErrorCode>,
 }
 
 
 /// A number representing the operation being performed.
+///
+/// # References
+///
+/// - [RFC 1035 §4.1.1](https://tools.ietf.org/html/rfc1035) — Domain Names,
+///   Implementation and Specification (November 1987)
+/// - [RFC 1996](https://tools.ietf.org/html/rfc1996) — A Mechanism for Prompt
+///   Notification of Zone Changes (August 1996)
+/// - [RFC 2136](https://tools.ietf.org/html/rfc2136) — Dynamic Updates in the
+///   Domain Name System (April 1997)
+/// - [RFC 8490](https://tools.ietf.org/html/rfc8490) — DNS Stateful
+///   Operations (March 2019)
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum Opcode {
 
@@ -162,8 +262,26 @@ pub enum Opcode {
     /// standard query.
     Query,
 
+    /// An inverse query, which asks for the names that have a given record.
+    /// This has been obsolete since RFC 3425.
+    IQuery,
+
+    /// A server status request.
+    Status,
+
+    /// A notification from a primary server that a zone has changed, which
+    /// prompts its secondaries to transfer it again.
+    Notify,
+
+    /// A dynamic update, which adds or removes records in a zone.
+    Update,
+
+    /// A DNS Stateful Operations message, which can only be sent over a
+    /// connection-oriented transport.
+    DSO,
+
     /// Any other opcode. This can be from 1 to 15, as the opcode field is
-    /// four bits wide, and 0 is taken.
+    /// four bits wide, but the ones with names above are taken.
     Other(u8),
 }
 
@@ -174,6 +292,8 @@ pub enum Opcode {
 ///
 /// - [RFC 6895 §2.3](https://tools.ietf.org/html/rfc6895#section-2.3) — Domain
 ///   Name System (DNS) IANA Considerations (April 2013)
+/// - [RFC 6891 §6.1.3](https://tools.ietf.org/html/rfc6891#section-6.1.3) —
+///   Extension Mechanisms for DNS (EDNS(0)) (April 2013)
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum ErrorCode {
 
@@ -193,10 +313,53 @@ pub enum ErrorCode {
     /// fulfil it.
     QueryRefused,
 
+    /// `YXDomain` — A name exists when it should not, as a prerequisite of
+    /// an update failed.
+    YXDomain,
+
+    /// `YXRRset` — An RRset exists when it should not, as a prerequisite of
+    /// an update failed.
+    YXRRset,
+
+    /// `NXRRset` — An RRset does not exist when it should, as a prerequisite
+    /// of an update failed.
+    NXRRset,
+
+    /// `NotAuth` — The server is not authoritative for the zone being
+    /// updated.
+    NotAuthoritative,
+
+    /// `NotZone` — A name in an update is not within the zone being
+    /// updated.
+    NotInZone,
+
     /// `BADVERS` and `BADSIG` — The server did not accept the EDNS version,
     /// or failed to verify a signature. The same code is used for both.
     BadVersion,
 
+    /// `BADKEY` — The server did not recognise the key used to sign the
+    /// message.
+    BadKey,
+
+    /// `BADTIME` — The time the message was signed is too far from the
+    /// server’s own clock.
+    BadTime,
+
+    /// `BADMODE` — The server does not support the requested TKEY mode.
+    BadMode,
+
+    /// `BADNAME` — A TKEY record with the same key name already exists.
+    BadName,
+
+    /// `BADALG` — The server does not support the requested algorithm.
+    BadAlgorithm,
+
+    /// `BADTRUNC` — The message authentication code was truncated too much.
+    BadTruncation,
+
+    /// `BADCOOKIE` — The server cookie was missing or invalid.
+    BadCookie,
+
     /// An error code with no currently-defined meaning.
     Other(u16),
 
@@ -205,6 +368,92 @@ pub enum ErrorCode {
 }
 
 
+impl Response {
+
+    /// Returns the OPT pseudo-record in the Additional section, if the
+    /// server sent one.
+    pub fn opt(&self) -> Option<&OPT> {
+        self.additionals.iter().find_map(|a| match a {
+            Answer::Pseudo { opt, .. }  => Some(opt),
+            Answer::Standard { .. }     => None,
+        })
+    }
+
+    /// Returns the contents of every Extended DNS Error option in the
+    /// response’s OPT record, in the order the server sent them.
+    pub fn extended_errors(&self) -> impl Iterator<Item=&ExtendedError> {
+        self.opt().into_iter()
+            .flat_map(|opt| &opt.options)
+            .filter_map(|option| match option {
+                EDNSOption::ExtendedError(ede)  => Some(ede),
+                _                               => None,
+            })
+    }
+
+    /// Returns the contents of the Client Subnet option in the response’s
+    /// OPT record, if the server sent one.
+    pub fn client_subnet(&self) -> Option<&ClientSubnet> {
+        self.opt()?.options.iter()
+            .find_map(|option| match option {
+                EDNSOption::ClientSubnet(ecs)  => Some(ecs),
+                _                              => None,
+            })
+    }
+
+    /// Returns the contents of the NSID option in the response’s OPT record,
+    /// which identifies the server that answered, if the server sent one.
+    pub fn nsid(&self) -> Option<&[u8]> {
+        self.opt()?.options.iter()
+            .find_map(|option| match option {
+                EDNSOption::NSID(nsid)  => Some(&nsid[..]),
+                _                       => None,
+            })
+    }
+
+    /// Returns the contents of the Cookie option in the response’s OPT
+    /// record, if the server sent one.
+    pub fn cookie(&self) -> Option<&Cookie> {
+        self.opt()?.options.iter()
+            .find_map(|option| match option {
+                EDNSOption::Cookie(cookie)  => Some(cookie),
+                _                           => None,
+            })
+    }
+
+    /// Whether the server rejected the request with a `BADCOOKIE` error.
+    ///
+    /// # References
+    ///
+    /// - [RFC 7873 §8](https://tools.ietf.org/html/rfc7873#section-8) — Domain
+    ///   Name System (DNS) Cookies (May 2016)
+    pub fn is_bad_cookie(&self) -> bool {
+        self.flags.error_code == Some(ErrorCode::BadCookie)
+    }
+}
+
+impl Request {
+
+    /// Returns the contents of the Cookie option in the request’s OPT
+    /// record, if it has one.
+    pub fn cookie(&self) -> Option<&Cookie> {
+        self.additional.as_ref()?.options.iter()
+            .find_map(|option| match option {
+                EDNSOption::Cookie(cookie)  => Some(cookie),
+                _                           => None,
+            })
+    }
+
+    /// Returns the contents of the Cookie option in the request’s OPT
+    /// record, if it has one, so the server cookie can be filled in.
+    pub fn cookie_mut(&mut self) -> Option<&mut Cookie> {
+        self.additional.as_mut()?.options.iter_mut()
+            .find_map(|option| match option {
+                EDNSOption::Cookie(cookie)  => Some(cookie),
+                _                           => None,
+            })
+    }
+}
+
 impl Answer {
 
     /// Whether this Answer holds a standard record, not a pseudo record.
@@ -0,0 +1,233 @@
+//! Dynamic update messages, which add and remove records in a zone.
+
+use log::*;
+
+use crate::record::{Record, RecordType};
+use crate::strings::Labels;
+use crate::types::{Flags, QClass, Signature};
+use crate::wire::*;
+
+
+/// A dynamic update message, which asks the primary server for a zone to
+/// add or remove records in it, as long as a set of prerequisites holds.
+///
+/// # References
+///
+/// - [RFC 2136](https://tools.ietf.org/html/rfc2136) — Dynamic Updates in the
+///   Domain Name System (April 1997)
+#[derive(PartialEq, Debug)]
+pub struct Update {
+
+    /// The transaction ID of this update, which the response will echo.
+    pub transaction_id: u16,
+
+    /// The name of the zone being updated. Every name in the prerequisites
+    /// and operations must be within it.
+    pub zone: Labels,
+
+    /// The class of the zone being updated.
+    pub zone_class: QClass,
+
+    /// The conditions that must all hold for the server to perform the
+    /// update. If any of them fail, nothing gets changed.
+    pub prerequisites: Vec<Prerequisite>,
+
+    /// The changes to make to the zone, in order.
+    pub operations: Vec<Operation>,
+
+    /// The TSIG signature on this update, which gets written after every
+    /// other record. Most primary servers will refuse an update without one.
+    pub signature: Option<Signature>,
+}
+
+
+/// A condition that must hold before a server performs an update.
+#[derive(PartialEq, Debug)]
+pub enum Prerequisite {
+
+    /// At least one record of the given type must exist at the name. This
+    /// is `yxrrset` in `nsupdate`.
+    RRsetExists {
+
+        /// The name that must own the records.
+        name: Labels,
+
+        /// The type of the records.
+        record_type: RecordType,
+    },
+
+    /// No records of the given type may exist at the name. This is
+    /// `nxrrset` in `nsupdate`.
+    RRsetDoesNotExist {
+
+        /// The name that must not own the records.
+        name: Labels,
+
+        /// The type of the records.
+        record_type: RecordType,
+    },
+
+    /// The name must own at least one record of any type. This is
+    /// `yxdomain` in `nsupdate`.
+    NameInUse {
+
+        /// The name that must be in use.
+        name: Labels,
+    },
+
+    /// The name must not own any records at all. This is `nxdomain` in
+    /// `nsupdate`.
+    NameNotInUse {
+
+        /// The name that must not be in use.
+        name: Labels,
+    },
+}
+
+
+/// A change to make to the records in a zone.
+#[derive(PartialEq, Debug)]
+pub enum Operation {
+
+    /// Adds a record to the RRset at the name, creating it if necessary.
+    AddRecord {
+
+        /// The name that should own the record.
+        name: Labels,
+
+        /// The time-to-live duration of the record, in seconds.
+        ttl: u32,
+
+        /// The record to add.
+        record: Record,
+    },
+
+    /// Deletes one record from the RRset at the name, if it exists.
+    DeleteRecord {
+
+        /// The name that owns the record.
+        name: Labels,
+
+        /// The record to delete, which must match exactly.
+        record: Record,
+    },
+
+    /// Deletes every record of the given type at the name.
+    DeleteRRset {
+
+        /// The name that owns the records.
+        name: Labels,
+
+        /// The type of the records to delete.
+        record_type: RecordType,
+    },
+
+    /// Deletes every record at the name, of any type.
+    DeleteName {
+
+        /// The name to delete.
+        name: Labels,
+    },
+}
+
+
+/// The class used in prerequisites and deletions that match any record
+/// with the given name or type.
+const CLASS_ANY: QClass = QClass::Other(255);
+
+/// The class used in prerequisites and deletions that match the absence
+/// of records, or one record in particular.
+const CLASS_NONE: QClass = QClass::Other(254);
+
+/// The type used in prerequisites and deletions that apply to every
+/// record type at a name.
+const TYPE_ANY: u16 = 255;
+
+
+impl Update {
+
+    /// Converts this update to a vector of bytes, compressing the domain
+    /// names in it where possible. The zone goes in the Question section,
+    /// the prerequisites in the Answer section, and the operations in the
+    /// Authority section.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(512);
+        let mut compression = Compression::new();
+
+        bytes.write_u16::<BigEndian>(self.transaction_id)?;
+        bytes.write_u16::<BigEndian>(Flags::update().to_u16())?;
+
+        bytes.write_u16::<BigEndian>(1)?;  // zone count
+        bytes.write_u16::<BigEndian>(count(self.prerequisites.len())?)?;
+        bytes.write_u16::<BigEndian>(count(self.operations.len())?)?;
+        bytes.write_u16::<BigEndian>(u16::from(self.signature.is_some()))?;  // additional RR count
+
+        bytes.write_labels_compressed(&self.zone, &mut compression)?;
+        bytes.write_u16::<BigEndian>(RecordType::SOA.type_number())?;
+        bytes.write_u16::<BigEndian>(self.zone_class.to_u16())?;
+
+        for prerequisite in &self.prerequisites {
+            trace!("Writing prerequisite -> {:?}", prerequisite);
+
+            match prerequisite {
+                Prerequisite::RRsetExists { name, record_type } => {
+                    write_empty_record(&mut bytes, &mut compression, name, record_type.type_number(), CLASS_ANY)?;
+                }
+                Prerequisite::RRsetDoesNotExist { name, record_type } => {
+                    write_empty_record(&mut bytes, &mut compression, name, record_type.type_number(), CLASS_NONE)?;
+                }
+                Prerequisite::NameInUse { name } => {
+                    write_empty_record(&mut bytes, &mut compression, name, TYPE_ANY, CLASS_ANY)?;
+                }
+                Prerequisite::NameNotInUse { name } => {
+                    write_empty_record(&mut bytes, &mut compression, name, TYPE_ANY, CLASS_NONE)?;
+                }
+            }
+        }
+
+        for operation in &self.operations {
+            trace!("Writing operation -> {:?}", operation);
+
+            match operation {
+                Operation::AddRecord { name, ttl, record } => {
+                    write_record(&mut bytes, &mut compression, name, self.zone_class, *ttl, record)?;
+                }
+                Operation::DeleteRecord { name, record } => {
+                    write_record(&mut bytes, &mut compression, name, CLASS_NONE, 0, record)?;
+                }
+                Operation::DeleteRRset { name, record_type } => {
+                    write_empty_record(&mut bytes, &mut compression, name, record_type.type_number(), CLASS_ANY)?;
+                }
+                Operation::DeleteName { name } => {
+                    write_empty_record(&mut bytes, &mut compression, name, TYPE_ANY, CLASS_ANY)?;
+                }
+            }
+        }
+
+        if let Some(signature) = &self.signature {
+            signature.write(&mut bytes)?;
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Writes a resource record with the given record data.
+fn write_record(bytes: &mut Vec<u8>, compression: &mut Compression, name: &Labels, class: QClass, ttl: u32, record: &Record) -> io::Result<()> {
+    bytes.write_labels_compressed(name, compression)?;
+    bytes.write_u16::<BigEndian>(record.record_type().type_number())?;
+    bytes.write_u16::<BigEndian>(class.to_u16())?;
+    bytes.write_u32::<BigEndian>(ttl)?;
+    write_with_length(bytes, |bytes| record.write(bytes, compression))
+}
+
+/// Writes a resource record with a TTL of zero and no record data, which
+/// is how prerequisites and deletions refer to whole RRsets or names.
+fn write_empty_record(bytes: &mut Vec<u8>, compression: &mut Compression, name: &Labels, type_number: u16, class: QClass) -> io::Result<()> {
+    bytes.write_labels_compressed(name, compression)?;
+    bytes.write_u16::<BigEndian>(type_number)?;
+    bytes.write_u16::<BigEndian>(class.to_u16())?;
+    bytes.write_u32::<BigEndian>(0)?;  // TTL
+    bytes.write_u16::<BigEndian>(0)?;  // data length
+    Ok(())
+}
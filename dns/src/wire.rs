@@ -1,14 +1,19 @@
-//! Parsing the DNS wire protocol.
+//! Parsing and building the DNS wire protocol.
 
-pub(crate) use std::io::{Cursor, Read};
+pub(crate) use std::io::{self, Cursor, Read};
 pub(crate) use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+pub(crate) use crate::strings::{Compression, WriteLabels};
 
-use std::io;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 use log::*;
 
-use crate::record::{Record, RecordType, OPT};
-use crate::strings::{Labels, ReadLabels, WriteLabels};
+use crate::record::{Record, RecordType, OPT, SOA, TSIG, generic_number};
+use crate::registry::{self, UnknownMnemonic};
+use crate::strings::{Labels, ReadLabels};
 use crate::types::*;
+use crate::borrowed;
 
 
 impl Request {
@@ -22,22 +27,139 @@ impl Request {
 
         bytes.write_u16::<BigEndian>(1)?;  // query count
         bytes.write_u16::<BigEndian>(0)?;  // answer count
-        bytes.write_u16::<BigEndian>(0)?;  // authority RR count
-        bytes.write_u16::<BigEndian>(if self.additional.is_some() { 1 } else { 0 })?;  // additional RR count
+        bytes.write_u16::<BigEndian>(u16::from(self.authority.is_some()))?;  // authority RR count
+        bytes.write_u16::<BigEndian>(u16::from(self.additional.is_some()) + u16::from(self.signature.is_some()))?;  // additional RR count
 
         bytes.write_labels(&self.query.qname)?;
         bytes.write_u16::<BigEndian>(self.query.qtype.type_number())?;
         bytes.write_u16::<BigEndian>(self.query.qclass.to_u16())?;
 
+        if let Some(soa) = &self.authority {
+            bytes.write_labels(&self.query.qname)?;
+            bytes.write_u16::<BigEndian>(SOA::RR_TYPE)?;
+            bytes.write_u16::<BigEndian>(self.query.qclass.to_u16())?;
+            bytes.write_u32::<BigEndian>(0)?;  // TTL
+            write_with_length(&mut bytes, |bytes| soa.write(bytes, &mut Compression::disabled()))?;
+        }
+
         if let Some(opt) = &self.additional {
             bytes.write_u8(0)?;  // usually a name
             bytes.write_u16::<BigEndian>(OPT::RR_TYPE)?;
             bytes.extend(opt.to_bytes()?);
         }
 
+        if let Some(signature) = &self.signature {
+            signature.write(&mut bytes)?;
+        }
+
         Ok(bytes)
     }
 
+    /// Reads bytes off of the given slice, parsing them into a request.
+    ///
+    /// A request holds exactly one query, at most one SOA record in the
+    /// Authority section for IXFR requests, at most one OPT record, and an
+    /// optional TSIG record at the very end, so a packet with any other
+    /// number of questions, or with any other records, cannot be parsed as
+    /// one.
+    ///
+    /// # References
+    ///
+    /// - [RFC 9619](https://tools.ietf.org/html/rfc9619) — In the DNS, QDCOUNT
+    ///   Is (Usually) One (July 2024)
+    /// - [RFC 1995 §3](https://tools.ietf.org/html/rfc1995#section-3) —
+    ///   Incremental Zone Transfer in DNS (August 1996)
+    /// - [RFC 6891 §6.1.1](https://tools.ietf.org/html/rfc6891#section-6.1.1)
+    ///   — Extension Mechanisms for DNS (EDNS(0)) (April 2013)
+    /// - [RFC 8945 §5.1](https://tools.ietf.org/html/rfc8945#section-5.1) —
+    ///   Secret Key Transaction Authentication for DNS (TSIG) (November 2020)
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        info!("Parsing request");
+        trace!("Bytes -> {:?}", bytes);
+        let mut c = Cursor::new(bytes);
+
+        let transaction_id = c.read_u16::<BigEndian>()?;
+        trace!("Read txid -> {:?}", transaction_id);
+
+        let flags = Flags::from_u16(c.read_u16::<BigEndian>()?);
+        trace!("Read flags -> {:#?}", flags);
+
+        let query_count      = c.read_u16::<BigEndian>()?;
+        let answer_count     = c.read_u16::<BigEndian>()?;
+        let authority_count  = c.read_u16::<BigEndian>()?;
+        let additional_count = c.read_u16::<BigEndian>()?;
+
+        let counts = [
+            (Section::Question,   query_count,      1),
+            (Section::Answer,     answer_count,     0),
+        ];
+
+        for (section, count, expected) in counts.iter().copied() {
+            if count != expected {
+                warn!("Request has {} entries in the {:?} section", count, section);
+                return Err(WireError::WrongSectionCount { section, count });
+            }
+        }
+
+        if authority_count > 1 {
+            warn!("Request has {} authority records", authority_count);
+            return Err(WireError::WrongSectionCount { section: Section::Authority, count: authority_count });
+        }
+
+        if additional_count > 2 {
+            warn!("Request has {} additional records", additional_count);
+            return Err(WireError::WrongSectionCount { section: Section::Additional, count: additional_count });
+        }
+
+        debug!("Reading query from request");
+        let (qname, _) = c.read_labels()?;
+        let query = Query::from_bytes(qname, &mut c)?;
+
+        let mut authority = None;
+        if authority_count == 1 {
+            debug!("Reading authority record from request");
+            let (qname, _) = c.read_labels()?;
+
+            if let Answer::Standard { record: Record::SOA(soa), .. } = Answer::from_bytes(qname, &mut c)? {
+                authority = Some(soa);
+            }
+            else {
+                warn!("Request has a record other than SOA in the Authority section");
+                return Err(WireError::WrongSectionCount { section: Section::Authority, count: authority_count });
+            }
+        }
+
+        let mut additional = None;
+        let mut signature = None;
+        for index in 0 .. additional_count {
+            debug!("Reading additional record from request");
+            let start = c.position();
+            let (qname, _) = c.read_labels()?;
+
+            if index == additional_count - 1 && is_signature(&c) {
+                signature = Some(Signature::read(qname, &mut c, start)?);
+                continue;
+            }
+
+            match Answer::from_bytes(qname, &mut c)? {
+                Answer::Pseudo { opt, .. } if additional.is_none() => {
+                    additional = Some(opt);
+                }
+                Answer::Pseudo { .. } => {
+                    warn!("Request has more than one OPT record");
+                    return Err(WireError::WrongSectionCount { section: Section::Additional, count: additional_count });
+                }
+                Answer::Standard { record, .. } => {
+                    warn!("Request has a {:?} record in the Additional section", record.record_type());
+                    return Err(WireError::WrongSectionCount { section: Section::Additional, count: additional_count });
+                }
+            }
+        }
+
+        Ok(Self { transaction_id, flags, query, authority, additional, signature })
+    }
+
     /// Returns the OPT record to be sent as part of requests.
     pub fn additional_record() -> OPT {
         OPT {
@@ -45,7 +167,7 @@ impl Request {
             higher_bits: 0,
             edns0_version: 0,
             flags: 0,
-            data: Vec::new(),
+            options: Vec::new(),
         }
     }
 }
@@ -53,9 +175,27 @@ impl Request {
 
 impl Response {
 
-    /// Reads bytes off of the given slice, parsing them into a response.
-    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    /// Reads bytes off of the given slice, parsing them into a response. If
+    /// the last record is a TSIG record, it gets read into the response’s
+    /// signature rather than its additional records.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        Self::read(bytes, false)
+    }
+
+    /// Reads bytes off of the given slice, parsing them into a response
+    /// without giving up at the first record that fails to parse. Each
+    /// record that fails gets left out of its section and put in the
+    /// response’s list of malformed records instead, and parsing carries on
+    /// with the record after it, as long as the length of the failed record
+    /// can still be read. If it can’t, parsing stops there, keeping all the
+    /// records that came before. An error is only returned if the header
+    /// itself cannot be read.
+    pub fn from_bytes_lenient(bytes: &[u8]) -> Result<Self, WireError> {
+        Self::read(bytes, true)
+    }
+
+    #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+    fn read(bytes: &[u8], lenient: bool) -> Result<Self, WireError> {
         info!("Parsing response");
         trace!("Bytes -> {:?}", bytes);
         let mut c = Cursor::new(bytes);
@@ -77,39 +217,223 @@ impl Response {
         // them _entirely_, so cap the pre-allocation if the count looks
         // arbitrarily large (9 seems about right).
 
-        let mut queries = Vec::with_capacity(usize::from(query_count.min(9)));
+        let mut response = Self {
+            transaction_id,
+            flags,
+            queries:      Vec::with_capacity(usize::from(query_count.min(9))),
+            answers:      Vec::with_capacity(usize::from(answer_count.min(9))),
+            authorities:  Vec::with_capacity(usize::from(authority_count.min(9))),
+            additionals:  Vec::with_capacity(usize::from(additional_count.min(9))),
+            signature:    None,
+            malformed:    Vec::new(),
+        };
+
+        let counts = [ query_count, answer_count, authority_count, additional_count ];
+        if let Err(e) = response.read_sections(&mut c, counts, lenient) {
+            // When reading leniently, the record that parsing stopped at has
+            // already been added to the list of malformed records
+            if ! lenient {
+                return Err(e);
+            }
+        }
+
+        if let Some(opt) = response.opt() {
+            response.flags.error_code = ErrorCode::extend(response.flags.error_code, opt.higher_bits);
+            trace!("Combined extended rcode -> {:?}", response.flags.error_code);
+        }
+
+        Ok(response)
+    }
+
+    /// Reads the entries in each of the four sections, which have the given
+    /// counts, into this response.
+    fn read_sections(&mut self, c: &mut Cursor<&[u8]>, counts: [u16; 4], lenient: bool) -> Result<(), WireError> {
+        let [ query_count, answer_count, authority_count, additional_count ] = counts;
+
         debug!("Reading {}x query from response", query_count);
-        for _ in 0 .. query_count {
-            let (qname, _) = c.read_labels()?;
-            queries.push(Query::from_bytes(qname, &mut c)?);
+        for index in 0 .. query_count {
+            let query = read_entry(c, lenient, &mut self.malformed, Section::Question, index, |c| {
+                let (qname, _) = c.read_labels()?;
+                Query::from_bytes(qname, c)
+            })?;
+
+            self.queries.extend(query);
         }
 
-        let mut answers = Vec::with_capacity(usize::from(answer_count.min(9)));
         debug!("Reading {}x answer from response", answer_count);
-        for _ in 0 .. answer_count {
-            let (qname, _) = c.read_labels()?;
-            answers.push(Answer::from_bytes(qname, &mut c)?);
+        for index in 0 .. answer_count {
+            let answer = read_entry(c, lenient, &mut self.malformed, Section::Answer, index, |c| {
+                let (qname, _) = c.read_labels()?;
+                Answer::from_bytes(qname, c)
+            })?;
+
+            self.answers.extend(answer);
         }
 
-        let mut authorities = Vec::with_capacity(usize::from(authority_count.min(9)));
         debug!("Reading {}x authority from response", authority_count);
-        for _ in 0 .. authority_count {
-            let (qname, _) = c.read_labels()?;
-            authorities.push(Answer::from_bytes(qname, &mut c)?);
+        for index in 0 .. authority_count {
+            let authority = read_entry(c, lenient, &mut self.malformed, Section::Authority, index, |c| {
+                let (qname, _) = c.read_labels()?;
+                Answer::from_bytes(qname, c)
+            })?;
+
+            self.authorities.extend(authority);
         }
 
-        let mut additionals = Vec::with_capacity(usize::from(additional_count.min(9)));
         debug!("Reading {}x additional answer from response", additional_count);
-        for _ in 0 .. additional_count {
-            let (qname, _) = c.read_labels()?;
-            additionals.push(Answer::from_bytes(qname, &mut c)?);
+        for index in 0 .. additional_count {
+            let additional = read_entry(c, lenient, &mut self.malformed, Section::Additional, index, |c| {
+                let start = c.position();
+                let (qname, _) = c.read_labels()?;
+
+                if index == additional_count - 1 && is_signature(c) {
+                    debug!("Reading signature from response");
+                    Signature::read(qname, c, start).map(Additional::Signature)
+                }
+                else {
+                    Answer::from_bytes(qname, c).map(Additional::Answer)
+                }
+            })?;
+
+            match additional {
+                Some(Additional::Answer(answer))        => self.additionals.push(answer),
+                Some(Additional::Signature(signature))  => self.signature = Some(signature),
+                None                                    => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts this response to a vector of bytes, compressing the domain
+    /// names in it where possible.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(512);
+        let mut compression = Compression::new();
+
+        bytes.write_u16::<BigEndian>(self.transaction_id)?;
+        bytes.write_u16::<BigEndian>(self.flags.to_u16())?;
+
+        bytes.write_u16::<BigEndian>(count(self.queries.len())?)?;
+        bytes.write_u16::<BigEndian>(count(self.answers.len())?)?;
+        bytes.write_u16::<BigEndian>(count(self.authorities.len())?)?;
+        bytes.write_u16::<BigEndian>(count(self.additionals.len() + usize::from(self.signature.is_some()))?)?;
+
+        for query in &self.queries {
+            bytes.write_labels_compressed(&query.qname, &mut compression)?;
+            bytes.write_u16::<BigEndian>(query.qtype.type_number())?;
+            bytes.write_u16::<BigEndian>(query.qclass.to_u16())?;
         }
 
-        Ok(Self { transaction_id, flags, queries, answers, authorities, additionals })
+        for answer in self.answers.iter().chain(&self.authorities).chain(&self.additionals) {
+            answer.write(&mut bytes, &mut compression)?;
+        }
+
+        if let Some(signature) = &self.signature {
+            signature.write(&mut bytes)?;
+        }
+
+        Ok(bytes)
     }
 }
 
 
+impl Signature {
+
+    /// Reads a TSIG record from the given cursor, which has just read the
+    /// record’s owner name, starting at the given position. The bytes of
+    /// the message up to that position are kept, with the header changed
+    /// back to how it was before the message was signed.
+    ///
+    /// # References
+    ///
+    /// - [RFC 8945 §4.3.2](https://tools.ietf.org/html/rfc8945#section-4.3.2)
+    ///   — Secret Key Transaction Authentication for DNS (TSIG) (November 2020)
+    fn read(key_name: Labels, c: &mut Cursor<&[u8]>, start: u64) -> Result<Self, WireError> {
+        let _type_number = c.read_u16::<BigEndian>()?;
+        let _class = c.read_u16::<BigEndian>()?;
+        let _ttl = c.read_u32::<BigEndian>()?;
+
+        let record_length = c.read_u16::<BigEndian>()?;
+        trace!("Read record length -> {:?}", record_length);
+
+        let tsig = TSIG::read(record_length, c)?;
+        trace!("Read signature -> {:?}", tsig);
+
+        let start = usize::try_from(start).map_err(|_| WireError::IO)?;
+        let mut signed_bytes = c.get_ref()[.. start].to_vec();
+        signed_bytes[0 .. 2].copy_from_slice(&tsig.original_id.to_be_bytes());
+
+        let additional_count = u16::from_be_bytes([ signed_bytes[10], signed_bytes[11] ]);
+        signed_bytes[10 .. 12].copy_from_slice(&additional_count.saturating_sub(1).to_be_bytes());
+
+        Ok(Self { key_name, tsig, signed_bytes })
+    }
+
+    /// Writes the TSIG record to the end of the given buffer. None of the
+    /// names in it are compressed.
+    pub(crate) fn write(&self, bytes: &mut Vec<u8>) -> io::Result<()> {
+        bytes.write_labels(&self.key_name)?;
+        bytes.write_u16::<BigEndian>(TSIG::RR_TYPE)?;
+        bytes.write_u16::<BigEndian>(TSIG::CLASS)?;
+        bytes.write_u32::<BigEndian>(0)?;  // TTL
+        write_with_length(bytes, |bytes| self.tsig.write(bytes, &mut Compression::disabled()))
+    }
+}
+
+/// An entry in the Additional section, which is either a record or the
+/// TSIG signature at the very end.
+enum Additional {
+    Answer(Answer),
+    Signature(Signature),
+}
+
+/// Reads one entry of a section of a response, using the given function.
+/// When reading leniently, an entry that fails to parse gets added to the
+/// list of malformed records and skipped over, as long as its length can
+/// still be read. If it can’t be, the error is returned to stop reading.
+fn read_entry<T>(c: &mut Cursor<&[u8]>, lenient: bool, malformed: &mut Vec<MalformedRecord>, section: Section, index: u16, read: impl FnOnce(&mut Cursor<&[u8]>) -> Result<T, WireError>) -> Result<Option<T>, WireError> {
+    let start = c.position();
+
+    let error = match read(c) {
+        Ok(entry)            => return Ok(Some(entry)),
+        Err(e) if ! lenient  => return Err(e),
+        Err(e)               => e,
+    };
+
+    let offset = usize::try_from(start).map_err(|_| WireError::IO)?;
+    warn!("Malformed entry #{} in {:?} section at offset {} -> {:?}", index, section, offset, error);
+    malformed.push(MalformedRecord { section, index: usize::from(index), offset, error });
+
+    let message = *c.get_ref();
+    let next = match section {
+        Section::Question  => borrowed::read_query(message, offset)?.1,
+        _                  => borrowed::read_answer(message, offset)?.1,
+    };
+
+    c.set_position(next as u64);
+    Ok(None)
+}
+
+/// Whether the record that the cursor is about to read, having just read
+/// its owner name, is a TSIG record.
+fn is_signature(c: &Cursor<&[u8]>) -> bool {
+    let position = usize::try_from(c.position()).unwrap_or(usize::MAX);
+
+    match c.get_ref().get(position .. position.saturating_add(2)) {
+        Some(type_bytes)  => u16::from_be_bytes([ type_bytes[0], type_bytes[1] ]) == TSIG::RR_TYPE,
+        None              => false,
+    }
+}
+
+/// Converts the number of entries in a section to the number in the header,
+/// returning an error if there are too many.
+pub(crate) fn count(len: usize) -> io::Result<u16> {
+    u16::try_from(len)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many records in one section"))
+}
+
+
 impl Query {
 
     /// Reads bytes from the given cursor, and parses them into a query with
@@ -160,6 +484,34 @@ impl Answer {
             Ok(Self::Standard { qclass, qname, record, ttl })
         }
     }
+
+    /// Converts this answer to a vector of bytes. The domain names in it are
+    /// written out in full, as the bytes could end up anywhere in a message.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(32);
+        self.write(&mut bytes, &mut Compression::disabled())?;
+        Ok(bytes)
+    }
+
+    /// Writes this answer to the end of the given buffer, which holds the
+    /// message written so far.
+    pub fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()> {
+        match self {
+            Self::Standard { qname, qclass, ttl, record } => {
+                bytes.write_labels_compressed(qname, compression)?;
+                bytes.write_u16::<BigEndian>(record.record_type().type_number())?;
+                bytes.write_u16::<BigEndian>(qclass.to_u16())?;
+                bytes.write_u32::<BigEndian>(*ttl)?;
+                write_with_length(bytes, |bytes| record.write(bytes, compression))
+            }
+            Self::Pseudo { qname, opt } => {
+                bytes.write_labels_compressed(qname, compression)?;
+                bytes.write_u16::<BigEndian>(OPT::RR_TYPE)?;
+                bytes.extend(opt.to_bytes()?);
+                Ok(())
+            }
+        }
+    }
 }
 
 
@@ -168,7 +520,7 @@ impl Record {
     /// Reads at most `len` bytes from the given curser, and parses them into
     /// a record structure depending on the type number, which has already been read.
     #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
-    fn from_bytes(record_type: RecordType, len: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
+    pub(crate) fn from_bytes(record_type: RecordType, len: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError> {
         if cfg!(feature = "with_mutagen") {
             warn!("Mutation is enabled!");
         }
@@ -185,18 +537,26 @@ impl Record {
             RecordType::AAAA        => read_record!(AAAA),
             RecordType::CAA         => read_record!(CAA),
             RecordType::CNAME       => read_record!(CNAME),
+            RecordType::DNSKEY      => read_record!(DNSKEY),
+            RecordType::DS          => read_record!(DS),
             RecordType::EUI48       => read_record!(EUI48),
             RecordType::EUI64       => read_record!(EUI64),
             RecordType::HINFO       => read_record!(HINFO),
+            RecordType::HTTPS       => read_record!(HTTPS),
             RecordType::LOC         => read_record!(LOC),
             RecordType::MX          => read_record!(MX),
             RecordType::NAPTR       => read_record!(NAPTR),
             RecordType::NS          => read_record!(NS),
+            RecordType::NSEC        => read_record!(NSEC),
+            RecordType::NSEC3       => read_record!(NSEC3),
+            RecordType::NSEC3PARAM  => read_record!(NSEC3PARAM),
             RecordType::OPENPGPKEY  => read_record!(OPENPGPKEY),
             RecordType::PTR         => read_record!(PTR),
+            RecordType::RRSIG       => read_record!(RRSIG),
             RecordType::SSHFP       => read_record!(SSHFP),
             RecordType::SOA         => read_record!(SOA),
             RecordType::SRV         => read_record!(SRV),
+            RecordType::SVCB        => read_record!(SVCB),
             RecordType::TLSA        => read_record!(TLSA),
             RecordType::TXT         => read_record!(TXT),
             RecordType::URI         => read_record!(URI),
@@ -211,11 +571,69 @@ impl Record {
             }
         }
     }
+
+    /// Writes the data of this record, without its length, to the end of the
+    /// given buffer, which holds the message written so far.
+    pub fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()> {
+        match self {
+            Self::A(a)                    => a.write(bytes, compression),
+            Self::AAAA(aaaa)              => aaaa.write(bytes, compression),
+            Self::CAA(caa)                => caa.write(bytes, compression),
+            Self::CNAME(cname)            => cname.write(bytes, compression),
+            Self::DNSKEY(dnskey)          => dnskey.write(bytes, compression),
+            Self::DS(ds)                  => ds.write(bytes, compression),
+            Self::EUI48(eui48)            => eui48.write(bytes, compression),
+            Self::EUI64(eui64)            => eui64.write(bytes, compression),
+            Self::HINFO(hinfo)            => hinfo.write(bytes, compression),
+            Self::HTTPS(https)            => https.write(bytes, compression),
+            Self::LOC(loc)                => loc.write(bytes, compression),
+            Self::MX(mx)                  => mx.write(bytes, compression),
+            Self::NAPTR(naptr)            => naptr.write(bytes, compression),
+            Self::NS(ns)                  => ns.write(bytes, compression),
+            Self::NSEC(nsec)              => nsec.write(bytes, compression),
+            Self::NSEC3(nsec3)            => nsec3.write(bytes, compression),
+            Self::NSEC3PARAM(nsec3param)  => nsec3param.write(bytes, compression),
+            Self::OPENPGPKEY(openpgpkey)  => openpgpkey.write(bytes, compression),
+            Self::PTR(ptr)                => ptr.write(bytes, compression),
+            Self::RRSIG(rrsig)            => rrsig.write(bytes, compression),
+            Self::SSHFP(sshfp)            => sshfp.write(bytes, compression),
+            Self::SOA(soa)                => soa.write(bytes, compression),
+            Self::SRV(srv)                => srv.write(bytes, compression),
+            Self::SVCB(svcb)              => svcb.write(bytes, compression),
+            Self::TLSA(tlsa)              => tlsa.write(bytes, compression),
+            Self::TXT(txt)                => txt.write(bytes, compression),
+            Self::URI(uri)                => uri.write(bytes, compression),
+
+            Self::Other { bytes: other_bytes, .. } => {
+                bytes.extend_from_slice(other_bytes);
+                Ok(())
+            }
+        }
+    }
 }
 
 
 impl QClass {
-    fn from_u16(uu: u16) -> Self {
+
+    /// Determines the class with a given name, or `None` if none is known.
+    /// Matches names case-insensitively. Any class can also be given by its
+    /// number in the generic form, such as `CLASS99`.
+    ///
+    /// # References
+    ///
+    /// - [RFC 3597 §5](https://tools.ietf.org/html/rfc3597#section-5) — Handling
+    ///   of Unknown DNS Resource Record (RR) Types (September 2003)
+    pub fn from_class_name(class_name: &str) -> Option<Self> {
+        if let Some(class_number) = generic_number(class_name, "CLASS") {
+            return Some(Self::from_u16(class_number));
+        }
+
+        let (_, class_number) = registry::number(registry::CLASSES, class_name)?;
+        Some(Self::from_u16(class_number))
+    }
+
+    /// Returns the class that the given number represents.
+    pub fn from_u16(uu: u16) -> Self {
         match uu {
             0x0001 => Self::IN,
             0x0003 => Self::CH,
@@ -224,7 +642,8 @@ impl QClass {
         }
     }
 
-    fn to_u16(self) -> u16 {
+    /// Returns the number that represents this class.
+    pub fn to_u16(self) -> u16 {
         match self {
             Self::IN        => 0x0001,
             Self::CH        => 0x0003,
@@ -232,6 +651,29 @@ impl QClass {
             Self::Other(uu) => uu,
         }
     }
+
+    /// Returns the mnemonic of this class, such as `IN` or `NONE`, or `None`
+    /// if it has not been given one.
+    pub fn mnemonic(self) -> Option<&'static str> {
+        registry::mnemonic(registry::CLASSES, self.to_u16())
+    }
+}
+
+impl fmt::Display for QClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mnemonic() {
+            Some(name)  => write!(f, "{}", name),
+            None        => write!(f, "CLASS{}", self.to_u16()),
+        }
+    }
+}
+
+impl FromStr for QClass {
+    type Err = UnknownMnemonic;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::from_class_name(input).ok_or_else(|| UnknownMnemonic(input.into()))
+    }
 }
 
 
@@ -242,6 +684,12 @@ impl Flags {
         Self::from_u16(0b_0000_0001_0000_0000)
     }
 
+    /// The set of flags that represents a dynamic update, which has every
+    /// flag other than the opcode cleared.
+    pub fn update() -> Self {
+        Self::from_u16(0b_0010_1000_0000_0000)
+    }
+
     /// The set of flags that represents a successful response.
     pub fn standard_response() -> Self {
         Self::from_u16(0b_1000_0001_1000_0000)
@@ -251,10 +699,7 @@ impl Flags {
     pub fn to_u16(self) -> u16 {                 // 0123 4567 89AB CDEF
         let mut                          bits  = 0b_0000_0000_0000_0000;
         if self.response               { bits |= 0b_1000_0000_0000_0000; }
-        match self.opcode {
-            Opcode::Query     =>       { bits |= 0b_0000_0000_0000_0000; }
-            Opcode::Other(_)  =>       { unimplemented!(); }
-        }
+                                         bits |= u16::from(self.opcode.to_bits()) << 11;
         if self.authoritative          { bits |= 0b_0000_0100_0000_0000; }
         if self.truncated              { bits |= 0b_0000_0010_0000_0000; }
         if self.recursion_desired      { bits |= 0b_0000_0001_0000_0000; }
@@ -262,6 +707,9 @@ impl Flags {
         // (the Z bit is reserved)               0b_0000_0000_0100_0000
         if self.authentic_data         { bits |= 0b_0000_0000_0010_0000; }
         if self.checking_disabled      { bits |= 0b_0000_0000_0001_0000; }
+        if let Some(rcode) = self.error_code {
+                                         bits |= rcode.to_bits() & 0b_1111;
+        }
 
         bits
     }
@@ -289,23 +737,62 @@ impl Opcode {
 
     /// Extracts the opcode from this four-bit number, which should have been
     /// extracted from the packet and shifted to be in the range 0–15.
-    fn from_bits(bits: u8) -> Self {
-        if bits == 0 {
-            Self::Query
+    pub(crate) fn from_bits(bits: u8) -> Self {
+        match bits {
+            0 => Self::Query,
+            1 => Self::IQuery,
+            2 => Self::Status,
+            4 => Self::Notify,
+            5 => Self::Update,
+            6 => Self::DSO,
+            n => {
+                assert!(n <= 15, "bits {:#08b} out of range", n);
+                Self::Other(n)
+            }
         }
-        else {
-            assert!(bits <= 15, "bits {:#08b} out of range", bits);
-            Self::Other(bits)
+    }
+
+    /// Returns the number of this opcode. Only the last four bits of it fit
+    /// in the flags field.
+    pub(crate) fn to_bits(self) -> u8 {
+        match self {
+            Self::Query     => 0,
+            Self::IQuery    => 1,
+            Self::Status    => 2,
+            Self::Notify    => 4,
+            Self::Update    => 5,
+            Self::DSO       => 6,
+            Self::Other(n)  => n & 0b_1111,
         }
     }
+
+    /// Determines the opcode with the given name, such as `NOTIFY`, or the
+    /// given number. Matches names case-insensitively.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let opcode = match name.to_ascii_uppercase().as_str() {
+            "QUERY"   => Self::Query,
+            "IQUERY"  => Self::IQuery,
+            "STATUS"  => Self::Status,
+            "NOTIFY"  => Self::Notify,
+            "UPDATE"  => Self::Update,
+            "DSO"     => Self::DSO,
+            number    => {
+                let bits = number.parse::<u8>().ok().filter(|n| *n <= 15)?;
+                Self::from_bits(bits)
+            }
+        };
+
+        Some(opcode)
+    }
 }
 
 
 impl ErrorCode {
 
-    /// Extracts the rcode from the last four bits of the flags field.
-    fn from_bits(bits: u16) -> Option<Self> {
-        if (0x0F01 .. 0x0FFF).contains(&bits) {
+    /// Converts an rcode number to an error code. This is either the last
+    /// four bits of the flags field, or the full twelve-bit extended rcode.
+    pub(crate) fn from_bits(bits: u16) -> Option<Self> {
+        if (0x0F01 ..= 0x0FFF).contains(&bits) {
             return Some(Self::Private(bits));
         }
 
@@ -316,14 +803,77 @@ impl ErrorCode {
             3 => Some(Self::NXDomain),
             4 => Some(Self::NotImplemented),
             5 => Some(Self::QueryRefused),
+            6 => Some(Self::YXDomain),
+            7 => Some(Self::YXRRset),
+            8 => Some(Self::NXRRset),
+            9 => Some(Self::NotAuthoritative),
+           10 => Some(Self::NotInZone),
            16 => Some(Self::BadVersion),
+           17 => Some(Self::BadKey),
+           18 => Some(Self::BadTime),
+           19 => Some(Self::BadMode),
+           20 => Some(Self::BadName),
+           21 => Some(Self::BadAlgorithm),
+           22 => Some(Self::BadTruncation),
+           23 => Some(Self::BadCookie),
             n => Some(Self::Other(n)),
         }
     }
+
+    /// Combines the error code from the header of a response with the
+    /// higher eight bits of the extended rcode, which are held in its OPT
+    /// record, to get the full extended error code.
+    pub(crate) fn extend(header_code: Option<Self>, higher_bits: u8) -> Option<Self> {
+        let lower_bits = header_code.map_or(0, Self::to_bits);
+        Self::from_bits((u16::from(higher_bits) << 4) | lower_bits)
+    }
+
+    /// Returns the number of this rcode. Only the last four bits of it fit
+    /// in the flags field.
+    pub fn to_bits(self) -> u16 {
+        match self {
+            Self::FormatError       => 1,
+            Self::ServerFailure     => 2,
+            Self::NXDomain          => 3,
+            Self::NotImplemented    => 4,
+            Self::QueryRefused      => 5,
+            Self::YXDomain          => 6,
+            Self::YXRRset           => 7,
+            Self::NXRRset           => 8,
+            Self::NotAuthoritative  => 9,
+            Self::NotInZone         => 10,
+            Self::BadVersion        => 16,
+            Self::BadKey            => 17,
+            Self::BadTime           => 18,
+            Self::BadMode           => 19,
+            Self::BadName           => 20,
+            Self::BadAlgorithm      => 21,
+            Self::BadTruncation     => 22,
+            Self::BadCookie         => 23,
+            Self::Private(n)        |
+            Self::Other(n)          => n,
+        }
+    }
+
+    /// Returns the mnemonic of this rcode, such as `NXDOMAIN`, or `None` if
+    /// it has not been given one.
+    pub fn mnemonic(self) -> Option<&'static str> {
+        registry::mnemonic(registry::RCODES, self.to_bits())
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mnemonic() {
+            Some(name)  => write!(f, "{}", name),
+            None        => write!(f, "{}", self.to_bits()),
+        }
+    }
 }
 
 
-/// Trait for decoding DNS record structures from bytes read over the wire.
+/// Trait for decoding DNS record structures from bytes read over the wire,
+/// and encoding them back.
 pub trait Wire: Sized {
 
     /// This record’s type as a string, such as `"A"` or `"CNAME"`.
@@ -337,11 +887,35 @@ pub trait Wire: Sized {
     /// throughout the complete data — by this point, we have read the entire
     /// response into a buffer.
     fn read(len: u16, c: &mut Cursor<&[u8]>) -> Result<Self, WireError>;
+
+    /// Write the record’s data, without its length, to the end of the given
+    /// buffer. The buffer holds the complete message written so far, so any
+    /// domain names that are allowed to be compressed can point back to
+    /// names in the table.
+    fn write(&self, bytes: &mut Vec<u8>, compression: &mut Compression) -> io::Result<()>;
+}
+
+
+/// Writes whatever the function writes to the buffer, preceded by its length
+/// as a two-byte number. Returns an error if it ends up too long for that.
+pub(crate) fn write_with_length<F>(bytes: &mut Vec<u8>, write: F) -> io::Result<()>
+where F: FnOnce(&mut Vec<u8>) -> io::Result<()>
+{
+    let start = bytes.len();
+    bytes.write_u16::<BigEndian>(0)?;  // filled in afterwards
+    write(bytes)?;
+
+    let length = u16::try_from(bytes.len() - start - 2)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "data is longer than 65535 bytes"))?;
+
+    bytes[start .. start + 2].copy_from_slice(&length.to_be_bytes());
+    Ok(())
 }
 
 
 /// Something that can go wrong deciphering a record.
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WireError {
 
     /// There was an IO error reading from the cursor.
@@ -405,6 +979,18 @@ pub enum WireError {
         length_after_labels: u16,
     },
 
+    /// When the type bitmaps at the end of a NSEC or NSEC3 record run past,
+    /// or stop short of, the length of the record as specified in the packet.
+    WrongBitmapLength {
+
+        /// The length of the record’s data, as specified in the packet.
+        stated_length: u16,
+
+        /// The computed length of the record’s data, based on the number of
+        /// bytes consumed by reading the type bitmaps.
+        length_after_bitmaps: u16,
+    },
+
     /// When the data contained a string containing a cycle of pointers.
     /// Contains the vector of indexes that was being checked.
     TooMuchRecursion(Box<[u16]>),
@@ -423,11 +1009,30 @@ pub enum WireError {
 
         /// The maximum version that this version of dog supports.
         maximum_supported_version: u8,
-    }
+    },
+
+    /// When a request contained a number of entries in one of its sections
+    /// that a `Request` cannot hold: anything other than exactly one
+    /// question, anything other than a single SOA authority record, or
+    /// anything other than a single OPT record and a TSIG record.
+    WrongSectionCount {
+
+        /// The section with the wrong number of entries.
+        section: Section,
+
+        /// The number of entries in that section, as specified in the packet.
+        count: u16,
+    },
+
+    /// When a SVCB or HTTPS record contained a parameter with a value that
+    /// is malformed for its key, or keys that were not in increasing order.
+    /// Contains the offending key.
+    InvalidSvcParam(u16),
 }
 
 /// The rule for how long a record in a packet should be.
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "with_serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MandatedLength {
 
     /// The record should be exactly this many bytes in length.
@@ -0,0 +1,274 @@
+//! Reading the fields of an entry one at a time.
+
+use std::str::FromStr;
+
+use crate::record::RecordType;
+use crate::strings::{Labels, unescape, unescape_one};
+use crate::types::QClass;
+
+use super::ZoneErrorKind;
+use super::tokens::Token;
+
+
+/// The tokens of an entry that have yet to be read, along with the origin
+/// that any relative names in them are relative to.
+pub(super) struct Fields<'a> {
+    tokens: &'a [Token],
+    origin: &'a Labels,
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(tokens: &'a [Token], origin: &'a Labels) -> Self {
+        Self { tokens, origin }
+    }
+
+    /// Whether every token has been read.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the next token without reading it.
+    pub fn peek(&self) -> Option<&'a Token> {
+        self.tokens.first()
+    }
+
+    /// Reads the next token, whatever it is.
+    pub fn skip(&mut self) {
+        if ! self.tokens.is_empty() {
+            self.tokens = &self.tokens[1..];
+        }
+    }
+
+    /// Reads the next token, returning an error naming the field if there
+    /// are none left.
+    pub fn next(&mut self, field: &'static str) -> Result<&'a Token, ZoneErrorKind> {
+        let token = self.peek().ok_or(ZoneErrorKind::MissingField(field))?;
+        self.skip();
+        Ok(token)
+    }
+
+    /// Returns an error if there are any tokens that have not been read.
+    pub fn finish(&self) -> Result<(), ZoneErrorKind> {
+        match self.peek() {
+            Some(token)  => Err(ZoneErrorKind::TrailingData(token.text.clone())),
+            None         => Ok(()),
+        }
+    }
+
+    /// Reads the next token as a number.
+    pub fn number<N: FromStr>(&mut self, field: &'static str) -> Result<N, ZoneErrorKind> {
+        let token = self.next(field)?;
+        let is_digits = ! token.text.is_empty() && token.text.bytes().all(|b| b.is_ascii_digit());
+
+        match token.text.parse() {
+            Ok(number) if is_digits && ! token.quoted => Ok(number),
+            _  => Err(invalid(field, token)),
+        }
+    }
+
+    /// Reads the next token as a TTL or other duration, which can be a
+    /// number of seconds or a sequence of numbers with units.
+    pub fn ttl(&mut self, field: &'static str) -> Result<u32, ZoneErrorKind> {
+        let token = self.next(field)?;
+        parse_ttl(&token.text).ok_or_else(|| invalid(field, token))
+    }
+
+    /// Whether the next token looks like a TTL, rather than a class or type.
+    pub fn peek_is_ttl(&self) -> bool {
+        matches!(self.peek(), Some(token) if ! token.quoted && token.text.starts_with(|c: char| c.is_ascii_digit()))
+    }
+
+    /// Returns the class that the next token names, if it names one.
+    pub fn peek_class(&self) -> Option<QClass> {
+        let token = self.peek().filter(|t| ! t.quoted)?;
+        QClass::from_class_name(&token.text)
+    }
+
+    /// Reads the next token as a record type name, or a type number in the
+    /// generic `TYPE` form.
+    pub fn record_type(&mut self) -> Result<RecordType, ZoneErrorKind> {
+        let token = self.next("record type")?;
+        RecordType::from_type_name(&token.text).ok_or_else(|| ZoneErrorKind::UnknownType(token.text.clone()))
+    }
+
+    /// Reads the next token as a domain name.
+    pub fn name(&mut self, field: &'static str) -> Result<Labels, ZoneErrorKind> {
+        let token = self.next(field)?;
+        parse_name(&token.text, self.origin).ok_or_else(|| invalid(field, token))
+    }
+
+    /// Reads the next token as a character-string, which can be quoted or
+    /// not, and returns its bytes with any escapes decoded.
+    pub fn text(&mut self, field: &'static str) -> Result<Vec<u8>, ZoneErrorKind> {
+        let token = self.next(field)?;
+        unescape(&token.text).ok_or_else(|| invalid(field, token))
+    }
+
+    /// Reads every remaining token and joins them together, for fields such
+    /// as Base64 or hexadecimal data that are allowed to contain spaces.
+    /// There must be at least one.
+    pub fn rest(&mut self, field: &'static str) -> Result<String, ZoneErrorKind> {
+        let mut joined = self.next(field)?.text.clone();
+
+        while let Some(token) = self.peek() {
+            joined.push_str(&token.text);
+            self.skip();
+        }
+
+        Ok(joined)
+    }
+}
+
+/// Returns an error saying that the token is not valid for the field.
+pub(super) fn invalid(field: &'static str, token: &Token) -> ZoneErrorKind {
+    ZoneErrorKind::InvalidField { field, value: token.text.clone() }
+}
+
+
+/// Parses a domain name, which is absolute if it ends with a dot, and
+/// relative to the origin otherwise. Dots and other characters can be
+/// escaped with a backslash.
+pub(super) fn parse_name(text: &str, origin: &Labels) -> Option<Labels> {
+    if text == "@" {
+        return Some(origin.clone());
+    }
+    else if text == "." {
+        return Some(Labels::root());
+    }
+
+    let mut segments = Vec::new();
+    let mut segment = Vec::new();
+    let mut absolute = false;
+    let mut bytes = text.bytes();
+
+    while let Some(byte) = bytes.next() {
+        absolute = false;
+
+        match byte {
+            b'\\' => {
+                segment.push(unescape_one(&mut bytes)?);
+            }
+            b'.' => {
+                segments.push(segment);
+                segment = Vec::new();
+                absolute = true;
+            }
+            _ => {
+                segment.push(byte);
+            }
+        }
+    }
+
+    if ! absolute {
+        segments.push(segment);
+    }
+
+    let labels = Labels::from_segments(segments).ok()?;
+    let labels = if absolute { labels } else { labels.extend(origin) };
+
+    // Each label takes up its length plus one byte, with one more for the root
+    let wire_length = labels.iter().map(|l| l.len() + 1).sum::<usize>() + 1;
+    if wire_length > 255 {
+        return None;
+    }
+
+    Some(labels)
+}
+
+/// Parses a TTL, which is either a number of seconds, or a sequence of
+/// numbers each followed by a unit, such as `1h30m`.
+fn parse_ttl(text: &str) -> Option<u32> {
+    if text.bytes().all(|b| b.is_ascii_digit()) {
+        return text.parse().ok();
+    }
+
+    let mut total = 0_u32;
+    let mut number = None;
+
+    for c in text.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            number = Some(number.unwrap_or(0_u32).checked_mul(10)?.checked_add(digit)?);
+            continue;
+        }
+
+        let multiplier = match c.to_ascii_lowercase() {
+            's'  => 1,
+            'm'  => 60,
+            'h'  => 60 * 60,
+            'd'  => 60 * 60 * 24,
+            'w'  => 60 * 60 * 24 * 7,
+            _    => return None,
+        };
+
+        total = total.checked_add(number.take()?.checked_mul(multiplier)?)?;
+    }
+
+    if number.is_some() {
+        return None;
+    }
+
+    Some(total)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn relative_name() {
+        assert_eq!(parse_name("www", &Labels::encode("dom.ain").unwrap()),
+                   Some(Labels::encode("www.dom.ain").unwrap()));
+    }
+
+    #[test]
+    fn escaped_dot() {
+        let name = parse_name("a\\.b.c.", &Labels::root()).unwrap();
+        assert_eq!(name.iter().collect::<Vec<_>>(), vec![ &b"a.b"[..], b"c" ]);
+    }
+
+    #[test]
+    fn decimal_escape() {
+        let name = parse_name("a\\032b.", &Labels::root()).unwrap();
+        assert_eq!(name.iter().collect::<Vec<_>>(), vec![ &b"a b"[..] ]);
+    }
+
+    #[test]
+    fn empty_label() {
+        assert_eq!(parse_name("a..b.", &Labels::root()), None);
+    }
+
+    #[test]
+    fn overlong_label() {
+        assert_eq!(parse_name(&"a".repeat(64), &Labels::root()), None);
+    }
+
+    #[test]
+    fn unescapes() {
+        assert_eq!(unescape("a\\\"b\\\\c\\255"),
+                   Some(b"a\"b\\c\xff".to_vec()));
+    }
+
+    #[test]
+    fn out_of_range_escape() {
+        assert_eq!(unescape("\\256"), None);
+    }
+
+    #[test]
+    fn ttl_seconds() {
+        assert_eq!(parse_ttl("3600"), Some(3600));
+    }
+
+    #[test]
+    fn ttl_units() {
+        assert_eq!(parse_ttl("1h30M"), Some(5400));
+        assert_eq!(parse_ttl("1w1d"), Some(691_200));
+    }
+
+    #[test]
+    fn ttl_invalid() {
+        assert_eq!(parse_ttl("1h30"), None);
+        assert_eq!(parse_ttl("h"), None);
+        assert_eq!(parse_ttl("99999999999"), None);
+    }
+}
@@ -0,0 +1,446 @@
+//! Parsing zone files in the master file format.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+use crate::record::RecordType;
+use crate::strings::Labels;
+use crate::types::{Answer, QClass};
+use crate::wire::WireError;
+
+mod fields;
+mod rdata;
+mod tokens;
+
+use self::fields::Fields;
+use self::tokens::Entry;
+
+
+/// How many files deep `$INCLUDE` directives can go before we give up, in
+/// case a file includes itself.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+
+/// Parses the text of a zone file into the records it contains, in the
+/// order they appear. Relative names are made absolute using the given
+/// origin until a `$ORIGIN` directive changes it. The files named by any
+/// `$INCLUDE` directives are read relative to the current directory.
+///
+/// # References
+///
+/// - [RFC 1035 §5](https://tools.ietf.org/html/rfc1035#section-5) — Domain
+///   Names - Implementation and Specification (November 1987)
+/// - [RFC 2308 §4](https://tools.ietf.org/html/rfc2308#section-4) — Negative
+///   Caching of DNS Queries (March 1998)
+/// - [RFC 3597 §5](https://tools.ietf.org/html/rfc3597#section-5) — Handling
+///   of Unknown DNS Resource Record (RR) Types (September 2003)
+pub fn parse_zone(input: &str, origin: &Labels) -> Result<Vec<Answer>, ZoneError> {
+    let mut answers = Vec::new();
+    let mut parser = Parser::new(origin.clone(), None);
+    parser.parse(input, &mut answers, 0)?;
+    Ok(answers)
+}
+
+/// Reads and parses the zone file at the given path, in the same way as
+/// `parse_zone`. The files named by any `$INCLUDE` directives are read
+/// relative to the directory of the file that includes them.
+pub fn read_zone_file(path: &Path, origin: &Labels) -> Result<Vec<Answer>, ZoneError> {
+    let mut answers = Vec::new();
+    read_file(path, origin.clone(), None, &mut answers, 0)?;
+    Ok(answers)
+}
+
+/// Parses a domain name written in the same way as in a zone file, which is
+/// absolute if it ends with a dot, and relative to the origin otherwise.
+/// Returns `None` if the name is invalid.
+pub fn parse_relative_name(input: &str, origin: &Labels) -> Option<Labels> {
+    fields::parse_name(input, origin)
+}
+
+/// Reads the file at the given path and parses it, adding its records to
+/// the list. The default TTL carries over from the including file, if any.
+fn read_file(path: &Path, origin: Labels, default_ttl: Option<u32>, answers: &mut Vec<Answer>, depth: usize) -> Result<(), ZoneError> {
+    info!("Reading zone file {}", path.display());
+
+    let input = fs::read_to_string(path).map_err(|e| ZoneError {
+        path: Some(path.to_owned()),
+        line: 0,
+        kind: ZoneErrorKind::IO(e.to_string()),
+    })?;
+
+    let mut parser = Parser::new(origin, Some(path.to_owned()));
+    parser.default_ttl = default_ttl;
+    parser.parse(&input, answers, depth)
+}
+
+
+/// Something that can go wrong while parsing a zone file, along with where
+/// it went wrong.
+#[derive(PartialEq, Debug)]
+pub struct ZoneError {
+
+    /// The path to the file the error is in, or `None` if the text was not
+    /// read from a file.
+    pub path: Option<PathBuf>,
+
+    /// The line the error is on, counting from 1. This is 0 if the file
+    /// could not be read at all.
+    pub line: usize,
+
+    /// What went wrong.
+    pub kind: ZoneErrorKind,
+}
+
+/// The reason a zone file could not be parsed.
+#[derive(PartialEq, Debug)]
+pub enum ZoneErrorKind {
+
+    /// A file could not be read, for the given reason.
+    IO(String),
+
+    /// A quoted string was never closed.
+    UnterminatedString,
+
+    /// A closing parenthesis was missing, or there was one too many.
+    UnbalancedParentheses,
+
+    /// A directive other than `$ORIGIN`, `$TTL`, or `$INCLUDE` was used.
+    UnknownDirective(String),
+
+    /// `$INCLUDE` directives were nested too deeply.
+    IncludeTooDeep,
+
+    /// A record has no owner name, because it is indented but there was no
+    /// record before it.
+    MissingOwner,
+
+    /// A record has no TTL, and there was no `$TTL` directive or earlier
+    /// record to take one from.
+    MissingTTL,
+
+    /// The type of a record is not one that dog knows the name of.
+    UnknownType(String),
+
+    /// The record type is known, but dog cannot parse its data from text,
+    /// so it must be written in the generic `\#` format.
+    UnsupportedType(RecordType),
+
+    /// A required field of a directive or record was missing.
+    MissingField(&'static str),
+
+    /// A field had a value that could not be parsed.
+    InvalidField {
+
+        /// The name of the field.
+        field: &'static str,
+
+        /// The value the field had.
+        value: String,
+    },
+
+    /// There was more data after the end of the record or directive.
+    TrailingData(String),
+
+    /// Record data in the generic format had a different number of bytes
+    /// than its stated length.
+    WrongGenericLength {
+
+        /// The length given before the data.
+        stated_length: usize,
+
+        /// The number of bytes of data that were actually there.
+        actual_length: usize,
+    },
+
+    /// Record data in the generic format could not be parsed as the record
+    /// type it was given for.
+    InvalidGenericData(WireError),
+}
+
+impl fmt::Display for ZoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+
+        if self.line > 0 {
+            write!(f, "{}: ", self.line)?;
+        }
+        else if self.path.is_some() {
+            write!(f, " ")?;
+        }
+
+        match &self.kind {
+            ZoneErrorKind::IO(message)                 => write!(f, "{}", message),
+            ZoneErrorKind::UnterminatedString          => write!(f, "Unterminated quoted string"),
+            ZoneErrorKind::UnbalancedParentheses       => write!(f, "Unbalanced parentheses"),
+            ZoneErrorKind::UnknownDirective(name)      => write!(f, "Unknown directive {}", name),
+            ZoneErrorKind::IncludeTooDeep              => write!(f, "$INCLUDE directives nested too deeply"),
+            ZoneErrorKind::MissingOwner                => write!(f, "Record has no owner name"),
+            ZoneErrorKind::MissingTTL                  => write!(f, "Record has no TTL"),
+            ZoneErrorKind::UnknownType(name)           => write!(f, "Unknown record type {:?}", name),
+            ZoneErrorKind::UnsupportedType(rtype)      => write!(f, "{} records must use the generic \\# format", rtype),
+            ZoneErrorKind::MissingField(field)         => write!(f, "Missing {}", field),
+            ZoneErrorKind::InvalidField { field, value }  => write!(f, "Invalid {} {:?}", field, value),
+            ZoneErrorKind::TrailingData(data)          => write!(f, "Unexpected {:?} after the end of the entry", data),
+            ZoneErrorKind::WrongGenericLength { stated_length, actual_length } => {
+                write!(f, "Generic data is {} bytes long, not {}", actual_length, stated_length)
+            }
+            ZoneErrorKind::InvalidGenericData(error)   => write!(f, "Invalid generic data ({:?})", error),
+        }
+    }
+}
+
+
+/// The state that carries over from one entry in a zone file to the next.
+struct Parser {
+
+    /// The path of the file being parsed, if any.
+    path: Option<PathBuf>,
+
+    /// The name that relative names are relative to.
+    origin: Labels,
+
+    /// The TTL set by the last `$TTL` directive.
+    default_ttl: Option<u32>,
+
+    /// The owner name of the last record, for indented records.
+    last_owner: Option<Labels>,
+
+    /// The TTL of the last record, for records without one if there is no
+    /// `$TTL` directive.
+    last_ttl: Option<u32>,
+
+    /// The class of the last record, for records without one.
+    last_class: QClass,
+}
+
+impl Parser {
+    fn new(origin: Labels, path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            origin,
+            default_ttl: None,
+            last_owner: None,
+            last_ttl: None,
+            last_class: QClass::IN,
+        }
+    }
+
+    /// Parses every entry in the input, adding each record to the list.
+    fn parse(&mut self, input: &str, answers: &mut Vec<Answer>, depth: usize) -> Result<(), ZoneError> {
+        let entries = tokens::entries(input)
+            .map_err(|(line, kind)| self.error(line, kind))?;
+
+        for entry in entries {
+            let line = entry.line;
+            self.parse_entry(&entry, answers, depth)
+                .map_err(|e| match e {
+                    EntryError::Here(kind)      => self.error(line, kind),
+                    EntryError::Included(error) => error,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn error(&self, line: usize, kind: ZoneErrorKind) -> ZoneError {
+        ZoneError { path: self.path.clone(), line, kind }
+    }
+
+    /// Parses one entry, which could be a directive or a record.
+    fn parse_entry(&mut self, entry: &Entry, answers: &mut Vec<Answer>, depth: usize) -> Result<(), EntryError> {
+        let first = &entry.tokens[0];
+
+        if ! entry.indented && ! first.quoted && first.text.starts_with('$') {
+            let directive = first.text.to_ascii_uppercase();
+            let mut fields = Fields::new(&entry.tokens[1..], &self.origin);
+
+            match &directive[..] {
+                "$ORIGIN" => {
+                    let origin = fields.name("origin")?;
+                    fields.finish()?;
+                    self.origin = origin;
+                    debug!("Origin is now {}", self.origin);
+                }
+                "$TTL" => {
+                    self.default_ttl = Some(fields.ttl("TTL")?);
+                    fields.finish()?;
+                    debug!("Default TTL is now {:?}", self.default_ttl);
+                }
+                "$INCLUDE" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(ZoneErrorKind::IncludeTooDeep.into());
+                    }
+
+                    let file_name = fields.text("file name")?;
+                    let file_name = String::from_utf8(file_name)
+                        .map_err(|e| ZoneErrorKind::InvalidField { field: "file name", value: String::from_utf8_lossy(e.as_bytes()).into() })?;
+
+                    let origin = if fields.is_empty() { self.origin.clone() }
+                                                 else { fields.name("origin")? };
+                    fields.finish()?;
+
+                    let path = match self.path.as_ref().and_then(|p| p.parent()) {
+                        Some(directory)  => directory.join(&file_name),
+                        None             => PathBuf::from(&file_name),
+                    };
+
+                    read_file(&path, origin, self.default_ttl, answers, depth + 1)
+                        .map_err(EntryError::Included)?;
+                }
+                _ => {
+                    return Err(ZoneErrorKind::UnknownDirective(first.text.clone()).into());
+                }
+            }
+
+            return Ok(());
+        }
+
+        let mut fields = Fields::new(&entry.tokens, &self.origin);
+
+        let qname = if entry.indented {
+            self.last_owner.clone().ok_or(ZoneErrorKind::MissingOwner)?
+        }
+        else {
+            fields.name("owner name")?
+        };
+
+        // The TTL and class are both optional, and can come in either order
+        let mut ttl = None;
+        let mut qclass = None;
+        for _ in 0 .. 2 {
+            if ttl.is_none() && fields.peek_is_ttl() {
+                ttl = Some(fields.ttl("TTL")?);
+            }
+            else if qclass.is_none() {
+                if let Some(class) = fields.peek_class() {
+                    fields.skip();
+                    qclass = Some(class);
+                }
+            }
+        }
+
+        let record_type = fields.record_type()?;
+        let record = rdata::parse_record_data(record_type, &mut fields)?;
+        fields.finish()?;
+
+        let ttl = ttl.or(self.default_ttl).or(self.last_ttl)
+                     .ok_or(ZoneErrorKind::MissingTTL)?;
+
+        let qclass = qclass.unwrap_or(self.last_class);
+
+        self.last_owner = Some(qname.clone());
+        self.last_ttl = Some(ttl);
+        self.last_class = qclass;
+
+        trace!("Parsed record -> {} {} {:?} {:?}", qname, ttl, qclass, record);
+        answers.push(Answer::Standard { qname, qclass, ttl, record });
+        Ok(())
+    }
+}
+
+
+/// An error from parsing one entry, which is either a problem with the
+/// entry itself, or an error in a file that it included, which already has
+/// its location filled in.
+enum EntryError {
+    Here(ZoneErrorKind),
+    Included(ZoneError),
+}
+
+impl From<ZoneErrorKind> for EntryError {
+    fn from(kind: ZoneErrorKind) -> Self {
+        Self::Here(kind)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use crate::record::{Record, A};
+    use std::net::Ipv4Addr;
+
+    fn origin() -> Labels {
+        Labels::encode("dom.ain").unwrap()
+    }
+
+    fn a(name: &str, ttl: u32, octet: u8) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode(name).unwrap(),
+            qclass: QClass::IN,
+            ttl,
+            record: Record::A(A { address: Ipv4Addr::new(127, 0, 0, octet) }),
+        }
+    }
+
+    #[test]
+    fn relative_and_absolute_names() {
+        let zone = "www 60 IN A 127.0.0.1\n\
+                    www.other. 60 IN A 127.0.0.2\n\
+                    @ 60 IN A 127.0.0.3\n";
+
+        assert_eq!(parse_zone(zone, &origin()),
+                   Ok(vec![ a("www.dom.ain", 60, 1), a("www.other", 60, 2), a("dom.ain", 60, 3) ]));
+    }
+
+    #[test]
+    fn defaults_carry_over() {
+        let zone = "$TTL 1h\n\
+                    www A 127.0.0.1\n\
+                    \x20   IN 30 A 127.0.0.2\n\
+                    api A 127.0.0.3\n";
+
+        assert_eq!(parse_zone(zone, &origin()),
+                   Ok(vec![ a("www.dom.ain", 3600, 1), a("www.dom.ain", 30, 2), a("api.dom.ain", 3600, 3) ]));
+    }
+
+    #[test]
+    fn previous_ttl_without_directive() {
+        let zone = "www 45 A 127.0.0.1\n\
+                    api A 127.0.0.2\n";
+
+        assert_eq!(parse_zone(zone, &origin()),
+                   Ok(vec![ a("www.dom.ain", 45, 1), a("api.dom.ain", 45, 2) ]));
+    }
+
+    #[test]
+    fn origin_directive() {
+        let zone = "$ORIGIN sub\n\
+                    www 60 A 127.0.0.1\n\
+                    $ORIGIN example.\n\
+                    www 60 A 127.0.0.2\n";
+
+        assert_eq!(parse_zone(zone, &origin()),
+                   Ok(vec![ a("www.sub.dom.ain", 60, 1), a("www.example", 60, 2) ]));
+    }
+
+    #[test]
+    fn missing_ttl() {
+        assert_eq!(parse_zone("www A 127.0.0.1", &origin()),
+                   Err(ZoneError { path: None, line: 1, kind: ZoneErrorKind::MissingTTL }));
+    }
+
+    #[test]
+    fn missing_owner() {
+        assert_eq!(parse_zone("\n  60 A 127.0.0.1", &origin()),
+                   Err(ZoneError { path: None, line: 2, kind: ZoneErrorKind::MissingOwner }));
+    }
+
+    #[test]
+    fn unknown_directive() {
+        assert_eq!(parse_zone("$GENERATE 1-10 x A 127.0.0.$", &origin()),
+                   Err(ZoneError { path: None, line: 1, kind: ZoneErrorKind::UnknownDirective("$GENERATE".into()) }));
+    }
+
+    #[test]
+    fn trailing_data() {
+        assert_eq!(parse_zone("www 60 A 127.0.0.1 127.0.0.2", &origin()),
+                   Err(ZoneError { path: None, line: 1, kind: ZoneErrorKind::TrailingData("127.0.0.2".into()) }));
+    }
+}
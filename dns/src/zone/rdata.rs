@@ -0,0 +1,399 @@
+//! Parsing the data of each type of record from its presentation format.
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use crate::record::*;
+use crate::strings::unescape;
+use crate::wire::{WireError, MandatedLength};
+
+use super::ZoneErrorKind;
+use super::fields::{Fields, invalid};
+
+
+/// Parses the data of a record of the given type from the remaining fields
+/// of an entry. Data in the generic `\#` format is accepted for any type.
+#[allow(clippy::too_many_lines)]
+pub(super) fn parse_record_data(record_type: RecordType, fields: &mut Fields<'_>) -> Result<Record, ZoneErrorKind> {
+    if matches!(fields.peek(), Some(token) if token.is("\\#")) {
+        fields.skip();
+        return parse_generic_data(record_type, fields);
+    }
+
+    let record = match record_type {
+        RecordType::A => {
+            let token = fields.next("address")?;
+            let address = token.text.parse().map_err(|_| invalid("address", token))?;
+            Record::A(A { address })
+        }
+        RecordType::AAAA => {
+            let token = fields.next("address")?;
+            let address = token.text.parse().map_err(|_| invalid("address", token))?;
+            Record::AAAA(AAAA { address })
+        }
+        RecordType::CAA => {
+            let flags: u8 = fields.number("flags")?;
+            let tag = fields.text("tag")?.into_boxed_slice();
+            let value = fields.text("value")?.into_boxed_slice();
+            Record::CAA(CAA { critical: flags & 0b_1000_0000 != 0, tag, value })
+        }
+        RecordType::CNAME => {
+            Record::CNAME(CNAME { domain: fields.name("domain")? })
+        }
+        RecordType::DNSKEY => {
+            let flags = fields.number("flags")?;
+            let protocol = fields.number("protocol")?;
+            let algorithm = algorithm(fields)?;
+            let public_key = base64(fields, "public key")?;
+            Record::DNSKEY(DNSKEY { flags, protocol, algorithm, public_key })
+        }
+        RecordType::DS => {
+            let key_tag = fields.number("key tag")?;
+            let algorithm = algorithm(fields)?;
+            let digest_type = fields.number("digest type")?;
+            let digest = hex(fields, "digest")?;
+            Record::DS(DS { key_tag, algorithm, digest_type, digest })
+        }
+        RecordType::EUI48 => {
+            let token = fields.next("address")?;
+            let octets = eui_octets(&token.text)
+                .and_then(|octets| <[u8; 6]>::try_from(octets.as_slice()).ok())
+                .ok_or_else(|| invalid("address", token))?;
+            Record::EUI48(EUI48 { octets })
+        }
+        RecordType::EUI64 => {
+            let token = fields.next("address")?;
+            let octets = eui_octets(&token.text)
+                .and_then(|octets| <[u8; 8]>::try_from(octets.as_slice()).ok())
+                .ok_or_else(|| invalid("address", token))?;
+            Record::EUI64(EUI64 { octets })
+        }
+        RecordType::HINFO => {
+            let cpu = character_string(fields, "CPU")?;
+            let os = character_string(fields, "OS")?;
+            Record::HINFO(HINFO { cpu, os })
+        }
+        RecordType::HTTPS => {
+            let (priority, target, parameters) = service_binding(fields)?;
+            Record::HTTPS(HTTPS { priority, target, parameters })
+        }
+        RecordType::LOC => {
+            let mut texts = Vec::new();
+            while let Some(token) = fields.peek() {
+                texts.push(token.text.as_str());
+                fields.skip();
+            }
+
+            LOC::from_fields(&texts)
+                .map(Record::LOC)
+                .ok_or_else(|| ZoneErrorKind::InvalidField { field: "location", value: texts.join(" ") })?
+        }
+        RecordType::MX => {
+            let preference = fields.number("preference")?;
+            let exchange = fields.name("exchange")?;
+            Record::MX(MX { preference, exchange })
+        }
+        RecordType::NAPTR => {
+            let order = fields.number("order")?;
+            let preference = fields.number("preference")?;
+            let flags = character_string(fields, "flags")?;
+            let service = character_string(fields, "service")?;
+            let regex = character_string(fields, "regex")?;
+            let replacement = fields.name("replacement")?;
+            Record::NAPTR(NAPTR { order, preference, flags, service, regex, replacement })
+        }
+        RecordType::NS => {
+            Record::NS(NS { nameserver: fields.name("nameserver")? })
+        }
+        RecordType::NSEC => {
+            let next_domain = fields.name("next domain")?;
+            let types = type_list(fields)?;
+            Record::NSEC(NSEC { next_domain, types })
+        }
+        RecordType::NSEC3 => {
+            let hash_algorithm = fields.number("hash algorithm")?;
+            let flags = fields.number("flags")?;
+            let iterations = fields.number("iterations")?;
+            let salt = salt(fields)?;
+
+            let token = fields.next("next hashed owner")?;
+            let next_hashed_owner = decode_base32hex(&token.text).ok_or_else(|| invalid("next hashed owner", token))?;
+
+            let types = type_list(fields)?;
+            Record::NSEC3(NSEC3 { hash_algorithm, flags, iterations, salt, next_hashed_owner, types })
+        }
+        RecordType::NSEC3PARAM => {
+            let hash_algorithm = fields.number("hash algorithm")?;
+            let flags = fields.number("flags")?;
+            let iterations = fields.number("iterations")?;
+            let salt = salt(fields)?;
+            Record::NSEC3PARAM(NSEC3PARAM { hash_algorithm, flags, iterations, salt })
+        }
+        RecordType::OPENPGPKEY => {
+            Record::OPENPGPKEY(OPENPGPKEY { key: base64(fields, "key")? })
+        }
+        RecordType::PTR => {
+            Record::PTR(PTR { cname: fields.name("domain")? })
+        }
+        RecordType::RRSIG => {
+            let type_covered = fields.record_type()?;
+            let algorithm = algorithm(fields)?;
+            let labels = fields.number("labels")?;
+            let original_ttl = fields.ttl("original TTL")?;
+            let signature_expiration = timestamp(fields, "signature expiration")?;
+            let signature_inception = timestamp(fields, "signature inception")?;
+            let key_tag = fields.number("key tag")?;
+            let signer_name = fields.name("signer name")?;
+            let signature = base64(fields, "signature")?;
+
+            Record::RRSIG(RRSIG {
+                type_covered, algorithm, labels, original_ttl, signature_expiration,
+                signature_inception, key_tag, signer_name, signature,
+            })
+        }
+        RecordType::SSHFP => {
+            let algorithm = fields.number("algorithm")?;
+            let fingerprint_type = fields.number("fingerprint type")?;
+            let fingerprint = hex(fields, "fingerprint")?;
+            Record::SSHFP(SSHFP { algorithm, fingerprint_type, fingerprint })
+        }
+        RecordType::SOA => {
+            let mname = fields.name("primary nameserver")?;
+            let rname = fields.name("mailbox")?;
+            let serial = fields.number("serial")?;
+            let refresh_interval = fields.ttl("refresh interval")?;
+            let retry_interval = fields.ttl("retry interval")?;
+            let expire_limit = fields.ttl("expire limit")?;
+            let minimum_ttl = fields.ttl("minimum TTL")?;
+            Record::SOA(SOA { mname, rname, serial, refresh_interval, retry_interval, expire_limit, minimum_ttl })
+        }
+        RecordType::SRV => {
+            let priority = fields.number("priority")?;
+            let weight = fields.number("weight")?;
+            let port = fields.number("port")?;
+            let target = fields.name("target")?;
+            Record::SRV(SRV { priority, weight, port, target })
+        }
+        RecordType::SVCB => {
+            let (priority, target, parameters) = service_binding(fields)?;
+            Record::SVCB(SVCB { priority, target, parameters })
+        }
+        RecordType::TLSA => {
+            let certificate_usage = fields.number("certificate usage")?;
+            let selector = fields.number("selector")?;
+            let matching_type = fields.number("matching type")?;
+            let certificate_data = hex(fields, "certificate data")?;
+            Record::TLSA(TLSA { certificate_usage, selector, matching_type, certificate_data })
+        }
+        RecordType::TXT => {
+            let mut messages = vec![ character_string(fields, "text")? ];
+            while ! fields.is_empty() {
+                messages.push(character_string(fields, "text")?);
+            }
+
+            Record::TXT(TXT { messages })
+        }
+        RecordType::URI => {
+            let priority = fields.number("priority")?;
+            let weight = fields.number("weight")?;
+            let target = fields.text("target")?.into_boxed_slice();
+            Record::URI(URI { priority, weight, target })
+        }
+        RecordType::Other(_) => {
+            return Err(ZoneErrorKind::UnsupportedType(record_type));
+        }
+    };
+
+    Ok(record)
+}
+
+/// Parses record data in the generic format, which is its length followed
+/// by its bytes in hexadecimal, and then decodes it as the given type.
+fn parse_generic_data(record_type: RecordType, fields: &mut Fields<'_>) -> Result<Record, ZoneErrorKind> {
+    let stated_length: usize = fields.number("generic data length")?;
+    let bytes = if stated_length == 0 { Vec::new() }
+                                 else { hex(fields, "generic data")? };
+
+    if bytes.len() != stated_length {
+        return Err(ZoneErrorKind::WrongGenericLength { stated_length, actual_length: bytes.len() });
+    }
+
+    let length = u16::try_from(stated_length)
+        .map_err(|_| ZoneErrorKind::InvalidField { field: "generic data length", value: stated_length.to_string() })?;
+
+    let mut c = Cursor::new(&bytes[..]);
+    let record = Record::from_bytes(record_type, length, &mut c)
+        .map_err(ZoneErrorKind::InvalidGenericData)?;
+
+    if c.position() != u64::from(length) {
+        let mandated_length = MandatedLength::Exactly(u16::try_from(c.position()).unwrap_or(u16::MAX));
+        let error = WireError::WrongRecordLength { stated_length: length, mandated_length };
+        return Err(ZoneErrorKind::InvalidGenericData(error));
+    }
+
+    Ok(record)
+}
+
+/// Reads a character-string, which can be at most 255 bytes long.
+fn character_string(fields: &mut Fields<'_>, field: &'static str) -> Result<Box<[u8]>, ZoneErrorKind> {
+    let token = fields.peek().ok_or(ZoneErrorKind::MissingField(field))?;
+    let bytes = fields.text(field)?;
+
+    if bytes.len() > 255 {
+        return Err(invalid(field, token));
+    }
+
+    Ok(bytes.into_boxed_slice())
+}
+
+/// Reads a DNSSEC algorithm, which can be a number or a mnemonic.
+fn algorithm(fields: &mut Fields<'_>) -> Result<Algorithm, ZoneErrorKind> {
+    let token = fields.next("algorithm")?;
+
+    if let Ok(number) = token.text.parse::<u8>() {
+        Ok(Algorithm::from(number))
+    }
+    else {
+        Algorithm::from_mnemonic(&token.text).ok_or_else(|| invalid("algorithm", token))
+    }
+}
+
+/// Reads a signature timestamp, in either of its two forms.
+fn timestamp(fields: &mut Fields<'_>, field: &'static str) -> Result<u32, ZoneErrorKind> {
+    let token = fields.next(field)?;
+    parse_timestamp(&token.text).ok_or_else(|| invalid(field, token))
+}
+
+/// Reads every remaining field as a record type, as in the type bitmap of
+/// an NSEC or NSEC3 record.
+fn type_list(fields: &mut Fields<'_>) -> Result<Vec<RecordType>, ZoneErrorKind> {
+    let mut types = Vec::new();
+    while ! fields.is_empty() {
+        types.push(fields.record_type()?);
+    }
+
+    Ok(types)
+}
+
+/// Reads an NSEC3 salt, which is hexadecimal, or a dash if it is empty.
+fn salt(fields: &mut Fields<'_>) -> Result<Vec<u8>, ZoneErrorKind> {
+    let token = fields.next("salt")?;
+
+    if token.is("-") {
+        Ok(Vec::new())
+    }
+    else {
+        decode_hex(&token.text).ok_or_else(|| invalid("salt", token))
+    }
+}
+
+/// Reads every remaining field as one block of hexadecimal data.
+fn hex(fields: &mut Fields<'_>, field: &'static str) -> Result<Vec<u8>, ZoneErrorKind> {
+    let text = fields.rest(field)?;
+    decode_hex(&text).ok_or(ZoneErrorKind::InvalidField { field, value: text })
+}
+
+/// Reads every remaining field as one block of Base64 data.
+fn base64(fields: &mut Fields<'_>, field: &'static str) -> Result<Vec<u8>, ZoneErrorKind> {
+    let text = fields.rest(field)?;
+    base64::decode(&text).map_err(|_| ZoneErrorKind::InvalidField { field, value: text })
+}
+
+/// Reads the priority, target, and parameters of a SVCB or HTTPS record.
+/// Each parameter is a key, optionally followed by an equals sign and a
+/// value.
+fn service_binding(fields: &mut Fields<'_>) -> Result<(u16, crate::Labels, Vec<SvcParam>), ZoneErrorKind> {
+    let priority = fields.number("priority")?;
+    let target = fields.name("target")?;
+    let mut parameters = Vec::new();
+
+    while let Some(token) = fields.peek() {
+        fields.skip();
+
+        let (key, value) = match token.text.find('=') {
+            Some(equals)  => (&token.text[.. equals], Some(unescape(&token.text[equals + 1 ..]).ok_or_else(|| invalid("service parameter", token))?)),
+            None          => (token.text.as_str(), None),
+        };
+
+        let parameter = SvcParam::from_presentation(key, value.as_deref())
+            .ok_or_else(|| invalid("service parameter", token))?;
+        parameters.push(parameter);
+    }
+
+    Ok((priority, target, parameters))
+}
+
+/// Parses an EUI-48 or EUI-64 address, which is written as hexadecimal
+/// pairs separated by dashes. The caller checks the number of octets.
+fn eui_octets(text: &str) -> Option<Vec<u8>> {
+    text.split('-')
+        .map(|part| if part.len() == 2 { u8::from_str_radix(part, 16).ok() } else { None })
+        .collect()
+}
+
+/// Decodes hexadecimal text into bytes, ignoring case.
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 == 1 || ! text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0 .. text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&text[i .. i + 2], 16).ok())
+        .collect()
+}
+
+/// Decodes text in Base32 with the extended hex alphabet, without padding,
+/// as used for the hashed owner names in NSEC3 records.
+fn decode_base32hex(text: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut buffer = 0_u16;
+    let mut bits = 0;
+
+    for c in text.chars() {
+        let value = c.to_digit(32)?;
+        buffer = (buffer << 5) | u16::try_from(value).ok()?;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            output.push(u8::try_from((buffer >> bits) & 0xFF).ok()?);
+        }
+    }
+
+    // Any leftover bits must be zero padding
+    if buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+
+    Some(output)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hex_decoding() {
+        assert_eq!(decode_hex("DEADbeef"), Some(vec![ 0xde, 0xad, 0xbe, 0xef ]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+
+    #[test]
+    fn base32hex_decoding() {
+        // The hashed owner name of “example” from the RFC 5155 example zone
+        assert_eq!(decode_base32hex("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom"),
+                   Some(vec![ 0x06, 0x53, 0x68, 0xab, 0xee, 0xd7, 0xec, 0x6e, 0x9f, 0xeb,
+                              0xa9, 0x6b, 0x8c, 0x8b, 0xc3, 0xe8, 0xb7, 0x91, 0xf7, 0x16 ]));
+        assert_eq!(decode_base32hex("w"), None);
+    }
+
+    #[test]
+    fn eui48_decoding() {
+        assert_eq!(eui_octets("00-00-5e-00-53-2a"), Some(vec![ 0x00, 0x00, 0x5e, 0x00, 0x53, 0x2a ]));
+        assert_eq!(eui_octets("00-00-5e-00-053"), None);
+        assert_eq!(eui_octets("00-00-5e-00-53-zz"), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Splitting the text of a zone file into entries and tokens.
+
+use super::ZoneErrorKind;
+
+
+/// One entry in a zone file — a directive or a resource record — which is
+/// usually one line, but can span several if it uses parentheses.
+#[derive(PartialEq, Debug)]
+pub(super) struct Entry {
+
+    /// The line that the entry starts on, counting from 1.
+    pub line: usize,
+
+    /// Whether the line began with whitespace, meaning the entry has no
+    /// owner name of its own.
+    pub indented: bool,
+
+    /// The tokens in the entry, with comments and parentheses removed.
+    pub tokens: Vec<Token>,
+}
+
+/// A single whitespace-delimited token in an entry.
+#[derive(PartialEq, Debug, Clone)]
+pub(super) struct Token {
+
+    /// The text of the token, with any quotes removed but its backslash
+    /// escapes left as they are, as what they mean depends on the field.
+    pub text: String,
+
+    /// Whether any part of the token was in quotes.
+    pub quoted: bool,
+}
+
+impl Token {
+
+    /// Whether this token is the given text, without any quotes.
+    pub fn is(&self, text: &str) -> bool {
+        ! self.quoted && self.text == text
+    }
+}
+
+
+/// Splits the text of a zone file into entries. Returns the line number and
+/// reason if a quote or parenthesis is never closed.
+#[allow(clippy::too_many_lines)]
+pub(super) fn entries(input: &str) -> Result<Vec<Entry>, (usize, ZoneErrorKind)> {
+    let mut entries = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    let mut line = 1;
+    let mut at_line_start = true;
+    let mut depth = 0_usize;
+    let mut current: Option<Entry> = None;
+    let mut token: Option<Token> = None;
+    let mut in_quotes = false;
+    let mut quote_line = 0;
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            let token = token.as_mut().expect("no token in quotes");
+
+            match c {
+                '"' => {
+                    in_quotes = false;
+                }
+                '\\' => {
+                    token.text.push(c);
+                    match chars.next() {
+                        Some(next) => {
+                            if next == '\n' { line += 1; }
+                            token.text.push(next);
+                        }
+                        None => {
+                            return Err((quote_line, ZoneErrorKind::UnterminatedString));
+                        }
+                    }
+                }
+                '\n' => {
+                    line += 1;
+                    token.text.push(c);
+                }
+                _ => {
+                    token.text.push(c);
+                }
+            }
+
+            continue;
+        }
+
+        if at_line_start {
+            at_line_start = false;
+
+            if current.is_none() {
+                current = Some(Entry { line, indented: c == ' ' || c == '\t', tokens: Vec::new() });
+            }
+        }
+
+        match c {
+            ' ' | '\t' | '\r' | '\n' | '(' | ')' | ';' => {
+                if let Some(finished) = token.take() {
+                    current.as_mut().expect("no entry").tokens.push(finished);
+                }
+
+                match c {
+                    '(' => {
+                        depth += 1;
+                    }
+                    ')' => {
+                        if depth == 0 {
+                            return Err((line, ZoneErrorKind::UnbalancedParentheses));
+                        }
+                        depth -= 1;
+                    }
+                    ';' => {
+                        while let Some(&next) = chars.peek() {
+                            if next == '\n' { break; }
+                            chars.next();
+                        }
+                    }
+                    '\n' => {
+                        line += 1;
+
+                        if depth == 0 {
+                            at_line_start = true;
+                            if let Some(entry) = current.take() {
+                                if ! entry.tokens.is_empty() {
+                                    entries.push(entry);
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            '"' => {
+                in_quotes = true;
+                quote_line = line;
+                token.get_or_insert_with(|| Token { text: String::new(), quoted: true }).quoted = true;
+            }
+            '\\' => {
+                let token = token.get_or_insert_with(|| Token { text: String::new(), quoted: false });
+                token.text.push(c);
+                if let Some(next) = chars.next() {
+                    if next == '\n' { line += 1; }
+                    token.text.push(next);
+                }
+            }
+            _ => {
+                token.get_or_insert_with(|| Token { text: String::new(), quoted: false }).text.push(c);
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err((quote_line, ZoneErrorKind::UnterminatedString));
+    }
+
+    if depth > 0 {
+        return Err((line, ZoneErrorKind::UnbalancedParentheses));
+    }
+
+    if let Some(mut entry) = current.take() {
+        if let Some(finished) = token.take() {
+            entry.tokens.push(finished);
+        }
+
+        if ! entry.tokens.is_empty() {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn plain(text: &str) -> Token {
+        Token { text: text.into(), quoted: false }
+    }
+
+    fn quoted(text: &str) -> Token {
+        Token { text: text.into(), quoted: true }
+    }
+
+    #[test]
+    fn one_line() {
+        assert_eq!(entries("www IN A 127.0.0.1\n"),
+                   Ok(vec![ Entry { line: 1, indented: false, tokens: vec![ plain("www"), plain("IN"), plain("A"), plain("127.0.0.1") ] } ]));
+    }
+
+    #[test]
+    fn indented_line() {
+        assert_eq!(entries("  A 127.0.0.1"),
+                   Ok(vec![ Entry { line: 1, indented: true, tokens: vec![ plain("A"), plain("127.0.0.1") ] } ]));
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        assert_eq!(entries("; a comment\n\n@ NS ns1 ; another\n"),
+                   Ok(vec![ Entry { line: 3, indented: false, tokens: vec![ plain("@"), plain("NS"), plain("ns1") ] } ]));
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(entries("@ SOA ns1 admin (\n  1 ; serial\n  2 3 4 5 )\nwww A 127.0.0.1"),
+                   Ok(vec![
+                       Entry { line: 1, indented: false, tokens: vec![ plain("@"), plain("SOA"), plain("ns1"), plain("admin"),
+                                                                       plain("1"), plain("2"), plain("3"), plain("4"), plain("5") ] },
+                       Entry { line: 4, indented: false, tokens: vec![ plain("www"), plain("A"), plain("127.0.0.1") ] },
+                   ]));
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(entries("@ TXT \"a ; (b)\" \"c\\\"d\"\n"),
+                   Ok(vec![ Entry { line: 1, indented: false, tokens: vec![ plain("@"), plain("TXT"), quoted("a ; (b)"), quoted("c\\\"d") ] } ]));
+    }
+
+    #[test]
+    fn quotes_mid_token() {
+        assert_eq!(entries("@ HTTPS 1 . alpn=\"h2,h3\""),
+                   Ok(vec![ Entry { line: 1, indented: false, tokens: vec![ plain("@"), plain("HTTPS"), plain("1"), plain("."), quoted("alpn=h2,h3") ] } ]));
+    }
+
+    #[test]
+    fn escaped_specials() {
+        assert_eq!(entries("a\\;b\\ c TXT x"),
+                   Ok(vec![ Entry { line: 1, indented: false, tokens: vec![ plain("a\\;b\\ c"), plain("TXT"), plain("x") ] } ]));
+    }
+
+    #[test]
+    fn unterminated_string() {
+        assert_eq!(entries("@ TXT \"oops\n"),
+                   Err((1, ZoneErrorKind::UnterminatedString)));
+    }
+
+    #[test]
+    fn unclosed_parenthesis() {
+        assert_eq!(entries("@ SOA ( 1 2\n"),
+                   Err((2, ZoneErrorKind::UnbalancedParentheses)));
+    }
+
+    #[test]
+    fn extra_parenthesis() {
+        assert_eq!(entries("@ A 127.0.0.1 )\n"),
+                   Err((1, ZoneErrorKind::UnbalancedParentheses)));
+    }
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "with_serde")]
+
+use std::net::Ipv4Addr;
+
+use dns::{Request, Response, Answer, Flags, Opcode, ErrorCode, Query, Labels, QClass};
+use dns::record::{Record, RecordType, UnknownQtype, A, MX, TXT, NS, SOA};
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+
+#[test]
+fn query_layout() {
+    let query = Query {
+        qname: Labels::encode("lookup.dog").unwrap(),
+        qclass: QClass::IN,
+        qtype: RecordType::MX,
+    };
+
+    assert_eq!(serde_json::to_value(&query).unwrap(), json!({
+        "qname": "lookup.dog.",
+        "qclass": 1,
+        "qtype": 15,
+    }));
+}
+
+#[test]
+fn record_layout() {
+    let record = Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) });
+
+    assert_eq!(serde_json::to_value(&record).unwrap(), json!({
+        "A": { "address": "192.0.2.1" },
+    }));
+}
+
+#[test]
+fn unknown_record_layout() {
+    let record = Record::Other { type_number: UnknownQtype::from(99), bytes: vec![ 1, 2 ] };
+
+    assert_eq!(serde_json::to_value(&record).unwrap(), json!({
+        "Other": { "type_number": 99, "bytes": [ 1, 2 ] },
+    }));
+}
+
+#[test]
+fn flags_layout() {
+    let mut flags = Flags::standard_response();
+    flags.error_code = Some(ErrorCode::BadCookie);
+
+    assert_eq!(serde_json::to_value(flags).unwrap(), json!({
+        "response": true,
+        "opcode": 0,
+        "authoritative": false,
+        "truncated": false,
+        "recursion_desired": true,
+        "recursion_available": true,
+        "authentic_data": false,
+        "checking_disabled": false,
+        "error_code": 23,
+    }));
+}
+
+#[test]
+fn escaped_labels() {
+    let labels = Labels::encode("My\\032Printer\\.2._ipp.local").unwrap();
+    let value = serde_json::to_value(&labels).unwrap();
+
+    assert_eq!(value, json!("My\\032Printer\\.2._ipp.local."));
+    assert_eq!(serde_json::from_value::<Labels>(value).unwrap().iter().collect::<Vec<_>>(),
+               labels.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn root_label() {
+    assert_eq!(serde_json::to_value(Labels::root()).unwrap(),
+               json!("."));
+    assert_eq!(serde_json::from_value::<Labels>(json!(".")).unwrap(),
+               Labels::root());
+}
+
+#[test]
+fn invalid_label() {
+    assert!(serde_json::from_value::<Labels>(json!("one\\256.two")).is_err());
+}
+
+#[test]
+fn invalid_opcode() {
+    assert!(serde_json::from_value::<Opcode>(json!(16)).is_err());
+    assert_eq!(serde_json::from_value::<Opcode>(json!(5)).unwrap(),
+               Opcode::Update);
+}
+
+#[test]
+fn request_round_trip() {
+    let request = Request {
+        transaction_id: 0x1234,
+        flags: Flags::query(),
+        query: Query {
+            qname: Labels::encode("lookup.dog").unwrap(),
+            qclass: QClass::IN,
+            qtype: RecordType::A,
+        },
+        authority: None,
+        additional: Some(Request::additional_record()),
+        signature: None,
+    };
+
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), request);
+}
+
+#[test]
+fn response_round_trip() {
+    let qname = Labels::encode("lookup.dog").unwrap();
+
+    let response = Response {
+        transaction_id: 0x1234,
+        flags: Flags::standard_response(),
+        queries: vec![
+            Query { qname: qname.clone(), qclass: QClass::IN, qtype: RecordType::MX },
+        ],
+        answers: vec![
+            Answer::Standard {
+                qname: qname.clone(),
+                qclass: QClass::IN,
+                ttl: 300,
+                record: Record::MX(MX { preference: 10, exchange: Labels::encode("mail.lookup.dog").unwrap() }),
+            },
+            Answer::Standard {
+                qname: qname.clone(),
+                qclass: QClass::IN,
+                ttl: 300,
+                record: Record::TXT(TXT { messages: vec![ b"v=spf1 -all".to_vec().into_boxed_slice() ] }),
+            },
+        ],
+        authorities: vec![
+            Answer::Standard {
+                qname: qname.clone(),
+                qclass: QClass::IN,
+                ttl: 86400,
+                record: Record::NS(NS { nameserver: Labels::encode("ns1.lookup.dog").unwrap() }),
+            },
+            Answer::Standard {
+                qname,
+                qclass: QClass::IN,
+                ttl: 86400,
+                record: Record::SOA(SOA {
+                    mname: Labels::encode("ns1.lookup.dog").unwrap(),
+                    rname: Labels::encode("hostmaster.lookup.dog").unwrap(),
+                    serial: 2_021_010_101,
+                    refresh_interval: 3600,
+                    retry_interval: 600,
+                    expire_limit: 604_800,
+                    minimum_ttl: 300,
+                }),
+            },
+        ],
+        additionals: vec![
+            Answer::Pseudo { qname: Labels::root(), opt: Request::additional_record() },
+        ],
+        signature: None,
+        malformed: Vec::new(),
+    };
+
+    let json = serde_json::to_string(&response).unwrap();
+    assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), response);
+}
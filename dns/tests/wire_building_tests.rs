@@ -1,5 +1,7 @@
-use dns::{Request, Flags, Query, Labels, QClass};
-use dns::record::RecordType;
+use std::net::Ipv4Addr;
+
+use dns::{Request, Response, Answer, Flags, Opcode, Query, Labels, QClass, Update, Prerequisite, Operation};
+use dns::record::{Record, RecordType, A, CNAME, NS, SOA};
 
 use pretty_assertions::assert_eq;
 
@@ -14,7 +16,9 @@ fn build_request() {
             qclass: QClass::Other(0x42),
             qtype: RecordType::from(0x1234),
         },
+        authority: None,
         additional: Some(Request::additional_record()),
+        signature: None,
     };
 
     let result = vec![
@@ -39,3 +43,270 @@ fn build_request() {
 
     assert_eq!(request.to_bytes().unwrap(), result);
 }
+
+
+
+#[test]
+fn build_ixfr_request() {
+    let request = Request {
+        transaction_id: 0x1234,
+        flags: Flags::query(),
+        query: Query {
+            qname: Labels::encode("rfcs.io").unwrap(),
+            qclass: QClass::IN,
+            qtype: RecordType::IXFR,
+        },
+        authority: Some(SOA {
+            mname: Labels::root(),
+            rname: Labels::root(),
+            serial: 0x0102_0304,
+            refresh_interval: 0,
+            retry_interval: 0,
+            expire_limit: 0,
+            minimum_ttl: 0,
+        }),
+        additional: None,
+        signature: None,
+    };
+
+    let result = vec![
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,  // counts (1, 0, 1, 0)
+
+        // query:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0xfb,  // type IXFR
+        0x00, 0x01,  // class IN
+
+        // SOA record:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // name
+        0x00, 0x06,  // type SOA
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x00,  // TTL
+        0x00, 0x16,  // data length
+        0x00,  // mname
+        0x00,  // rname
+        0x01, 0x02, 0x03, 0x04,  // serial
+        0x00, 0x00, 0x00, 0x00,  // refresh interval
+        0x00, 0x00, 0x00, 0x00,  // retry interval
+        0x00, 0x00, 0x00, 0x00,  // expire limit
+        0x00, 0x00, 0x00, 0x00,  // minimum TTL
+    ];
+
+    assert_eq!(request.to_bytes().unwrap(), result);
+    assert_eq!(Request::from_bytes(&result), Ok(request));
+}
+
+#[test]
+fn build_notify_request() {
+    let mut flags = Flags::query();
+    flags.opcode = Opcode::Notify;
+    flags.recursion_desired = false;
+    flags.authoritative = true;
+
+    let request = Request {
+        transaction_id: 0x1234,
+        flags,
+        query: Query {
+            qname: Labels::encode("rfcs.io").unwrap(),
+            qclass: QClass::IN,
+            qtype: RecordType::SOA,
+        },
+        authority: None,
+        additional: None,
+        signature: None,
+    };
+
+    let result = vec![
+        0x12, 0x34,  // transaction ID
+        0x24, 0x00,  // flags (notify, authoritative)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (1, 0, 0, 0)
+
+        // query:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0x06,  // type SOA
+        0x00, 0x01,  // class IN
+    ];
+
+    assert_eq!(request.to_bytes().unwrap(), result);
+}
+
+
+#[test]
+fn opcodes_round_trip() {
+    for &opcode in &[ Opcode::Query, Opcode::IQuery, Opcode::Status, Opcode::Notify, Opcode::Update, Opcode::DSO, Opcode::Other(3), Opcode::Other(15) ] {
+        let mut flags = Flags::query();
+        flags.opcode = opcode;
+        assert_eq!(Flags::from_u16(flags.to_u16()), flags);
+    }
+}
+
+
+#[test]
+fn build_response() {
+    let response = Response {
+        transaction_id: 0x0dcd,
+        flags: Flags::standard_response(),
+        queries: vec![
+            Query {
+                qname: Labels::encode("dns.lookup.dog").unwrap(),
+                qclass: QClass::IN,
+                qtype: RecordType::A,
+            },
+        ],
+        answers: vec![
+            Answer::Standard {
+                qname: Labels::encode("dns.lookup.dog").unwrap(),
+                qclass: QClass::IN,
+                ttl: 933,
+                record: Record::A(A {
+                    address: Ipv4Addr::new(138, 68, 117, 94),
+                }),
+            },
+        ],
+        authorities: vec![
+            Answer::Standard {
+                qname: Labels::encode("lookup.dog").unwrap(),
+                qclass: QClass::IN,
+                ttl: 300,
+                record: Record::NS(NS {
+                    nameserver: Labels::encode("ns.lookup.dog").unwrap(),
+                }),
+            },
+        ],
+        additionals: vec![
+            Answer::Pseudo {
+                qname: Labels::root(),
+                opt: Request::additional_record(),
+            },
+        ],
+        signature: None,
+        malformed: Vec::new(),
+    };
+
+    let result = vec![
+        0x0d, 0xcd,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01,  // counts (1, 1, 1, 1)
+
+        // the query:
+        0x03, 0x64, 0x6e, 0x73, 0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03,
+        0x64, 0x6f, 0x67, 0x00,  // "dns.lookup.dog."
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // the answer:
+        0xc0, 0x0c,  // name, pointing back to position 0x0c (12)
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x03, 0xa5,  // TTL (933 seconds)
+        0x00, 0x04,  // record data length 4
+        0x8a, 0x44, 0x75, 0x5e,  // record data (138.68.117.94)
+
+        // the authority:
+        0xc0, 0x10,  // name, pointing back to position 0x10 (16)
+        0x00, 0x02,  // type NS
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x01, 0x2c,  // TTL (300 seconds)
+        0x00, 0x05,  // record data length 5
+        0x02, 0x6e, 0x73, 0xc0, 0x10,  // record data ("ns" then a pointer)
+
+        // the additional:
+        0x00,        // no name
+        0x00, 0x29,  // type OPT
+        0x02, 0x00,  // UDP payload size (512)
+        0x00,        // higher bits
+        0x00,        // EDNS(0) version
+        0x00, 0x00,  // more flags
+        0x00, 0x00,  // no data
+    ];
+
+    assert_eq!(response.to_bytes().unwrap(), result);
+    assert_eq!(Response::from_bytes(&result), Ok(response));
+}
+
+
+#[test]
+fn build_answer() {
+    let answer = Answer::Standard {
+        qname: Labels::encode("lookup.dog").unwrap(),
+        qclass: QClass::IN,
+        ttl: 300,
+        record: Record::CNAME(CNAME {
+            domain: Labels::encode("dns.lookup.dog").unwrap(),
+        }),
+    };
+
+    let result = vec![
+        0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03, 0x64, 0x6f, 0x67, 0x00,  // "lookup.dog."
+        0x00, 0x05,  // type CNAME
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x01, 0x2c,  // TTL (300 seconds)
+        0x00, 0x10,  // record data length 16
+        0x03, 0x64, 0x6e, 0x73, 0x06, 0x6c, 0x6f, 0x6f, 0x6b, 0x75, 0x70, 0x03,
+        0x64, 0x6f, 0x67, 0x00,  // record data, written in full
+    ];
+
+    assert_eq!(answer.to_bytes().unwrap(), result);
+}
+
+
+#[test]
+fn build_update() {
+    let update = Update {
+        transaction_id: 0x1234,
+        zone: Labels::encode("dom.ain").unwrap(),
+        zone_class: QClass::IN,
+        prerequisites: vec![
+            Prerequisite::NameNotInUse { name: Labels::encode("new.dom.ain").unwrap() },
+        ],
+        operations: vec![
+            Operation::AddRecord {
+                name: Labels::encode("new.dom.ain").unwrap(),
+                ttl: 300,
+                record: Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) }),
+            },
+            Operation::DeleteRRset {
+                name: Labels::encode("old.dom.ain").unwrap(),
+                record_type: RecordType::NS,
+            },
+        ],
+        signature: None,
+    };
+
+    let result = vec![
+        0x12, 0x34,  // transaction ID
+        0x28, 0x00,  // flags (update)
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00,  // counts (1, 1, 2, 0)
+
+        // zone:
+        0x03, 0x64, 0x6f, 0x6d, 0x03, 0x61, 0x69, 0x6e, 0x00,  // "dom.ain."
+        0x00, 0x06,  // type SOA
+        0x00, 0x01,  // class IN
+
+        // prerequisite, that the name is not in use:
+        0x03, 0x6e, 0x65, 0x77, 0xc0, 0x0c,  // "new" then a pointer to "dom.ain."
+        0x00, 0xff,  // type ANY
+        0x00, 0xfe,  // class NONE
+        0x00, 0x00, 0x00, 0x00,  // TTL
+        0x00, 0x00,  // no data
+
+        // operation, to add a record:
+        0xc0, 0x19,  // pointer to "new.dom.ain."
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x01, 0x2c,  // TTL (300 seconds)
+        0x00, 0x04,  // record data length 4
+        0xc0, 0x00, 0x02, 0x01,  // record data
+
+        // operation, to delete an RRset:
+        0x03, 0x6f, 0x6c, 0x64, 0xc0, 0x0c,  // "old" then a pointer to "dom.ain."
+        0x00, 0x02,  // type NS
+        0x00, 0xff,  // class ANY
+        0x00, 0x00, 0x00, 0x00,  // TTL
+        0x00, 0x00,  // no data
+    ];
+
+    assert_eq!(update.to_bytes().unwrap(), result);
+}
@@ -1,7 +1,7 @@
 use std::net::Ipv4Addr;
 
-use dns::{Response, Query, Answer, Labels, Flags, Opcode, QClass};
-use dns::record::{Record, A, CNAME, OPT, SOA, UnknownQtype, RecordType};
+use dns::{Request, Response, Query, Answer, Labels, Flags, Opcode, QClass, Section, Signature, ErrorCode, WireError, MandatedLength, MalformedRecord};
+use dns::record::{Record, A, CNAME, OPT, SOA, TSIG, UnknownQtype, RecordType};
 
 use pretty_assertions::assert_eq;
 
@@ -82,10 +82,12 @@ fn parse_response_standard() {
                     higher_bits: 0,
                     edns0_version: 0,
                     flags: 0,
-                    data: vec![],
+                    options: vec![],
                 },
             },
         ],
+        signature: None,
+        malformed: Vec::new(),
     };
 
     assert_eq!(Response::from_bytes(buf), Ok(response));
@@ -148,6 +150,8 @@ fn parse_response_with_mixed_string() {
         ],
         authorities: vec![],
         additionals: vec![],
+        signature: None,
+        malformed: Vec::new(),
     };
 
     assert_eq!(Response::from_bytes(buf), Ok(response));
@@ -261,11 +265,421 @@ fn parse_response_with_multiple_additionals() {
                     higher_bits: 0,
                     edns0_version: 0,
                     flags: 0,
-                    data: vec![],
+                    options: vec![],
                 },
             },
         ],
+        signature: None,
+        malformed: Vec::new(),
     };
 
     assert_eq!(Response::from_bytes(buf), Ok(response));
 }
+
+
+#[test]
+fn parse_response_extended_error_code() {
+    let buf = &[
+        0x4a, 0x1b,  // transaction ID
+        0x81, 0x87,  // flags (standard query, response, YXRRset)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (0, 0, 0, 1)
+
+        // OPT record:
+        0x00,  // name
+        0x00, 0x29,  // type OPT
+        0x04, 0xd0,  // UDP payload size (1232)
+        0x01,  // higher bits
+        0x00,  // EDNS(0) version
+        0x00, 0x00,  // more flags
+        0x00, 0x00,  // no data
+    ];
+
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.flags.error_code, Some(ErrorCode::BadCookie));
+    assert!(response.is_bad_cookie());
+
+    // only the lower four bits get written back to the header
+    assert_eq!(response.to_bytes().unwrap(), buf.to_vec());
+}
+
+
+#[test]
+fn parse_response_extended_error_code_without_header_bits() {
+    let buf = &[
+        0x4a, 0x1c,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (0, 0, 0, 1)
+
+        // OPT record:
+        0x00,  // name
+        0x00, 0x29,  // type OPT
+        0x04, 0xd0,  // UDP payload size (1232)
+        0x01,  // higher bits
+        0x00,  // EDNS(0) version
+        0x00, 0x00,  // more flags
+        0x00, 0x00,  // no data
+    ];
+
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.flags.error_code, Some(ErrorCode::BadVersion));
+    assert!(!response.is_bad_cookie());
+}
+
+
+fn buf_with_bad_record() -> Vec<u8> {
+    vec![
+        0xba, 0xad,  // transaction ID
+        0x81, 0x80,  // flags (standard query, response, no error)
+        0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,  // counts (1, 3, 0, 0)
+
+        // the query:
+        0x03, 0x64, 0x6f, 0x67, 0x00,  // "dog."
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // the first answer:
+        0xc0, 0x0c,  // backtrack to "dog." at position 0x0c (12)
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60)
+        0x00, 0x04,  // data length 4
+        0xc0, 0x00, 0x02, 0x01,  // IP address
+
+        // the second answer, which is one byte too long:
+        0xc0, 0x0c,  // backtrack to "dog." at position 0x0c (12)
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60)
+        0x00, 0x05,  // data length 5
+        0xc0, 0x00, 0x02, 0x02, 0xff,  // IP address and an extra byte
+
+        // the third answer:
+        0xc0, 0x0c,  // backtrack to "dog." at position 0x0c (12)
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60)
+        0x00, 0x04,  // data length 4
+        0xc0, 0x00, 0x02, 0x03,  // IP address
+    ]
+}
+
+fn address_answer(last_octet: u8) -> Answer {
+    Answer::Standard {
+        qname: Labels::encode("dog").unwrap(),
+        qclass: QClass::IN,
+        ttl: 60,
+        record: Record::A(A { address: Ipv4Addr::new(192, 0, 2, last_octet) }),
+    }
+}
+
+
+#[test]
+fn parse_response_with_bad_record() {
+    assert_eq!(Response::from_bytes(&buf_with_bad_record()),
+               Err(WireError::WrongRecordLength { stated_length: 5, mandated_length: MandatedLength::Exactly(4) }));
+}
+
+
+#[test]
+fn parse_response_leniently() {
+    let response = Response::from_bytes_lenient(&buf_with_bad_record()).unwrap();
+
+    assert_eq!(response.answers, vec![ address_answer(1), address_answer(3) ]);
+    assert_eq!(response.malformed, vec![
+        MalformedRecord {
+            section: Section::Answer,
+            index: 1,
+            offset: 37,
+            error: WireError::WrongRecordLength { stated_length: 5, mandated_length: MandatedLength::Exactly(4) },
+        },
+    ]);
+}
+
+
+#[test]
+fn parse_response_leniently_stops_when_length_is_unreadable() {
+    let mut buf = buf_with_bad_record();
+    buf.truncate(buf.len() - 8);
+
+    let response = Response::from_bytes_lenient(&buf).unwrap();
+
+    assert_eq!(response.answers, vec![ address_answer(1) ]);
+    assert_eq!(response.malformed, vec![
+        MalformedRecord {
+            section: Section::Answer,
+            index: 1,
+            offset: 37,
+            error: WireError::WrongRecordLength { stated_length: 5, mandated_length: MandatedLength::Exactly(4) },
+        },
+        MalformedRecord {
+            section: Section::Answer,
+            index: 2,
+            offset: 54,
+            error: WireError::IO,
+        },
+    ]);
+}
+
+
+#[test]
+fn parse_response_leniently_without_header() {
+    assert_eq!(Response::from_bytes_lenient(&[ 0xba, 0xad, 0x81 ]),
+               Err(WireError::IO));
+}
+
+
+#[test]
+fn parse_request() {
+    let buf = &[
+        0xce, 0xac,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (1, 0, 0, 1)
+
+        // query:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0x10,  // type TXT
+        0x00, 0x01,  // class IN
+
+        // OPT record:
+        0x00,  // name
+        0x00, 0x29,  // type OPT
+        0x04, 0xd0,  // UDP payload size (1232)
+        0x00,  // higher bits
+        0x00,  // EDNS(0) version
+        0x80, 0x00,  // more flags (DO bit set)
+        0x00, 0x00,  // no data
+    ];
+
+    let request = Request {
+        transaction_id: 0xceac,
+        flags: Flags::query(),
+        query: Query {
+            qname: Labels::encode("rfcs.io").unwrap(),
+            qclass: QClass::IN,
+            qtype: RecordType::TXT,
+        },
+        authority: None,
+        additional: Some(OPT {
+            udp_payload_size: 1232,
+            higher_bits: 0,
+            edns0_version: 0,
+            flags: OPT::DNSSEC_OK,
+            options: vec![],
+        }),
+        signature: None,
+    };
+
+    assert_eq!(Request::from_bytes(buf), Ok(request));
+}
+
+
+#[test]
+fn parse_request_without_opt() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (1, 0, 0, 0)
+
+        // query:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+    ];
+
+    let request = Request::from_bytes(buf).unwrap();
+    assert_eq!(request.additional, None);
+    assert_eq!(request.to_bytes().unwrap(), buf.to_vec());
+}
+
+
+#[test]
+fn parse_request_with_two_questions() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (2, 0, 0, 0)
+
+        // queries:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0xc0, 0x0c,  // qname, the same as before
+        0x00, 0x1c,  // type AAAA
+        0x00, 0x01,  // class IN
+    ];
+
+    assert_eq!(Request::from_bytes(buf),
+               Err(WireError::WrongSectionCount { section: Section::Question, count: 2 }));
+}
+
+
+#[test]
+fn parse_request_with_answer() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,  // counts (1, 1, 0, 0)
+    ];
+
+    assert_eq!(Request::from_bytes(buf),
+               Err(WireError::WrongSectionCount { section: Section::Answer, count: 1 }));
+}
+
+
+#[test]
+fn parse_request_with_standard_additional() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (1, 0, 0, 1)
+
+        // query:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // additional:
+        0xc0, 0x0c,  // name
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+        0x00, 0x00, 0x00, 0x3c,  // TTL (60 seconds)
+        0x00, 0x04,  // record data length 4
+        0x7f, 0x00, 0x00, 0x01,  // record data (127.0.0.1)
+    ];
+
+    assert_eq!(Request::from_bytes(buf),
+               Err(WireError::WrongSectionCount { section: Section::Additional, count: 1 }));
+}
+
+
+#[test]
+fn parse_request_with_two_opts() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,  // counts (1, 0, 0, 2)
+
+        // query:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // OPT records:
+        0x00, 0x00, 0x29, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x29, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    assert_eq!(Request::from_bytes(buf),
+               Err(WireError::WrongSectionCount { section: Section::Additional, count: 2 }));
+}
+
+
+#[test]
+fn parse_signed_request() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,  // counts (1, 0, 0, 1)
+
+        // query:
+        0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+        0x00, 0x01,  // type A
+        0x00, 0x01,  // class IN
+
+        // TSIG record:
+        0x03, 0x6b, 0x65, 0x79, 0x00,  // name
+        0x00, 0xfa,  // type TSIG
+        0x00, 0xff,  // class ANY
+        0x00, 0x00, 0x00, 0x00,  // TTL
+        0x00, 0x1f,  // record data length 31
+        0x0b, 0x68, 0x6d, 0x61, 0x63, 0x2d, 0x73, 0x68, 0x61, 0x32, 0x35, 0x36, 0x00,  // algorithm
+        0x00, 0x00, 0x60, 0x00, 0x00, 0x00,  // time signed
+        0x01, 0x2c,  // fudge
+        0x00, 0x02, 0xab, 0xcd,  // MAC
+        0x43, 0x21,  // original ID
+        0x00, 0x00,  // error
+        0x00, 0x00,  // other data
+    ];
+
+    let request = Request::from_bytes(buf).unwrap();
+    assert_eq!(request.additional, None);
+    assert_eq!(request.signature, Some(Signature {
+        key_name: Labels::encode("key").unwrap(),
+        tsig: TSIG {
+            algorithm: Labels::encode("hmac-sha256").unwrap(),
+            time_signed: 0x6000_0000,
+            fudge: 300,
+            mac: vec![ 0xab, 0xcd ],
+            original_id: 0x4321,
+            error: 0,
+            other_data: vec![],
+        },
+        signed_bytes: vec![
+            0x43, 0x21,  // original ID
+            0x01, 0x00,  // flags (standard query)
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts, without the TSIG record
+            0x04, 0x72, 0x66, 0x63, 0x73, 0x02, 0x69, 0x6f, 0x00,  // qname
+            0x00, 0x01,  // type A
+            0x00, 0x01,  // class IN
+        ],
+    }));
+
+    assert_eq!(request.to_bytes().unwrap(), buf.to_vec());
+}
+
+
+#[test]
+fn parse_signed_response() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x81, 0x80,  // flags (standard response)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,  // counts (0, 0, 0, 2)
+
+        // OPT record:
+        0x00,  // name
+        0x00, 0x29,  // type OPT
+        0x02, 0x00,  // UDP payload size
+        0x00,  // higher bits
+        0x00,  // EDNS(0) version
+        0x00, 0x00,  // more flags
+        0x00, 0x00,  // no data
+
+        // TSIG record:
+        0x03, 0x6b, 0x65, 0x79, 0x00,  // name
+        0x00, 0xfa,  // type TSIG
+        0x00, 0xff,  // class ANY
+        0x00, 0x00, 0x00, 0x00,  // TTL
+        0x00, 0x1d,  // record data length 29
+        0x0b, 0x68, 0x6d, 0x61, 0x63, 0x2d, 0x73, 0x68, 0x61, 0x32, 0x35, 0x36, 0x00,  // algorithm
+        0x00, 0x00, 0x60, 0x00, 0x00, 0x00,  // time signed
+        0x01, 0x2c,  // fudge
+        0x00, 0x00,  // no MAC
+        0x12, 0x34,  // original ID
+        0x00, 0x11,  // error (BADKEY)
+        0x00, 0x00,  // other data
+    ];
+
+    let response = Response::from_bytes(buf).unwrap();
+    assert_eq!(response.additionals.len(), 1);
+
+    let signature = response.signature.unwrap();
+    assert_eq!(signature.key_name, Labels::encode("key").unwrap());
+    assert_eq!(signature.tsig.error, TSIG::BADKEY);
+    assert_eq!(signature.signed_bytes, buf[.. 23].iter().enumerate()
+        .map(|(index, byte)| if index == 11 { 0x01 } else { *byte })
+        .collect::<Vec<_>>());
+}
+
+
+#[test]
+fn parse_truncated_request() {
+    let buf = &[
+        0x12, 0x34,  // transaction ID
+        0x01, 0x00,  // flags (standard query)
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // counts (1, 0, 0, 0)
+        0x04, 0x72, 0x66,  // the start of a qname
+    ];
+
+    assert_eq!(Request::from_bytes(buf),
+               Err(WireError::IO));
+}
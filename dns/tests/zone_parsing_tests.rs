@@ -0,0 +1,190 @@
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use dns::{Answer, Labels, QClass, ZoneError, ZoneErrorKind, parse_zone, read_zone_file};
+use dns::record::*;
+
+use pretty_assertions::assert_eq;
+
+
+fn name(input: &str) -> Labels {
+    Labels::encode(input).unwrap()
+}
+
+fn answer(qname: &str, ttl: u32, record: Record) -> Answer {
+    Answer::Standard { qname: name(qname), qclass: QClass::IN, ttl, record }
+}
+
+
+#[test]
+fn parse_example_zone() {
+    let zone = r#"
+$ORIGIN example.com.
+$TTL 1h
+@       IN  SOA ns1 hostmaster (
+                2024010101 ; serial
+                2h         ; refresh
+                30m        ; retry
+                2w         ; expire
+                300 )      ; minimum
+
+        IN  NS    ns1
+        IN  MX    10 mail.example.net.
+        IN  TXT   "v=spf1 -all" "second \"string\""
+ns1  60 IN  A     192.0.2.1
+        IN  AAAA  2001:db8::1
+www     IN  CNAME @
+_sip._tcp   SRV   10 60 5060 sip
+    "#;
+
+    let answers = parse_zone(zone, &Labels::root()).unwrap();
+
+    assert_eq!(answers, vec![
+        answer("example.com", 3600, Record::SOA(SOA {
+            mname: name("ns1.example.com"),
+            rname: name("hostmaster.example.com"),
+            serial: 2024010101,
+            refresh_interval: 7200,
+            retry_interval: 1800,
+            expire_limit: 1_209_600,
+            minimum_ttl: 300,
+        })),
+        answer("example.com", 3600, Record::NS(NS { nameserver: name("ns1.example.com") })),
+        answer("example.com", 3600, Record::MX(MX { preference: 10, exchange: name("mail.example.net") })),
+        answer("example.com", 3600, Record::TXT(TXT { messages: vec![
+            Box::new(*b"v=spf1 -all"),
+            Box::new(*b"second \"string\""),
+        ] })),
+        answer("ns1.example.com", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) })),
+        answer("ns1.example.com", 3600, Record::AAAA(AAAA { address: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1) })),
+        answer("www.example.com", 3600, Record::CNAME(CNAME { domain: name("example.com") })),
+        answer("_sip._tcp.example.com", 3600, Record::SRV(SRV { priority: 10, weight: 60, port: 5060, target: name("sip.example.com") })),
+    ]);
+}
+
+#[test]
+fn parse_generic_data() {
+    let zone = "a 60 IN A \\# 4 C0000201\n\
+                b 60 CLASS32 TYPE731 \\# 6 abcd (\n\
+                  ef012345 )\n\
+                c 60 IN TYPE62347 \\# 0\n";
+
+    let answers = parse_zone(zone, &name("dom.ain")).unwrap();
+
+    assert_eq!(answers, vec![
+        answer("a.dom.ain", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) })),
+        Answer::Standard {
+            qname: name("b.dom.ain"),
+            qclass: QClass::Other(32),
+            ttl: 60,
+            record: Record::Other { type_number: UnknownQtype::UnheardOf(731), bytes: vec![ 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45 ] },
+        },
+        Answer::Standard {
+            qname: name("c.dom.ain"),
+            qclass: QClass::IN,
+            ttl: 60,
+            record: Record::Other { type_number: UnknownQtype::UnheardOf(62347), bytes: vec![] },
+        },
+    ]);
+}
+
+#[test]
+fn parse_generic_data_wrong_length() {
+    assert_eq!(parse_zone("a 60 IN A \\# 5 C0000201", &name("dom.ain")),
+               Err(ZoneError { path: None, line: 1, kind: ZoneErrorKind::WrongGenericLength { stated_length: 5, actual_length: 4 } }));
+}
+
+#[test]
+fn parse_type_without_text_format() {
+    let afsdb = RecordType::from_type_name("AFSDB").unwrap();
+
+    assert_eq!(parse_zone("a 60 IN AFSDB 1 afs", &name("dom.ain")),
+               Err(ZoneError { path: None, line: 1, kind: ZoneErrorKind::UnsupportedType(afsdb) }));
+}
+
+#[test]
+fn parse_every_type() {
+    let zone = r#"
+$TTL 300
+caa         CAA     0 issue "letsencrypt.org"
+dnskey      DNSKEY  257 3 ECDSAP256SHA256 AQID BAU=
+ds          DS      12345 13 2 ABCDEF01 23456789
+eui48       EUI48   00-00-5e-00-53-2a
+eui64       EUI64   00-00-5e-ef-10-00-00-2a
+hinfo       HINFO   "Generic PC" Linux
+https       HTTPS   1 . alpn="h2,h3" port=8443 ipv4hint=192.0.2.1
+loc         LOC     51 30 12.748 N 0 7 39.611 W 0.00m 1m 10000m 10m
+naptr       NAPTR   100 10 "S" "SIP+D2U" "" _sip._udp
+nsec        NSEC    host.dom.ain. A MX RRSIG NSEC TYPE1234
+nsec3       NSEC3   1 1 12 aabbccdd 0p9mhaveqvm6t7vbl5lop2u3t2rp3tom A RRSIG
+nsec3param  NSEC3PARAM 1 0 0 -
+openpgpkey  OPENPGPKEY AQID
+ptr         PTR     host
+rrsig       RRSIG   A 13 2 3600 20210105014640 1609811200 12345 dom.ain. AQIDBA==
+sshfp       SSHFP   4 2 DEADBEEF
+tlsa        TLSA    3 1 1 0123 4567
+uri         URI     10 1 "https://dom.ain/"
+    "#;
+
+    let answers = parse_zone(zone, &name("dom.ain")).unwrap();
+    let types = answers.iter().map(|a| match a {
+        Answer::Standard { record, .. } => record.record_type().to_string(),
+        Answer::Pseudo { .. }           => unreachable!(),
+    }).collect::<Vec<_>>();
+
+    assert_eq!(types, vec![
+        "CAA", "DNSKEY", "DS", "EUI48", "EUI64", "HINFO", "HTTPS", "LOC", "NAPTR", "NSEC",
+        "NSEC3", "NSEC3PARAM", "OPENPGPKEY", "PTR", "RRSIG", "SSHFP", "TLSA", "URI",
+    ]);
+
+    // Every record should also be able to be written to the wire
+    for answer in &answers {
+        assert!(answer.to_bytes().is_ok(), "Could not write {:?}", answer);
+    }
+}
+
+#[test]
+fn parse_included_file() {
+    let directory = std::env::temp_dir().join(format!("dog-zone-test-{}", std::process::id()));
+    fs::create_dir_all(directory.join("sub")).unwrap();
+
+    fs::write(directory.join("main.zone"), "\
+$TTL 60
+www A 192.0.2.1
+$INCLUDE sub/hosts.zone hosts
+api A 192.0.2.3
+").unwrap();
+
+    fs::write(directory.join("sub/hosts.zone"), "\
+db A 192.0.2.2
+").unwrap();
+
+    let result = read_zone_file(&directory.join("main.zone"), &name("dom.ain"));
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(result, Ok(vec![
+        answer("www.dom.ain", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 1) })),
+        answer("db.hosts.dom.ain", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 2) })),
+        answer("api.dom.ain", 60, Record::A(A { address: Ipv4Addr::new(192, 0, 2, 3) })),
+    ]));
+}
+
+#[test]
+fn parse_missing_included_file() {
+    let path = std::env::temp_dir().join("dog-zone-test-nonexistent.zone");
+    let result = read_zone_file(&path, &name("dom.ain"));
+
+    assert!(matches!(result, Err(ZoneError { line: 0, kind: ZoneErrorKind::IO(_), .. })));
+}
+
+#[test]
+fn parse_invalid_field() {
+    assert_eq!(parse_zone("\n\nwww 60 IN A 192.0.2.256", &name("dom.ain")),
+               Err(ZoneError { path: None, line: 3, kind: ZoneErrorKind::InvalidField { field: "address", value: "192.0.2.256".into() } }));
+}
+
+#[test]
+fn parse_short_eui48() {
+    assert_eq!(parse_zone("eui 60 IN EUI48 00-00-5e-00-53", &name("dom.ain")),
+               Err(ZoneError { path: None, line: 1, kind: ZoneErrorKind::InvalidField { field: "address", value: "00-00-5e-00-53".into() } }));
+}
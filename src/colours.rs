@@ -12,24 +12,35 @@ pub struct Colours {
     pub answer: Style,
     pub authority: Style,
     pub additional: Style,
+    pub removed: Style,
+    pub added: Style,
+    pub malformed: Style,
 
     pub a: Style,
     pub aaaa: Style,
     pub caa: Style,
     pub cname: Style,
+    pub dnskey: Style,
+    pub ds: Style,
     pub eui48: Style,
     pub eui64: Style,
     pub hinfo: Style,
+    pub https: Style,
     pub loc: Style,
     pub mx: Style,
     pub ns: Style,
     pub naptr: Style,
+    pub nsec: Style,
+    pub nsec3: Style,
+    pub nsec3param: Style,
     pub openpgpkey: Style,
     pub opt: Style,
     pub ptr: Style,
+    pub rrsig: Style,
     pub sshfp: Style,
     pub soa: Style,
     pub srv: Style,
+    pub svcb: Style,
     pub tlsa: Style,
     pub txt: Style,
     pub uri: Style,
@@ -47,24 +58,35 @@ impl Colours {
             answer: Style::default(),
             authority: Cyan.normal(),
             additional: Green.normal(),
+            removed: Red.bold(),
+            added: Green.bold(),
+            malformed: White.on(Red),
 
             a: Green.bold(),
             aaaa: Green.bold(),
             caa: Red.normal(),
             cname: Yellow.normal(),
+            dnskey: Purple.bold(),
+            ds: Purple.bold(),
             eui48: Yellow.normal(),
             eui64: Yellow.bold(),
             hinfo: Yellow.normal(),
+            https: Cyan.bold(),
             loc: Yellow.normal(),
             mx: Cyan.normal(),
             naptr: Green.normal(),
+            nsec: Blue.normal(),
+            nsec3: Blue.normal(),
+            nsec3param: Blue.normal(),
             ns: Red.normal(),
             openpgpkey: Cyan.normal(),
             opt: Purple.normal(),
             ptr: Red.normal(),
+            rrsig: Purple.normal(),
             sshfp: Cyan.normal(),
             soa: Purple.normal(),
             srv: Cyan.normal(),
+            svcb: Cyan.bold(),
             tlsa: Yellow.normal(),
             txt: Yellow.normal(),
             uri: Yellow.normal(),
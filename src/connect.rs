@@ -33,14 +33,15 @@ impl TransportType {
 
     /// Creates a boxed `Transport` depending on the transport type. The
     /// parameter will be a URL for the HTTPS transport type, and a
-    /// stringified address for the others.
-    pub fn make_transport(self, param: String) -> Box<dyn Transport> {
+    /// stringified address for the others. A lenient transport keeps the
+    /// records that fail to parse instead of rejecting the response.
+    pub fn make_transport(self, param: String, lenient: bool) -> Box<dyn Transport> {
         match self {
-            Self::Automatic  => Box::new(AutoTransport::new(param)),
-            Self::UDP        => Box::new(UdpTransport::new(param)),
-            Self::TCP        => Box::new(TcpTransport::new(param)),
-            Self::TLS        => Box::new(TlsTransport::new(param)),
-            Self::HTTPS      => Box::new(HttpsTransport::new(param)),
+            Self::Automatic  => Box::new(AutoTransport::new(param).lenient(lenient)),
+            Self::UDP        => Box::new(UdpTransport::new(param).lenient(lenient)),
+            Self::TCP        => Box::new(TcpTransport::new(param).lenient(lenient)),
+            Self::TLS        => Box::new(TlsTransport::new(param).lenient(lenient)),
+            Self::HTTPS      => Box::new(HttpsTransport::new(param).lenient(lenient)),
         }
     }
 }
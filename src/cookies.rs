@@ -0,0 +1,163 @@
+//! Keeping track of the server cookies that nameservers send back, so they
+//! can be replayed in later requests.
+
+use std::collections::HashMap;
+
+use log::*;
+
+use dns::{Request, Response};
+use dns::record::Cookie;
+use dns_transport::{Transport, Error as TransportError};
+
+use crate::tsig::Key;
+
+
+/// The server cookies that have been received so far, keyed by the
+/// nameserver that sent them.
+///
+/// # References
+///
+/// - [RFC 7873 §5.3](https://tools.ietf.org/html/rfc7873#section-5.3) —
+///   Domain Name System (DNS) Cookies (May 2016)
+#[derive(PartialEq, Debug, Default)]
+pub struct CookieJar {
+    server_cookies: HashMap<String, Vec<u8>>,
+}
+
+impl CookieJar {
+
+    /// Sends the request to the nameserver, filling in any server cookie it
+    /// gave us earlier. If the server rejects the request with `BADCOOKIE`
+    /// but hands over a fresh server cookie, the request is sent once more
+    /// with the new one. Each time, the request gets signed with the TSIG
+    /// key, if there is one, after its cookie has been filled in.
+    pub fn send(&mut self, transport: &dyn Transport, nameserver: &str, request: &mut Request, key: Option<&Key>) -> Result<Response, TransportError> {
+        self.replay(nameserver, request);
+        sign(request, key);
+        let response = transport.send(request)?;
+
+        if self.store(nameserver, request, &response) && response.is_bad_cookie() {
+            info!("Server {} sent BADCOOKIE, retrying with its server cookie", nameserver);
+            self.replay(nameserver, request);
+            sign(request, key);
+
+            let response = transport.send(request)?;
+            self.store(nameserver, request, &response);
+            return Ok(response);
+        }
+
+        Ok(response)
+    }
+
+    /// Fills in the server cookie of the request’s Cookie option, if it has
+    /// one and the nameserver has sent us a server cookie before.
+    fn replay(&self, nameserver: &str, request: &mut Request) {
+        if let (Some(cookie), Some(server)) = (request.cookie_mut(), self.server_cookies.get(nameserver)) {
+            debug!("Replaying server cookie for {}", nameserver);
+            cookie.server = Some(server.clone());
+        }
+    }
+
+    /// Remembers the server cookie in the response, returning whether there
+    /// was one. It is ignored if the client cookie that comes back with it
+    /// does not match the one that was sent.
+    fn store(&mut self, nameserver: &str, request: &Request, response: &Response) -> bool {
+        let sent = match request.cookie() {
+            Some(cookie)  => cookie.client,
+            None          => return false,
+        };
+
+        match response.cookie() {
+            Some(cookie) if cookie.client != sent => {
+                warn!("Server {} sent back a different client cookie", nameserver);
+                false
+            }
+            Some(Cookie { server: Some(server), .. }) => {
+                self.server_cookies.insert(nameserver.into(), server.clone());
+                true
+            }
+            _ => {
+                false
+            }
+        }
+    }
+}
+
+/// Signs the request with the key, if there is one.
+fn sign(request: &mut Request, key: Option<&Key>) {
+    if let Some(key) = key {
+        key.sign_request(request);
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use dns::record::EDNSOption;
+
+    fn request(client: [u8; 8]) -> Request {
+        let mut opt = Request::additional_record();
+        opt.options.push(EDNSOption::Cookie(Cookie { client, server: None }));
+
+        Request {
+            transaction_id: 0x1234,
+            flags: dns::Flags::query(),
+            query: dns::Query {
+                qname: dns::Labels::encode("dom.ain").unwrap(),
+                qtype: dns::record::RecordType::A,
+                qclass: dns::QClass::IN,
+            },
+            authority: None,
+            additional: Some(opt),
+            signature: None,
+        }
+    }
+
+    fn response(cookie: Cookie) -> Response {
+        let mut opt = Request::additional_record();
+        opt.options.push(EDNSOption::Cookie(cookie));
+
+        Response {
+            transaction_id: 0x1234,
+            flags: dns::Flags::standard_response(),
+            queries: Vec::new(),
+            answers: Vec::new(),
+            authorities: Vec::new(),
+            additionals: vec![ dns::Answer::Pseudo { qname: dns::Labels::root(), opt } ],
+            signature: None,
+            malformed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn replays_server_cookie() {
+        let mut jar = CookieJar::default();
+        let server = Some(vec![ 0xAA; 8 ]);
+        assert_eq!(jar.store("ns", &request([1; 8]), &response(Cookie { client: [1; 8], server: server.clone() })),
+                   true);
+
+        let mut later = request([1; 8]);
+        jar.replay("ns", &mut later);
+        assert_eq!(later.cookie().unwrap().server, server);
+    }
+
+    #[test]
+    fn other_nameserver() {
+        let mut jar = CookieJar::default();
+        jar.store("ns", &request([1; 8]), &response(Cookie { client: [1; 8], server: Some(vec![ 0xAA; 8 ]) }));
+
+        let mut later = request([1; 8]);
+        jar.replay("another ns", &mut later);
+        assert_eq!(later.cookie().unwrap().server, None);
+    }
+
+    #[test]
+    fn mismatched_client_cookie() {
+        let mut jar = CookieJar::default();
+        assert_eq!(jar.store("ns", &request([1; 8]), &response(Cookie { client: [2; 8], server: Some(vec![ 0xAA; 8 ]) })),
+                   false);
+        assert_eq!(jar, CookieJar::default());
+    }
+}
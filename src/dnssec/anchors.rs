@@ -0,0 +1,52 @@
+//! The built-in trust anchors for the root zone.
+
+use dns::record::{Algorithm, DS};
+
+
+/// The DS records of the root zone’s key-signing keys, as published by IANA.
+/// These are where every chain of trust begins.
+///
+/// # References
+///
+/// - [IANA root anchors](https://data.iana.org/root-anchors/root-anchors.xml)
+static ROOT_ANCHORS: &[(u16, u8, u8, &str)] = &[
+    (20326, 8, 2, "e06d44b80b8f1d39a95c0b0d7c65d08458e880409bbc683457104237c7f8ec8d"),  // KSK-2017
+    (38696, 8, 2, "683d2d0acb8c9b712a1948b27f741219298d0a450d612c483af444a4c0fb2b16"),  // KSK-2024
+];
+
+/// Returns the root zone’s trust anchors as DS records.
+pub fn root_anchors() -> Vec<DS> {
+    ROOT_ANCHORS.iter()
+        .map(|&(key_tag, algorithm, digest_type, hex_digest)| {
+            DS {
+                key_tag,
+                algorithm: Algorithm::from(algorithm),
+                digest_type,
+                digest: decode_hex(hex_digest),
+            }
+        })
+        .collect()
+}
+
+fn decode_hex(input: &str) -> Vec<u8> {
+    (0 .. input.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&input[i .. i + 2], 16).expect("Invalid trust anchor"))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn anchors_decode() {
+        let anchors = root_anchors();
+
+        assert_eq!(anchors.len(), 2);
+        assert_eq!(anchors[0].key_tag, 20326);
+        assert_eq!(anchors[0].hex_digest(), ROOT_ANCHORS[0].3);
+        assert_eq!(anchors[1].digest.len(), 32);
+    }
+}
@@ -0,0 +1,371 @@
+//! The canonical forms of names and records, which are what get signed.
+//!
+//! # References
+//!
+//! - [RFC 4034 §6](https://tools.ietf.org/html/rfc4034#section-6) — Resource
+//!   Records for the DNS Security Extensions (March 2005)
+//! - [RFC 6840 §5.1](https://tools.ietf.org/html/rfc6840#section-5.1) —
+//!   Clarifications and Implementation Notes for DNS Security (February 2013)
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use dns::{Labels, QClass};
+use dns::record::{Record, RecordType, DNSKEY, RRSIG, SvcParam};
+
+
+/// Returns the uncompressed wire form of a name with its letters
+/// lowercased.
+pub fn name_bytes(name: &Labels) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_name(&mut bytes, &name.to_lowercase());
+    bytes
+}
+
+/// Compares two names in canonical order: label by label, starting from the
+/// rightmost, with the letters in each label lowercased.
+pub fn compare_names(a: &Labels, b: &Labels) -> Ordering {
+    let a_labels = a.iter().rev().map(<[u8]>::to_ascii_lowercase);
+    let b_labels = b.iter().rev().map(<[u8]>::to_ascii_lowercase);
+    a_labels.cmp(b_labels)
+}
+
+/// Whether two names are the same, ignoring the case of ASCII letters.
+pub fn same_name(a: &Labels, b: &Labels) -> bool {
+    compare_names(a, b) == Ordering::Equal
+}
+
+/// Returns the wildcard name directly below the given name.
+pub fn wildcard_of(name: &Labels) -> Labels {
+    Labels::encode("*").expect("Invalid wildcard").extend(name)
+}
+
+/// Returns the canonical record data of a record, or `None` if the record
+/// cannot be converted.
+pub fn record_data(record: &Record) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    match record {
+        Record::A(a) => {
+            bytes.extend_from_slice(&a.address.octets());
+        }
+        Record::AAAA(aaaa) => {
+            bytes.extend_from_slice(&aaaa.address.octets());
+        }
+        Record::CAA(caa) => {
+            bytes.push(if caa.critical { 0b_1000_0000 } else { 0 });
+            write_string(&mut bytes, &caa.tag);
+            bytes.extend_from_slice(&caa.value);
+        }
+        Record::CNAME(cname) => {
+            write_name(&mut bytes, &cname.domain.to_lowercase());
+        }
+        Record::DNSKEY(dnskey) => {
+            write_dnskey(&mut bytes, dnskey);
+        }
+        Record::DS(ds) => {
+            bytes.extend_from_slice(&ds.key_tag.to_be_bytes());
+            bytes.push(ds.algorithm.number());
+            bytes.push(ds.digest_type);
+            bytes.extend_from_slice(&ds.digest);
+        }
+        Record::EUI48(eui48) => {
+            bytes.extend_from_slice(&eui48.octets);
+        }
+        Record::EUI64(eui64) => {
+            bytes.extend_from_slice(&eui64.octets);
+        }
+        Record::HINFO(hinfo) => {
+            write_string(&mut bytes, &hinfo.cpu);
+            write_string(&mut bytes, &hinfo.os);
+        }
+        Record::HTTPS(https) => {
+            write_service_binding(&mut bytes, https.priority, &https.target, &https.parameters);
+        }
+        Record::LOC(_) => {
+            return None;
+        }
+        Record::MX(mx) => {
+            bytes.extend_from_slice(&mx.preference.to_be_bytes());
+            write_name(&mut bytes, &mx.exchange.to_lowercase());
+        }
+        Record::NAPTR(naptr) => {
+            bytes.extend_from_slice(&naptr.order.to_be_bytes());
+            bytes.extend_from_slice(&naptr.preference.to_be_bytes());
+            write_string(&mut bytes, &naptr.flags);
+            write_string(&mut bytes, &naptr.service);
+            write_string(&mut bytes, &naptr.regex);
+            write_name(&mut bytes, &naptr.replacement.to_lowercase());
+        }
+        Record::NS(ns) => {
+            write_name(&mut bytes, &ns.nameserver.to_lowercase());
+        }
+        Record::NSEC(nsec) => {
+            // The next domain name is *not* lowercased; see RFC 6840 §5.1
+            write_name(&mut bytes, &nsec.next_domain);
+            write_type_bitmaps(&mut bytes, &nsec.types);
+        }
+        Record::NSEC3(nsec3) => {
+            bytes.push(nsec3.hash_algorithm);
+            bytes.push(nsec3.flags);
+            bytes.extend_from_slice(&nsec3.iterations.to_be_bytes());
+            write_string(&mut bytes, &nsec3.salt);
+            write_string(&mut bytes, &nsec3.next_hashed_owner);
+            write_type_bitmaps(&mut bytes, &nsec3.types);
+        }
+        Record::NSEC3PARAM(nsec3param) => {
+            bytes.push(nsec3param.hash_algorithm);
+            bytes.push(nsec3param.flags);
+            bytes.extend_from_slice(&nsec3param.iterations.to_be_bytes());
+            write_string(&mut bytes, &nsec3param.salt);
+        }
+        Record::OPENPGPKEY(openpgpkey) => {
+            bytes.extend_from_slice(&openpgpkey.key);
+        }
+        Record::PTR(ptr) => {
+            write_name(&mut bytes, &ptr.cname.to_lowercase());
+        }
+        Record::RRSIG(rrsig) => {
+            write_signed_fields(&mut bytes, rrsig);
+            bytes.extend_from_slice(&rrsig.signature);
+        }
+        Record::SSHFP(sshfp) => {
+            bytes.push(sshfp.algorithm);
+            bytes.push(sshfp.fingerprint_type);
+            bytes.extend_from_slice(&sshfp.fingerprint);
+        }
+        Record::SOA(soa) => {
+            write_name(&mut bytes, &soa.mname.to_lowercase());
+            write_name(&mut bytes, &soa.rname.to_lowercase());
+            bytes.extend_from_slice(&soa.serial.to_be_bytes());
+            bytes.extend_from_slice(&soa.refresh_interval.to_be_bytes());
+            bytes.extend_from_slice(&soa.retry_interval.to_be_bytes());
+            bytes.extend_from_slice(&soa.expire_limit.to_be_bytes());
+            bytes.extend_from_slice(&soa.minimum_ttl.to_be_bytes());
+        }
+        Record::SRV(srv) => {
+            bytes.extend_from_slice(&srv.priority.to_be_bytes());
+            bytes.extend_from_slice(&srv.weight.to_be_bytes());
+            bytes.extend_from_slice(&srv.port.to_be_bytes());
+            write_name(&mut bytes, &srv.target.to_lowercase());
+        }
+        Record::SVCB(svcb) => {
+            write_service_binding(&mut bytes, svcb.priority, &svcb.target, &svcb.parameters);
+        }
+        Record::TLSA(tlsa) => {
+            bytes.push(tlsa.certificate_usage);
+            bytes.push(tlsa.selector);
+            bytes.push(tlsa.matching_type);
+            bytes.extend_from_slice(&tlsa.certificate_data);
+        }
+        Record::TXT(txt) => {
+            for message in &txt.messages {
+                write_string(&mut bytes, message);
+            }
+        }
+        Record::URI(uri) => {
+            bytes.extend_from_slice(&uri.priority.to_be_bytes());
+            bytes.extend_from_slice(&uri.weight.to_be_bytes());
+            bytes.extend_from_slice(&uri.target);
+        }
+        Record::Other { bytes: other_bytes, .. } => {
+            bytes.extend_from_slice(other_bytes);
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Returns the canonical record data of a DNSKEY record, which is what a DS
+/// record’s digest is computed over.
+pub fn dnskey_data(dnskey: &DNSKEY) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_dnskey(&mut bytes, dnskey);
+    bytes
+}
+
+/// Returns the data that an RRSIG record’s signature is made over: the
+/// RRSIG’s own fields, except the signature, followed by every record in
+/// the set in canonical order. The owner name should already have been
+/// rewritten if the records were expanded from a wildcard.
+pub fn signed_data(rrsig: &RRSIG, qname: &Labels, record_type: RecordType, qclass: QClass, records: &[&Record]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    write_signed_fields(&mut bytes, rrsig);
+
+    let mut datas = records.iter()
+        .map(|r| record_data(r))
+        .collect::<Option<Vec<_>>>()?;
+    datas.sort();
+    datas.dedup();
+
+    let owner = name_bytes(qname);
+    for data in datas {
+        bytes.extend_from_slice(&owner);
+        bytes.extend_from_slice(&record_type.type_number().to_be_bytes());
+        bytes.extend_from_slice(&qclass.to_u16().to_be_bytes());
+        bytes.extend_from_slice(&rrsig.original_ttl.to_be_bytes());
+        bytes.extend_from_slice(&u16::try_from(data.len()).ok()?.to_be_bytes());
+        bytes.extend(data);
+    }
+
+    Some(bytes)
+}
+
+
+fn write_name(bytes: &mut Vec<u8>, name: &Labels) {
+    for segment in name.iter() {
+        write_string(bytes, segment);
+    }
+
+    bytes.push(0);
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &[u8]) {
+    bytes.push(u8::try_from(string.len()).unwrap_or(u8::MAX));
+    bytes.extend_from_slice(string);
+}
+
+fn write_service_binding(bytes: &mut Vec<u8>, priority: u16, target: &Labels, parameters: &[SvcParam]) {
+    // The target name is not lowercased, as SVCB is not in the RFC 4034 list
+    bytes.extend_from_slice(&priority.to_be_bytes());
+    write_name(bytes, target);
+
+    for parameter in parameters {
+        let mut value = Vec::new();
+
+        match parameter {
+            SvcParam::Mandatory(keys) => {
+                for key in keys {
+                    value.extend_from_slice(&key.to_be_bytes());
+                }
+            }
+            SvcParam::Alpn(ids) => {
+                for id in ids {
+                    write_string(&mut value, id);
+                }
+            }
+            SvcParam::NoDefaultAlpn => {}
+            SvcParam::Port(port) => {
+                value.extend_from_slice(&port.to_be_bytes());
+            }
+            SvcParam::Ipv4Hint(addresses) => {
+                for address in addresses {
+                    value.extend_from_slice(&address.octets());
+                }
+            }
+            SvcParam::Ipv6Hint(addresses) => {
+                for address in addresses {
+                    value.extend_from_slice(&address.octets());
+                }
+            }
+            SvcParam::Ech(data) | SvcParam::Other(_, data) => {
+                value.extend_from_slice(data);
+            }
+        }
+
+        bytes.extend_from_slice(&parameter.key().to_be_bytes());
+        bytes.extend_from_slice(&u16::try_from(value.len()).unwrap_or(u16::MAX).to_be_bytes());
+        bytes.extend_from_slice(&value);
+    }
+}
+
+fn write_dnskey(bytes: &mut Vec<u8>, dnskey: &DNSKEY) {
+    bytes.extend_from_slice(&dnskey.flags.to_be_bytes());
+    bytes.push(dnskey.protocol);
+    bytes.push(dnskey.algorithm.number());
+    bytes.extend_from_slice(&dnskey.public_key);
+}
+
+fn write_signed_fields(bytes: &mut Vec<u8>, rrsig: &RRSIG) {
+    bytes.extend_from_slice(&rrsig.type_covered.type_number().to_be_bytes());
+    bytes.push(rrsig.algorithm.number());
+    bytes.push(rrsig.labels);
+    bytes.extend_from_slice(&rrsig.original_ttl.to_be_bytes());
+    bytes.extend_from_slice(&rrsig.signature_expiration.to_be_bytes());
+    bytes.extend_from_slice(&rrsig.signature_inception.to_be_bytes());
+    bytes.extend_from_slice(&rrsig.key_tag.to_be_bytes());
+    write_name(bytes, &rrsig.signer_name.to_lowercase());
+}
+
+fn write_type_bitmaps(bytes: &mut Vec<u8>, types: &[RecordType]) {
+    let mut numbers = types.iter().map(|t| t.type_number()).collect::<Vec<_>>();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let mut index = 0;
+    while index < numbers.len() {
+        let [window, _] = numbers[index].to_be_bytes();
+        let mut bitmap = [0_u8; 32];
+        let mut bitmap_length = 0;
+
+        while let Some([this_window, low]) = numbers.get(index).map(|n| n.to_be_bytes()) {
+            if this_window != window {
+                break;
+            }
+
+            bitmap[usize::from(low / 8)] |= 0b_1000_0000 >> (low % 8);
+            bitmap_length = low / 8 + 1;
+            index += 1;
+        }
+
+        bytes.push(window);
+        bytes.push(bitmap_length);
+        bytes.extend_from_slice(&bitmap[.. usize::from(bitmap_length)]);
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use dns::record::{MX, NSEC};
+
+    #[test]
+    fn lowercase_name() {
+        let name = Labels::encode("Lookup.DOG").unwrap();
+
+        assert_eq!(name_bytes(&name),
+                   b"\x06lookup\x03dog\x00".to_vec());
+    }
+
+    #[test]
+    fn canonical_order() {
+        // The example ordering from RFC 4034 §6.1
+        let names = [ "example", "a.example", "yljkjljk.a.example", "Z.a.example",
+                      "zABC.a.EXAMPLE", "z.example", "*.z.example" ];
+
+        for pair in names.windows(2) {
+            let a = Labels::encode(pair[0]).unwrap();
+            let b = Labels::encode(pair[1]).unwrap();
+            assert_eq!(compare_names(&a, &b), Ordering::Less, "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn mx_data() {
+        let record = Record::MX(MX {
+            preference: 10,
+            exchange: Labels::encode("MX.bsago.me").unwrap(),
+        });
+
+        assert_eq!(record_data(&record),
+                   Some(b"\x00\x0a\x02mx\x05bsago\x02me\x00".to_vec()));
+    }
+
+    #[test]
+    fn nsec_data() {
+        let record = Record::NSEC(NSEC {
+            next_domain: Labels::encode("bsago.me").unwrap(),
+            types: vec![ RecordType::MX, RecordType::A, RecordType::NSEC,
+                         RecordType::RRSIG, RecordType::from(257) ],
+        });
+
+        assert_eq!(record_data(&record),
+                   Some(vec![
+                       0x05, b'b', b's', b'a', b'g', b'o', 0x02, b'm', b'e', 0x00,
+                       0x00, 0x06, 0x40, 0x01, 0x00, 0x00, 0x00, 0x03,
+                       0x01, 0x01, 0x40,
+                   ]));
+    }
+}
@@ -0,0 +1,573 @@
+//! Building the chain of trust from the root zone down to a set of records.
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::*;
+
+use dns::{Request, Response, Query, Answer, Flags, QClass, ErrorCode, Labels};
+use dns::record::{Record, RecordType, DNSKEY, DS, OPT, RRSIG};
+use dns_transport::Transport;
+
+use crate::txid::TxidGenerator;
+
+use super::{Status, Problem, Validation, RRsetStatus};
+use super::anchors::root_anchors;
+use super::canonical::{dnskey_data, name_bytes, same_name, signed_data, wildcard_of};
+use super::crypto::{self, CryptoError};
+use super::denial::{DenialRecords, Denial, Delegation};
+
+
+/// A **validator** checks the signatures on the records in a response,
+/// sending its own requests for the DS and DNSKEY records it needs over the
+/// same transport that the response came from.
+pub struct Validator<'t> {
+
+    /// The transport to send lookups over.
+    transport: &'t dyn Transport,
+
+    /// How to generate the transaction IDs of lookups.
+    txid_generator: TxidGenerator,
+
+    /// The current time, as a number of seconds since the Unix epoch,
+    /// wrapped around to fit in 32 bits the way RRSIG timestamps are.
+    now: u32,
+
+    /// The zone that each name that has been looked up belongs to. The
+    /// names are stored in lowercase.
+    zones: BTreeMap<Labels, Rc<Zone>>,
+}
+
+/// A zone that has been found while walking down from the root.
+#[derive(Debug)]
+struct Zone {
+
+    /// The name of the zone’s apex.
+    name: Labels,
+
+    /// Whether the zone has a set of keys we trust.
+    state: ZoneState,
+}
+
+#[derive(PartialEq, Debug)]
+enum ZoneState {
+
+    /// The zone’s DNSKEY records have been authenticated.
+    Secure(Vec<DNSKEY>),
+
+    /// The zone has been proven to be unsigned.
+    Insecure,
+
+    /// The zone’s keys could not be authenticated.
+    Failed(Status),
+}
+
+/// A set of records with the same owner name, type, and class, along with
+/// the RRSIG records that cover them.
+#[derive(Debug)]
+struct RRset<'a> {
+    owner: &'a Labels,
+    record_type: RecordType,
+    qclass: QClass,
+    records: Vec<&'a Record>,
+    signatures: Vec<&'a RRSIG>,
+}
+
+/// The RFC 6891 recommended payload size, which is big enough to hold most
+/// DNSKEY responses without them getting truncated.
+const VALIDATION_PAYLOAD_SIZE: u16 = 1232;
+
+
+impl<'t> Validator<'t> {
+
+    /// Creates a new validator that sends its lookups over the given
+    /// transport.
+    pub fn new(transport: &'t dyn Transport, txid_generator: TxidGenerator) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| (d.as_secs() % (1 << 32)).try_into().unwrap())
+            .unwrap_or_default();
+
+        Self { transport, txid_generator, now, zones: BTreeMap::new() }
+    }
+
+    /// Validates every set of records in the Answer section of a response.
+    /// If the response is negative, it validates the proof of non-existence
+    /// in the Authority section instead.
+    pub fn validate(&mut self, query: &Query, response: &Response) -> Validation {
+        let mut validation = Validation::default();
+        let denial_records = DenialRecords::from_answers(&response.authorities);
+
+        let answer_rrsets = rrsets(&response.answers);
+        for rrset in &answer_rrsets {
+            let status = self.validate_rrset(rrset, &denial_records);
+            info!("Validated {} {} -> {:?}", rrset.owner, rrset.record_type, status);
+
+            let qname = rrset.owner.clone();
+            validation.rrsets.push(RRsetStatus { qname, record_type: rrset.record_type, status });
+        }
+
+        if answer_rrsets.is_empty() && matches!(response.flags.error_code, None | Some(ErrorCode::NXDomain)) {
+            let status = self.validate_denial(query, response, &denial_records);
+            info!("Validated denial of {} {} -> {:?}", query.qname, query.qtype, status);
+            validation.denial = Some(status);
+        }
+
+        validation
+    }
+
+    fn validate_rrset(&mut self, rrset: &RRset<'_>, denial_records: &DenialRecords<'_>) -> Status {
+        // DS records live in the parent zone, not the zone they delegate to
+        let zone = match (rrset.record_type, rrset.owner.parent()) {
+            (RecordType::DS, Some(parent))  => self.zone_of(&parent),
+            _                               => self.zone_of(rrset.owner),
+        };
+
+        let keys = match &zone.state {
+            ZoneState::Secure(keys)     => keys.iter().collect::<Vec<_>>(),
+            ZoneState::Insecure         => return Status::Insecure,
+            ZoneState::Failed(status)   => return status.clone(),
+        };
+
+        match self.verify_signatures(rrset, &zone.name, &keys) {
+            Ok(None) => {
+                Status::Secure
+            }
+            Ok(Some(wildcard_labels)) => {
+                match denial_records.prove_wildcard_expansion(rrset.owner, wildcard_labels) {
+                    Denial::Proven                        => Status::Secure,
+                    Denial::OptOut | Denial::Unsupported  => Status::Insecure,
+                    Denial::Unproven                      => Status::Bogus(Problem::MissingDenial),
+                }
+            }
+            Err(status) => {
+                status
+            }
+        }
+    }
+
+    fn validate_denial(&mut self, query: &Query, response: &Response, denial_records: &DenialRecords<'_>) -> Status {
+        let zone = self.zone_of(&query.qname);
+
+        let keys = match &zone.state {
+            ZoneState::Secure(keys)     => keys.iter().collect::<Vec<_>>(),
+            ZoneState::Insecure         => return Status::Insecure,
+            ZoneState::Failed(status)   => return status.clone(),
+        };
+
+        if let Err(status) = self.verify_denial_records(&response.authorities, &zone.name, &keys) {
+            return status;
+        }
+
+        let denial = if response.flags.error_code == Some(ErrorCode::NXDomain) {
+            denial_records.prove_nxdomain(&query.qname)
+        }
+        else {
+            denial_records.prove_nodata(&query.qname, query.qtype)
+        };
+
+        match denial {
+            Denial::Proven                        => Status::Secure,
+            Denial::OptOut | Denial::Unsupported  => Status::Insecure,
+            Denial::Unproven                      => Status::Bogus(Problem::MissingDenial),
+        }
+    }
+
+    /// Returns the zone that the given name belongs to, walking down from
+    /// the root and looking up the DS records for each name on the way to
+    /// find where the zone cuts are.
+    fn zone_of(&mut self, name: &Labels) -> Rc<Zone> {
+        let name = name.to_lowercase();
+        if let Some(zone) = self.zones.get(&name) {
+            return Rc::clone(zone);
+        }
+
+        let zone = match name.parent() {
+            None => {
+                let state = self.trust_keys(&name, &root_anchors());
+                Rc::new(Zone { name: name.clone(), state })
+            }
+            Some(parent) => {
+                let parent_zone = self.zone_of(&parent);
+
+                if let ZoneState::Secure(keys) = &parent_zone.state {
+                    let keys = keys.iter().collect::<Vec<_>>();
+
+                    match self.find_delegation(&name, &parent_zone.name, &keys) {
+                        Some(state)  => Rc::new(Zone { name: name.clone(), state }),
+                        None         => Rc::clone(&parent_zone),
+                    }
+                }
+                else {
+                    // Everything below an unsigned or broken zone is too
+                    Rc::clone(&parent_zone)
+                }
+            }
+        };
+
+        debug!("Zone of {} -> {} ({:?})", name, zone.name, zone.state);
+        self.zones.insert(name, Rc::clone(&zone));
+        zone
+    }
+
+    /// Looks up the DS records for a name in a signed zone to work out
+    /// whether it is the apex of a child zone. Returns the state of the
+    /// child zone, or `None` if the name is not a zone cut.
+    fn find_delegation(&mut self, name: &Labels, parent_name: &Labels, parent_keys: &[&DNSKEY]) -> Option<ZoneState> {
+        let response = match self.lookup(name, RecordType::DS) {
+            Ok(response)  => response,
+            Err(problem)  => return Some(ZoneState::Failed(Status::Indeterminate(problem))),
+        };
+
+        let answer_rrsets = rrsets(&response.answers);
+
+        if let Some(rrset) = answer_rrsets.iter().find(|r| r.record_type == RecordType::DS && same_name(r.owner, name)) {
+            if let Err(status) = self.verify_signatures(rrset, parent_name, parent_keys) {
+                return Some(ZoneState::Failed(status));
+            }
+
+            let ds_records = rrset.records.iter()
+                .filter_map(|r| if let Record::DS(ds) = r { Some(ds) } else { None })
+                .collect::<Vec<_>>();
+
+            return Some(self.trust_keys(name, &ds_records));
+        }
+
+        if answer_rrsets.iter().any(|r| r.record_type == RecordType::CNAME && same_name(r.owner, name)) {
+            // A name with a CNAME record cannot have NS records as well
+            return None;
+        }
+
+        if let Err(status) = self.verify_denial_records(&response.authorities, parent_name, parent_keys) {
+            return Some(ZoneState::Failed(status));
+        }
+
+        let denial_records = DenialRecords::from_answers(&response.authorities);
+        match denial_records.delegation(name) {
+            Delegation::NotACut                             => None,
+            Delegation::Unsigned | Delegation::Unsupported  => Some(ZoneState::Insecure),
+            Delegation::HasDS | Delegation::Unproven        => Some(ZoneState::Failed(Status::Bogus(Problem::MissingDenial))),
+        }
+    }
+
+    /// Looks up the DNSKEY records for a zone, and checks that at least one
+    /// of them matches one of the given DS records and has signed the rest.
+    fn trust_keys<D: Borrow<DS>>(&mut self, zone_name: &Labels, ds_records: &[D]) -> ZoneState {
+        let supported_ds = ds_records.iter()
+            .map(D::borrow)
+            .filter(|ds| crypto::supports_algorithm(ds.algorithm.number()) && crypto::supports_digest(ds.digest_type))
+            .collect::<Vec<_>>();
+
+        if supported_ds.is_empty() {
+            // RFC 4035 §5.2: treat the zone as unsigned if none of its DS
+            // records can be used
+            warn!("No supported DS records for {}", zone_name);
+            return ZoneState::Insecure;
+        }
+
+        let response = match self.lookup(zone_name, RecordType::DNSKEY) {
+            Ok(response)  => response,
+            Err(problem)  => return ZoneState::Failed(Status::Indeterminate(problem)),
+        };
+
+        let answer_rrsets = rrsets(&response.answers);
+        let rrset = match answer_rrsets.iter().find(|r| r.record_type == RecordType::DNSKEY && same_name(r.owner, zone_name)) {
+            Some(rrset)  => rrset,
+            None         => return ZoneState::Failed(Status::Bogus(Problem::MissingDelegationKey(zone_name.clone()))),
+        };
+
+        let owner_bytes = name_bytes(zone_name);
+        let trusted_keys = rrset.records.iter()
+            .filter_map(|r| if let Record::DNSKEY(dnskey) = r { Some(dnskey) } else { None })
+            .filter(|dnskey| supported_ds.iter().any(|ds| ds_matches(ds, &owner_bytes, dnskey)))
+            .collect::<Vec<_>>();
+
+        if trusted_keys.is_empty() {
+            return ZoneState::Failed(Status::Bogus(Problem::MissingDelegationKey(zone_name.clone())));
+        }
+
+        if let Err(status) = self.verify_signatures(rrset, zone_name, &trusted_keys) {
+            return ZoneState::Failed(status);
+        }
+
+        let keys = response.answers.into_iter()
+            .filter_map(|answer| match answer {
+                Answer::Standard { qname, record: Record::DNSKEY(dnskey), .. } if same_name(&qname, zone_name) => Some(dnskey),
+                _ => None,
+            })
+            .collect();
+
+        ZoneState::Secure(keys)
+    }
+
+    /// Checks the signatures on every NSEC and NSEC3 record in a section.
+    fn verify_denial_records(&self, section: &[Answer], zone_name: &Labels, keys: &[&DNSKEY]) -> Result<(), Status> {
+        for rrset in rrsets(section) {
+            if matches!(rrset.record_type, RecordType::NSEC | RecordType::NSEC3) {
+                self.verify_signatures(&rrset, zone_name, keys)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether any of the signatures on a set of records can be
+    /// verified using one of the given keys from the given zone.
+    ///
+    /// If the signature is valid, returns the number of labels in the
+    /// wildcard name if the records were expanded from one, so that the
+    /// caller can check the proof that the name didn’t exist.
+    fn verify_signatures(&self, rrset: &RRset<'_>, zone_name: &Labels, keys: &[&DNSKEY]) -> Result<Option<usize>, Status> {
+        let mut problem = Problem::MissingSignature;
+
+        let owner_labels = match rrset.owner.iter().next() {
+            Some([b'*'])  => rrset.owner.len() - 1,
+            _             => rrset.owner.len(),
+        };
+
+        for rrsig in &rrset.signatures {
+            let signature_labels = usize::from(rrsig.labels);
+
+            if ! same_name(&rrsig.signer_name, zone_name) {
+                problem = Problem::WrongSigner(rrsig.signer_name.clone());
+                continue;
+            }
+
+            if ! crypto::supports_algorithm(rrsig.algorithm.number()) {
+                problem = Problem::UnsupportedAlgorithm(rrsig.algorithm);
+                continue;
+            }
+
+            if serial_before(self.now, rrsig.signature_inception) {
+                problem = Problem::SignatureNotYetValid;
+                continue;
+            }
+
+            if serial_before(rrsig.signature_expiration, self.now) {
+                problem = Problem::SignatureExpired;
+                continue;
+            }
+
+            if signature_labels > owner_labels {
+                problem = Problem::InvalidSignature;
+                continue;
+            }
+
+            let signed_owner = if signature_labels < owner_labels {
+                wildcard_of(&rrset.owner.suffix(signature_labels))
+            }
+            else {
+                rrset.owner.clone()
+            };
+
+            let data = match signed_data(rrsig, &signed_owner, rrset.record_type, rrset.qclass, &rrset.records) {
+                Some(data)  => data,
+                None        => return Err(Status::Indeterminate(Problem::UnsupportedRecordType(rrset.record_type))),
+            };
+
+            let candidates = keys.iter()
+                .filter(|k| k.key_tag() == rrsig.key_tag && k.algorithm == rrsig.algorithm)
+                .filter(|k| k.is_zone_key() && k.protocol == 3);
+
+            problem = Problem::MissingKey(rrsig.key_tag);
+            for key in candidates {
+                match crypto::verify(rrsig.algorithm.number(), &key.public_key, &data, &rrsig.signature) {
+                    Ok(()) => {
+                        let wildcard_labels = if signature_labels < owner_labels { Some(signature_labels) } else { None };
+                        return Ok(wildcard_labels);
+                    }
+                    Err(CryptoError::Unsupported) => {
+                        problem = Problem::UnsupportedAlgorithm(rrsig.algorithm);
+                    }
+                    Err(CryptoError::Invalid) => {
+                        problem = Problem::InvalidSignature;
+                    }
+                }
+            }
+        }
+
+        // Whether a zone can be treated as unsigned because we don’t know
+        // its algorithms is decided from its DS records, in `trust_keys`.
+        // Once we trust one of its keys, an attacker could otherwise strip
+        // its signatures and replace them with ones we can’t check.
+        Err(Status::Bogus(problem))
+    }
+
+    /// Sends a request for records needed to build the chain of trust.
+    fn lookup(&self, qname: &Labels, qtype: RecordType) -> Result<Response, Problem> {
+        let mut flags = Flags::query();
+        flags.checking_disabled = true;
+
+        let mut opt = Request::additional_record();
+        opt.udp_payload_size = VALIDATION_PAYLOAD_SIZE;
+        opt.flags |= OPT::DNSSEC_OK;
+
+        let transaction_id = self.txid_generator.generate();
+        let query = Query { qname: qname.clone(), qtype, qclass: QClass::IN };
+        let request = Request { transaction_id, flags, query, authority: None, additional: Some(opt), signature: None };
+
+        debug!("Looking up {} {} for validation", qname, qtype);
+        match self.transport.send(&request) {
+            Ok(response) if ! response.malformed.is_empty() => {
+                warn!("Lookup of {} {} returned malformed records: {:?}", qname, qtype, response.malformed);
+                Err(Problem::LookupFailed(qname.clone(), qtype))
+            }
+            Ok(response) if matches!(response.flags.error_code, None | Some(ErrorCode::NXDomain)) => {
+                Ok(response)
+            }
+            Ok(response) => {
+                warn!("Lookup of {} {} returned {:?}", qname, qtype, response.flags.error_code);
+                Err(Problem::LookupFailed(qname.clone(), qtype))
+            }
+            Err(e) => {
+                warn!("Lookup of {} {} failed: {:?}", qname, qtype, e);
+                Err(Problem::LookupFailed(qname.clone(), qtype))
+            }
+        }
+    }
+}
+
+
+/// Groups the records in a section into sets with the same owner name,
+/// type, and class, attaching the signatures that cover each set.
+fn rrsets(section: &[Answer]) -> Vec<RRset<'_>> {
+    let mut rrsets: Vec<RRset<'_>> = Vec::new();
+
+    for answer in section {
+        if let Answer::Standard { qname, qclass, record, .. } = answer {
+            if let Record::RRSIG(_) = record {
+                continue;
+            }
+
+            let record_type = record.record_type();
+            match rrsets.iter_mut().find(|r| r.record_type == record_type && r.qclass == *qclass && same_name(r.owner, qname)) {
+                Some(rrset) => {
+                    rrset.records.push(record);
+                }
+                None => {
+                    let records = vec![ record ];
+                    rrsets.push(RRset { owner: qname, record_type, qclass: *qclass, records, signatures: Vec::new() });
+                }
+            }
+        }
+    }
+
+    for answer in section {
+        if let Answer::Standard { qname, record: Record::RRSIG(rrsig), .. } = answer {
+            if let Some(rrset) = rrsets.iter_mut().find(|r| r.record_type == rrsig.type_covered && same_name(r.owner, qname)) {
+                rrset.signatures.push(rrsig);
+            }
+        }
+    }
+
+    rrsets
+}
+
+/// Whether the first timestamp comes before the second, using the serial
+/// number arithmetic that RRSIG records use so that the timestamps keep
+/// working after they wrap around in 2106.
+///
+/// # References
+///
+/// - [RFC 4034 §3.1.5](https://tools.ietf.org/html/rfc4034#section-3.1.5) —
+///   Resource Records for the DNS Security Extensions (March 2005)
+/// - [RFC 1982](https://tools.ietf.org/html/rfc1982) — Serial Number
+///   Arithmetic (August 1996)
+fn serial_before(first: u32, second: u32) -> bool {
+    first != second && second.wrapping_sub(first) < 0x8000_0000
+}
+
+/// Whether the DS record holds the digest of the DNSKEY record.
+fn ds_matches(ds: &DS, owner_bytes: &[u8], dnskey: &DNSKEY) -> bool {
+    if ds.key_tag != dnskey.key_tag() || ds.algorithm != dnskey.algorithm {
+        return false;
+    }
+
+    let mut data = owner_bytes.to_vec();
+    data.extend(dnskey_data(dnskey));
+
+    match crypto::digest(ds.digest_type, &data) {
+        Ok(digest)  => digest == ds.digest,
+        Err(_)      => false,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use dns::record::Algorithm;
+
+    /// A transport that answers every lookup with a DNSKEY record, along
+    /// with a forged signature that uses an algorithm dog doesn’t know.
+    struct ForgedTransport;
+
+    impl Transport for ForgedTransport {
+        fn send(&self, request: &dns::Request) -> Result<Response, dns_transport::Error> {
+            let qname = request.query.qname.clone();
+            let rrsig = RRSIG {
+                type_covered: RecordType::DNSKEY,
+                algorithm: Algorithm::from(200),
+                labels: 2,
+                original_ttl: 3600,
+                signature_expiration: u32::MAX,
+                signature_inception: 0,
+                key_tag: dnskey().key_tag(),
+                signer_name: qname.clone(),
+                signature: vec![ 0xAB; 64 ],
+            };
+
+            let answers = vec![
+                Answer::Standard { qname: qname.clone(), qclass: QClass::IN, ttl: 3600, record: Record::DNSKEY(dnskey()) },
+                Answer::Standard { qname: qname.clone(), qclass: QClass::IN, ttl: 3600, record: Record::RRSIG(rrsig) },
+            ];
+
+            let query = Query { qname, qtype: request.query.qtype, qclass: QClass::IN };
+            Ok(Response {
+                transaction_id: request.transaction_id,
+                flags: Flags::standard_response(),
+                queries: vec![ query ],
+                answers,
+                authorities: Vec::new(),
+                additionals: Vec::new(),
+                signature: None,
+                malformed: Vec::new(),
+            })
+        }
+    }
+
+    fn dnskey() -> DNSKEY {
+        DNSKEY { flags: 257, protocol: 3, algorithm: Algorithm::from(15), public_key: vec![ 7; 32 ] }
+    }
+
+    #[cfg(feature = "with_dnssec")]
+    #[test]
+    fn forged_unsupported_algorithm() {
+        let zone = Labels::encode("lookup.dog").unwrap();
+        let mut data = name_bytes(&zone);
+        data.extend(dnskey_data(&dnskey()));
+
+        let ds = DS {
+            key_tag: dnskey().key_tag(),
+            algorithm: Algorithm::from(15),
+            digest_type: 2,
+            digest: crypto::digest(2, &data).unwrap(),
+        };
+
+        let mut validator = Validator::new(&ForgedTransport, TxidGenerator::Sequence(0));
+        assert_eq!(validator.trust_keys(&zone, &[ ds ]),
+                   ZoneState::Failed(Status::Bogus(Problem::UnsupportedAlgorithm(Algorithm::from(200)))));
+    }
+
+    #[test]
+    fn serial_arithmetic() {
+        assert!(serial_before(1, 2));
+        assert!(! serial_before(2, 1));
+        assert!(! serial_before(2, 2));
+        assert!(serial_before(0xFFFF_FFF0, 0x10));
+        assert!(! serial_before(0x10, 0xFFFF_FFF0));
+    }
+}
@@ -0,0 +1,224 @@
+//! Verifying signatures and computing digests.
+//!
+//! The cryptography is done by the `ring` crate, which is only included when
+//! dog is compiled with the `with_dnssec` feature. Without it, every
+//! algorithm is treated as unsupported.
+
+
+/// Something that went wrong during a cryptographic operation.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum CryptoError {
+
+    /// The algorithm or digest type is not one that dog can use.
+    Unsupported,
+
+    /// The signature did not match, or the key was malformed.
+    #[cfg_attr(not(feature = "with_dnssec"), allow(dead_code))]
+    Invalid,
+}
+
+
+/// Verifies that the signature is valid for the message using the public
+/// key, which is in the format used in DNSKEY records for its algorithm.
+///
+/// # References
+///
+/// - [RFC 3110 §2](https://tools.ietf.org/html/rfc3110) — RSA/SHA-1 SIGs and
+///   RSA KEYs in the Domain Name System (May 2001)
+/// - [RFC 5702](https://tools.ietf.org/html/rfc5702) — Use of SHA-2
+///   Algorithms with RSA in DNSKEY and RRSIG Resource Records for DNSSEC
+///   (October 2009)
+/// - [RFC 6605](https://tools.ietf.org/html/rfc6605) — Elliptic Curve Digital
+///   Signature Algorithm (DSA) for DNSSEC (April 2012)
+/// - [RFC 8080](https://tools.ietf.org/html/rfc8080) — Edwards-Curve Digital
+///   Security Algorithm (EdDSA) for DNSSEC (February 2017)
+#[cfg(feature = "with_dnssec")]
+pub fn verify(algorithm: u8, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+    use ring::signature::*;
+
+    match algorithm {
+        5 | 7  => verify_rsa(&RSA_PKCS1_1024_8192_SHA1_FOR_LEGACY_USE_ONLY, public_key, message, signature),
+        8      => verify_rsa(&RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY, public_key, message, signature),
+        10     => verify_rsa(&RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY, public_key, message, signature),
+        13     => verify_ecdsa(&ECDSA_P256_SHA256_FIXED, public_key, message, signature),
+        14     => verify_ecdsa(&ECDSA_P384_SHA384_FIXED, public_key, message, signature),
+        15     => {
+            UnparsedPublicKey::new(&ED25519, public_key)
+                .verify(message, signature)
+                .map_err(|_| CryptoError::Invalid)
+        }
+        _      => Err(CryptoError::Unsupported),
+    }
+}
+
+/// Whether signatures made with the given algorithm can be verified.
+pub fn supports_algorithm(algorithm: u8) -> bool {
+    cfg!(feature = "with_dnssec") && matches!(algorithm, 5 | 7 | 8 | 10 | 13 | 14 | 15)
+}
+
+#[cfg(feature = "with_dnssec")]
+fn verify_rsa(params: &ring::signature::RsaParameters, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+    // The exponent length takes up one byte, or three if the first is zero
+    let (exponent_length, rest) = match public_key {
+        [0, high, low, rest @ ..]  => (usize::from(u16::from_be_bytes([*high, *low])), rest),
+        [0, ..] | []               => return Err(CryptoError::Invalid),
+        [length, rest @ ..]        => (usize::from(*length), rest),
+    };
+
+    if exponent_length == 0 || rest.len() <= exponent_length {
+        return Err(CryptoError::Invalid);
+    }
+
+    let (e, n) = rest.split_at(exponent_length);
+    if n.len() < 1024 / 8 {
+        return Err(CryptoError::Unsupported);
+    }
+
+    ring::signature::RsaPublicKeyComponents { n, e }
+        .verify(params, message, signature)
+        .map_err(|_| CryptoError::Invalid)
+}
+
+#[cfg(feature = "with_dnssec")]
+fn verify_ecdsa(algorithm: &'static ring::signature::EcdsaVerificationAlgorithm, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+    // DNSKEY records omit the byte that marks the point as uncompressed
+    let mut point = Vec::with_capacity(public_key.len() + 1);
+    point.push(0x04);
+    point.extend_from_slice(public_key);
+
+    ring::signature::UnparsedPublicKey::new(algorithm, point)
+        .verify(message, signature)
+        .map_err(|_| CryptoError::Invalid)
+}
+
+#[cfg(not(feature = "with_dnssec"))]
+pub fn verify(_algorithm: u8, _public_key: &[u8], _message: &[u8], _signature: &[u8]) -> Result<(), CryptoError> {
+    Err(CryptoError::Unsupported)
+}
+
+
+/// Whether digests with the given DS digest type can be computed.
+pub fn supports_digest(digest_type: u8) -> bool {
+    cfg!(feature = "with_dnssec") && matches!(digest_type, 1 | 2 | 4)
+}
+
+/// Computes a digest of the given data, using the hash function that the
+/// DS digest type number refers to.
+///
+/// # References
+///
+/// - [RFC 4509](https://tools.ietf.org/html/rfc4509) — Use of SHA-256 in
+///   DNSSEC Delegation Signer (DS) Resource Records (May 2006)
+/// - [RFC 6605 §2](https://tools.ietf.org/html/rfc6605#section-2) — Elliptic
+///   Curve Digital Signature Algorithm (DSA) for DNSSEC (April 2012)
+#[cfg(feature = "with_dnssec")]
+pub fn digest(digest_type: u8, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    use ring::digest;
+
+    let algorithm = match digest_type {
+        1  => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+        2  => &digest::SHA256,
+        4  => &digest::SHA384,
+        _  => return Err(CryptoError::Unsupported),
+    };
+
+    Ok(digest::digest(algorithm, data).as_ref().to_vec())
+}
+
+#[cfg(not(feature = "with_dnssec"))]
+pub fn digest(_digest_type: u8, _data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    Err(CryptoError::Unsupported)
+}
+
+
+/// Hashes a name in canonical wire form the way NSEC3 records do, by
+/// repeatedly hashing it with the salt appended.
+///
+/// # References
+///
+/// - [RFC 5155 §5](https://tools.ietf.org/html/rfc5155#section-5) — DNS
+///   Security (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+#[cfg(feature = "with_dnssec")]
+pub fn nsec3_hash(hash_algorithm: u8, name: &[u8], salt: &[u8], iterations: u16) -> Result<Vec<u8>, CryptoError> {
+    use ring::digest;
+
+    if hash_algorithm != 1 {
+        return Err(CryptoError::Unsupported);
+    }
+
+    let mut hash = name.to_vec();
+    for _ in 0 ..= iterations {
+        let mut context = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+        context.update(&hash);
+        context.update(salt);
+        hash = context.finish().as_ref().to_vec();
+    }
+
+    Ok(hash)
+}
+
+#[cfg(not(feature = "with_dnssec"))]
+pub fn nsec3_hash(_hash_algorithm: u8, _name: &[u8], _salt: &[u8], _iterations: u16) -> Result<Vec<u8>, CryptoError> {
+    Err(CryptoError::Unsupported)
+}
+
+
+#[cfg(all(test, feature = "with_dnssec"))]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[test]
+    fn ed25519_signature() {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let signature = key_pair.sign(b"a message");
+        let public_key = key_pair.public_key().as_ref();
+
+        assert_eq!(verify(15, public_key, b"a message", signature.as_ref()),
+                   Ok(()));
+        assert_eq!(verify(15, public_key, b"another message", signature.as_ref()),
+                   Err(CryptoError::Invalid));
+    }
+
+    #[test]
+    fn unsupported_algorithm() {
+        assert_eq!(verify(3, &[], b"a message", &[]),
+                   Err(CryptoError::Unsupported));
+    }
+
+    #[test]
+    fn truncated_rsa_key() {
+        assert_eq!(verify(8, &[ 0x00, 0x01 ], b"a message", &[]),
+                   Err(CryptoError::Invalid));
+    }
+
+    #[test]
+    fn sha256_digest() {
+        assert_eq!(digest(2, b"abc").map(|d| hex(&d)),
+                   Ok(String::from("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")));
+    }
+
+    #[test]
+    fn unsupported_digest() {
+        assert_eq!(digest(3, b"abc"),
+                   Err(CryptoError::Unsupported));
+    }
+
+    #[test]
+    fn nsec3_example() {
+        // The hash of “example” from the RFC 5155 Appendix A example zone,
+        // which is “0p9mhaveqvm6t7vbl5lop2u3t2rp3tom” in Base32
+        let name = b"\x07example\x00";
+        let salt = [ 0xaa, 0xbb, 0xcc, 0xdd ];
+
+        assert_eq!(nsec3_hash(1, name, &salt, 12).map(|h| hex(&h)),
+                   Ok(String::from("065368abeed7ec6e9feba96b8c8bc3e8b791f716")));
+    }
+}
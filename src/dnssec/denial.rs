@@ -0,0 +1,383 @@
+//! Authenticated denial of existence, using NSEC and NSEC3 records to prove
+//! that a name or record type does not exist.
+//!
+//! The functions in here assume the signatures on the records have already
+//! been checked: they only look at what the records claim.
+//!
+//! # References
+//!
+//! - [RFC 4035 §5.4](https://tools.ietf.org/html/rfc4035#section-5.4) —
+//!   Protocol Modifications for the DNS Security Extensions (March 2005)
+//! - [RFC 5155 §8](https://tools.ietf.org/html/rfc5155#section-8) — DNS
+//!   Security (DNSSEC) Hashed Authenticated Denial of Existence (March 2008)
+
+use std::cmp::Ordering;
+
+use dns::{Answer, Labels};
+use dns::record::{Record, RecordType, NSEC, NSEC3};
+
+use super::canonical::{compare_names, name_bytes, same_name, wildcard_of};
+use super::crypto::{nsec3_hash, CryptoError};
+
+
+/// The NSEC and NSEC3 records from one section of a response.
+#[derive(Debug, Default)]
+pub struct DenialRecords<'a> {
+    nsecs: Vec<(&'a Labels, &'a NSEC)>,
+    nsec3s: Vec<(&'a Labels, &'a NSEC3)>,
+}
+
+/// How strong a proof of non-existence is.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Denial {
+
+    /// The records prove that the name or type does not exist.
+    Proven,
+
+    /// The name is covered by an NSEC3 record with the Opt-Out flag set, so
+    /// it may be an unsigned delegation.
+    OptOut,
+
+    /// The records do not prove anything.
+    Unproven,
+
+    /// The NSEC3 records use a hash algorithm that dog cannot compute.
+    Unsupported,
+}
+
+/// What the records say about whether a name is a delegation point without
+/// a DS record, in response to a query for its DS records.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Delegation {
+
+    /// The name is a delegation with no DS records, so the child zone is
+    /// unsigned.
+    Unsigned,
+
+    /// The name is not a delegation point, as it either has no NS records
+    /// or does not exist.
+    NotACut,
+
+    /// The records say that DS records exist at the name.
+    HasDS,
+
+    /// The records do not prove anything.
+    Unproven,
+
+    /// The NSEC3 records use a hash algorithm that dog cannot compute.
+    Unsupported,
+}
+
+
+impl<'a> DenialRecords<'a> {
+
+    /// Gathers up the NSEC and NSEC3 records from a list of answers.
+    pub fn from_answers(answers: &'a [Answer]) -> Self {
+        let mut records = Self::default();
+
+        for answer in answers {
+            match answer {
+                Answer::Standard { qname, record: Record::NSEC(nsec), .. } => {
+                    records.nsecs.push((qname, nsec));
+                }
+                Answer::Standard { qname, record: Record::NSEC3(nsec3), .. } => {
+                    records.nsec3s.push((qname, nsec3));
+                }
+                _ => {}
+            }
+        }
+
+        records
+    }
+
+    /// Proves that the name exists but has no records of the given type.
+    pub fn prove_nodata(&self, qname: &Labels, qtype: RecordType) -> Denial {
+        let absent = |types: &[RecordType]| ! types.contains(&qtype) && ! types.contains(&RecordType::CNAME);
+
+        if self.nsecs.iter().any(|(owner, nsec)| same_name(owner, qname) && absent(&nsec.types)) {
+            return Denial::Proven;
+        }
+
+        match self.matching_nsec3(qname) {
+            Ok(Some(nsec3)) if absent(&nsec3.types)  => Denial::Proven,
+            Ok(_)                                    => Denial::Unproven,
+            Err(_)                                   => Denial::Unsupported,
+        }
+    }
+
+    /// Proves that the name does not exist, and that there is no wildcard
+    /// that could have been expanded to match it.
+    pub fn prove_nxdomain(&self, qname: &Labels) -> Denial {
+        if let Some((owner, nsec)) = self.covering_nsec(qname) {
+            let encloser = longer(common_ancestor(qname, owner), common_ancestor(qname, &nsec.next_domain));
+            let wildcard = wildcard_of(&encloser);
+
+            if self.covering_nsec(&wildcard).is_some() {
+                return Denial::Proven;
+            }
+        }
+
+        match self.closest_encloser_proof(qname) {
+            Ok(Some((encloser, opt_out))) => {
+                match self.covering_nsec3(&wildcard_of(&encloser)) {
+                    Ok(Some(_)) if opt_out  => Denial::OptOut,
+                    Ok(Some(_))             => Denial::Proven,
+                    Ok(None)                => Denial::Unproven,
+                    Err(_)                  => Denial::Unsupported,
+                }
+            }
+            Ok(None)  => Denial::Unproven,
+            Err(_)    => Denial::Unsupported,
+        }
+    }
+
+    /// Proves that a name that was answered using a wildcard does not exist
+    /// itself, given the number of labels in the wildcard’s owner name.
+    pub fn prove_wildcard_expansion(&self, qname: &Labels, wildcard_labels: usize) -> Denial {
+        if self.covering_nsec(qname).is_some() {
+            return Denial::Proven;
+        }
+
+        let next_closer = qname.suffix(wildcard_labels + 1);
+        match self.covering_nsec3(&next_closer) {
+            Ok(Some(nsec3)) if nsec3.is_opt_out()  => Denial::OptOut,
+            Ok(Some(_))                            => Denial::Proven,
+            Ok(None)                               => Denial::Unproven,
+            Err(_)                                 => Denial::Unsupported,
+        }
+    }
+
+    /// Works out whether the name is an unsigned delegation, from the
+    /// response to a query for its DS records.
+    pub fn delegation(&self, name: &Labels) -> Delegation {
+        let bitmap_delegation = |types: &[RecordType]| {
+            if types.contains(&RecordType::DS) {
+                Delegation::HasDS
+            }
+            else if types.contains(&RecordType::NS) && ! types.contains(&RecordType::SOA) {
+                Delegation::Unsigned
+            }
+            else {
+                Delegation::NotACut
+            }
+        };
+
+        if let Some((_, nsec)) = self.nsecs.iter().find(|(owner, _)| same_name(owner, name)) {
+            return bitmap_delegation(&nsec.types);
+        }
+
+        if self.covering_nsec(name).is_some() {
+            return Delegation::NotACut;
+        }
+
+        match self.matching_nsec3(name) {
+            Ok(Some(nsec3))  => return bitmap_delegation(&nsec3.types),
+            Ok(None)         => {},
+            Err(_)           => return Delegation::Unsupported,
+        }
+
+        match self.closest_encloser_proof(name) {
+            Ok(Some((_, true)))   => Delegation::Unsigned,
+            Ok(Some((_, false)))  => Delegation::NotACut,
+            Ok(None)              => Delegation::Unproven,
+            Err(_)                => Delegation::Unsupported,
+        }
+    }
+
+    fn covering_nsec(&self, name: &Labels) -> Option<(&'a Labels, &'a NSEC)> {
+        self.nsecs.iter()
+            .find(|(owner, nsec)| covers(compare_names(owner, name), compare_names(name, &nsec.next_domain), compare_names(&nsec.next_domain, owner)))
+            .copied()
+    }
+
+    fn matching_nsec3(&self, name: &Labels) -> Result<Option<&'a NSEC3>, CryptoError> {
+        for (owner, nsec3) in &self.nsec3s {
+            if let Some(owner_hash) = nsec3_owner_hash(owner, name) {
+                if owner_hash == hash_name(nsec3, name)? {
+                    return Ok(Some(nsec3));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn covering_nsec3(&self, name: &Labels) -> Result<Option<&'a NSEC3>, CryptoError> {
+        for (owner, nsec3) in &self.nsec3s {
+            if let Some(owner_hash) = nsec3_owner_hash(owner, name) {
+                let hash = hash_name(nsec3, name)?;
+                let next = &nsec3.next_hashed_owner;
+
+                if covers(owner_hash.cmp(&hash), hash.cmp(next), next.cmp(&owner_hash)) {
+                    return Ok(Some(nsec3));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Finds the closest existing ancestor of a name that does not exist,
+    /// and checks that the name one label below it is covered. Returns the
+    /// closest encloser, and whether the covering record has Opt-Out set.
+    fn closest_encloser_proof(&self, name: &Labels) -> Result<Option<(Labels, bool)>, CryptoError> {
+        let mut next_closer = name.clone();
+
+        while let Some(encloser) = next_closer.parent() {
+            if self.matching_nsec3(&encloser)?.is_some() {
+                return match self.covering_nsec3(&next_closer)? {
+                    Some(nsec3)  => Ok(Some((encloser, nsec3.is_opt_out()))),
+                    None         => Ok(None),
+                };
+            }
+
+            next_closer = encloser;
+        }
+
+        Ok(None)
+    }
+}
+
+
+/// Whether a name falls between an owner and the next name, given how the
+/// owner compares to the name, how the name compares to the next name, and
+/// how the next name compares to the owner. The last record in a zone
+/// wraps around to the first, so its next name is lower than its owner.
+fn covers(owner_to_name: Ordering, name_to_next: Ordering, next_to_owner: Ordering) -> bool {
+    owner_to_name == Ordering::Less && (name_to_next == Ordering::Less || next_to_owner != Ordering::Greater)
+}
+
+fn common_ancestor(a: &Labels, b: &Labels) -> Labels {
+    let count = a.iter().rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x.eq_ignore_ascii_case(y))
+        .count();
+
+    a.suffix(count)
+}
+
+fn longer(a: Labels, b: Labels) -> Labels {
+    if b.len() > a.len() { b } else { a }
+}
+
+fn hash_name(nsec3: &NSEC3, name: &Labels) -> Result<Vec<u8>, CryptoError> {
+    nsec3_hash(nsec3.hash_algorithm, &name_bytes(name), &nsec3.salt, nsec3.iterations)
+}
+
+/// Decodes the hash in the first label of an NSEC3 record’s owner name, if
+/// the given name belongs to the zone the record is in.
+fn nsec3_owner_hash(owner: &Labels, name: &Labels) -> Option<Vec<u8>> {
+    let zone = owner.parent()?;
+    if ! name.is_within(&zone) {
+        return None;
+    }
+
+    decode_base32hex(owner.iter().next()?)
+}
+
+fn decode_base32hex(input: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+
+    for &byte in input {
+        buffer = (buffer << 5) | char::from(byte).to_digit(32)?;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            let [_, _, _, byte] = (buffer >> bits).to_be_bytes();
+            output.push(byte);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(output)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use dns::QClass;
+
+    fn nsec(owner: &str, next: &str, types: Vec<RecordType>) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode(owner).unwrap(),
+            qclass: QClass::IN,
+            ttl: 3600,
+            record: Record::NSEC(NSEC { next_domain: Labels::encode(next).unwrap(), types }),
+        }
+    }
+
+    fn name(input: &str) -> Labels {
+        Labels::encode(input).unwrap()
+    }
+
+    #[test]
+    fn base32hex() {
+        assert_eq!(decode_base32hex(b"cpnmuoj1e8"),
+                   Some(b"foobar".to_vec()));
+        assert_eq!(decode_base32hex(b"CPNMUOJ1E8"),
+                   Some(b"foobar".to_vec()));
+        assert_eq!(decode_base32hex(b"xyz"),
+                   None);
+    }
+
+    #[test]
+    fn nodata() {
+        let answers = vec![ nsec("a.example", "c.example", vec![ RecordType::A, RecordType::RRSIG, RecordType::NSEC ]) ];
+        let records = DenialRecords::from_answers(&answers);
+
+        assert_eq!(records.prove_nodata(&name("a.example"), RecordType::MX), Denial::Proven);
+        assert_eq!(records.prove_nodata(&name("a.example"), RecordType::A),  Denial::Unproven);
+        assert_eq!(records.prove_nodata(&name("b.example"), RecordType::MX), Denial::Unproven);
+    }
+
+    #[test]
+    fn nxdomain() {
+        let answers = vec![
+            nsec("a.example", "c.example", vec![ RecordType::A ]),
+            nsec("example", "a.example", vec![ RecordType::SOA, RecordType::NS ]),
+        ];
+        let records = DenialRecords::from_answers(&answers);
+
+        assert_eq!(records.prove_nxdomain(&name("b.example")), Denial::Proven);
+    }
+
+    #[test]
+    fn nxdomain_without_wildcard_proof() {
+        let answers = vec![ nsec("a.example", "c.example", vec![ RecordType::A ]) ];
+        let records = DenialRecords::from_answers(&answers);
+
+        assert_eq!(records.prove_nxdomain(&name("b.example")), Denial::Unproven);
+    }
+
+    #[test]
+    fn last_nsec_wraps_around() {
+        let answers = vec![ nsec("z.example", "example", vec![ RecordType::A ]) ];
+        let records = DenialRecords::from_answers(&answers);
+
+        assert!(records.covering_nsec(&name("zz.example")).is_some());
+        assert!(records.covering_nsec(&name("a.example")).is_none());
+    }
+
+    #[test]
+    fn unsigned_delegation() {
+        let answers = vec![ nsec("b.example", "c.example", vec![ RecordType::NS, RecordType::RRSIG, RecordType::NSEC ]) ];
+        let records = DenialRecords::from_answers(&answers);
+
+        assert_eq!(records.delegation(&name("b.example")), Delegation::Unsigned);
+        assert_eq!(records.delegation(&name("bb.example")), Delegation::NotACut);
+        assert_eq!(records.delegation(&name("d.example")), Delegation::Unproven);
+    }
+
+    #[test]
+    fn not_a_delegation() {
+        let answers = vec![ nsec("b.example", "c.example", vec![ RecordType::A, RecordType::RRSIG, RecordType::NSEC ]) ];
+        let records = DenialRecords::from_answers(&answers);
+
+        assert_eq!(records.delegation(&name("b.example")), Delegation::NotACut);
+    }
+}
@@ -0,0 +1,163 @@
+//! DNSSEC validation, performed locally rather than trusting the `AD` bit.
+//!
+//! When validation is turned on, dog asks for DNSSEC records alongside its
+//! answers, then walks down the delegation path from the root zone, checking
+//! each DS and DNSKEY record it finds against the zone above, until it has a
+//! set of keys it trusts for the zone that signed the answer.
+
+use std::fmt;
+
+use dns::Labels;
+use dns::record::{Algorithm, RecordType};
+
+mod anchors;
+mod canonical;
+mod chain;
+mod crypto;
+mod denial;
+
+pub use self::chain::Validator;
+
+
+/// The outcome of validating one or more records.
+///
+/// # References
+///
+/// - [RFC 4035 §4.3](https://tools.ietf.org/html/rfc4035#section-4.3) —
+///   Protocol Modifications for the DNS Security Extensions (March 2005)
+#[derive(PartialEq, Debug, Clone)]
+pub enum Status {
+
+    /// There is an unbroken chain of signed DNSKEY and DS records from a
+    /// trust anchor to the records.
+    Secure,
+
+    /// There is proof that no chain of trust exists for the records, because
+    /// they belong to a zone that is not signed.
+    Insecure,
+
+    /// A chain of trust should exist, but the records failed validation.
+    Bogus(Problem),
+
+    /// Whether the records are secure could not be determined.
+    Indeterminate(Problem),
+}
+
+/// The reason that a set of records could not be validated.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Problem {
+
+    /// The records were unsigned, despite being in a signed zone.
+    MissingSignature,
+
+    /// The current time is after the expiration time of every signature.
+    SignatureExpired,
+
+    /// The current time is before the inception time of every signature.
+    SignatureNotYetValid,
+
+    /// A signature’s signer name is not the zone the records belong to.
+    WrongSigner(Labels),
+
+    /// There is no key in the zone with the key tag given in a signature.
+    MissingKey(u16),
+
+    /// A signature did not verify using the key it refers to.
+    InvalidSignature,
+
+    /// None of the zone’s DNSKEY records match its DS records.
+    MissingDelegationKey(Labels),
+
+    /// A negative response did not contain records that prove the name or
+    /// record type does not exist.
+    MissingDenial,
+
+    /// A signature or key uses an algorithm that dog cannot verify.
+    UnsupportedAlgorithm(Algorithm),
+
+    /// The records cannot be converted into their canonical form, so their
+    /// signature cannot be checked.
+    UnsupportedRecordType(RecordType),
+
+    /// A request for the records needed to build the chain of trust failed.
+    LookupFailed(Labels, RecordType),
+}
+
+/// The validation results for every set of records in a response.
+#[derive(PartialEq, Debug, Default)]
+pub struct Validation {
+
+    /// The status of each set of records in the Answer section.
+    pub rrsets: Vec<RRsetStatus>,
+
+    /// If the response has no answers, the status of the proof that the
+    /// name or record type does not exist.
+    pub denial: Option<Status>,
+}
+
+/// The validation result for a set of records with the same name and type.
+#[derive(PartialEq, Debug)]
+pub struct RRsetStatus {
+
+    /// The owner name of the records.
+    pub qname: Labels,
+
+    /// The type of the records.
+    pub record_type: RecordType,
+
+    /// Whether the records validated.
+    pub status: Status,
+}
+
+
+impl Validation {
+
+    /// Returns every status in this validation result, in order.
+    pub fn statuses(&self) -> impl Iterator<Item=&Status> {
+        self.rrsets.iter().map(|r| &r.status).chain(self.denial.iter())
+    }
+
+    /// Whether any of the records in the response failed validation.
+    pub fn is_bogus(&self) -> bool {
+        self.statuses().any(|s| matches!(s, Status::Bogus(_)))
+    }
+}
+
+impl Status {
+
+    /// The name of this status, as it gets displayed to the user.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Secure            => "secure",
+            Self::Insecure          => "insecure",
+            Self::Bogus(_)          => "bogus",
+            Self::Indeterminate(_)  => "indeterminate",
+        }
+    }
+
+    /// The reason this status is not secure, if there is one.
+    pub fn problem(&self) -> Option<&Problem> {
+        match self {
+            Self::Bogus(p) | Self::Indeterminate(p)  => Some(p),
+            Self::Secure | Self::Insecure            => None,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSignature            => write!(f, "Records are unsigned in a signed zone"),
+            Self::SignatureExpired            => write!(f, "Signature has expired"),
+            Self::SignatureNotYetValid        => write!(f, "Signature is not yet valid"),
+            Self::WrongSigner(signer)         => write!(f, "Signature has unexpected signer {}", signer),
+            Self::MissingKey(key_tag)         => write!(f, "No DNSKEY with key tag {}", key_tag),
+            Self::InvalidSignature            => write!(f, "Signature does not verify"),
+            Self::MissingDelegationKey(zone)  => write!(f, "No DNSKEY for {} matches its DS records", zone),
+            Self::MissingDenial               => write!(f, "No proof of non-existence"),
+            Self::UnsupportedAlgorithm(alg)   => write!(f, "Signature uses unsupported algorithm {}", alg),
+            Self::UnsupportedRecordType(rt)   => write!(f, "Cannot canonicalise {} records", rt),
+            Self::LookupFailed(qname, rt)     => write!(f, "Failed to look up {} {}", qname, rt),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Checking internationalised domain names for characters that could make
+//! one name look like another.
+
+use std::fmt;
+
+use dns::{Response, Answer, Labels};
+use dns::record::Record;
+
+
+/// A reason why a name might not be the name that it looks like.
+///
+/// # References
+///
+/// - [UTS #39](https://www.unicode.org/reports/tr39/) — Unicode Security
+///   Mechanisms (September 2023)
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Homograph {
+
+    /// A label mixes letters from two scripts that are not normally used
+    /// together, such as Latin and Cyrillic.
+    MixedScripts(Script, Script),
+
+    /// A label is written entirely in letters from a script other than
+    /// Latin, each of which looks like a Latin letter.
+    LookalikeScript(Script),
+
+    /// A label contains a letter that looks like a different, more common,
+    /// letter in the same script, such as a dotless ‘ı’.
+    LookalikeLetter(char),
+}
+
+/// The writing system that a letter belongs to, as far as checking for
+/// homographs is concerned.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Georgian,
+    Cherokee,
+    Hangul,
+    Hiragana,
+    Katakana,
+    Han,
+    Other,
+}
+
+/// Checks every name in a response for letters that could be mistaken for
+/// others: the names being queried, the owner names of its records, and the
+/// names in their data. Each name with a problem is only returned once.
+pub fn response_homographs(response: &Response) -> Vec<(Labels, Homograph)> {
+    let mut names: Vec<&Labels> = response.queries.iter().map(|q| &q.qname).collect();
+
+    for answer in response.answers.iter().chain(&response.authorities).chain(&response.additionals) {
+        if let Answer::Standard { qname, record, .. } = answer {
+            names.push(qname);
+            names.extend(record_names(record));
+        }
+    }
+
+    let mut homographs: Vec<(Labels, Homograph)> = Vec::new();
+    for name in names {
+        if homographs.iter().any(|(seen, _)| seen == name) {
+            continue;
+        }
+
+        if let Some(homograph) = check(name) {
+            homographs.push((name.clone(), homograph));
+        }
+    }
+
+    homographs
+}
+
+/// Checks each label of a name for letters that could be mistaken for
+/// others, returning the first problem found. Only names with at least one
+/// internationalised label get checked.
+pub fn check(name: &Labels) -> Option<Homograph> {
+    if name.iter().all(|segment| ! is_a_label(segment)) {
+        return None;
+    }
+
+    name.unicode_segments().find_map(|label| check_label(&label))
+}
+
+/// Returns the names in a record’s data that point to other domains.
+fn record_names(record: &Record) -> Vec<&Labels> {
+    match record {
+        Record::CNAME(cname)   => vec![ &cname.domain ],
+        Record::HTTPS(https)   => vec![ &https.target ],
+        Record::MX(mx)         => vec![ &mx.exchange ],
+        Record::NAPTR(naptr)   => vec![ &naptr.replacement ],
+        Record::NS(ns)         => vec![ &ns.nameserver ],
+        Record::NSEC(nsec)     => vec![ &nsec.next_domain ],
+        Record::PTR(ptr)       => vec![ &ptr.cname ],
+        Record::SOA(soa)       => vec![ &soa.mname, &soa.rname ],
+        Record::SRV(srv)       => vec![ &srv.target ],
+        Record::SVCB(svcb)     => vec![ &svcb.target ],
+        _                      => Vec::new(),
+    }
+}
+
+fn is_a_label(segment: &[u8]) -> bool {
+    segment.len() >= 4 && segment[.. 4].eq_ignore_ascii_case(b"xn--")
+}
+
+fn check_label(label: &str) -> Option<Homograph> {
+    let mut scripts = Vec::new();
+    for letter in label.chars() {
+        if let Some(script) = script(letter) {
+            if ! scripts.contains(&script) {
+                scripts.push(script);
+            }
+        }
+    }
+
+    if let [ first, second, .. ] = *scripts.as_slice() {
+        if ! allowed_together(&scripts) {
+            return Some(Homograph::MixedScripts(first, second));
+        }
+    }
+
+    match scripts.as_slice() {
+        [ Script::Latin ] => {
+            label.chars().find(|c| LATIN_LOOKALIKES.contains(c)).map(Homograph::LookalikeLetter)
+        }
+        [ script ] => {
+            if label.chars().filter(|c| c.is_alphabetic()).all(|c| NON_LATIN_LOOKALIKES.contains(&c)) {
+                Some(Homograph::LookalikeScript(*script))
+            }
+            else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether a set of scripts is one of the combinations that get written
+/// together: Japanese uses Han, Hiragana, and Katakana, Korean uses Han and
+/// Hangul, and either can be mixed with Latin.
+fn allowed_together(scripts: &[Script]) -> bool {
+    use Script::*;
+
+    scripts.iter().all(|s| [ Latin, Han, Hiragana, Katakana ].contains(s))
+        || scripts.iter().all(|s| [ Latin, Han, Hangul ].contains(s))
+}
+
+/// Returns the script that a letter belongs to, or `None` if it is not a
+/// letter, or is used in every script, such as digits and hyphens.
+fn script(letter: char) -> Option<Script> {
+    if ! letter.is_alphabetic() || ('\u{0300}' ..= '\u{036F}').contains(&letter) {
+        return None;
+    }
+
+    let script = match u32::from(letter) {
+        0x0041 ..= 0x024F | 0x1E00 ..= 0x1EFF | 0x2C60 ..= 0x2C7F | 0xA720 ..= 0xA7FF => Script::Latin,
+        0x0370 ..= 0x03FF | 0x1F00 ..= 0x1FFF                                          => Script::Greek,
+        0x0400 ..= 0x052F | 0x1C80 ..= 0x1C8F | 0x2DE0 ..= 0x2DFF | 0xA640 ..= 0xA69F => Script::Cyrillic,
+        0x0530 ..= 0x058F                                                              => Script::Armenian,
+        0x0590 ..= 0x05FF                                                              => Script::Hebrew,
+        0x0600 ..= 0x06FF | 0x0750 ..= 0x077F | 0x08A0 ..= 0x08FF                      => Script::Arabic,
+        0x0900 ..= 0x097F                                                              => Script::Devanagari,
+        0x0E00 ..= 0x0E7F                                                              => Script::Thai,
+        0x10A0 ..= 0x10FF | 0x2D00 ..= 0x2D2F                                          => Script::Georgian,
+        0x13A0 ..= 0x13FF | 0xAB70 ..= 0xABBF                                          => Script::Cherokee,
+        0x1100 ..= 0x11FF | 0x3130 ..= 0x318F | 0xAC00 ..= 0xD7AF                      => Script::Hangul,
+        0x3040 ..= 0x309F                                                              => Script::Hiragana,
+        0x30A0 ..= 0x30FF                                                              => Script::Katakana,
+        0x3400 ..= 0x4DBF | 0x4E00 ..= 0x9FFF | 0xF900 ..= 0xFAFF                      => Script::Han,
+        _                                                                              => Script::Other,
+    };
+
+    Some(script)
+}
+
+/// Lowercase letters from other scripts that look like Latin letters.
+const NON_LATIN_LOOKALIKES: &[char] = &[
+    // Cyrillic
+    'а', 'е', 'о', 'р', 'с', 'у', 'х', 'ѕ', 'і', 'ј', 'һ', 'ӏ', 'ԁ', 'ԛ', 'ԝ', 'ү',
+    // Greek
+    'α', 'ι', 'κ', 'ν', 'ο', 'ρ', 'υ',
+    // Armenian
+    'հ', 'ո', 'ս', 'ց', 'զ', 'օ',
+];
+
+/// Latin letters that look like other, more common, Latin letters.
+const LATIN_LOOKALIKES: &[char] = &[
+    'ı', 'ǀ', 'ɑ', 'ɡ', 'ɩ', 'ʟ', 'ꞁ',
+];
+
+
+impl fmt::Display for Homograph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MixedScripts(a, b)    => write!(f, "mixes {:?} and {:?} letters", a, b),
+            Self::LookalikeScript(s)    => write!(f, "is written in {:?} letters that look like Latin ones", s),
+            Self::LookalikeLetter(c)    => write!(f, "contains the letter ‘{}’ (U+{:04X}), which looks like another", c, u32::from(*c)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn plain_latin() {
+        assert_eq!(check_label("lookup"), None);
+        assert_eq!(check_label("bücher"), None);
+    }
+
+    #[test]
+    fn plain_cyrillic() {
+        assert_eq!(check_label("москва"), None);
+    }
+
+    #[test]
+    fn japanese() {
+        assert_eq!(check_label("日本語のサイト"), None);
+        assert_eq!(check_label("dogの日"), None);
+    }
+
+    #[test]
+    fn latin_and_cyrillic() {
+        assert_eq!(check_label("pаypal"),  // with a Cyrillic ‘а’
+                   Some(Homograph::MixedScripts(Script::Latin, Script::Cyrillic)));
+    }
+
+    #[test]
+    fn greek_and_cyrillic() {
+        assert_eq!(check_label("αа"),
+                   Some(Homograph::MixedScripts(Script::Greek, Script::Cyrillic)));
+    }
+
+    #[test]
+    fn whole_script_lookalike() {
+        assert_eq!(check_label("аррӏе"),  // all Cyrillic
+                   Some(Homograph::LookalikeScript(Script::Cyrillic)));
+    }
+
+    #[test]
+    fn dotless_i() {
+        assert_eq!(check_label("lınk"),
+                   Some(Homograph::LookalikeLetter('ı')));
+    }
+
+    #[test]
+    fn digits_and_hyphens() {
+        assert_eq!(check_label("xn-123"), None);
+    }
+
+    #[test]
+    fn ascii_names_unchecked() {
+        assert_eq!(check(&Labels::encode("lookup.dog").unwrap()), None);
+    }
+
+    #[test]
+    fn escaped_dot_in_label() {
+        // One label, rather than two labels that each spell ‘аррӏе’ in
+        // Cyrillic letters
+        let name = Labels::encode("xn--80ak6aa92e\\.xn--80ak6aa92e.lookup.dog").unwrap();
+        assert_eq!(check(&name), None);
+    }
+
+    #[test]
+    fn description() {
+        assert_eq!(Homograph::MixedScripts(Script::Latin, Script::Cyrillic).to_string(),
+                   "mixes Latin and Cyrillic letters");
+    }
+}
@@ -25,17 +25,25 @@ use log::*;
 
 mod colours;
 mod connect;
+mod cookies;
+mod dnssec;
 mod hints;
+mod idn;
 mod logger;
 mod output;
 mod requests;
 mod resolve;
 mod table;
+mod tsig;
 mod txid;
+mod update;
+mod zone;
 
 mod options;
 use self::options::*;
 
+use self::txid::TxidGenerator;
+
 
 /// Configures logging, parses the command-line options, and handles any
 /// errors before passing control over to the Dog type.
@@ -98,15 +106,21 @@ fn main() {
 
 
 /// Runs dog with some options, returning the status to exit with.
-fn run(Options { requests, format, measure_time }: Options) -> i32 {
+fn run(Options { requests, format, measure_time, validate, lenient, update, tsig_key }: Options) -> i32 {
     use std::time::Instant;
 
+    if let Some(update) = update {
+        return run_update(update, requests, format, measure_time, tsig_key.as_ref());
+    }
+
     let should_show_opt = requests.edns.should_show();
 
     let mut responses = Vec::new();
     let timer = if measure_time { Some(Instant::now()) } else { None };
 
     let mut errored = false;
+    let mut bogus = false;
+    let mut unverified = false;
 
     let local_host_hints = match hints::LocalHosts::load() {
         Ok(lh) => lh,
@@ -122,7 +136,7 @@ fn run(Options { requests, format, measure_time }: Options) -> i32 {
         }
     }
 
-    let request_tuples = match requests.generate() {
+    let request_tuples = match requests.generate(lenient) {
         Ok(rt) => rt,
         Err(e) => {
             eprintln!("Unable to obtain resolver: {}", e);
@@ -130,24 +144,51 @@ fn run(Options { requests, format, measure_time }: Options) -> i32 {
         }
     };
 
-    for (transport, request_list) in request_tuples {
+    let mut cookie_jar = cookies::CookieJar::default();
+
+    for (transport, nameserver, request_list) in request_tuples {
+        let mut validator = if validate { Some(dnssec::Validator::new(&*transport, TxidGenerator::Random)) } else { None };
+
         let request_list_len = request_list.len();
-        for (i, request) in request_list.into_iter().enumerate() {
-            let result = transport.send(&request);
+        for (i, mut request) in request_list.into_iter().enumerate() {
+            let result =
+                if is_transfer(request.query.qtype) {
+                    transfer(&nameserver, &mut request, tsig_key.as_ref())
+                }
+                else {
+                    cookie_jar.send(&*transport, &nameserver, &mut request, tsig_key.as_ref())
+                        .map(|response| {
+                            let verification = verify(tsig_key.as_ref(), request.signature.as_ref(), &response);
+                            (response, Vec::new(), verification)
+                        })
+                };
 
             match result {
-                Ok(mut response) => {
+                Ok((mut response, differences, verification)) => {
                     if response.flags.error_code.is_some() && i != request_list_len - 1 {
                         continue;
                     }
 
+                    let validation = validator.as_mut().map(|v| v.validate(&request.query, &response));
+                    if validation.as_ref().map_or(false, dnssec::Validation::is_bogus) {
+                        bogus = true;
+                    }
+
+                    if matches!(verification, Some(tsig::Verification::Failed(_))) {
+                        unverified = true;
+                    }
+
+                    let extended_errors = response.extended_errors().cloned().collect();
+                    let client_subnet = response.client_subnet().copied();
+                    let nsid = response.nsid().map(<[u8]>::to_vec);
+
                     if ! should_show_opt {
                         response.answers.retain(dns::Answer::is_standard);
                         response.authorities.retain(dns::Answer::is_standard);
                         response.additionals.retain(dns::Answer::is_standard);
                     }
 
-                    responses.push(response);
+                    responses.push(output::Received { response, differences, validation, verification, extended_errors, client_subnet, nsid });
                     break;
                 }
                 Err(e) => {
@@ -164,6 +205,12 @@ fn run(Options { requests, format, measure_time }: Options) -> i32 {
         if errored {
             exits::NETWORK_ERROR
         }
+        else if bogus {
+            exits::DNSSEC_BOGUS
+        }
+        else if unverified {
+            exits::TSIG_FAILED
+        }
         else {
             exits::SUCCESS
         }
@@ -174,6 +221,104 @@ fn run(Options { requests, format, measure_time }: Options) -> i32 {
 }
 
 
+/// Sends a dynamic update to the nameserver over TCP, signing it if there is
+/// a key, and returning the status to exit with.
+fn run_update(update: update::UpdateGenerator, requests: requests::RequestGenerator, format: output::OutputFormat, measure_time: bool, tsig_key: Option<&tsig::Key>) -> i32 {
+    use std::time::Instant;
+
+    let mut resolver_types = requests.inputs.resolver_types.into_iter();
+    let resolver_type = if let Some(rt) = resolver_types.next() { rt }
+    else {
+        eprintln!("dog: Invalid options: No nameserver to send the update to");
+        return exits::OPTIONS_ERROR;
+    };
+
+    if resolver_types.next().is_some() {
+        warn!("Only sending the update to the first nameserver");
+    }
+
+    let nameserver = match resolver_type.obtain() {
+        Ok(resolver) => resolver.nameserver(),
+        Err(e) => {
+            eprintln!("Unable to obtain resolver: {}", e);
+            return exits::SYSTEM_ERROR;
+        }
+    };
+
+    // Any other transport was rejected while parsing the options
+    let transport = dns_transport::TcpTransport::new(nameserver);
+    let mut update = update.generate(requests.txid_generator.generate());
+    if let Some(key) = tsig_key {
+        key.sign_update(&mut update);
+    }
+    info!("Sending update -> {:#?}", update);
+
+    let timer = if measure_time { Some(Instant::now()) } else { None };
+
+    match transport.send_update(&update) {
+        Ok(response) => {
+            let duration = timer.map(|t| t.elapsed());
+            let verification = verify(tsig_key, update.signature.as_ref(), &response);
+            let unverified = matches!(verification, Some(tsig::Verification::Failed(_)));
+
+            if ! format.print_update(&response, verification, duration) {
+                exits::UPDATE_FAILED
+            }
+            else if unverified {
+                exits::TSIG_FAILED
+            }
+            else {
+                exits::SUCCESS
+            }
+        }
+        Err(e) => {
+            format.print_error(e);
+            exits::NETWORK_ERROR
+        }
+    }
+}
+
+
+/// Whether a query type asks for a full or incremental zone transfer, which
+/// has to be sent over TCP and gets a stream of messages back.
+fn is_transfer(qtype: dns::record::RecordType) -> bool {
+    qtype == dns::record::RecordType::AXFR || qtype == dns::record::RecordType::IXFR
+}
+
+/// Transfers a zone from the nameserver over TCP, signing the request if
+/// there is a key, and merging the stream of messages into one response so
+/// that it can be printed like any other. An incremental transfer also
+/// returns the differences between the versions of the zone.
+fn transfer(nameserver: &str, request: &mut dns::Request, tsig_key: Option<&tsig::Key>) -> Result<(dns::Response, Vec<dns::Difference>, Option<tsig::Verification>), dns_transport::Error> {
+    if let Some(key) = tsig_key {
+        key.sign_request(request);
+    }
+
+    // Any other transport, or lenient parsing, was rejected while parsing
+    // the options
+    let transport = dns_transport::TcpTransport::new(nameserver.into());
+    let transfer = transport.transfer(request)?;
+
+    let verification = match (tsig_key, &request.signature) {
+        (Some(key), Some(sent))  => Some(key.verify_transfer(sent, transfer.messages())),
+        _                        => None,
+    };
+
+    let (response, differences) = transfer.into_response();
+    Ok((response, differences, verification))
+}
+
+
+/// Verifies the signature on the response using the key, if the request
+/// was signed with one.
+fn verify(tsig_key: Option<&tsig::Key>, sent: Option<&dns::Signature>, response: &dns::Response) -> Option<tsig::Verification> {
+    match (tsig_key, sent) {
+        (Some(key), Some(sent))  => Some(key.verify(sent, response)),
+        _                        => None,
+    }
+}
+
+
 /// Checks whether the options contain parameters that will cause dog to fail
 /// because the feature is disabled by exiting if so.
 #[allow(unused)]
@@ -192,6 +337,18 @@ fn disabled_feature_check(options: &Options) {
         eprintln!("dog: Cannot use '--https': This version of dog has been compiled without HTTPS support");
         exit(exits::OPTIONS_ERROR);
     }
+
+    #[cfg(not(feature = "with_dnssec"))]
+    if options.validate {
+        eprintln!("dog: Cannot use '--validate': This version of dog has been compiled without DNSSEC support");
+        exit(exits::OPTIONS_ERROR);
+    }
+
+    #[cfg(not(feature = "with_tsig"))]
+    if options.tsig_key.is_some() {
+        eprintln!("dog: Cannot use '--tsig': This version of dog has been compiled without TSIG support");
+        exit(exits::OPTIONS_ERROR);
+    }
 }
 
 
@@ -213,4 +370,16 @@ mod exits {
 
     /// Exit code for when the system network configuration could not be determined.
     pub const SYSTEM_ERROR: i32 = 4;
+
+    /// Exit code for when DNSSEC validation was requested, and at least one
+    /// of the responses failed it.
+    pub const DNSSEC_BOGUS: i32 = 5;
+
+    /// Exit code for when a dynamic update was sent, and the server
+    /// responded with an error rather than performing it.
+    pub const UPDATE_FAILED: i32 = 6;
+
+    /// Exit code for when requests were signed with a TSIG key, and at least
+    /// one of the responses failed verification.
+    pub const TSIG_FAILED: i32 = 7;
 }
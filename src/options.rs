@@ -2,17 +2,21 @@
 
 use std::ffi::OsStr;
 use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use log::*;
 
-use dns::{QClass, Labels};
-use dns::record::RecordType;
+use dns::{QClass, Labels, Opcode, Prerequisite, Operation};
+use dns::record::{RecordType, ClientSubnet, Record};
 
 use crate::connect::TransportType;
 use crate::output::{OutputFormat, UseColours, TextFormat};
 use crate::requests::{RequestGenerator, Inputs, ProtocolTweaks, UseEDNS};
 use crate::resolve::ResolverType;
+use crate::tsig::{Key, KeyError};
 use crate::txid::TxidGenerator;
+use crate::update::UpdateGenerator;
 
 
 /// The command-line options used when running dog.
@@ -27,6 +31,21 @@ pub struct Options {
 
     /// How to format the output data.
     pub format: OutputFormat,
+
+    /// Whether to validate the DNSSEC signatures in each response.
+    pub validate: bool,
+
+    /// Whether to show the records in a response that parsed when some of
+    /// the others are malformed, instead of failing with an error.
+    pub lenient: bool,
+
+    /// The dynamic update to send instead of any queries, if the user has
+    /// asked for one.
+    pub update: Option<UpdateGenerator>,
+
+    /// The key to sign requests and updates with, and to verify the
+    /// responses to them with.
+    pub tsig_key: Option<Key>,
 }
 
 impl Options {
@@ -50,11 +69,22 @@ impl Options {
         opts.optmulti("t", "type",        "Type of the DNS record being queried (A, MX, NS...)", "TYPE");
         opts.optmulti("n", "nameserver",  "Address of the nameserver to send packets to", "ADDR");
         opts.optmulti("",  "class",       "Network class of the DNS record being queried (IN, CH, HS)", "CLASS");
+        opts.optopt  ("",  "serial",      "Serial number of the zone already held, for IXFR queries", "NUMBER");
 
         // Sending options
         opts.optopt  ("",  "edns",         "Whether to OPT in to EDNS (disable, hide, show)", "SETTING");
         opts.optopt  ("",  "txid",         "Set the transaction ID to a specific value", "NUMBER");
+        opts.optopt  ("",  "opcode",       "Set the opcode of the request (QUERY, NOTIFY, UPDATE...)", "OPCODE");
         opts.optmulti("Z", "",             "Set uncommon protocol tweaks", "TWEAKS");
+        opts.optflag ("",  "validate",     "Validate DNSSEC signatures from the root zone down");
+        opts.optopt  ("",  "tsig",         "Sign requests with a TSIG key ([ALGORITHM:]NAME:SECRET)", "KEY");
+        opts.optopt  ("",  "tsig-file",    "Sign requests with the TSIG key in a BIND key file", "PATH");
+
+        // Update options
+        opts.optopt  ("",  "update",       "Send a dynamic update to a zone instead of querying", "ZONE");
+        opts.optmulti("",  "prereq",       "Require a condition to hold for the update to happen", "PREREQ");
+        opts.optmulti("",  "add",          "Add a record, written as in a zone file", "RECORD");
+        opts.optmulti("",  "delete",       "Delete a name, an RRset, or a record", "RECORD");
 
         // Protocol options
         opts.optflag ("U", "udp",          "Use the DNS protocol over UDP");
@@ -66,9 +96,12 @@ impl Options {
         opts.optopt  ("",  "color",        "When to use terminal colors",  "WHEN");
         opts.optopt  ("",  "colour",       "When to use terminal colours", "WHEN");
         opts.optflag ("J", "json",         "Display the output as JSON");
+        opts.optflag ("",  "zone",         "Display the answers in zone file format");
         opts.optflag ("",  "seconds",      "Do not format durations, display them as seconds");
+        opts.optflag ("",  "unicode",      "Display internationalised domain names in Unicode");
         opts.optflag ("1", "short",        "Short mode: display nothing but the first result");
         opts.optflag ("",  "time",         "Print how long the response took to arrive");
+        opts.optflag ("",  "lenient",      "Show the records that parsed when others are malformed");
 
         // Meta options
         opts.optflag ("v", "version",      "Print version information");
@@ -90,7 +123,7 @@ impl Options {
         else {
             match Self::deduce(matches) {
                 Ok(opts) => {
-                    if opts.requests.inputs.domains.is_empty() {
+                    if opts.requests.inputs.domains.is_empty() && opts.update.is_none() {
                         OptionsResult::Help(HelpReason::NoDomains, uc)
                     }
                     else {
@@ -106,10 +139,58 @@ impl Options {
 
     fn deduce(matches: getopts::Matches) -> Result<Self, OptionsError> {
         let measure_time = matches.opt_present("time");
+        let validate = matches.opt_present("validate");
+        let lenient = matches.opt_present("lenient");
         let format = OutputFormat::deduce(&matches);
+        let update = UpdateGenerator::deduce(&matches)?;
+        let tsig_key = deduce_tsig_key(&matches)?;
         let requests = RequestGenerator::deduce(matches)?;
 
-        Ok(Self { requests, measure_time, format })
+        if update.is_some() {
+            if ! requests.inputs.domains.is_empty() {
+                return Err(OptionsError::UpdateWithQueries);
+            }
+
+            if requests.inputs.transport_types.iter().any(|t| ! matches!(t, TransportType::TCP | TransportType::Automatic)) {
+                return Err(OptionsError::UpdateTransport);
+            }
+        }
+
+        if requests.inputs.record_types.iter().any(|rt| *rt == RecordType::AXFR || *rt == RecordType::IXFR) {
+            if requests.inputs.transport_types.iter().any(|t| ! matches!(t, TransportType::TCP | TransportType::Automatic)) {
+                return Err(OptionsError::TransferTransport);
+            }
+
+            if lenient {
+                return Err(OptionsError::LenientTransfer);
+            }
+        }
+
+        Ok(Self { requests, measure_time, format, validate, lenient, update, tsig_key })
+    }
+}
+
+
+/// Reads the TSIG key from the command-line, or from the key file that the
+/// user has pointed to, if either was given.
+fn deduce_tsig_key(matches: &getopts::Matches) -> Result<Option<Key>, OptionsError> {
+    match (matches.opt_str("tsig"), matches.opt_str("tsig-file")) {
+        (Some(_), Some(_)) => {
+            Err(OptionsError::TwoTsigKeys)
+        }
+        (Some(argument), None) => {
+            Key::from_argument(&argument).map(Some).map_err(OptionsError::InvalidTsigKey)
+        }
+        (None, Some(path)) => {
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| OptionsError::InvalidTsigKeyFile(path.clone(), e.to_string()))?;
+
+            Key::from_key_file(&contents).map(Some)
+                .map_err(|e| OptionsError::InvalidTsigKeyFile(path, e.to_string()))
+        }
+        (None, None) => {
+            Ok(None)
+        }
     }
 }
 
@@ -118,7 +199,32 @@ impl RequestGenerator {
     fn deduce(matches: getopts::Matches) -> Result<Self, OptionsError> {
         let edns = UseEDNS::deduce(&matches)?;
         let txid_generator = TxidGenerator::deduce(&matches)?;
-        let protocol_tweaks = ProtocolTweaks::deduce(&matches)?;
+        let mut protocol_tweaks = ProtocolTweaks::deduce(&matches)?;
+
+        if matches.opt_present("validate") {
+            if edns == UseEDNS::Disable {
+                return Err(OptionsError::ValidateWithoutEDNS);
+            }
+
+            protocol_tweaks.set_dnssec_ok_flag = true;
+            protocol_tweaks.set_checking_disabled_flag = true;
+            if protocol_tweaks.udp_payload_size.is_none() {
+                protocol_tweaks.udp_payload_size = Some(1232);
+            }
+        }
+
+        if edns == UseEDNS::Disable {
+            if protocol_tweaks.request_nsid {
+                return Err(OptionsError::NSIDWithoutEDNS);
+            }
+            else if protocol_tweaks.client_cookie.is_some() {
+                return Err(OptionsError::CookieWithoutEDNS);
+            }
+            else if protocol_tweaks.client_subnet.is_some() {
+                return Err(OptionsError::SubnetWithoutEDNS);
+            }
+        }
+
         let inputs = Inputs::deduce(matches)?;
 
         Ok(Self { inputs, txid_generator, edns, protocol_tweaks })
@@ -134,6 +240,7 @@ impl Inputs {
         inputs.load_free_args(matches)?;
         inputs.check_for_missing_nameserver()?;
         inputs.load_fallbacks();
+        inputs.check_for_missing_serial()?;
         Ok(inputs)
     }
 
@@ -180,7 +287,7 @@ impl Inputs {
         }
 
         for class_name in matches.opt_strs("class") {
-            if let Some(class) = parse_class_name(&class_name) {
+            if let Some(class) = QClass::from_class_name(&class_name) {
                 self.add_class(class);
             }
             else if let Ok(class_number) = class_name.parse() {
@@ -191,6 +298,13 @@ impl Inputs {
             }
         }
 
+        if let Some(serial) = matches.opt_str("serial") {
+            match serial.parse() {
+                Ok(number)  => self.serial = Some(number),
+                Err(_)      => return Err(OptionsError::InvalidSerial(serial)),
+            }
+        }
+
         Ok(())
     }
 
@@ -204,14 +318,14 @@ impl Inputs {
                 if argument.eq_ignore_ascii_case("OPT") {
                     return Err(OptionsError::QueryTypeOPT);
                 }
-                else if let Some(class) = parse_class_name(&argument) {
-                    trace!("Got qclass -> {:?}", &argument);
-                    self.add_class(class);
-                }
                 else if let Some(record_type) = RecordType::from_type_name(&argument) {
                     trace!("Got qtype -> {:?}", &argument);
                     self.add_type(record_type);
                 }
+                else if let Some(class) = QClass::from_class_name(&argument) {
+                    trace!("Got qclass -> {:?}", &argument);
+                    self.add_class(class);
+                }
                 else {
                     trace!("Got single-word domain -> {:?}", &argument);
                     self.add_domain(&argument)?;
@@ -253,6 +367,15 @@ impl Inputs {
         }
     }
 
+    fn check_for_missing_serial(&self) -> Result<(), OptionsError> {
+        if self.serial.is_none() && self.record_types.contains(&RecordType::IXFR) {
+            Err(OptionsError::IxfrWithoutSerial)
+        }
+        else {
+            Ok(())
+        }
+    }
+
     fn add_domain(&mut self, input: &str) -> Result<(), OptionsError> {
         if let Ok(domain) = Labels::encode(input) {
             self.domains.push(domain);
@@ -289,18 +412,135 @@ fn is_constant_name(argument: &str) -> bool {
     argument.chars().all(|c| c.is_ascii_alphanumeric())
 }
 
-fn parse_class_name(input: &str) -> Option<QClass> {
-    if input.eq_ignore_ascii_case("IN") {
-        Some(QClass::IN)
+
+impl UpdateGenerator {
+    fn deduce(matches: &getopts::Matches) -> Result<Option<Self>, OptionsError> {
+        let zone = if let Some(zone) = matches.opt_str("update") {
+            Labels::encode(&zone).map_err(|_| OptionsError::InvalidDomain(zone.clone()))?
+        }
+        else if matches.opt_present("prereq") || matches.opt_present("add") || matches.opt_present("delete") {
+            return Err(OptionsError::UpdateWithoutZone);
+        }
+        else {
+            return Ok(None);
+        };
+
+        let zone_class = match matches.opt_str("class") {
+            Some(class_name) => {
+                QClass::from_class_name(&class_name).ok_or(OptionsError::InvalidQueryClass(class_name))?
+            }
+            None => {
+                QClass::IN
+            }
+        };
+
+        let prerequisites = matches.opt_strs("prereq").iter()
+            .map(|prereq| parse_prerequisite(prereq, &zone))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Additions and deletions get performed in the order they were
+        // given, so they have to be put back together by their positions
+        let mut changes = matches.opt_strs_pos("add").into_iter().map(|(position, text)| (position, true, text))
+            .chain(matches.opt_strs_pos("delete").into_iter().map(|(position, text)| (position, false, text)))
+            .collect::<Vec<_>>();
+        changes.sort_by_key(|change| change.0);
+
+        let mut operations = Vec::with_capacity(changes.len());
+        for (_, is_addition, text) in changes {
+            if is_addition {
+                let (name, ttl, record) = parse_update_record(&text, &zone, None)?;
+                operations.push(Operation::AddRecord { name, ttl, record });
+            }
+            else {
+                operations.push(parse_deletion(&text, &zone)?);
+            }
+        }
+
+        Ok(Some(Self { zone, zone_class, prerequisites, operations }))
     }
-    else if input.eq_ignore_ascii_case("CH") {
-        Some(QClass::CH)
+}
+
+/// Parses a prerequisite of an update, which is written in the same way as
+/// in `nsupdate`: a keyword, a name, and a record type for the ones that
+/// apply to every record of a type.
+fn parse_prerequisite(input: &str, zone: &Labels) -> Result<Prerequisite, OptionsError> {
+    let invalid = || OptionsError::InvalidPrerequisite(input.into());
+    let words = input.split_whitespace().collect::<Vec<_>>();
+
+    let prerequisite = match words[..] {
+        [ keyword, name, type_name ] if keyword.eq_ignore_ascii_case("yxrrset") || keyword.eq_ignore_ascii_case("nxrrset") => {
+            let name = dns::parse_relative_name(name, zone).ok_or_else(invalid)?;
+            let record_type = RecordType::from_type_name(type_name).ok_or_else(invalid)?;
+
+            if keyword.eq_ignore_ascii_case("yxrrset") {
+                Prerequisite::RRsetExists { name, record_type }
+            }
+            else {
+                Prerequisite::RRsetDoesNotExist { name, record_type }
+            }
+        }
+        [ keyword, name ] if keyword.eq_ignore_ascii_case("yxdomain") => {
+            let name = dns::parse_relative_name(name, zone).ok_or_else(invalid)?;
+            Prerequisite::NameInUse { name }
+        }
+        [ keyword, name ] if keyword.eq_ignore_ascii_case("nxdomain") => {
+            let name = dns::parse_relative_name(name, zone).ok_or_else(invalid)?;
+            Prerequisite::NameNotInUse { name }
+        }
+        _ => {
+            return Err(invalid());
+        }
+    };
+
+    Ok(prerequisite)
+}
+
+/// Parses a deletion in an update, which is either a name on its own, to
+/// delete everything at that name; a name and a record type, to delete
+/// every record of that type; or a whole record, to delete just that one.
+fn parse_deletion(input: &str, zone: &Labels) -> Result<Operation, OptionsError> {
+    let words = input.split_whitespace().collect::<Vec<_>>();
+    let parse_name = |name: &str| dns::parse_relative_name(name, zone).ok_or_else(|| OptionsError::InvalidDomain(name.into()));
+
+    if let [ name ] = words[..] {
+        return Ok(Operation::DeleteName { name: parse_name(name)? });
     }
-    else if input.eq_ignore_ascii_case("HS") {
-        Some(QClass::HS)
+
+    if let [ name, type_name ] = words[..] {
+        if let Some(record_type) = RecordType::from_type_name(type_name) {
+            return Ok(Operation::DeleteRRset { name: parse_name(name)?, record_type });
+        }
     }
-    else {
-        None
+
+    let (name, _, record) = parse_update_record(input, zone, Some(0))?;
+    Ok(Operation::DeleteRecord { name, record })
+}
+
+/// Parses a record in an update, which is written in the same way as a line
+/// of a zone file, with names relative to the zone being updated. The TTL
+/// can only be left out if there is a default one to use instead.
+fn parse_update_record(input: &str, zone: &Labels, default_ttl: Option<u32>) -> Result<(Labels, u32, Record), OptionsError> {
+    let text = match default_ttl {
+        Some(ttl)  => format!("$TTL {}\n{}", ttl, input),
+        None       => input.to_owned(),
+    };
+
+    match dns::parse_zone(&text, zone) {
+        Ok(mut answers) if answers.len() == 1 => {
+            match answers.remove(0) {
+                dns::Answer::Standard { qname, ttl, record, .. } => Ok((qname, ttl, record)),
+                dns::Answer::Pseudo { .. } => unreachable!("pseudo-record in zone"),
+            }
+        }
+        Ok(_) => {
+            Err(OptionsError::InvalidRecord(input.into(), "Expected exactly one record".into()))
+        }
+        Err(mut error) => {
+            // The error is always on the one line, so there is no need to
+            // say which line it was
+            error.line = 0;
+            Err(OptionsError::InvalidRecord(input.into(), error.to_string()))
+        }
     }
 }
 
@@ -321,6 +561,70 @@ impl TxidGenerator {
     }
 }
 
+/// Parses a client cookie written as sixteen hexadecimal digits, such as
+/// `0123456789abcdef`, into the eight bytes to send.
+fn parse_client_cookie(input: &str) -> Option<[u8; 8]> {
+    if input.len() != 16 || ! input.bytes().all(|b| b.is_ascii_hexdigit()) {
+        warn!("Client cookie must be sixteen hex digits: {:?}", input);
+        return None;
+    }
+
+    let mut client = [0; 8];
+    for (i, byte) in client.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&input[i * 2 .. i * 2 + 2], 16).ok()?;
+    }
+
+    Some(client)
+}
+
+/// Parses a network address with an optional prefix length, such as
+/// `203.0.113.0/24`, into the Client Subnet option to send. The prefix
+/// length defaults to the one recommended by RFC 7871 §11.1 if it is
+/// missing, and any address bits past it are cleared.
+fn parse_client_subnet(input: &str) -> Option<ClientSubnet> {
+    let (address_str, prefix_str) = match input.find('/') {
+        Some(slash)  => (&input[.. slash], Some(&input[slash + 1 ..])),
+        None         => (input, None),
+    };
+
+    let address: IpAddr = match address_str.parse() {
+        Ok(address) => {
+            address
+        }
+        Err(e) => {
+            warn!("Error parsing subnet address: {}", e);
+            return None;
+        }
+    };
+
+    let (default_prefix, maximum_prefix) = match address {
+        IpAddr::V4(_)  => (24, 32),
+        IpAddr::V6(_)  => (56, 128),
+    };
+
+    let source_prefix_length = match prefix_str.map(str::parse::<u8>) {
+        None                                          => default_prefix,
+        Some(Ok(prefix)) if prefix <= maximum_prefix  => prefix,
+        Some(otherwise) => {
+            warn!("Invalid subnet prefix length: {:?}", otherwise);
+            return None;
+        }
+    };
+
+    let address = match address {
+        IpAddr::V4(v4) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(source_prefix_length)).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+        }
+        IpAddr::V6(v6) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(source_prefix_length)).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+        }
+    };
+
+    Some(ClientSubnet { source_prefix_length, scope_prefix_length: 0, address })
+}
+
 fn parse_dec_or_hex(input: &str) -> Option<u16> {
     if let Some(hex_str) = input.strip_prefix("0x") {
         match u16::from_str_radix(hex_str, 16) {
@@ -354,7 +658,10 @@ impl OutputFormat {
             Self::Short(summary_format)
         }
         else if matches.opt_present("json") {
-            Self::JSON
+            Self::JSON(TextFormat::deduce(matches))
+        }
+        else if matches.opt_present("zone") {
+            Self::Zone
         }
         else {
             let use_colours = UseColours::deduce(matches);
@@ -383,7 +690,8 @@ impl UseColours {
 impl TextFormat {
     fn deduce(matches: &getopts::Matches) -> Self {
         let format_durations = ! matches.opt_present("seconds");
-        Self { format_durations }
+        let unicode_names = matches.opt_present("unicode");
+        Self { format_durations, unicode_names }
     }
 }
 
@@ -409,6 +717,13 @@ impl ProtocolTweaks {
     fn deduce(matches: &getopts::Matches) -> Result<Self, OptionsError> {
         let mut tweaks = Self::default();
 
+        if let Some(opcode) = matches.opt_str("opcode") {
+            match Opcode::from_name(&opcode) {
+                Some(op)  => tweaks.opcode = Some(op),
+                None      => return Err(OptionsError::InvalidOpcode(opcode)),
+            }
+        }
+
         for tweak_str in matches.opt_strs("Z") {
             match &*tweak_str {
                 "aa" | "authoritative" => {
@@ -420,6 +735,15 @@ impl ProtocolTweaks {
                 "cd" | "checking-disabled" => {
                     tweaks.set_checking_disabled_flag = true;
                 }
+                "do" | "dnssec-ok" => {
+                    tweaks.set_dnssec_ok_flag = true;
+                }
+                "nsid" => {
+                    tweaks.request_nsid = true;
+                }
+                "cookie" => {
+                    tweaks.client_cookie = Some(rand::random());
+                }
                 otherwise => {
                     if let Some(remaining_num) = tweak_str.strip_prefix("bufsize=") {
                         match remaining_num.parse() {
@@ -432,6 +756,18 @@ impl ProtocolTweaks {
                             }
                         }
                     }
+                    else if let Some(subnet) = tweak_str.strip_prefix("subnet=") {
+                        if let Some(client_subnet) = parse_client_subnet(subnet) {
+                            tweaks.client_subnet = Some(client_subnet);
+                            continue;
+                        }
+                    }
+                    else if let Some(cookie) = tweak_str.strip_prefix("cookie=") {
+                        if let Some(client_cookie) = parse_client_cookie(cookie) {
+                            tweaks.client_cookie = Some(client_cookie);
+                            continue;
+                        }
+                    }
 
                     return Err(OptionsError::InvalidTweak(otherwise.into()));
                 }
@@ -445,6 +781,7 @@ impl ProtocolTweaks {
 
 /// The result of the `Options::getopts` function.
 #[derive(PartialEq, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum OptionsResult {
 
     /// The options were parsed successfully.
@@ -484,22 +821,56 @@ pub enum OptionsError {
     InvalidQueryType(String),
     InvalidQueryClass(String),
     InvalidTxid(String),
+    InvalidOpcode(String),
+    InvalidSerial(String),
     InvalidTweak(String),
     QueryTypeOPT,
+    IxfrWithoutSerial,
     MissingHttpsUrl,
+    ValidateWithoutEDNS,
+    NSIDWithoutEDNS,
+    CookieWithoutEDNS,
+    SubnetWithoutEDNS,
+    InvalidPrerequisite(String),
+    InvalidRecord(String, String),
+    UpdateWithoutZone,
+    UpdateWithQueries,
+    UpdateTransport,
+    TransferTransport,
+    LenientTransfer,
+    InvalidTsigKey(KeyError),
+    InvalidTsigKeyFile(String, String),
+    TwoTsigKeys,
 }
 
 impl fmt::Display for OptionsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::InvalidDomain(domain)  => write!(f, "Invalid domain {:?}", domain),
-            Self::InvalidEDNS(edns)      => write!(f, "Invalid EDNS setting {:?}", edns),
-            Self::InvalidQueryType(qt)   => write!(f, "Invalid query type {:?}", qt),
-            Self::InvalidQueryClass(qc)  => write!(f, "Invalid query class {:?}", qc),
-            Self::InvalidTxid(txid)      => write!(f, "Invalid transaction ID {:?}", txid),
-            Self::InvalidTweak(tweak)    => write!(f, "Invalid protocol tweak {:?}", tweak),
-            Self::QueryTypeOPT           => write!(f, "OPT request is sent by default (see -Z flag)"),
-            Self::MissingHttpsUrl        => write!(f, "You must pass a URL as a nameserver when using --https"),
+            Self::InvalidDomain(domain)    => write!(f, "Invalid domain {:?}", domain),
+            Self::InvalidEDNS(edns)        => write!(f, "Invalid EDNS setting {:?}", edns),
+            Self::InvalidQueryType(qt)     => write!(f, "Invalid query type {:?}", qt),
+            Self::InvalidQueryClass(qc)    => write!(f, "Invalid query class {:?}", qc),
+            Self::InvalidTxid(txid)        => write!(f, "Invalid transaction ID {:?}", txid),
+            Self::InvalidOpcode(opcode)    => write!(f, "Invalid opcode {:?}", opcode),
+            Self::InvalidSerial(serial)    => write!(f, "Invalid serial number {:?}", serial),
+            Self::InvalidTweak(tweak)      => write!(f, "Invalid protocol tweak {:?}", tweak),
+            Self::QueryTypeOPT             => write!(f, "OPT request is sent by default (see -Z flag)"),
+            Self::IxfrWithoutSerial        => write!(f, "You must pass the serial number of the zone with --serial for IXFR queries"),
+            Self::MissingHttpsUrl          => write!(f, "You must pass a URL as a nameserver when using --https"),
+            Self::ValidateWithoutEDNS      => write!(f, "Cannot validate DNSSEC with EDNS disabled"),
+            Self::NSIDWithoutEDNS          => write!(f, "Cannot request an NSID with EDNS disabled"),
+            Self::CookieWithoutEDNS        => write!(f, "Cannot send a cookie with EDNS disabled"),
+            Self::SubnetWithoutEDNS        => write!(f, "Cannot send a client subnet with EDNS disabled"),
+            Self::InvalidPrerequisite(p)   => write!(f, "Invalid prerequisite {:?}", p),
+            Self::InvalidRecord(rec, e)    => write!(f, "Invalid record {:?}: {}", rec, e),
+            Self::UpdateWithoutZone        => write!(f, "You must pass a zone with --update to add or delete records"),
+            Self::UpdateWithQueries        => write!(f, "Cannot query domains while sending an update"),
+            Self::UpdateTransport          => write!(f, "Updates can only be sent over TCP"),
+            Self::TransferTransport        => write!(f, "Zone transfers can only be sent over TCP"),
+            Self::LenientTransfer          => write!(f, "Cannot read a zone transfer leniently"),
+            Self::InvalidTsigKey(e)        => write!(f, "Invalid TSIG key: {}", e),
+            Self::InvalidTsigKeyFile(p, e) => write!(f, "Invalid TSIG key file {:?}: {}", p, e),
+            Self::TwoTsigKeys              => write!(f, "Cannot use both --tsig and --tsig-file"),
         }
     }
 }
@@ -519,6 +890,7 @@ mod test {
                 classes:         vec![ QClass::IN ],
                 resolver_types:  vec![ ResolverType::SystemDefault ],
                 transport_types: vec![ TransportType::Automatic ],
+                serial:          None,
             }
         }
     }
@@ -768,6 +1140,28 @@ mod test {
         });
     }
 
+    #[test]
+    fn generic_names() {
+        let options = Options::getopts(&[ "lookup.dog", "--class", "CLASS99", "--type", "TYPE1234" ]).unwrap();
+        assert_eq!(options.requests.inputs, Inputs {
+            domains:      vec![ Labels::encode("lookup.dog").unwrap() ],
+            classes:      vec![ QClass::Other(99) ],
+            record_types: vec![ RecordType::from(1234) ],
+            .. Inputs::fallbacks()
+        });
+    }
+
+    #[test]
+    fn generic_names_free() {
+        let options = Options::getopts(&[ "lookup.dog", "type65", "CLASS3" ]).unwrap();
+        assert_eq!(options.requests.inputs, Inputs {
+            domains:      vec![ Labels::encode("lookup.dog").unwrap() ],
+            classes:      vec![ QClass::CH ],
+            record_types: vec![ RecordType::HTTPS ],
+            .. Inputs::fallbacks()
+        });
+    }
+
     #[test]
     fn edns_and_tweaks() {
         let options = Options::getopts(&[ "dom.ain", "--edns", "show", "-Z", "authentic" ]).unwrap();
@@ -782,30 +1176,166 @@ mod test {
         assert_eq!(options.requests.protocol_tweaks.set_checking_disabled_flag, true);
     }
 
+    #[test]
+    fn dnssec_ok_tweak() {
+        let options = Options::getopts(&[ "dom.ain", "-Z", "do" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.set_dnssec_ok_flag, true);
+        assert_eq!(options.validate, false);
+    }
+
+    #[test]
+    fn validate() {
+        let options = Options::getopts(&[ "dom.ain", "--validate" ]).unwrap();
+        assert_eq!(options.validate, true);
+        assert_eq!(options.requests.protocol_tweaks, ProtocolTweaks {
+            set_dnssec_ok_flag: true,
+            set_checking_disabled_flag: true,
+            udp_payload_size: Some(1232),
+            .. ProtocolTweaks::default()
+        });
+    }
+
+    #[test]
+    fn validate_keeps_udp_size() {
+        let options = Options::getopts(&[ "dom.ain", "--validate", "-Z", "bufsize=4096" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.udp_payload_size, Some(4096));
+    }
+
+    #[test]
+    fn strict_by_default() {
+        let options = Options::getopts(&[ "dom.ain" ]).unwrap();
+        assert_eq!(options.lenient, false);
+    }
+
+    #[test]
+    fn lenient() {
+        let options = Options::getopts(&[ "dom.ain", "--lenient" ]).unwrap();
+        assert_eq!(options.lenient, true);
+    }
+
+    #[test]
+    fn opcode() {
+        let options = Options::getopts(&[ "dom.ain", "--opcode", "notify" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.opcode, Some(Opcode::Notify));
+    }
+
+    #[test]
+    fn notify_flags() {
+        let options = Options::getopts(&[ "dom.ain", "--opcode", "notify" ]).unwrap();
+        let mut flags = dns::Flags::query();
+        options.requests.protocol_tweaks.set_request_flags(&mut flags);
+
+        assert_eq!(flags.authoritative, true);
+        assert_eq!(flags.recursion_desired, false);
+    }
+
+    #[test]
+    fn opcode_number() {
+        let options = Options::getopts(&[ "dom.ain", "--opcode=5" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.opcode, Some(Opcode::Update));
+    }
+
+    #[test]
+    fn ixfr_serial() {
+        let options = Options::getopts(&[ "dom.ain", "IXFR", "--serial", "42" ]).unwrap();
+        assert_eq!(options.requests.inputs.record_types, vec![ RecordType::IXFR ]);
+        assert_eq!(options.requests.inputs.serial, Some(42));
+    }
+
     #[test]
     fn udp_size() {
         let options = Options::getopts(&[ "dom.ain", "-Z", "bufsize=4096" ]).unwrap();
         assert_eq!(options.requests.protocol_tweaks.udp_payload_size, Some(4096));
     }
 
+    #[test]
+    fn nsid_tweak() {
+        let options = Options::getopts(&[ "dom.ain", "-Z", "nsid" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.request_nsid, true);
+    }
+
+    #[test]
+    fn random_cookie() {
+        let options = Options::getopts(&[ "dom.ain", "-Z", "cookie" ]).unwrap();
+        assert!(options.requests.protocol_tweaks.client_cookie.is_some());
+    }
+
+    #[test]
+    fn specific_cookie() {
+        let options = Options::getopts(&[ "dom.ain", "-Z", "cookie=0123456789ABCDEF" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.client_cookie,
+                   Some([ 0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF ]));
+    }
+
+    #[test]
+    fn client_subnet_v4() {
+        let options = Options::getopts(&[ "dom.ain", "-Z", "subnet=203.0.113.77/24" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.client_subnet, Some(ClientSubnet {
+            source_prefix_length: 24,
+            scope_prefix_length: 0,
+            address: IpAddr::V4(Ipv4Addr::new(203, 0, 113, 0)),
+        }));
+    }
+
+    #[test]
+    fn client_subnet_v6_default_prefix() {
+        let options = Options::getopts(&[ "dom.ain", "-Z", "subnet=2001:db8:1:2ff::1" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.client_subnet, Some(ClientSubnet {
+            source_prefix_length: 56,
+            scope_prefix_length: 0,
+            address: IpAddr::V6("2001:db8:1:200::".parse().unwrap()),
+        }));
+    }
+
+    #[test]
+    fn client_subnet_zero_prefix() {
+        let options = Options::getopts(&[ "dom.ain", "-Z", "subnet=0.0.0.0/0" ]).unwrap();
+        assert_eq!(options.requests.protocol_tweaks.client_subnet, Some(ClientSubnet {
+            source_prefix_length: 0,
+            scope_prefix_length: 0,
+            address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        }));
+    }
+
     #[test]
     fn short_mode() {
-        let tf = TextFormat { format_durations: true };
+        let tf = TextFormat { format_durations: true, unicode_names: false };
         let options = Options::getopts(&[ "dom.ain", "--short" ]).unwrap();
         assert_eq!(options.format, OutputFormat::Short(tf));
     }
 
     #[test]
     fn short_mode_seconds() {
-        let tf = TextFormat { format_durations: false };
+        let tf = TextFormat { format_durations: false, unicode_names: false };
         let options = Options::getopts(&[ "dom.ain", "--short", "--seconds" ]).unwrap();
         assert_eq!(options.format, OutputFormat::Short(tf));
     }
 
+    #[test]
+    fn short_mode_unicode() {
+        let tf = TextFormat { format_durations: true, unicode_names: true };
+        let options = Options::getopts(&[ "dom.ain", "--short", "--unicode" ]).unwrap();
+        assert_eq!(options.format, OutputFormat::Short(tf));
+    }
+
     #[test]
     fn json_output() {
+        let tf = TextFormat { format_durations: true, unicode_names: false };
         let options = Options::getopts(&[ "dom.ain", "--json" ]).unwrap();
-        assert_eq!(options.format, OutputFormat::JSON);
+        assert_eq!(options.format, OutputFormat::JSON(tf));
+    }
+
+    #[test]
+    fn json_output_unicode() {
+        let tf = TextFormat { format_durations: true, unicode_names: true };
+        let options = Options::getopts(&[ "dom.ain", "--json", "--unicode" ]).unwrap();
+        assert_eq!(options.format, OutputFormat::JSON(tf));
+    }
+
+    #[test]
+    fn zone_output() {
+        let options = Options::getopts(&[ "dom.ain", "--zone" ]).unwrap();
+        assert_eq!(options.format, OutputFormat::Zone);
     }
 
     #[test]
@@ -856,6 +1386,24 @@ mod test {
                    OptionsResult::InvalidOptions(OptionsError::InvalidTxid("0x10000".into())));
     }
 
+    #[test]
+    fn invalid_opcode() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "--opcode", "16" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidOpcode("16".into())));
+    }
+
+    #[test]
+    fn ixfr_without_serial() {
+        assert_eq!(Options::getopts(&[ "dom.ain", "IXFR" ]),
+                   OptionsResult::InvalidOptions(OptionsError::IxfrWithoutSerial));
+    }
+
+    #[test]
+    fn invalid_serial() {
+        assert_eq!(Options::getopts(&[ "dom.ain", "IXFR", "--serial", "-1" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidSerial("-1".into())));
+    }
+
     #[test]
     fn invalid_edns() {
         assert_eq!(Options::getopts(&[ "--edns=yep" ]),
@@ -868,6 +1416,30 @@ mod test {
                    OptionsResult::InvalidOptions(OptionsError::InvalidTweak("sleep".into())));
     }
 
+    #[test]
+    fn short_cookie() {
+        assert_eq!(Options::getopts(&[ "-Z", "cookie=0123" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidTweak("cookie=0123".into())));
+    }
+
+    #[test]
+    fn invalid_cookie() {
+        assert_eq!(Options::getopts(&[ "-Z", "cookie=0123456789abcdeg" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidTweak("cookie=0123456789abcdeg".into())));
+    }
+
+    #[test]
+    fn invalid_subnet_prefix() {
+        assert_eq!(Options::getopts(&[ "-Z", "subnet=192.0.2.0/33" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidTweak("subnet=192.0.2.0/33".into())));
+    }
+
+    #[test]
+    fn invalid_subnet_address() {
+        assert_eq!(Options::getopts(&[ "-Z", "subnet=lookup.dog" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidTweak("subnet=lookup.dog".into())));
+    }
+
     #[test]
     fn invalid_udp_size() {
         assert_eq!(Options::getopts(&[ "-Z", "bufsize=null" ]),
@@ -886,6 +1458,36 @@ mod test {
                    OptionsResult::InvalidOptions(OptionsError::InvalidTweak("bufsize=".into())));
     }
 
+    #[test]
+    fn validate_without_edns() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "--validate", "--edns", "disable" ]),
+                   OptionsResult::InvalidOptions(OptionsError::ValidateWithoutEDNS));
+    }
+
+    #[test]
+    fn subnet_without_edns() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "-Z", "subnet=192.0.2.0/24", "--edns", "disable" ]),
+                   OptionsResult::InvalidOptions(OptionsError::SubnetWithoutEDNS));
+    }
+
+    #[test]
+    fn cookie_without_edns() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "-Z", "cookie", "--edns", "disable" ]),
+                   OptionsResult::InvalidOptions(OptionsError::CookieWithoutEDNS));
+    }
+
+    #[test]
+    fn nsid_without_edns() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "-Z", "nsid", "--edns", "disable" ]),
+                   OptionsResult::InvalidOptions(OptionsError::NSIDWithoutEDNS));
+    }
+
+    #[test]
+    fn several_without_edns() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "-Z", "nsid", "-Z", "subnet=192.0.2.0/24", "--edns", "disable" ]),
+                   OptionsResult::InvalidOptions(OptionsError::NSIDWithoutEDNS));
+    }
+
     #[test]
     fn missing_https_url() {
         assert_eq!(Options::getopts(&[ "--https", "lookup.dog" ]),
@@ -918,6 +1520,170 @@ mod test {
                    OptionsResult::InvalidOptions(OptionsError::QueryTypeOPT));
     }
 
+    // update tests
+
+    fn name(input: &str) -> Labels {
+        Labels::encode(input).unwrap()
+    }
+
+    #[test]
+    fn update() {
+        let options = Options::getopts(&[ "--update", "dom.ain", "@ns1.dom.ain",
+                                          "--prereq", "nxdomain new",
+                                          "--add", "new 300 A 192.0.2.1",
+                                          "--delete", "old NS",
+                                          "--delete", "gone",
+                                          "--delete", "www A 192.0.2.2" ]).unwrap();
+
+        assert_eq!(options.requests.inputs.resolver_types, vec![ ResolverType::Specific("ns1.dom.ain".into()) ]);
+        assert_eq!(options.update, Some(UpdateGenerator {
+            zone: name("dom.ain"),
+            zone_class: QClass::IN,
+            prerequisites: vec![
+                Prerequisite::NameNotInUse { name: name("new.dom.ain") },
+            ],
+            operations: vec![
+                Operation::AddRecord { name: name("new.dom.ain"), ttl: 300, record: Record::A(dns::record::A { address: Ipv4Addr::new(192, 0, 2, 1) }) },
+                Operation::DeleteRRset { name: name("old.dom.ain"), record_type: RecordType::NS },
+                Operation::DeleteName { name: name("gone.dom.ain") },
+                Operation::DeleteRecord { name: name("www.dom.ain"), record: Record::A(dns::record::A { address: Ipv4Addr::new(192, 0, 2, 2) }) },
+            ],
+        }));
+    }
+
+    #[test]
+    fn update_keeps_order() {
+        let options = Options::getopts(&[ "--update", "dom.ain", "--delete", "www A", "--add", "www 60 A 192.0.2.1" ]).unwrap();
+        let operations = options.update.unwrap().operations;
+
+        assert_eq!(operations, vec![
+            Operation::DeleteRRset { name: name("www.dom.ain"), record_type: RecordType::A },
+            Operation::AddRecord { name: name("www.dom.ain"), ttl: 60, record: Record::A(dns::record::A { address: Ipv4Addr::new(192, 0, 2, 1) }) },
+        ]);
+    }
+
+    #[test]
+    fn update_prerequisites() {
+        let options = Options::getopts(&[ "--update", "dom.ain", "--prereq", "YXRRSET www AAAA", "--prereq", "nxrrset mail.dom.ain. MX", "--prereq", "yxdomain @" ]).unwrap();
+
+        assert_eq!(options.update.unwrap().prerequisites, vec![
+            Prerequisite::RRsetExists { name: name("www.dom.ain"), record_type: RecordType::AAAA },
+            Prerequisite::RRsetDoesNotExist { name: name("mail.dom.ain"), record_type: RecordType::MX },
+            Prerequisite::NameInUse { name: name("dom.ain") },
+        ]);
+    }
+
+    #[test]
+    fn update_class() {
+        let options = Options::getopts(&[ "--update", "dom.ain", "--class", "CH" ]).unwrap();
+        assert_eq!(options.update.unwrap().zone_class, QClass::CH);
+    }
+
+    #[test]
+    fn changes_without_update() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "--add", "www 60 A 192.0.2.1" ]),
+                   OptionsResult::InvalidOptions(OptionsError::UpdateWithoutZone));
+    }
+
+    #[test]
+    fn update_with_queries() {
+        assert_eq!(Options::getopts(&[ "--update", "dom.ain", "lookup.dog" ]),
+                   OptionsResult::InvalidOptions(OptionsError::UpdateWithQueries));
+    }
+
+    #[test]
+    fn update_over_udp() {
+        assert_eq!(Options::getopts(&[ "--update", "dom.ain", "--udp" ]),
+                   OptionsResult::InvalidOptions(OptionsError::UpdateTransport));
+    }
+
+    #[test]
+    fn transfer_over_udp() {
+        assert_eq!(Options::getopts(&[ "dom.ain", "AXFR", "--udp" ]),
+                   OptionsResult::InvalidOptions(OptionsError::TransferTransport));
+    }
+
+    #[test]
+    fn transfer_over_tls() {
+        assert_eq!(Options::getopts(&[ "dom.ain", "IXFR", "--serial", "1", "--tls" ]),
+                   OptionsResult::InvalidOptions(OptionsError::TransferTransport));
+    }
+
+    #[test]
+    fn transfer_over_https() {
+        assert_eq!(Options::getopts(&[ "dom.ain", "AXFR", "--https", "@https://dns.lookup.dog/dns-query" ]),
+                   OptionsResult::InvalidOptions(OptionsError::TransferTransport));
+    }
+
+    #[test]
+    fn lenient_transfer() {
+        assert_eq!(Options::getopts(&[ "dom.ain", "AXFR", "--lenient" ]),
+                   OptionsResult::InvalidOptions(OptionsError::LenientTransfer));
+    }
+
+    #[test]
+    fn update_over_tls() {
+        assert_eq!(Options::getopts(&[ "--update", "dom.ain", "--tls" ]),
+                   OptionsResult::InvalidOptions(OptionsError::UpdateTransport));
+    }
+
+    #[test]
+    fn update_over_https() {
+        assert_eq!(Options::getopts(&[ "--update", "dom.ain", "--https", "@https://dns.lookup.dog/dns-query" ]),
+                   OptionsResult::InvalidOptions(OptionsError::UpdateTransport));
+    }
+
+    #[test]
+    fn invalid_prerequisite() {
+        assert_eq!(Options::getopts(&[ "--update", "dom.ain", "--prereq", "yxrrset www" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidPrerequisite("yxrrset www".into())));
+    }
+
+    #[test]
+    fn invalid_update_record() {
+        assert_eq!(Options::getopts(&[ "--update", "dom.ain", "--add", "www A 192.0.2.1" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidRecord("www A 192.0.2.1".into(), "Record has no TTL".into())));
+    }
+
+    // tsig tests
+
+    #[test]
+    fn tsig() {
+        let options = Options::getopts(&[ "lookup.dog", "--tsig", "hmac-sha384:key.dom.ain:c2VjcmV0" ]).unwrap();
+        assert_eq!(options.tsig_key, Some(Key {
+            name: name("key.dom.ain"),
+            algorithm: crate::tsig::Algorithm::HmacSha384,
+            secret: b"secret".to_vec(),
+        }));
+    }
+
+    #[test]
+    fn tsig_file() {
+        let path = std::env::temp_dir().join(format!("dog-tsig-test-{}.key", std::process::id()));
+        fs::write(&path, "key \"key.dom.ain\" {\n\talgorithm hmac-sha256;\n\tsecret \"c2VjcmV0\";\n};\n").unwrap();
+
+        let options = Options::getopts(&[ "--update", "dom.ain", "--tsig-file", path.to_str().unwrap() ]);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(options.unwrap().tsig_key, Some(Key {
+            name: name("key.dom.ain"),
+            algorithm: crate::tsig::Algorithm::HmacSha256,
+            secret: b"secret".to_vec(),
+        }));
+    }
+
+    #[test]
+    fn invalid_tsig() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "--tsig", "hmac-md5:key:c2VjcmV0" ]),
+                   OptionsResult::InvalidOptions(OptionsError::InvalidTsigKey(KeyError::InvalidAlgorithm("hmac-md5".into()))));
+    }
+
+    #[test]
+    fn two_tsig_keys() {
+        assert_eq!(Options::getopts(&[ "lookup.dog", "--tsig", "key:c2VjcmV0", "--tsig-file", "key.conf" ]),
+                   OptionsResult::InvalidOptions(OptionsError::TwoTsigKeys));
+    }
+
     // txid tests
 
     #[test]
@@ -1,16 +1,20 @@
-//! Text and JSON output.
+//! Text, JSON, and zone file output.
 
 use std::fmt;
 use std::time::Duration;
 use std::env;
 
-use dns::{Response, Query, Answer, QClass, ErrorCode, WireError, MandatedLength};
-use dns::record::{Record, RecordType, UnknownQtype, OPT};
+use dns::{Response, Query, Answer, Difference, MalformedRecord, Labels, QClass, ErrorCode, WireError, MandatedLength};
+use dns::record::{Record, RecordType, UnknownQtype, Algorithm, OPT, SvcParam, EDNSOption, ExtendedError, ClientSubnet};
 use dns_transport::Error as TransportError;
 use json::{object, JsonValue};
 
 use crate::colours::Colours;
+use crate::dnssec::{Validation, Status};
+use crate::idn::{self, Homograph};
+use crate::tsig::Verification;
 use crate::table::{Table, Section};
+use crate::zone::zone_line;
 
 
 /// How to format the output data.
@@ -23,8 +27,46 @@ pub enum OutputFormat {
     /// Format the output as one line of plain text.
     Short(TextFormat),
 
-    /// Format the entries as JSON.
-    JSON,
+    /// Format the entries as JSON. Only the way domain names are displayed
+    /// is taken from the text format.
+    JSON(TextFormat),
+
+    /// Format the answers as lines of a zone file.
+    Zone,
+}
+
+
+/// A response that is ready to be printed, along with the information about
+/// it that does not get printed as part of the response itself.
+#[derive(Debug)]
+pub struct Received {
+
+    /// The response, with its OPT record removed if it should be hidden.
+    pub response: Response,
+
+    /// The changes between each version of the zone, if this was an
+    /// incremental zone transfer.
+    pub differences: Vec<Difference>,
+
+    /// The results of validating the response’s DNSSEC signatures, if this
+    /// was requested.
+    pub validation: Option<Validation>,
+
+    /// The result of verifying the response’s TSIG signature, if the
+    /// request was signed.
+    pub verification: Option<Verification>,
+
+    /// The Extended DNS Errors from the response’s OPT record, which are
+    /// printed even if the record itself is hidden.
+    pub extended_errors: Vec<ExtendedError>,
+
+    /// The Client Subnet option from the response’s OPT record, which holds
+    /// the network that the answer is valid for.
+    pub client_subnet: Option<ClientSubnet>,
+
+    /// The NSID option from the response’s OPT record, which identifies the
+    /// server instance that answered.
+    pub nsid: Option<Vec<u8>>,
 }
 
 
@@ -48,6 +90,10 @@ pub struct TextFormat {
 
     /// Whether to format TTLs as hours, minutes, and seconds.
     pub format_durations: bool,
+
+    /// Whether to display internationalised domain names in Unicode,
+    /// rather than in the ASCII form they are sent in.
+    pub unicode_names: bool,
 }
 
 impl UseColours {
@@ -76,42 +122,84 @@ impl OutputFormat {
 
     /// Prints the entirety of the output, formatted according to the
     /// settings. If the duration has been measured, it should also be
-    /// printed. Returns `false` if there were no results to print, and `true`
-    /// otherwise.
-    pub fn print(self, responses: Vec<Response>, duration: Option<Duration>) -> bool {
+    /// printed, as should the results of DNSSEC validation for any response
+    /// that was validated. Returns `false` if there were no results to print,
+    /// and `true` otherwise.
+    pub fn print(self, responses: Vec<Received>, duration: Option<Duration>) -> bool {
         match self {
             Self::Short(tf) => {
-                let all_answers = responses.into_iter().flat_map(|r| r.answers).collect::<Vec<_>>();
+                for received in &responses {
+                    for (name, homograph) in idn::response_homographs(&received.response) {
+                        eprintln!("{}", homograph_warning(&name, homograph));
+                    }
+                }
 
-                if all_answers.is_empty() {
+                let (all_answers, all_differences) = all_records(responses);
+
+                if all_answers.is_empty() && all_differences.is_empty() {
                     eprintln!("No results");
                     return false;
                 }
 
-                for answer in all_answers {
-                    match answer {
-                        Answer::Standard { record, .. } => {
+                for entry in all_answers {
+                    match entry {
+                        Ok(Answer::Standard { record, .. }) => {
                             println!("{}", tf.record_payload_summary(record))
                         }
-                        Answer::Pseudo { opt, .. } => {
+                        Ok(Answer::Pseudo { opt, .. }) => {
                             println!("{}", tf.pseudo_record_payload_summary(opt))
                         }
+                        Err(malformed) => {
+                            println!("{}", malformed_description(malformed));
+                        }
                     }
 
                 }
+
+                for difference in all_differences {
+                    for (sign, answer) in changed_records(difference) {
+                        if let Answer::Standard { record, .. } = answer {
+                            println!("{} {}", sign, tf.record_payload_summary(record));
+                        }
+                    }
+                }
             }
-            Self::JSON => {
-                let mut rs = Vec::new();
+            Self::Zone => {
+                let (all_answers, all_differences) = all_records(responses);
 
-                for response in responses {
-                    let json = object! {
-                        "queries": json_queries(response.queries),
-                        "answers": json_answers(response.answers),
-                        "authorities": json_answers(response.authorities),
-                        "additionals": json_answers(response.additionals),
-                    };
+                if all_answers.is_empty() && all_differences.is_empty() {
+                    eprintln!("No results");
+                    return false;
+                }
 
-                    rs.push(json);
+                for entry in all_answers {
+                    match entry {
+                        Ok(answer) => {
+                            if let Some(line) = zone_line(answer) {
+                                println!("{}", line);
+                            }
+                        }
+                        Err(malformed) => {
+                            println!("; {}", malformed_description(malformed));
+                        }
+                    }
+                }
+
+                for difference in all_differences {
+                    println!("; changes from serial {} to {}", difference.from_serial, difference.to_serial);
+
+                    for (sign, answer) in changed_records(difference) {
+                        if let Some(line) = zone_line(answer) {
+                            println!("{} {}", sign, line);
+                        }
+                    }
+                }
+            }
+            Self::JSON(tf) => {
+                let mut rs = Vec::new();
+
+                for received in responses {
+                    rs.push(json_response(received, tf));
                 }
 
                 if let Some(duration) = duration {
@@ -136,22 +224,55 @@ impl OutputFormat {
             Self::Text(uc, tf) => {
                 let mut table = Table::new(uc.palette(), tf);
 
-                for response in responses {
+                for Received { response, differences, validation, verification, extended_errors, client_subnet, nsid } in responses {
                     if let Some(rcode) = response.flags.error_code {
                         print_error_code(rcode);
                     }
 
-                    for a in response.answers {
-                        table.add_row(a, Section::Answer);
+                    for ede in &extended_errors {
+                        print_extended_error(ede);
+                    }
+
+                    for (name, homograph) in idn::response_homographs(&response) {
+                        eprintln!("{}", homograph_warning(&name, homograph));
+                    }
+
+                    if let Some(ecs) = client_subnet {
+                        println!("Client Subnet: {}/{} (scope /{})", ecs.address, ecs.source_prefix_length, ecs.scope_prefix_length);
+                    }
+
+                    if let Some(nsid) = nsid {
+                        println!("NSID: {} {}", hex_string(&nsid), Ascii(&nsid));
                     }
 
-                    for a in response.authorities {
-                        table.add_row(a, Section::Authority);
+                    if let Some(validation) = validation {
+                        print_validation(&validation);
                     }
 
-                    for a in response.additionals {
-                        table.add_row(a, Section::Additional);
+                    if let Some(verification) = verification {
+                        print_verification(&verification);
                     }
+
+                    for difference in differences {
+                        println!("Changes from serial {} to {}:", difference.from_serial, difference.to_serial);
+
+                        let mut changes = Table::new(uc.palette(), tf);
+                        for a in difference.removed {
+                            changes.add_row(a, Section::Removed);
+                        }
+
+                        for a in difference.added {
+                            changes.add_row(a, Section::Added);
+                        }
+
+                        changes.print(None);
+                    }
+
+                    let mut malformed = response.malformed;
+                    add_section(&mut table, Vec::new(), &mut malformed, dns::Section::Question, Section::Answer);
+                    add_section(&mut table, response.answers, &mut malformed, dns::Section::Answer, Section::Answer);
+                    add_section(&mut table, response.authorities, &mut malformed, dns::Section::Authority, Section::Authority);
+                    add_section(&mut table, response.additionals, &mut malformed, dns::Section::Additional, Section::Additional);
                 }
 
                 table.print(duration);
@@ -161,15 +282,61 @@ impl OutputFormat {
         true
     }
 
+    /// Prints the outcome of a dynamic update, which is only the status of
+    /// the response and the result of verifying its signature, as the
+    /// server does not send back any records. Returns `true` if the server
+    /// performed the update, and `false` otherwise.
+    pub fn print_update(self, response: &Response, verification: Option<Verification>, duration: Option<Duration>) -> bool {
+        let rcode = response.flags.error_code;
+
+        match self {
+            Self::Short(..) | Self::Text(..) | Self::Zone => {
+                match rcode {
+                    Some(rcode)  => print_error_code(rcode),
+                    None         => println!("Status: Updated"),
+                }
+
+                if let Some(verification) = verification {
+                    print_verification(&verification);
+                }
+
+                if let Some(dur) = duration {
+                    println!("Ran in {}ms", dur.as_millis());
+                }
+            }
+
+            Self::JSON(..) => {
+                let mut object = object! {
+                    "status": json_error_code(rcode),
+                };
+
+                if let Some(verification) = verification {
+                    object["tsig"] = json_verification(&verification);
+                }
+
+                if let Some(duration) = duration {
+                    object["duration"] = object! {
+                        "secs": duration.as_secs(),
+                        "millis": duration.subsec_millis(),
+                    };
+                }
+
+                println!("{}", object);
+            }
+        }
+
+        rcode.is_none()
+    }
+
     /// Print an error that’s ocurred while sending or receiving DNS packets
     /// to standard error.
     pub fn print_error(self, error: TransportError) {
         match self {
-            Self::Short(..) | Self::Text(..) => {
+            Self::Short(..) | Self::Text(..) | Self::Zone => {
                 eprintln!("Error [{}]: {}", erroneous_phase(&error), error_message(error));
             }
 
-            Self::JSON => {
+            Self::JSON(..) => {
                 let object = object! {
                     "error": true,
                     "error_phase": erroneous_phase(&error),
@@ -184,6 +351,17 @@ impl OutputFormat {
 
 impl TextFormat {
 
+    /// Formats a domain name, decoding any internationalised labels into
+    /// Unicode if the user asked for them to be.
+    pub fn name(self, labels: &Labels) -> String {
+        if self.unicode_names {
+            labels.to_unicode()
+        }
+        else {
+            labels.to_string()
+        }
+    }
+
     /// Formats a summary of a record in a received DNS response. Each record
     /// type contains wildly different data, so the format of the summary
     /// depends on what record it’s for.
@@ -204,7 +382,24 @@ impl TextFormat {
                 }
             }
             Record::CNAME(cname) => {
-                format!("{:?}", cname.domain.to_string())
+                format!("{:?}", self.name(&cname.domain))
+            }
+            Record::DNSKEY(dnskey) => {
+                format!("{} {} {} {:?} (key tag {})",
+                    dnskey.flags,
+                    dnskey.protocol,
+                    dnskey.algorithm,
+                    dnskey.base64_public_key(),
+                    dnskey.key_tag(),
+                )
+            }
+            Record::DS(ds) => {
+                format!("{} {} {} {}",
+                    ds.key_tag,
+                    ds.algorithm,
+                    ds.digest_type,
+                    ds.hex_digest(),
+                )
             }
             Record::EUI48(eui48) => {
                 format!("{:?}", eui48.formatted_address())
@@ -215,6 +410,9 @@ impl TextFormat {
             Record::HINFO(hinfo) => {
                 format!("{} {}", Ascii(&hinfo.cpu), Ascii(&hinfo.os))
             }
+            Record::HTTPS(https) => {
+                service_binding_summary(https.priority, &self.name(&https.target), &https.parameters)
+            }
             Record::LOC(loc) => {
                 format!("{} ({}, {}) ({}, {}, {})",
                     loc.size,
@@ -226,7 +424,7 @@ impl TextFormat {
                 )
             }
             Record::MX(mx) => {
-                format!("{} {:?}", mx.preference, mx.exchange.to_string())
+                format!("{} {:?}", mx.preference, self.name(&mx.exchange))
             }
             Record::NAPTR(naptr) => {
                 format!("{} {} {} {} {} {:?}",
@@ -235,17 +433,51 @@ impl TextFormat {
                     Ascii(&naptr.flags),
                     Ascii(&naptr.service),
                     Ascii(&naptr.regex),
-                    naptr.replacement.to_string(),
+                    self.name(&naptr.replacement),
                 )
             }
             Record::NS(ns) => {
-                format!("{:?}", ns.nameserver.to_string())
+                format!("{:?}", self.name(&ns.nameserver))
+            }
+            Record::NSEC(nsec) => {
+                format!("{:?} {}", self.name(&nsec.next_domain), type_list(&nsec.types))
+            }
+            Record::NSEC3(nsec3) => {
+                format!("{} {} {} {} {} {}",
+                    nsec3.hash_algorithm,
+                    nsec3.flags,
+                    nsec3.iterations,
+                    nsec3.hex_salt(),
+                    nsec3.base32_next_hashed_owner(),
+                    type_list(&nsec3.types),
+                )
+            }
+            Record::NSEC3PARAM(nsec3param) => {
+                format!("{} {} {} {}",
+                    nsec3param.hash_algorithm,
+                    nsec3param.flags,
+                    nsec3param.iterations,
+                    nsec3param.hex_salt(),
+                )
             }
             Record::OPENPGPKEY(opgp) => {
                 format!("{:?}", opgp.base64_key())
             }
             Record::PTR(ptr) => {
-                format!("{:?}", ptr.cname.to_string())
+                format!("{:?}", self.name(&ptr.cname))
+            }
+            Record::RRSIG(rrsig) => {
+                format!("{} {} {} {} {} {} {} {:?} {:?}",
+                    rrsig.type_covered,
+                    rrsig.algorithm,
+                    rrsig.labels,
+                    self.format_duration(rrsig.original_ttl),
+                    rrsig.formatted_expiration(),
+                    rrsig.formatted_inception(),
+                    rrsig.key_tag,
+                    self.name(&rrsig.signer_name),
+                    rrsig.base64_signature(),
+                )
             }
             Record::SSHFP(sshfp) => {
                 format!("{} {} {}",
@@ -256,8 +488,8 @@ impl TextFormat {
             }
             Record::SOA(soa) => {
                 format!("{:?} {:?} {} {} {} {} {}",
-                    soa.mname.to_string(),
-                    soa.rname.to_string(),
+                    self.name(&soa.mname),
+                    self.name(&soa.rname),
                     soa.serial,
                     self.format_duration(soa.refresh_interval),
                     self.format_duration(soa.retry_interval),
@@ -266,7 +498,10 @@ impl TextFormat {
                 )
             }
             Record::SRV(srv) => {
-                format!("{} {} {:?}:{}", srv.priority, srv.weight, srv.target.to_string(), srv.port)
+                format!("{} {} {:?}:{}", srv.priority, srv.weight, self.name(&srv.target), srv.port)
+            }
+            Record::SVCB(svcb) => {
+                service_binding_summary(svcb.priority, &self.name(&svcb.target), &svcb.parameters)
             }
             Record::TLSA(tlsa) => {
                 format!("{} {} {} {:?}",
@@ -284,7 +519,7 @@ impl TextFormat {
                 format!("{} {} {}", uri.priority, uri.weight, Ascii(&uri.target))
             }
             Record::Other { bytes, .. } => {
-                format!("{:?}", bytes)
+                generic_string(&bytes)
             }
         }
     }
@@ -292,12 +527,18 @@ impl TextFormat {
     /// Formats a summary of an OPT pseudo-record. Pseudo-records have a different
     /// structure than standard ones.
     pub fn pseudo_record_payload_summary(self, opt: OPT) -> String {
-        format!("{} {} {} {} {:?}",
+        let mut summary = format!("{} {} {} {}",
             opt.udp_payload_size,
             opt.higher_bits,
             opt.edns0_version,
-            opt.flags,
-            opt.data)
+            opt.flags);
+
+        for option in &opt.options {
+            summary.push(' ');
+            summary.push_str(&edns_option_summary(option));
+        }
+
+        summary
     }
 
     /// Formats a duration depending on whether it should be displayed as
@@ -312,6 +553,87 @@ impl TextFormat {
     }
 }
 
+/// Formats a list of record types, such as the ones in an NSEC type bitmap,
+/// separated by spaces.
+fn type_list(types: &[RecordType]) -> String {
+    types.iter().map(RecordType::to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// Formats an EDNS option as its name and a summary of its value.
+fn edns_option_summary(option: &EDNSOption) -> String {
+    match option {
+        EDNSOption::NSID(nsid) => {
+            format!("NSID={} {}", hex_string(nsid), Ascii(nsid))
+        }
+        EDNSOption::ClientSubnet(ecs) => {
+            format!("ECS={}/{}/{}", ecs.address, ecs.source_prefix_length, ecs.scope_prefix_length)
+        }
+        EDNSOption::Expire(Some(expire)) => {
+            format!("EXPIRE={}", expire)
+        }
+        EDNSOption::Expire(None) => {
+            "EXPIRE".into()
+        }
+        EDNSOption::Cookie(cookie) => {
+            format!("COOKIE={}{}", hex_string(&cookie.client), cookie.server.as_ref().map_or_else(String::new, |s| hex_string(s)))
+        }
+        EDNSOption::TcpKeepalive(Some(timeout)) => {
+            format!("KEEPALIVE={}.{}s", timeout / 10, timeout % 10)
+        }
+        EDNSOption::TcpKeepalive(None) => {
+            "KEEPALIVE".into()
+        }
+        EDNSOption::Padding(length) => {
+            format!("PADDING={}", length)
+        }
+        EDNSOption::Chain(trust_point) => {
+            format!("CHAIN={:?}", trust_point.to_string())
+        }
+        EDNSOption::ExtendedError(ede) => {
+            format!("EDE={} {:?}", ede.info_code, ede.extra_text)
+        }
+        EDNSOption::Other { code, data } => {
+            format!("{}={}", code, hex_string(data))
+        }
+    }
+}
+
+/// Formats a series of bytes as lowercase hexadecimal.
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Formats the data of a record in the generic format for unknown record
+/// types: its length in bytes, then the bytes themselves as hexadecimal.
+///
+/// # References
+///
+/// - [RFC 3597 §5](https://tools.ietf.org/html/rfc3597#section-5) — Handling
+///   of Unknown DNS Resource Record (RR) Types (September 2003)
+pub fn generic_string(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        String::from("\\# 0")
+    }
+    else {
+        format!("\\# {} {}", bytes.len(), hex_string(bytes))
+    }
+}
+
+/// Formats the fields of a SVCB or HTTPS record, with each parameter in its
+/// presentation form.
+fn service_binding_summary(priority: u16, target: &str, parameters: &[SvcParam]) -> String {
+    let mut summary = format!("{} {:?}", priority, target);
+
+    for parameter in parameters {
+        summary.push(' ');
+        summary.push_str(&parameter.to_string());
+    }
+
+    summary
+}
+
 /// Formats a duration as days, hours, minutes, and seconds, skipping leading
 /// zero units.
 fn format_duration_hms(seconds: u32) -> String {
@@ -338,11 +660,76 @@ fn format_duration_hms(seconds: u32) -> String {
     }
 }
 
+/// Serialises a received response, along with everything that was learnt
+/// about it, as a JSON object.
+fn json_response(received: Received, tf: TextFormat) -> JsonValue {
+    let Received { response, differences, validation, verification, extended_errors, client_subnet, nsid } = received;
+    let homographs = idn::response_homographs(&response);
+
+    let mut json = object! {
+        "status": json_error_code(response.flags.error_code),
+        "queries": json_queries(&response.queries, tf),
+        "answers": json_answers(response.answers, tf),
+        "authorities": json_answers(response.authorities, tf),
+        "additionals": json_answers(response.additionals, tf),
+    };
+
+    if ! homographs.is_empty() {
+        json["homographs"] = json_homographs(homographs);
+    }
+
+    if ! differences.is_empty() {
+        json["differences"] = json_differences(differences, tf);
+    }
+
+    if ! response.malformed.is_empty() {
+        json["malformed"] = json_malformed(response.malformed);
+    }
+
+    if ! extended_errors.is_empty() {
+        json["extended_errors"] = json_extended_errors(extended_errors);
+    }
+
+    if let Some(ecs) = client_subnet {
+        json["client_subnet"] = object! {
+            "address": ecs.address.to_string(),
+            "source_prefix_length": ecs.source_prefix_length,
+            "scope_prefix_length": ecs.scope_prefix_length,
+        };
+    }
+
+    if let Some(nsid) = nsid {
+        json["nsid"] = object! {
+            "hex": hex_string(&nsid),
+            "text": String::from_utf8_lossy(&nsid).into_owned(),
+        };
+    }
+
+    if let Some(validation) = validation {
+        json["dnssec"] = json_validation(validation);
+    }
+
+    if let Some(verification) = verification {
+        json["tsig"] = json_verification(&verification);
+    }
+
+    json
+}
+
+/// Serialises the error code of a response as its mnemonic, or its number if
+/// it does not have one.
+fn json_error_code(rcode: Option<ErrorCode>) -> JsonValue {
+    match rcode {
+        None         => "NOERROR".into(),
+        Some(rcode)  => rcode.mnemonic().map_or_else(|| rcode.to_bits().into(), JsonValue::from),
+    }
+}
+
 /// Serialises multiple DNS queries as a JSON value.
-fn json_queries(queries: Vec<Query>) -> JsonValue {
+fn json_queries(queries: &[Query], tf: TextFormat) -> JsonValue {
     let queries = queries.iter().map(|q| {
         object! {
-            "name": q.qname.to_string(),
+            "name": tf.name(&q.qname),
             "class": json_class(q.qclass),
             "type": json_record_type_name(q.qtype),
         }
@@ -352,25 +739,25 @@ fn json_queries(queries: Vec<Query>) -> JsonValue {
 }
 
 /// Serialises multiple received DNS answers as a JSON value.
-fn json_answers(answers: Vec<Answer>) -> JsonValue {
+fn json_answers(answers: Vec<Answer>, tf: TextFormat) -> JsonValue {
     let answers = answers.into_iter().map(|a| {
         match a {
             Answer::Standard { qname, qclass, ttl, record } => {
                 object! {
-                    "name": qname.to_string(),
+                    "name": tf.name(&qname),
                     "class": json_class(qclass),
                     "ttl": ttl,
                     "type": json_record_name(&record),
-                    "data": json_record_data(record),
+                    "data": json_record_data(record, tf),
                 }
             }
             Answer::Pseudo { qname, opt } => {
                 object! {
-                    "name": qname.to_string(),
+                    "name": tf.name(&qname),
                     "type": "OPT",
                     "data": {
                         "version": opt.edns0_version,
-                        "data": opt.data,
+                        "options": json_edns_options(opt.options),
                     },
                 }
             }
@@ -381,44 +768,252 @@ fn json_answers(answers: Vec<Answer>) -> JsonValue {
 }
 
 
-fn json_class(class: QClass) -> JsonValue {
-    match class {
-        QClass::IN        => "IN".into(),
-        QClass::CH        => "CH".into(),
-        QClass::HS        => "HS".into(),
-        QClass::Other(n)  => n.into(),
+/// Serialises the differences from an incremental zone transfer as a JSON
+/// value, with the removed and added records of each.
+fn json_differences(differences: Vec<Difference>, tf: TextFormat) -> JsonValue {
+    differences.into_iter().map(|difference| {
+        object! {
+            "from_serial": difference.from_serial,
+            "to_serial": difference.to_serial,
+            "removed": json_answers(difference.removed, tf),
+            "added": json_answers(difference.added, tf),
+        }
+    }).collect::<Vec<_>>().into()
+}
+
+/// Serialises the names that could be mistaken for others as a JSON value,
+/// with both forms of each name.
+fn json_homographs(homographs: Vec<(Labels, Homograph)>) -> JsonValue {
+    homographs.into_iter().map(|(name, homograph)| {
+        object! {
+            "name": name.to_string(),
+            "unicode": name.to_unicode(),
+            "warning": homograph.to_string(),
+        }
+    }).collect::<Vec<_>>().into()
+}
+
+/// Serialises the records that failed to parse as a JSON value, with the
+/// section and position of each.
+fn json_malformed(malformed: Vec<MalformedRecord>) -> JsonValue {
+    malformed.into_iter().map(|record| {
+        object! {
+            "section": json_section(record.section),
+            "index": record.index,
+            "offset": record.offset,
+            "error": wire_error_reason(record.error),
+        }
+    }).collect::<Vec<_>>().into()
+}
+
+fn json_section(section: dns::Section) -> JsonValue {
+    match section {
+        dns::Section::Question    => "question".into(),
+        dns::Section::Answer      => "answer".into(),
+        dns::Section::Authority   => "authority".into(),
+        dns::Section::Additional  => "additional".into(),
     }
 }
 
+/// Serialises a DNS class as its mnemonic, or its number if it has none.
+fn json_class(class: QClass) -> JsonValue {
+    class.mnemonic().map_or_else(|| class.to_u16().into(), JsonValue::from)
+}
 
-/// Serialises a DNS record type name.
+
+/// Serialises a DNS record type name, or its number if it has no name.
 fn json_record_type_name(record: RecordType) -> JsonValue {
     match record {
-        RecordType::A           => "A".into(),
-        RecordType::AAAA        => "AAAA".into(),
-        RecordType::CAA         => "CAA".into(),
-        RecordType::CNAME       => "CNAME".into(),
-        RecordType::EUI48       => "EUI48".into(),
-        RecordType::EUI64       => "EUI64".into(),
-        RecordType::HINFO       => "HINFO".into(),
-        RecordType::LOC         => "LOC".into(),
-        RecordType::MX          => "MX".into(),
-        RecordType::NAPTR       => "NAPTR".into(),
-        RecordType::NS          => "NS".into(),
-        RecordType::OPENPGPKEY  => "OPENPGPKEY".into(),
-        RecordType::PTR         => "PTR".into(),
-        RecordType::SOA         => "SOA".into(),
-        RecordType::SRV         => "SRV".into(),
-        RecordType::SSHFP       => "SSHFP".into(),
-        RecordType::TLSA        => "TLSA".into(),
-        RecordType::TXT         => "TXT".into(),
-        RecordType::URI         => "URI".into(),
-        RecordType::Other(unknown) => {
-            match unknown {
-                UnknownQtype::HeardOf(name, _)  => (*name).into(),
-                UnknownQtype::UnheardOf(num)    => (num).into(),
+        RecordType::Other(UnknownQtype::UnheardOf(num))  => num.into(),
+        _                                                => record.to_string().into(),
+    }
+}
+
+/// Serialises the Extended DNS Errors in a response.
+fn json_extended_errors(extended_errors: Vec<ExtendedError>) -> JsonValue {
+    let extended_errors = extended_errors.into_iter().map(|ede| {
+        object! {
+            "info_code": ede.info_code,
+            "name": ede.info_code_name(),
+            "extra_text": ede.extra_text,
+        }
+    }).collect::<Vec<_>>();
+
+    extended_errors.into()
+}
+
+/// Serialises the results of validating a response’s DNSSEC signatures.
+fn json_validation(validation: Validation) -> JsonValue {
+    let rrsets = validation.rrsets.into_iter().map(|rrset| {
+        let mut json = object! {
+            "name": rrset.qname.to_string(),
+            "type": json_record_type_name(rrset.record_type),
+        };
+
+        json_status(&mut json, &rrset.status);
+        json
+    }).collect::<Vec<_>>();
+
+    let mut json = object! {
+        "rrsets": rrsets,
+    };
+
+    if let Some(denial) = validation.denial {
+        let mut denial_json = object! {};
+        json_status(&mut denial_json, &denial);
+        json["denial"] = denial_json;
+    }
+
+    json
+}
+
+/// Serialises the result of verifying a response’s TSIG signature.
+fn json_verification(verification: &Verification) -> JsonValue {
+    let mut json = object! {
+        "status": verification.name(),
+    };
+
+    if let Some(problem) = verification.problem() {
+        json["problem"] = problem.to_string().into();
+    }
+
+    json
+}
+
+/// Adds the fields describing a DNSSEC validation status to a JSON object.
+fn json_status(json: &mut JsonValue, status: &Status) {
+    json["status"] = status.name().into();
+
+    if let Some(problem) = status.problem() {
+        json["problem"] = problem.to_string().into();
+    }
+}
+
+/// Serialises the parameters of a SVCB or HTTPS record as an object, keyed
+/// by the name of each parameter.
+fn json_service_parameters(parameters: Vec<SvcParam>) -> JsonValue {
+    let mut object = JsonValue::new_object();
+
+    for parameter in parameters {
+        let name = SvcParam::key_name(parameter.key());
+        let value: JsonValue = match parameter {
+            SvcParam::Mandatory(keys) => {
+                keys.into_iter().map(SvcParam::key_name).collect::<Vec<_>>().into()
+            }
+            SvcParam::Alpn(ids) => {
+                ids.iter().map(|id| String::from_utf8_lossy(id).to_string()).collect::<Vec<_>>().into()
+            }
+            SvcParam::NoDefaultAlpn => {
+                true.into()
+            }
+            SvcParam::Port(port) => {
+                port.into()
+            }
+            SvcParam::Ipv4Hint(addresses) => {
+                addresses.iter().map(ToString::to_string).collect::<Vec<_>>().into()
+            }
+            ref ech @ SvcParam::Ech(_) => {
+                ech.base64_ech().into()
+            }
+            SvcParam::Ipv6Hint(addresses) => {
+                addresses.iter().map(ToString::to_string).collect::<Vec<_>>().into()
+            }
+            SvcParam::Other(_, value) => {
+                hex_string(&value).into()
+            }
+        };
+
+        object[name] = value;
+    }
+
+    object
+}
+
+/// Serialises the options in an OPT record as a list of objects, each with
+/// the option’s code and its decoded fields.
+fn json_edns_options(options: Vec<EDNSOption>) -> JsonValue {
+    let options = options.into_iter().map(|option| {
+        let code = option.code();
+        let name = option.name();
+        match option {
+            EDNSOption::NSID(nsid) => {
+                object! {
+                    "code": code,
+                    "name": "NSID",
+                    "nsid": hex_string(&nsid),
+                }
+            }
+            EDNSOption::ClientSubnet(ecs) => {
+                object! {
+                    "code": code,
+                    "name": "ECS",
+                    "address": ecs.address.to_string(),
+                    "source_prefix_length": ecs.source_prefix_length,
+                    "scope_prefix_length": ecs.scope_prefix_length,
+                }
+            }
+            EDNSOption::Expire(expire) => {
+                object! {
+                    "code": code,
+                    "name": "EXPIRE",
+                    "expire": expire,
+                }
+            }
+            EDNSOption::Cookie(cookie) => {
+                object! {
+                    "code": code,
+                    "name": "COOKIE",
+                    "client": hex_string(&cookie.client),
+                    "server": cookie.server.map(|s| hex_string(&s)),
+                }
+            }
+            EDNSOption::TcpKeepalive(timeout) => {
+                object! {
+                    "code": code,
+                    "name": "KEEPALIVE",
+                    "timeout": timeout,
+                }
+            }
+            EDNSOption::Padding(length) => {
+                object! {
+                    "code": code,
+                    "name": "PADDING",
+                    "length": length,
+                }
+            }
+            EDNSOption::Chain(trust_point) => {
+                object! {
+                    "code": code,
+                    "name": "CHAIN",
+                    "trust_point": trust_point.to_string(),
+                }
+            }
+            EDNSOption::ExtendedError(ede) => {
+                object! {
+                    "code": code,
+                    "name": "EDE",
+                    "info_code": ede.info_code,
+                    "extra_text": ede.extra_text,
+                }
+            }
+            EDNSOption::Other { data, .. } => {
+                object! {
+                    "code": code,
+                    "name": name,
+                    "data": hex_string(&data),
+                }
             }
         }
+    }).collect::<Vec<_>>();
+
+    options.into()
+}
+
+/// Serialises a DNSSEC algorithm, using its mnemonic if it has one.
+fn json_algorithm(algorithm: Algorithm) -> JsonValue {
+    match algorithm {
+        Algorithm::Known(name, _)  => name.into(),
+        Algorithm::Unknown(num)    => num.into(),
     }
 }
 
@@ -429,18 +1024,26 @@ fn json_record_name(record: &Record) -> JsonValue {
         Record::AAAA(_)        => "AAAA".into(),
         Record::CAA(_)         => "CAA".into(),
         Record::CNAME(_)       => "CNAME".into(),
+        Record::DNSKEY(_)      => "DNSKEY".into(),
+        Record::DS(_)          => "DS".into(),
         Record::EUI48(_)       => "EUI48".into(),
         Record::EUI64(_)       => "EUI64".into(),
         Record::HINFO(_)       => "HINFO".into(),
+        Record::HTTPS(_)       => "HTTPS".into(),
         Record::LOC(_)         => "LOC".into(),
         Record::MX(_)          => "MX".into(),
         Record::NAPTR(_)       => "NAPTR".into(),
         Record::NS(_)          => "NS".into(),
+        Record::NSEC(_)        => "NSEC".into(),
+        Record::NSEC3(_)       => "NSEC3".into(),
+        Record::NSEC3PARAM(_)  => "NSEC3PARAM".into(),
         Record::OPENPGPKEY(_)  => "OPENPGPKEY".into(),
         Record::PTR(_)         => "PTR".into(),
+        Record::RRSIG(_)       => "RRSIG".into(),
         Record::SOA(_)         => "SOA".into(),
         Record::SRV(_)         => "SRV".into(),
         Record::SSHFP(_)       => "SSHFP".into(),
+        Record::SVCB(_)        => "SVCB".into(),
         Record::TLSA(_)        => "TLSA".into(),
         Record::TXT(_)         => "TXT".into(),
         Record::URI(_)         => "URI".into(),
@@ -458,7 +1061,7 @@ fn json_record_name(record: &Record) -> JsonValue {
 
 /// Even though DNS doesn’t specify a character encoding, strings are still
 /// converted from UTF-8, because JSON specifies UTF-8.
-fn json_record_data(record: Record) -> JsonValue {
+fn json_record_data(record: Record, tf: TextFormat) -> JsonValue {
     match record {
         Record::A(a) => {
             object! {
@@ -479,7 +1082,24 @@ fn json_record_data(record: Record) -> JsonValue {
         }
         Record::CNAME(cname) => {
             object! {
-                "domain": cname.domain.to_string(),
+                "domain": tf.name(&cname.domain),
+            }
+        }
+        Record::DNSKEY(dnskey) => {
+            object! {
+                "flags": dnskey.flags,
+                "protocol": dnskey.protocol,
+                "algorithm": json_algorithm(dnskey.algorithm),
+                "public_key": dnskey.base64_public_key(),
+                "key_tag": dnskey.key_tag(),
+            }
+        }
+        Record::DS(ds) => {
+            object! {
+                "key_tag": ds.key_tag,
+                "algorithm": json_algorithm(ds.algorithm),
+                "digest_type": ds.digest_type,
+                "digest": ds.hex_digest(),
             }
         }
         Record::EUI48(eui48) => {
@@ -498,6 +1118,13 @@ fn json_record_data(record: Record) -> JsonValue {
                 "os": String::from_utf8_lossy(&hinfo.os).to_string(),
             }
         }
+        Record::HTTPS(https) => {
+            object! {
+                "priority": https.priority,
+                "target": tf.name(&https.target),
+                "parameters": json_service_parameters(https.parameters),
+            }
+        }
         Record::LOC(loc) => {
             object! {
                 "size": loc.size.to_string(),
@@ -515,7 +1142,7 @@ fn json_record_data(record: Record) -> JsonValue {
         Record::MX(mx) => {
             object! {
                 "preference": mx.preference,
-                "exchange": mx.exchange.to_string(),
+                "exchange": tf.name(&mx.exchange),
             }
         }
         Record::NAPTR(naptr) => {
@@ -524,12 +1151,36 @@ fn json_record_data(record: Record) -> JsonValue {
                 "flags": String::from_utf8_lossy(&naptr.flags).to_string(),
                 "service": String::from_utf8_lossy(&naptr.service).to_string(),
                 "regex": String::from_utf8_lossy(&naptr.regex).to_string(),
-                "replacement": naptr.replacement.to_string(),
+                "replacement": tf.name(&naptr.replacement),
             }
         }
         Record::NS(ns) => {
             object! {
-                "nameserver": ns.nameserver.to_string(),
+                "nameserver": tf.name(&ns.nameserver),
+            }
+        }
+        Record::NSEC(nsec) => {
+            object! {
+                "next_domain": tf.name(&nsec.next_domain),
+                "types": nsec.types.into_iter().map(json_record_type_name).collect::<Vec<_>>(),
+            }
+        }
+        Record::NSEC3(nsec3) => {
+            object! {
+                "hash_algorithm": nsec3.hash_algorithm,
+                "flags": nsec3.flags,
+                "iterations": nsec3.iterations,
+                "salt": nsec3.hex_salt(),
+                "next_hashed_owner": nsec3.base32_next_hashed_owner(),
+                "types": nsec3.types.into_iter().map(json_record_type_name).collect::<Vec<_>>(),
+            }
+        }
+        Record::NSEC3PARAM(nsec3param) => {
+            object! {
+                "hash_algorithm": nsec3param.hash_algorithm,
+                "flags": nsec3param.flags,
+                "iterations": nsec3param.iterations,
+                "salt": nsec3param.hex_salt(),
             }
         }
         Record::OPENPGPKEY(opgp) => {
@@ -539,7 +1190,20 @@ fn json_record_data(record: Record) -> JsonValue {
         }
         Record::PTR(ptr) => {
             object! {
-                "cname": ptr.cname.to_string(),
+                "cname": tf.name(&ptr.cname),
+            }
+        }
+        Record::RRSIG(rrsig) => {
+            object! {
+                "type_covered": json_record_type_name(rrsig.type_covered),
+                "algorithm": json_algorithm(rrsig.algorithm),
+                "labels": rrsig.labels,
+                "original_ttl": rrsig.original_ttl,
+                "expiration": rrsig.formatted_expiration(),
+                "inception": rrsig.formatted_inception(),
+                "key_tag": rrsig.key_tag,
+                "signer_name": tf.name(&rrsig.signer_name),
+                "signature": rrsig.base64_signature(),
             }
         }
         Record::SSHFP(sshfp) => {
@@ -551,7 +1215,7 @@ fn json_record_data(record: Record) -> JsonValue {
         }
         Record::SOA(soa) => {
             object! {
-                "mname": soa.mname.to_string(),
+                "mname": tf.name(&soa.mname),
             }
         }
         Record::SRV(srv) => {
@@ -559,7 +1223,14 @@ fn json_record_data(record: Record) -> JsonValue {
                 "priority": srv.priority,
                 "weight": srv.weight,
                 "port": srv.port,
-                "target": srv.target.to_string(),
+                "target": tf.name(&srv.target),
+            }
+        }
+        Record::SVCB(svcb) => {
+            object! {
+                "priority": svcb.priority,
+                "target": tf.name(&svcb.target),
+                "parameters": json_service_parameters(svcb.parameters),
             }
         }
         Record::TLSA(tlsa) => {
@@ -587,7 +1258,7 @@ fn json_record_data(record: Record) -> JsonValue {
         }
         Record::Other { bytes, .. } => {
             object! {
-                "bytes": bytes,
+                "bytes": hex_string(&bytes),
             }
         }
     }
@@ -630,22 +1301,157 @@ impl fmt::Display for Ascii<'_> {
 /// an error.
 pub fn print_error_code(rcode: ErrorCode) {
     match rcode {
-        ErrorCode::FormatError     => println!("Status: Format Error"),
-        ErrorCode::ServerFailure   => println!("Status: Server Failure"),
-        ErrorCode::NXDomain        => println!("Status: NXDomain"),
-        ErrorCode::NotImplemented  => println!("Status: Not Implemented"),
-        ErrorCode::QueryRefused    => println!("Status: Query Refused"),
-        ErrorCode::BadVersion      => println!("Status: Bad Version"),
-        ErrorCode::Private(num)    => println!("Status: Private Reason ({})", num),
-        ErrorCode::Other(num)      => println!("Status: Other Failure ({})", num),
+        ErrorCode::FormatError       => println!("Status: Format Error"),
+        ErrorCode::ServerFailure     => println!("Status: Server Failure"),
+        ErrorCode::NXDomain          => println!("Status: NXDomain"),
+        ErrorCode::NotImplemented    => println!("Status: Not Implemented"),
+        ErrorCode::QueryRefused      => println!("Status: Query Refused"),
+        ErrorCode::YXDomain          => println!("Status: YXDomain"),
+        ErrorCode::YXRRset           => println!("Status: YXRRset"),
+        ErrorCode::NXRRset           => println!("Status: NXRRset"),
+        ErrorCode::NotAuthoritative  => println!("Status: Not Authoritative"),
+        ErrorCode::NotInZone         => println!("Status: Not In Zone"),
+        ErrorCode::BadVersion        => println!("Status: Bad Version"),
+        ErrorCode::BadKey            => println!("Status: Bad Key"),
+        ErrorCode::BadTime           => println!("Status: Bad Time"),
+        ErrorCode::BadMode           => println!("Status: Bad Mode"),
+        ErrorCode::BadName           => println!("Status: Bad Name"),
+        ErrorCode::BadAlgorithm      => println!("Status: Bad Algorithm"),
+        ErrorCode::BadTruncation     => println!("Status: Bad Truncation"),
+        ErrorCode::BadCookie         => println!("Status: Bad Cookie"),
+        ErrorCode::Private(num)      => println!("Status: Private Reason ({})", num),
+        ErrorCode::Other(num)        => println!("Status: Other Failure ({})", num),
+    }
+}
+
+/// Prints a message describing an Extended DNS Error, which gives the reason
+/// behind a response’s error code, or a problem with an otherwise successful
+/// response.
+fn print_extended_error(ede: &ExtendedError) {
+    let name = ede.info_code_name().unwrap_or("Unknown Error");
+
+    if ede.extra_text.is_empty() {
+        println!("Extended Error: {} ({})", name, ede.info_code);
+    }
+    else {
+        println!("Extended Error: {} ({}): {}", name, ede.info_code, Ascii(ede.extra_text.as_bytes()));
+    }
+}
+
+/// Formats a warning that a name could be mistaken for another, showing the
+/// name in both its Unicode and its ASCII forms.
+fn homograph_warning(name: &Labels, homograph: Homograph) -> String {
+    format!("Warning: {} ({}) {}", name.to_unicode(), name, homograph)
+}
+
+/// Prints the result of validating each set of records in a response, and
+/// of the proof of non-existence if the response had no answers.
+fn print_validation(validation: &Validation) {
+    for rrset in &validation.rrsets {
+        print_status(&format!("{} {}", rrset.qname, rrset.record_type), &rrset.status);
+    }
+
+    if let Some(denial) = &validation.denial {
+        print_status("denial of existence", denial);
+    }
+}
+
+fn print_status(subject: &str, status: &Status) {
+    match status.problem() {
+        Some(problem)  => println!("DNSSEC: {} ({}): {}", status.name(), subject, problem),
+        None           => println!("DNSSEC: {} ({})", status.name(), subject),
+    }
+}
+
+/// Prints the result of verifying the signature on a response.
+fn print_verification(verification: &Verification) {
+    match verification.problem() {
+        Some(problem)  => println!("TSIG: {}: {}", verification.name(), problem),
+        None           => println!("TSIG: {}", verification.name()),
     }
 }
 
+/// Gathers the answers and the differences from every response, for the
+/// output formats that print them all together. Any answers or queries that
+/// failed to parse are kept in their places, so they can be flagged.
+fn all_records(responses: Vec<Received>) -> (Vec<Result<Answer, MalformedRecord>>, Vec<Difference>) {
+    let mut all_answers = Vec::new();
+    let mut all_differences = Vec::new();
+
+    for received in responses {
+        let mut malformed = received.response.malformed;
+        all_answers.extend(interleave(Vec::new(), &mut malformed, dns::Section::Question));
+        all_answers.extend(interleave(received.response.answers, &mut malformed, dns::Section::Answer));
+        all_differences.extend(received.differences);
+    }
+
+    (all_answers, all_differences)
+}
+
+/// Puts the records that failed to parse in one section of a response back
+/// among the ones that parsed, in the places they were in, taking them out
+/// of the list of malformed records.
+fn interleave(answers: Vec<Answer>, malformed: &mut Vec<MalformedRecord>, section: dns::Section) -> Vec<Result<Answer, MalformedRecord>> {
+    let (in_section, others): (Vec<_>, Vec<_>) = std::mem::take(malformed).into_iter()
+        .partition(|record| record.section == section);
+    *malformed = others;
+
+    let mut in_section = in_section.into_iter().peekable();
+    let mut answers = answers.into_iter();
+    let mut entries = Vec::new();
+
+    loop {
+        let entry =
+            if in_section.peek().map(|record| record.index) == Some(entries.len()) {
+                Err(in_section.next().unwrap())
+            }
+            else if let Some(answer) = answers.next() {
+                Ok(answer)
+            }
+            else if let Some(record) = in_section.next() {
+                Err(record)
+            }
+            else {
+                break;
+            };
+
+        entries.push(entry);
+    }
+
+    entries
+}
+
+/// Adds the records in one section of a response to the table, with rows
+/// flagging the ones that failed to parse in the places they were in.
+fn add_section(table: &mut Table, answers: Vec<Answer>, malformed: &mut Vec<MalformedRecord>, from: dns::Section, section: Section) {
+    for entry in interleave(answers, malformed, from) {
+        match entry {
+            Ok(answer)     => table.add_row(answer, section),
+            Err(record)    => table.add_malformed_row(malformed_description(record), section),
+        }
+    }
+}
+
+/// Describes a record that failed to parse, and where it was.
+fn malformed_description(record: MalformedRecord) -> String {
+    let kind = if record.section == dns::Section::Question { "query" } else { "record" };
+    format!("Malformed {} at offset {}: {}", kind, record.offset, wire_error_reason(record.error))
+}
+
+/// Pairs each record in a difference with the sign that shows whether it was
+/// removed or added, with the removed records first.
+fn changed_records(difference: Difference) -> impl Iterator<Item = (char, Answer)> {
+    let removed = difference.removed.into_iter().map(|a| ('-', a));
+    let added = difference.added.into_iter().map(|a| ('+', a));
+    removed.chain(added)
+}
+
 /// Returns the “phase” of operation where an error occurred. This gets shown
 /// to the user so they can debug what went wrong.
 fn erroneous_phase(error: &TransportError) -> &'static str {
     match error {
-        TransportError::WireError(_)          => "protocol",
+        TransportError::WireError(_)          |
+        TransportError::TransferError(_)      => "protocol",
         TransportError::TruncatedResponse     |
         TransportError::NetworkError(_)       => "network",
         #[cfg(feature = "with_nativetls")]
@@ -664,6 +1470,7 @@ fn error_message(error: TransportError) -> String {
     match error {
         TransportError::WireError(e)          => wire_error_message(e),
         TransportError::TruncatedResponse     => "Truncated response".into(),
+        TransportError::TransferError(e)      => e.to_string(),
         TransportError::NetworkError(e)       => e.to_string(),
         #[cfg(feature = "with_nativetls")]
         TransportError::TlsError(e)           => e.to_string(),
@@ -681,27 +1488,41 @@ fn error_message(error: TransportError) -> String {
 /// Formats a wire error into its human-readable message, describing what was
 /// wrong with the packet we received.
 fn wire_error_message(error: WireError) -> String {
+    format!("Malformed packet: {}", wire_error_reason(error))
+}
+
+/// Describes what was wrong with a part of a packet that failed to parse.
+fn wire_error_reason(error: WireError) -> String {
     match error {
         WireError::IO => {
-            "Malformed packet: insufficient data".into()
+            "insufficient data".into()
         }
         WireError::WrongRecordLength { stated_length, mandated_length: MandatedLength::Exactly(len) } => {
-            format!("Malformed packet: record length should be {}, got {}", len, stated_length )
+            format!("record length should be {}, got {}", len, stated_length )
         }
         WireError::WrongRecordLength { stated_length, mandated_length: MandatedLength::AtLeast(len) } => {
-            format!("Malformed packet: record length should be at least {}, got {}", len, stated_length )
+            format!("record length should be at least {}, got {}", len, stated_length )
         }
         WireError::WrongLabelLength { stated_length, length_after_labels } => {
-            format!("Malformed packet: length {} was specified, but read {} bytes", stated_length, length_after_labels)
+            format!("length {} was specified, but read {} bytes", stated_length, length_after_labels)
+        }
+        WireError::WrongBitmapLength { stated_length, length_after_bitmaps } => {
+            format!("length {} was specified, but the type bitmaps end after {} bytes", stated_length, length_after_bitmaps)
         }
         WireError::TooMuchRecursion(indices) => {
-            format!("Malformed packet: too much recursion: {:?}", indices)
+            format!("too much recursion: {:?}", indices)
         }
         WireError::OutOfBounds(index) => {
-            format!("Malformed packet: out of bounds ({})", index)
+            format!("out of bounds ({})", index)
         }
         WireError::WrongVersion { stated_version, maximum_supported_version } => {
-            format!("Malformed packet: record specifies version {}, expected up to {}", stated_version, maximum_supported_version)
+            format!("record specifies version {}, expected up to {}", stated_version, maximum_supported_version)
+        }
+        WireError::InvalidSvcParam(key) => {
+            format!("invalid service parameter {}", SvcParam::key_name(key))
+        }
+        WireError::WrongSectionCount { section, count } => {
+            format!("a request cannot have {} entries in the {:?} section", count, section)
         }
     }
 }
@@ -734,4 +1555,127 @@ mod test {
         assert_eq!(Ascii("pâté".as_bytes()).to_string(),
                    "\"p\\195\\162t\\195\\169\"");
     }
+
+    #[test]
+    fn generic_data() {
+        assert_eq!(generic_string(&[ 0x0a, 0xff, 0x00 ]),
+                   "\\# 3 0aff00");
+    }
+
+    #[test]
+    fn generic_data_empty() {
+        assert_eq!(generic_string(&[]),
+                   "\\# 0");
+    }
+
+    fn unknown_record() -> Record {
+        Record::Other { type_number: UnknownQtype::UnheardOf(65280), bytes: vec![ 0x0a, 0xff, 0x00 ] }
+    }
+
+    #[test]
+    fn unknown_record_text() {
+        let tf = TextFormat { format_durations: false, unicode_names: false };
+        assert_eq!(tf.record_payload_summary(unknown_record()),
+                   "\\# 3 0aff00");
+    }
+
+    #[test]
+    fn unknown_record_json() {
+        let tf = TextFormat { format_durations: false, unicode_names: false };
+        assert_eq!(json_record_data(unknown_record(), tf),
+                   object! { "bytes": "0aff00" });
+    }
+
+    fn malformed(section: dns::Section, index: usize) -> MalformedRecord {
+        MalformedRecord { section, index, offset: 12, error: WireError::IO }
+    }
+
+    fn answer(ttl: u32) -> Answer {
+        Answer::Standard {
+            qname: dns::Labels::root(),
+            qclass: QClass::IN,
+            ttl,
+            record: dns::record::Record::A(dns::record::A { address: std::net::Ipv4Addr::new(192, 0, 2, 1) }),
+        }
+    }
+
+    #[test]
+    fn interleave_in_place() {
+        let mut all = vec![ malformed(dns::Section::Answer, 1), malformed(dns::Section::Additional, 0) ];
+        let entries = interleave(vec![ answer(1), answer(2) ], &mut all, dns::Section::Answer);
+
+        assert_eq!(entries, vec![
+            Ok(answer(1)),
+            Err(malformed(dns::Section::Answer, 1)),
+            Ok(answer(2)),
+        ]);
+        assert_eq!(all, vec![ malformed(dns::Section::Additional, 0) ]);
+    }
+
+    #[test]
+    fn interleave_trailing() {
+        let mut all = vec![ malformed(dns::Section::Answer, 2) ];
+        let entries = interleave(vec![ answer(1) ], &mut all, dns::Section::Answer);
+
+        assert_eq!(entries, vec![
+            Ok(answer(1)),
+            Err(malformed(dns::Section::Answer, 2)),
+        ]);
+        assert!(all.is_empty());
+    }
+
+    #[test]
+    fn json_extended_rcode() {
+        let buf = &[
+            0x12, 0x34,  // transaction ID
+            0x81, 0x80,  // flags (standard query, no error in the header)
+            0x00, 0x00,  // query count
+            0x00, 0x00,  // answer count
+            0x00, 0x00,  // authority RR count
+            0x00, 0x01,  // additional RR count
+
+            // OPT record:
+            0x00,  // name
+            0x00, 0x29,  // type OPT
+            0x10, 0x00,  // UDP payload size
+            0x01,  // higher bits of the rcode
+            0x00,  // EDNS version
+            0x00, 0x00,  // flags
+            0x00, 0x00,  // data length
+        ];
+
+        let received = Received {
+            response: Response::from_bytes(buf).unwrap(),
+            differences: Vec::new(),
+            validation: None,
+            verification: None,
+            extended_errors: Vec::new(),
+            client_subnet: None,
+            nsid: None,
+        };
+
+        let tf = TextFormat { format_durations: false, unicode_names: false };
+        assert_eq!(json_response(received, tf)["status"],
+                   JsonValue::from("BADVERS"));
+    }
+
+    #[test]
+    fn json_class_names() {
+        assert_eq!(json_class(QClass::IN),         JsonValue::from("IN"));
+        assert_eq!(json_class(QClass::Other(254)), JsonValue::from("NONE"));
+        assert_eq!(json_class(QClass::Other(255)), JsonValue::from("ANY"));
+        assert_eq!(json_class(QClass::Other(99)),  JsonValue::from(99));
+    }
+
+    #[test]
+    fn describe_wire_error() {
+        assert_eq!(wire_error_message(WireError::IO),
+                   "Malformed packet: insufficient data");
+    }
+
+    #[test]
+    fn describe_malformed_query() {
+        assert_eq!(malformed_description(malformed(dns::Section::Question, 0)),
+                   "Malformed query at offset 12: insufficient data");
+    }
 }
@@ -1,5 +1,7 @@
 //! Request generation based on the user’s input arguments.
 
+use dns::record::{ClientSubnet, Cookie, EDNSOption, RecordType, SOA};
+
 use crate::connect::TransportType;
 use crate::resolve::{ResolverType, ResolverLookupError};
 use crate::txid::TxidGenerator;
@@ -41,12 +43,20 @@ pub struct Inputs {
 
     /// The list of transport types to send queries over.
     pub transport_types: Vec<TransportType>,
+
+    /// The serial number of the version of the zone that the user already
+    /// has, which gets sent with IXFR queries.
+    pub serial: Option<u32>,
 }
 
 /// Weird protocol options that are allowed by the spec but are not common.
 #[derive(PartialEq, Debug, Default, Copy, Clone)]
 pub struct ProtocolTweaks {
 
+    /// Use this opcode in the header of each request, rather than the
+    /// opcode for a standard query.
+    pub opcode: Option<dns::Opcode>,
+
     /// Set the `AA` (Authoritative Answer) flag in the header of each request.
     pub set_authoritative_flag: bool,
 
@@ -56,8 +66,23 @@ pub struct ProtocolTweaks {
     /// Set the `CD` (Checking Disabled) flag in the header of each request.
     pub set_checking_disabled_flag: bool,
 
+    /// Set the `DO` (DNSSEC OK) flag in the OPT record of each request.
+    pub set_dnssec_ok_flag: bool,
+
     /// Set the buffer size field in the OPT record of each request.
     pub udp_payload_size: Option<u16>,
+
+    /// Attach a Client Subnet option with this network to the OPT record of
+    /// each request.
+    pub client_subnet: Option<ClientSubnet>,
+
+    /// Attach an empty NSID option to the OPT record of each request, asking
+    /// the server to say which instance it is.
+    pub request_nsid: bool,
+
+    /// Attach a Cookie option with this client cookie to the OPT record of
+    /// each request.
+    pub client_cookie: Option<[u8; 8]>,
 }
 
 /// Whether to send or display OPT packets.
@@ -77,16 +102,17 @@ pub enum UseEDNS {
 }
 
 
-/// The entry type for `RequestGenerator`: a transport to send a request, and
-/// a list of one or more DNS queries to send over it, as determined by the
-/// search path in the resolver.
-pub type RequestSet = (Box<dyn dns_transport::Transport>, Vec<dns::Request>);
+/// The entry type for `RequestGenerator`: a transport to send a request, the
+/// nameserver it sends them to, and a list of one or more DNS queries to send
+/// over it, as determined by the search path in the resolver.
+pub type RequestSet = (Box<dyn dns_transport::Transport>, String, Vec<dns::Request>);
 
 impl RequestGenerator {
 
     /// Iterate through the inputs matrix, returning pairs of DNS request list
-    /// and the details of the transport to send them down.
-    pub fn generate(self) -> Result<Vec<RequestSet>, ResolverLookupError> {
+    /// and the details of the transport to send them down. The transports
+    /// parse responses leniently if `lenient` is set.
+    pub fn generate(self, lenient: bool) -> Result<Vec<RequestSet>, ResolverLookupError> {
         let mut requests = Vec::new();
 
         let resolvers = self.inputs.resolver_types.into_iter()
@@ -110,16 +136,22 @@ impl RequestGenerator {
                             }
 
                             let nameserver = resolver.nameserver();
-                            let transport = transport_type.make_transport(nameserver);
+                            let transport = transport_type.make_transport(nameserver.clone(), lenient);
 
                             let mut request_list = Vec::new();
                             for qname in resolver.name_list(domain) {
                                 let transaction_id = self.txid_generator.generate();
+
+                                let mut authority = None;
+                                if qtype == RecordType::IXFR {
+                                    authority = self.inputs.serial.map(known_soa);
+                                }
+
                                 let query = dns::Query { qname, qtype, qclass };
-                                let request = dns::Request { transaction_id, flags, query, additional: additional.clone() };
+                                let request = dns::Request { transaction_id, flags, query, authority, additional: additional.clone(), signature: None };
                                 request_list.push(request);
                             }
-                            requests.push((transport, request_list));
+                            requests.push((transport, nameserver, request_list));
                         }
                     }
                 }
@@ -130,6 +162,20 @@ impl RequestGenerator {
     }
 }
 
+/// Creates the SOA record that gets sent in an IXFR query. Only its serial
+/// number is used by the server, so the other fields are left empty.
+fn known_soa(serial: u32) -> SOA {
+    SOA {
+        mname: dns::Labels::root(),
+        rname: dns::Labels::root(),
+        serial,
+        refresh_interval: 0,
+        retry_interval: 0,
+        expire_limit: 0,
+        minimum_ttl: 0,
+    }
+}
+
 impl UseEDNS {
 
     /// Whether the user wants to send OPT records.
@@ -147,6 +193,17 @@ impl ProtocolTweaks {
 
     /// Sets fields in the DNS flags based on the user’s requested tweaks.
     pub fn set_request_flags(self, flags: &mut dns::Flags) {
+        if let Some(opcode) = self.opcode {
+            flags.opcode = opcode;
+        }
+
+        // RFC 1996 §3.7: a NOTIFY request has the AA bit set, and has no
+        // use for recursion
+        if self.opcode == Some(dns::Opcode::Notify) {
+            flags.authoritative = true;
+            flags.recursion_desired = false;
+        }
+
         if self.set_authoritative_flag {
             flags.authoritative = true;
         }
@@ -160,11 +217,27 @@ impl ProtocolTweaks {
         }
     }
 
-    /// Set the payload size field in the outgoing OPT record, if the user has
-    /// requested to do so.
+    /// Set the payload size field, flags, and options in the outgoing OPT
+    /// record, if the user has requested to do so.
     pub fn set_request_opt_fields(self, opt: &mut dns::record::OPT) {
         if let Some(bufsize) = self.udp_payload_size {
             opt.udp_payload_size = bufsize;
         }
+
+        if self.set_dnssec_ok_flag {
+            opt.flags |= dns::record::OPT::DNSSEC_OK;
+        }
+
+        if let Some(client_subnet) = self.client_subnet {
+            opt.options.push(EDNSOption::ClientSubnet(client_subnet));
+        }
+
+        if self.request_nsid {
+            opt.options.push(EDNSOption::NSID(Vec::new()));
+        }
+
+        if let Some(client) = self.client_cookie {
+            opt.options.push(EDNSOption::Cookie(Cookie { client, server: None }));
+        }
     }
 }
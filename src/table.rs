@@ -42,6 +42,12 @@ pub enum Section {
 
     /// This record was found in the **Additional** section.
     Additional,
+
+    /// This record was removed from a zone, in an incremental transfer.
+    Removed,
+
+    /// This record was added to a zone, in an incremental transfer.
+    Added,
 }
 
 
@@ -58,20 +64,27 @@ impl Table {
         match answer {
             Answer::Standard { record, qname, ttl, .. } => {
                 let qtype = self.coloured_record_type(&record);
-                let qname = qname.to_string();
+                let qname = self.text_format.name(&qname);
                 let summary = self.text_format.record_payload_summary(record);
                 let ttl = Some(self.text_format.format_duration(ttl));
                 self.rows.push(Row { qtype, qname, ttl, summary, section });
             }
             Answer::Pseudo { qname, opt } => {
                 let qtype = self.colours.opt.paint("OPT");
-                let qname = qname.to_string();
+                let qname = self.text_format.name(&qname);
                 let summary = self.text_format.pseudo_record_payload_summary(opt);
                 self.rows.push(Row { qtype, qname, ttl: None, summary, section });
             }
         }
     }
 
+    /// Adds a row to the table flagging a record that failed to parse, in
+    /// the place of that record, with the given description of the problem.
+    pub fn add_malformed_row(&mut self, description: String, section: Section) {
+        let qtype = self.colours.malformed.paint("ERR");
+        self.rows.push(Row { qtype, qname: String::new(), ttl: None, summary: description, section });
+    }
+
     /// Prints the formatted table to stdout.
     pub fn print(self, duration: Option<Duration>) {
         if ! self.rows.is_empty() {
@@ -86,7 +99,7 @@ impl Table {
 
                 print!("{} {} ", r.qtype, self.colours.qname.paint(&r.qname));
 
-                for _ in 0 .. qname_len - r.qname.len() {
+                for _ in 0 .. qname_len - r.qname.chars().count() {
                     print!(" ");
                 }
 
@@ -118,18 +131,26 @@ impl Table {
             Record::AAAA(_)        => self.colours.aaaa.paint("AAAA"),
             Record::CAA(_)         => self.colours.caa.paint("CAA"),
             Record::CNAME(_)       => self.colours.cname.paint("CNAME"),
+            Record::DNSKEY(_)      => self.colours.dnskey.paint("DNSKEY"),
+            Record::DS(_)          => self.colours.ds.paint("DS"),
             Record::EUI48(_)       => self.colours.eui48.paint("EUI48"),
             Record::EUI64(_)       => self.colours.eui64.paint("EUI64"),
             Record::HINFO(_)       => self.colours.hinfo.paint("HINFO"),
+            Record::HTTPS(_)       => self.colours.https.paint("HTTPS"),
             Record::LOC(_)         => self.colours.loc.paint("LOC"),
             Record::MX(_)          => self.colours.mx.paint("MX"),
             Record::NAPTR(_)       => self.colours.ns.paint("NAPTR"),
             Record::NS(_)          => self.colours.ns.paint("NS"),
+            Record::NSEC(_)        => self.colours.nsec.paint("NSEC"),
+            Record::NSEC3(_)       => self.colours.nsec3.paint("NSEC3"),
+            Record::NSEC3PARAM(_)  => self.colours.nsec3param.paint("NSEC3PARAM"),
             Record::OPENPGPKEY(_)  => self.colours.openpgpkey.paint("OPENPGPKEY"),
             Record::PTR(_)         => self.colours.ptr.paint("PTR"),
+            Record::RRSIG(_)       => self.colours.rrsig.paint("RRSIG"),
             Record::SSHFP(_)       => self.colours.sshfp.paint("SSHFP"),
             Record::SOA(_)         => self.colours.soa.paint("SOA"),
             Record::SRV(_)         => self.colours.srv.paint("SRV"),
+            Record::SVCB(_)        => self.colours.svcb.paint("SVCB"),
             Record::TLSA(_)        => self.colours.tlsa.paint("TLSA"),
             Record::TXT(_)         => self.colours.txt.paint("TXT"),
             Record::URI(_)         => self.colours.uri.paint("URI"),
@@ -143,7 +164,7 @@ impl Table {
     }
 
     fn max_qname_len(&self) -> usize {
-        self.rows.iter().map(|r| r.qname.len()).max().unwrap()
+        self.rows.iter().map(|r| r.qname.chars().count()).max().unwrap()
     }
 
     fn max_ttl_len(&self) -> usize {
@@ -155,6 +176,8 @@ impl Table {
             Section::Answer      => self.colours.answer.paint(" "),
             Section::Authority   => self.colours.authority.paint("A"),
             Section::Additional  => self.colours.additional.paint("+"),
+            Section::Removed     => self.colours.removed.paint("-"),
+            Section::Added       => self.colours.added.paint("+"),
         }
     }
 }
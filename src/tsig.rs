@@ -0,0 +1,754 @@
+//! Signing requests and verifying responses with TSIG keys.
+//!
+//! The MACs are computed by the `ring` crate, which is only included when
+//! dog is compiled with the `with_tsig` feature. Without it, dog refuses to
+//! start if it has been given a key.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::*;
+
+use dns::{Labels, Request, Response, Signature, Update};
+use dns::record::TSIG;
+
+
+/// A secret key shared with a nameserver, which is used to sign requests
+/// and verify the responses to them.
+///
+/// # References
+///
+/// - [RFC 8945](https://tools.ietf.org/html/rfc8945) — Secret Key Transaction
+///   Authentication for DNS (TSIG) (November 2020)
+#[derive(PartialEq, Debug, Clone)]
+pub struct Key {
+
+    /// The name of the key, which the server uses to look it up.
+    pub name: Labels,
+
+    /// The algorithm used to compute the MACs.
+    pub algorithm: Algorithm,
+
+    /// The secret bytes of the key.
+    pub secret: Vec<u8>,
+}
+
+/// One of the HMAC algorithms that keys can use.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Algorithm {
+
+    /// HMAC with SHA-256, which is the default.
+    HmacSha256,
+
+    /// HMAC with SHA-384.
+    HmacSha384,
+
+    /// HMAC with SHA-512.
+    HmacSha512,
+}
+
+/// Something wrong with a key given by the user.
+#[derive(PartialEq, Debug)]
+pub enum KeyError {
+
+    /// The algorithm is not one that dog supports.
+    InvalidAlgorithm(String),
+
+    /// The key name is not a valid domain name.
+    InvalidName(String),
+
+    /// The secret is not valid base64.
+    InvalidSecret,
+
+    /// A key file did not have this field.
+    MissingField(&'static str),
+
+    /// The key was not in the format that was expected.
+    InvalidFormat,
+}
+
+
+/// The outcome of verifying the signature on a response.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Verification {
+
+    /// The response was signed with our key, and its MAC matched.
+    Verified,
+
+    /// The response could not be verified, or the server could not verify
+    /// our request.
+    Failed(Problem),
+}
+
+/// The reason that a response could not be verified.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Problem {
+
+    /// The server could not verify the MAC on our request.
+    BadSignature,
+
+    /// The server does not know our key, or does not support its algorithm.
+    BadKey,
+
+    /// The server’s clock is too far from the time our request was signed,
+    /// along with the server’s time, if it sent it.
+    BadTime(Option<u64>),
+
+    /// The server sent back some other TSIG error code.
+    OtherError(u16),
+
+    /// The response did not have a TSIG record.
+    Unsigned,
+
+    /// The response was signed with a different key or algorithm.
+    WrongKey(Labels),
+
+    /// The MAC in the response did not match.
+    InvalidSignature,
+
+    /// The response was signed too long ago, or too far in the future, for
+    /// the fudge value it gave.
+    OutsideTimeWindow,
+}
+
+
+/// The number of seconds that the time a request was signed is allowed to
+/// differ from the server’s clock. This is the value recommended by the RFC.
+const FUDGE: u16 = 300;
+
+
+impl Key {
+
+    /// Parses a key given as `[ALGORITHM:]NAME:SECRET`, the form used by the
+    /// `-y` option of `dig`. The algorithm defaults to HMAC-SHA256.
+    pub fn from_argument(input: &str) -> Result<Self, KeyError> {
+        let parts = input.split(':').collect::<Vec<_>>();
+
+        match parts[..] {
+            [ name, secret ]             => Self::from_parts("hmac-sha256", name, secret),
+            [ algorithm, name, secret ]  => Self::from_parts(algorithm, name, secret),
+            _                            => Err(KeyError::InvalidFormat),
+        }
+    }
+
+    /// Parses a key file in the format used by BIND and written out by
+    /// `tsig-keygen`, which holds a `key` statement with an algorithm and a
+    /// secret inside it. Any other statements in the file are ignored.
+    pub fn from_key_file(contents: &str) -> Result<Self, KeyError> {
+        let mut text = String::new();
+        for line in contents.lines() {
+            let line = line.trim_start();
+            if ! line.starts_with('#') && ! line.starts_with("//") {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+
+        let tokens = text
+            .replace('{', " { ")
+            .replace('}', " } ")
+            .replace(';', " ; ");
+
+        let mut tokens = tokens.split_whitespace().map(|t| t.trim_matches('"'));
+        let mut key = None;
+
+        while let Some(token) = tokens.next() {
+            if token == "key" {
+                let name = tokens.next().ok_or(KeyError::MissingField("name"))?;
+                key = Some(name);
+                break;
+            }
+        }
+
+        let name = key.ok_or(KeyError::MissingField("key"))?;
+        if tokens.next() != Some("{") {
+            return Err(KeyError::InvalidFormat);
+        }
+
+        let mut algorithm = None;
+        let mut secret = None;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "algorithm"  => algorithm = tokens.next(),
+                "secret"     => secret = tokens.next(),
+                "}"          => break,
+                _            => {}
+            }
+        }
+
+        let algorithm = algorithm.ok_or(KeyError::MissingField("algorithm"))?;
+        let secret = secret.ok_or(KeyError::MissingField("secret"))?;
+        Self::from_parts(algorithm, name, secret)
+    }
+
+    fn from_parts(algorithm: &str, name: &str, secret: &str) -> Result<Self, KeyError> {
+        let algorithm = Algorithm::from_name(algorithm)
+            .ok_or_else(|| KeyError::InvalidAlgorithm(algorithm.into()))?;
+
+        let name = Labels::encode(name)
+            .map_err(|_| KeyError::InvalidName(name.into()))?;
+
+        let secret = base64::decode(secret)
+            .map_err(|_| KeyError::InvalidSecret)?;
+
+        Ok(Self { name, algorithm, secret })
+    }
+
+    /// Signs the request with this key, replacing any signature it already
+    /// has. This must be done after every other change to the request.
+    pub fn sign_request(&self, request: &mut Request) {
+        request.signature = None;
+        let message = request.to_bytes().expect("failed to serialise request");
+        request.signature = Some(self.sign(request.transaction_id, message, now()));
+    }
+
+    /// Signs the update with this key, replacing any signature it already
+    /// has.
+    pub fn sign_update(&self, update: &mut Update) {
+        update.signature = None;
+        let message = update.to_bytes().expect("failed to serialise update");
+        update.signature = Some(self.sign(update.transaction_id, message, now()));
+    }
+
+    /// Computes the signature for a message, which has been written out
+    /// without one, as if it were signed at the given time.
+    ///
+    /// # References
+    ///
+    /// - [RFC 8945 §5.1](https://tools.ietf.org/html/rfc8945#section-5.1) —
+    ///   Secret Key Transaction Authentication for DNS (TSIG) (November 2020)
+    fn sign(&self, transaction_id: u16, message: Vec<u8>, time_signed: u64) -> Signature {
+        let mut tsig = TSIG {
+            algorithm: self.algorithm.name(),
+            time_signed,
+            fudge: FUDGE,
+            mac: Vec::new(),
+            original_id: transaction_id,
+            error: 0,
+            other_data: Vec::new(),
+        };
+
+        let mut data = message.clone();
+        data.extend(tsig.variables(&self.name).expect("failed to serialise TSIG variables"));
+        tsig.mac = self.algorithm.mac(&self.secret, &data);
+
+        debug!("Signed message with key {} -> {:?}", self.name, tsig);
+        Signature { key_name: self.name.clone(), tsig, signed_bytes: message }
+    }
+
+    /// Verifies the signature on a response, given the signature that was
+    /// sent with the request it answers. The MAC of the request is included
+    /// in the data that the response’s MAC covers, which ties the two
+    /// messages together.
+    ///
+    /// # References
+    ///
+    /// - [RFC 8945 §5.3](https://tools.ietf.org/html/rfc8945#section-5.3) —
+    ///   Secret Key Transaction Authentication for DNS (TSIG) (November 2020)
+    pub fn verify(&self, sent: &Signature, response: &Response) -> Verification {
+        match &response.signature {
+            Some(received)  => self.verify_signature(sent, received, false),
+            None            => Verification::Failed(Problem::Unsigned),
+        }
+    }
+
+    /// Verifies the signatures on every message in a zone transfer, given
+    /// the signature that was sent with the request. The first message is
+    /// verified like any other response, and each message after it includes
+    /// the MAC of the one before it, so the whole stream is tied together.
+    ///
+    /// Servers are allowed to leave some of the messages in the middle of
+    /// the stream unsigned, but dog does not keep the bytes of unsigned
+    /// messages around, so it needs every message to be signed.
+    ///
+    /// # References
+    ///
+    /// - [RFC 8945 §5.3.1](https://tools.ietf.org/html/rfc8945#section-5.3.1)
+    ///   — Secret Key Transaction Authentication for DNS (TSIG) (November 2020)
+    pub fn verify_transfer(&self, sent: &Signature, messages: &[Response]) -> Verification {
+        let mut prior = sent;
+
+        for (index, message) in messages.iter().enumerate() {
+            let verification = match &message.signature {
+                Some(received)  => self.verify_signature(prior, received, index > 0),
+                None            => Verification::Failed(Problem::Unsigned),
+            };
+
+            match (verification, &message.signature) {
+                (Verification::Verified, Some(received)) => {
+                    prior = received;
+                }
+                (verification, _) => {
+                    debug!("Message {} of transfer failed verification", index);
+                    return verification;
+                }
+            }
+        }
+
+        Verification::Verified
+    }
+
+    /// Verifies the signature that was received with a response, given the
+    /// signature that came before it. Messages after the first in a stream
+    /// only have the timer fields of the TSIG variables in their MACs.
+    fn verify_signature(&self, prior: &Signature, received: &Signature, timers_only: bool) -> Verification {
+        // Servers do not sign the responses with these errors, as they
+        // could not use the key.
+        match received.tsig.error {
+            0             => {}
+            TSIG::BADSIG  => return Verification::Failed(Problem::BadSignature),
+            TSIG::BADKEY  => return Verification::Failed(Problem::BadKey),
+            _             => {}
+        }
+
+        if received.key_name.to_lowercase() != self.name.to_lowercase()
+        || received.tsig.algorithm.to_lowercase() != self.algorithm.name()
+        {
+            return Verification::Failed(Problem::WrongKey(received.key_name.clone()));
+        }
+
+        let mut data = Vec::new();
+        data.extend(&u16::try_from(prior.tsig.mac.len()).expect("MAC too long").to_be_bytes());
+        data.extend(&prior.tsig.mac);
+        data.extend(&received.signed_bytes);
+
+        if timers_only {
+            data.extend(received.tsig.timers().expect("failed to serialise TSIG timers"));
+        }
+        else {
+            data.extend(received.tsig.variables(&received.key_name).expect("failed to serialise TSIG variables"));
+        }
+
+        if ! self.algorithm.mac_matches(&self.secret, &data, &received.tsig.mac) {
+            return Verification::Failed(Problem::InvalidSignature);
+        }
+
+        match received.tsig.error {
+            0              => {}
+            TSIG::BADTIME  => return Verification::Failed(Problem::BadTime(server_time(&received.tsig.other_data))),
+            other          => return Verification::Failed(Problem::OtherError(other)),
+        }
+
+        let now = now();
+        let time_signed = received.tsig.time_signed;
+        let difference = now.checked_sub(time_signed).unwrap_or_else(|| time_signed - now);
+        if difference > u64::from(received.tsig.fudge) {
+            return Verification::Failed(Problem::OutsideTimeWindow);
+        }
+
+        Verification::Verified
+    }
+}
+
+impl Algorithm {
+
+    /// Looks up an algorithm by its name, with or without the trailing dot,
+    /// and ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match &*name.trim_end_matches('.').to_ascii_lowercase() {
+            "hmac-sha256"  => Some(Self::HmacSha256),
+            "hmac-sha384"  => Some(Self::HmacSha384),
+            "hmac-sha512"  => Some(Self::HmacSha512),
+            _              => None,
+        }
+    }
+
+    /// The name of the algorithm, as it gets written in a TSIG record.
+    pub fn name(self) -> Labels {
+        let name = match self {
+            Self::HmacSha256  => "hmac-sha256",
+            Self::HmacSha384  => "hmac-sha384",
+            Self::HmacSha512  => "hmac-sha512",
+        };
+
+        Labels::encode(name).expect("invalid algorithm name")
+    }
+
+    #[cfg(feature = "with_tsig")]
+    fn ring_algorithm(self) -> ring::hmac::Algorithm {
+        match self {
+            Self::HmacSha256  => ring::hmac::HMAC_SHA256,
+            Self::HmacSha384  => ring::hmac::HMAC_SHA384,
+            Self::HmacSha512  => ring::hmac::HMAC_SHA512,
+        }
+    }
+
+    /// Computes the MAC of the data using the secret.
+    #[cfg(feature = "with_tsig")]
+    fn mac(self, secret: &[u8], data: &[u8]) -> Vec<u8> {
+        let key = ring::hmac::Key::new(self.ring_algorithm(), secret);
+        ring::hmac::sign(&key, data).as_ref().to_vec()
+    }
+
+    #[cfg(not(feature = "with_tsig"))]
+    fn mac(self, _secret: &[u8], _data: &[u8]) -> Vec<u8> {
+        unreachable!("TSIG feature disabled")
+    }
+
+    /// Whether the MAC of the data using the secret is the given one. The
+    /// comparison takes the same amount of time wherever they differ.
+    #[cfg(feature = "with_tsig")]
+    fn mac_matches(self, secret: &[u8], data: &[u8], mac: &[u8]) -> bool {
+        let key = ring::hmac::Key::new(self.ring_algorithm(), secret);
+        ring::hmac::verify(&key, data, mac).is_ok()
+    }
+
+    #[cfg(not(feature = "with_tsig"))]
+    fn mac_matches(self, _secret: &[u8], _data: &[u8], _mac: &[u8]) -> bool {
+        unreachable!("TSIG feature disabled")
+    }
+}
+
+impl Verification {
+
+    /// The name of this outcome, as it gets displayed to the user.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Verified   => "verified",
+            Self::Failed(_)  => "failed",
+        }
+    }
+
+    /// The reason verification failed, if it did.
+    pub fn problem(&self) -> Option<&Problem> {
+        match self {
+            Self::Failed(p)  => Some(p),
+            Self::Verified   => None,
+        }
+    }
+}
+
+
+/// The current time, as a number of seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Reads the server’s clock time out of the other data of a TSIG record
+/// with the `BADTIME` error, where it is a 48-bit number.
+fn server_time(other_data: &[u8]) -> Option<u64> {
+    if other_data.len() == 6 {
+        Some(other_data.iter().fold(0, |time, b| (time << 8) | u64::from(*b)))
+    }
+    else {
+        None
+    }
+}
+
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAlgorithm(a)  => write!(f, "Unsupported algorithm {:?}", a),
+            Self::InvalidName(name)    => write!(f, "Invalid key name {:?}", name),
+            Self::InvalidSecret        => write!(f, "Secret is not valid base64"),
+            Self::MissingField(field)  => write!(f, "Key file has no {}", field),
+            Self::InvalidFormat        => write!(f, "Expected [ALGORITHM:]NAME:SECRET"),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadSignature           => write!(f, "Server could not verify the request’s signature (BADSIG)"),
+            Self::BadKey                 => write!(f, "Server does not know the key or its algorithm (BADKEY)"),
+            Self::BadTime(Some(time))    => write!(f, "Server’s clock is too far from ours, at {} (BADTIME)", time),
+            Self::BadTime(None)          => write!(f, "Server’s clock is too far from ours (BADTIME)"),
+            Self::OtherError(code)       => write!(f, "Server returned TSIG error {}", code),
+            Self::Unsigned               => write!(f, "Response is not signed"),
+            Self::WrongKey(name)         => write!(f, "Response is signed with unexpected key {}", name),
+            Self::InvalidSignature       => write!(f, "Signature does not verify"),
+            Self::OutsideTimeWindow      => write!(f, "Signature time is outside the fudge window"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn key() -> Key {
+        Key {
+            name: Labels::encode("key").unwrap(),
+            algorithm: Algorithm::HmacSha256,
+            secret: b"secret".to_vec(),
+        }
+    }
+
+    #[test]
+    fn key_argument() {
+        assert_eq!(Key::from_argument("key:c2VjcmV0"),
+                   Ok(key()));
+    }
+
+    #[test]
+    fn key_argument_with_algorithm() {
+        assert_eq!(Key::from_argument("HMAC-SHA512:key:c2VjcmV0"),
+                   Ok(Key { algorithm: Algorithm::HmacSha512, .. key() }));
+    }
+
+    #[test]
+    fn key_argument_without_secret() {
+        assert_eq!(Key::from_argument("key"),
+                   Err(KeyError::InvalidFormat));
+    }
+
+    #[test]
+    fn key_argument_with_unknown_algorithm() {
+        assert_eq!(Key::from_argument("hmac-md5:key:c2VjcmV0"),
+                   Err(KeyError::InvalidAlgorithm("hmac-md5".into())));
+    }
+
+    #[test]
+    fn key_argument_with_invalid_secret() {
+        assert_eq!(Key::from_argument("key:not base64!"),
+                   Err(KeyError::InvalidSecret));
+    }
+
+    #[test]
+    fn key_file() {
+        let contents = "\
+# generated by tsig-keygen
+key \"key\" {
+\talgorithm hmac-sha384;
+\tsecret \"c2VjcmV0\";
+};
+";
+
+        assert_eq!(Key::from_key_file(contents),
+                   Ok(Key { algorithm: Algorithm::HmacSha384, .. key() }));
+    }
+
+    #[test]
+    fn key_file_without_secret() {
+        assert_eq!(Key::from_key_file("key \"key\" { algorithm hmac-sha256; };"),
+                   Err(KeyError::MissingField("secret")));
+    }
+
+    #[test]
+    fn key_file_without_key() {
+        assert_eq!(Key::from_key_file("options { };"),
+                   Err(KeyError::MissingField("key")));
+    }
+
+    #[cfg(feature = "with_tsig")]
+    mod signing {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn request() -> Request {
+            Request {
+                transaction_id: 0x1234,
+                flags: dns::Flags::query(),
+                query: dns::Query {
+                    qname: Labels::encode("rfcs.io").unwrap(),
+                    qtype: dns::record::RecordType::A,
+                    qclass: dns::QClass::IN,
+                },
+                authority: None,
+                additional: None,
+                signature: None,
+            }
+        }
+
+        /// Signs the response in the way that a server would, by including
+        /// the MAC of the request it is responding to.
+        fn sign_response(key: &Key, sent: &Signature, error: u16, time_signed: u64) -> Response {
+            let response = Response {
+                transaction_id: 0x1234,
+                flags: dns::Flags::standard_response(),
+                queries: Vec::new(),
+                answers: Vec::new(),
+                authorities: Vec::new(),
+                additionals: Vec::new(),
+                signature: None,
+                malformed: Vec::new(),
+            };
+
+            let mut data = Vec::new();
+            data.extend(&u16::try_from(sent.tsig.mac.len()).unwrap().to_be_bytes());
+            data.extend(&sent.tsig.mac);
+
+            let mut signature = key.sign(0x1234, response.to_bytes().unwrap(), time_signed);
+            signature.tsig.error = error;
+            data.extend(&signature.signed_bytes);
+            data.extend(signature.tsig.variables(&key.name).unwrap());
+            signature.tsig.mac = key.algorithm.mac(&key.secret, &data);
+
+            Response { signature: Some(signature), .. response }
+        }
+
+        #[test]
+        fn known_mac() {
+            let message = request().to_bytes().unwrap();
+            let signature = key().sign(0x1234, message, 0x6000_0000);
+
+            assert_eq!(signature.tsig.mac, vec![
+                0x7c, 0xe1, 0x2b, 0x35, 0x65, 0x83, 0xf4, 0x3c, 0xc7, 0xd3, 0xe6, 0x12, 0xb5, 0x47, 0xb5, 0x0c,
+                0x70, 0xad, 0x5e, 0xab, 0x68, 0xcf, 0xbd, 0x6e, 0xfc, 0x9e, 0x0d, 0xf3, 0xff, 0x5c, 0x64, 0x8c,
+            ]);
+        }
+
+        #[test]
+        fn signed_request_round_trip() {
+            let mut request = request();
+            key().sign_request(&mut request);
+
+            let parsed = Request::from_bytes(&request.to_bytes().unwrap()).unwrap();
+            assert_eq!(parsed.signature, request.signature);
+        }
+
+        #[test]
+        fn verified() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let response = sign_response(&key(), &sent, 0, now());
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Verified);
+        }
+
+        #[test]
+        fn tampered() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let mut response = sign_response(&key(), &sent, 0, now());
+            response.signature.as_mut().unwrap().signed_bytes[3] ^= 0x04;
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Failed(Problem::InvalidSignature));
+        }
+
+        #[test]
+        fn signed_with_other_secret() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let other = Key { secret: b"other".to_vec(), .. key() };
+            let response = sign_response(&other, &sent, 0, now());
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Failed(Problem::InvalidSignature));
+        }
+
+        #[test]
+        fn signed_long_ago() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let response = sign_response(&key(), &sent, 0, now() - 3600);
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Failed(Problem::OutsideTimeWindow));
+        }
+
+        #[test]
+        fn bad_time() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let response = sign_response(&key(), &sent, TSIG::BADTIME, now());
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Failed(Problem::BadTime(None)));
+        }
+
+        #[test]
+        fn bad_key() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let mut response = sign_response(&key(), &sent, TSIG::BADKEY, now());
+            response.signature.as_mut().unwrap().tsig.mac.clear();
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Failed(Problem::BadKey));
+        }
+
+        #[test]
+        fn wrong_key() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let other = Key { name: Labels::encode("other").unwrap(), .. key() };
+            let response = sign_response(&other, &sent, 0, now());
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Failed(Problem::WrongKey(other.name)));
+        }
+
+        /// Signs a message after the first in a zone transfer, which only
+        /// includes the timers and the MAC of the message before it.
+        fn sign_subsequent(key: &Key, prior: &Signature) -> Response {
+            let response = sign_response(key, prior, 0, now());
+            let mut signature = response.signature.unwrap();
+
+            let mut data = Vec::new();
+            data.extend(&u16::try_from(prior.tsig.mac.len()).unwrap().to_be_bytes());
+            data.extend(&prior.tsig.mac);
+            data.extend(&signature.signed_bytes);
+            data.extend(signature.tsig.timers().unwrap());
+            signature.tsig.mac = key.algorithm.mac(&key.secret, &data);
+
+            Response { signature: Some(signature), .. response }
+        }
+
+        #[test]
+        fn transfer_verified() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let first = sign_response(&key(), &sent, 0, now());
+            let second = sign_subsequent(&key(), first.signature.as_ref().unwrap());
+            let third = sign_subsequent(&key(), second.signature.as_ref().unwrap());
+            assert_eq!(key().verify_transfer(&sent, &[ first, second, third ]),
+                       Verification::Verified);
+        }
+
+        #[test]
+        fn transfer_out_of_order() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let first = sign_response(&key(), &sent, 0, now());
+            let second = sign_subsequent(&key(), first.signature.as_ref().unwrap());
+            let third = sign_subsequent(&key(), second.signature.as_ref().unwrap());
+            assert_eq!(key().verify_transfer(&sent, &[ first, third, second ]),
+                       Verification::Failed(Problem::InvalidSignature));
+        }
+
+        #[test]
+        fn transfer_with_unsigned_message() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let first = sign_response(&key(), &sent, 0, now());
+            let mut second = sign_subsequent(&key(), first.signature.as_ref().unwrap());
+            second.signature = None;
+            assert_eq!(key().verify_transfer(&sent, &[ first, second ]),
+                       Verification::Failed(Problem::Unsigned));
+        }
+
+        #[test]
+        fn unsigned() {
+            let mut request = request();
+            key().sign_request(&mut request);
+            let sent = request.signature.unwrap();
+
+            let mut response = sign_response(&key(), &sent, 0, now());
+            response.signature = None;
+            assert_eq!(key().verify(&sent, &response),
+                       Verification::Failed(Problem::Unsigned));
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Dynamic update generation based on the user’s input arguments.
+
+use dns::{Labels, QClass, Prerequisite, Operation};
+
+
+/// All the information necessary to generate a dynamic update to one zone.
+#[derive(PartialEq, Debug)]
+pub struct UpdateGenerator {
+
+    /// The name of the zone to update.
+    pub zone: Labels,
+
+    /// The class of the zone to update.
+    pub zone_class: QClass,
+
+    /// The conditions that must hold for the update to go ahead.
+    pub prerequisites: Vec<Prerequisite>,
+
+    /// The records to add and delete, in the order the user gave them.
+    pub operations: Vec<Operation>,
+}
+
+impl UpdateGenerator {
+
+    /// Builds the update message, using the given transaction ID.
+    pub fn generate(self, transaction_id: u16) -> dns::Update {
+        dns::Update {
+            transaction_id,
+            zone: self.zone,
+            zone_class: self.zone_class,
+            prerequisites: self.prerequisites,
+            operations: self.operations,
+            signature: None,
+        }
+    }
+}
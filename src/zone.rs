@@ -0,0 +1,354 @@
+//! Formatting records in the master file format used by zone files.
+
+use dns::{Answer, Labels, Compression};
+use dns::record::{Record, RecordType, SvcParam};
+
+use crate::output::generic_string;
+
+
+/// Formats an answer as one line of a zone file, with its owner name, TTL,
+/// class, type, and data separated by spaces. OPT pseudo-records do not
+/// belong in zone files, so nothing is returned for them, and a record whose
+/// data cannot be written back out is returned as a comment.
+///
+/// # References
+///
+/// - [RFC 1035 §5.1](https://tools.ietf.org/html/rfc1035#section-5.1) —
+///   Domain Names - Implementation and Specification (November 1987)
+pub fn zone_line(answer: Answer) -> Option<String> {
+    match answer {
+        Answer::Standard { qname, qclass, ttl, record } => {
+            let line = format!("{} {} {} {}",
+                domain_name(&qname),
+                ttl,
+                qclass,
+                record.record_type(),
+            );
+
+            match record_data(&record) {
+                Some(data)  => Some(format!("{} {}", line, data)),
+                None        => Some(format!("; {} (unrepresentable data)", line)),
+            }
+        }
+        Answer::Pseudo { .. } => {
+            None
+        }
+    }
+}
+
+/// Formats the data of a record in its presentation format. Records whose
+/// data has no textual form, such as those of unknown types, are written
+/// in the generic format instead.
+fn record_data(record: &Record) -> Option<String> {
+    let data = match record {
+        Record::A(a) => {
+            a.address.to_string()
+        }
+        Record::AAAA(aaaa) => {
+            aaaa.address.to_string()
+        }
+        Record::CAA(caa) => {
+            format!("{} {} {}",
+                if caa.critical { 128 } else { 0 },
+                escaped(&caa.tag),
+                character_string(&caa.value),
+            )
+        }
+        Record::CNAME(cname) => {
+            domain_name(&cname.domain)
+        }
+        Record::DNSKEY(dnskey) => {
+            format!("{} {} {} {}",
+                dnskey.flags,
+                dnskey.protocol,
+                dnskey.algorithm.number(),
+                dnskey.base64_public_key(),
+            )
+        }
+        Record::DS(ds) => {
+            format!("{} {} {} {}",
+                ds.key_tag,
+                ds.algorithm.number(),
+                ds.digest_type,
+                ds.hex_digest(),
+            )
+        }
+        Record::EUI48(eui48) => {
+            eui48.formatted_address()
+        }
+        Record::EUI64(eui64) => {
+            eui64.formatted_address()
+        }
+        Record::HINFO(hinfo) => {
+            format!("{} {}", character_string(&hinfo.cpu), character_string(&hinfo.os))
+        }
+        Record::HTTPS(https) => {
+            service_binding(https.priority, &https.target, &https.parameters)
+        }
+        Record::LOC(loc) => {
+            loc.formatted_location()?
+        }
+        Record::MX(mx) => {
+            format!("{} {}", mx.preference, domain_name(&mx.exchange))
+        }
+        Record::NAPTR(naptr) => {
+            format!("{} {} {} {} {} {}",
+                naptr.order,
+                naptr.preference,
+                character_string(&naptr.flags),
+                character_string(&naptr.service),
+                character_string(&naptr.regex),
+                domain_name(&naptr.replacement),
+            )
+        }
+        Record::NS(ns) => {
+            domain_name(&ns.nameserver)
+        }
+        Record::NSEC(nsec) => {
+            format!("{}{}", domain_name(&nsec.next_domain), type_bitmap(&nsec.types))
+        }
+        Record::NSEC3(nsec3) => {
+            format!("{} {} {} {} {}{}",
+                nsec3.hash_algorithm,
+                nsec3.flags,
+                nsec3.iterations,
+                nsec3.hex_salt(),
+                nsec3.base32_next_hashed_owner(),
+                type_bitmap(&nsec3.types),
+            )
+        }
+        Record::NSEC3PARAM(nsec3param) => {
+            format!("{} {} {} {}",
+                nsec3param.hash_algorithm,
+                nsec3param.flags,
+                nsec3param.iterations,
+                nsec3param.hex_salt(),
+            )
+        }
+        Record::OPENPGPKEY(opgp) => {
+            opgp.base64_key()
+        }
+        Record::PTR(ptr) => {
+            domain_name(&ptr.cname)
+        }
+        Record::RRSIG(rrsig) => {
+            format!("{} {} {} {} {} {} {} {} {}",
+                rrsig.type_covered,
+                rrsig.algorithm.number(),
+                rrsig.labels,
+                rrsig.original_ttl,
+                rrsig.formatted_expiration(),
+                rrsig.formatted_inception(),
+                rrsig.key_tag,
+                domain_name(&rrsig.signer_name),
+                rrsig.base64_signature(),
+            )
+        }
+        Record::SSHFP(sshfp) => {
+            format!("{} {} {}",
+                sshfp.algorithm,
+                sshfp.fingerprint_type,
+                sshfp.hex_fingerprint(),
+            )
+        }
+        Record::SOA(soa) => {
+            format!("{} {} {} {} {} {} {}",
+                domain_name(&soa.mname),
+                domain_name(&soa.rname),
+                soa.serial,
+                soa.refresh_interval,
+                soa.retry_interval,
+                soa.expire_limit,
+                soa.minimum_ttl,
+            )
+        }
+        Record::SRV(srv) => {
+            format!("{} {} {} {}", srv.priority, srv.weight, srv.port, domain_name(&srv.target))
+        }
+        Record::SVCB(svcb) => {
+            service_binding(svcb.priority, &svcb.target, &svcb.parameters)
+        }
+        Record::TLSA(tlsa) => {
+            format!("{} {} {} {}",
+                tlsa.certificate_usage,
+                tlsa.selector,
+                tlsa.matching_type,
+                tlsa.hex_certificate_data(),
+            )
+        }
+        Record::TXT(txt) if txt.messages.is_empty() => {
+            generic_data(record)?
+        }
+        Record::TXT(txt) => {
+            let messages = txt.messages.iter().map(|t| character_string(t)).collect::<Vec<_>>();
+            messages.join(" ")
+        }
+        Record::URI(uri) => {
+            format!("{} {} {}", uri.priority, uri.weight, character_string(&uri.target))
+        }
+        Record::Other { .. } => {
+            generic_data(record)?
+        }
+    };
+
+    Some(data)
+}
+
+/// Formats the data of a record in the generic format for unknown record
+/// types, for records that have no other representation.
+fn generic_data(record: &Record) -> Option<String> {
+    let mut bytes = Vec::new();
+    record.write(&mut bytes, &mut Compression::disabled()).ok()?;
+    Some(generic_string(&bytes))
+}
+
+/// Formats the fields of a SVCB or HTTPS record, with each parameter in its
+/// presentation form.
+fn service_binding(priority: u16, target: &Labels, parameters: &[SvcParam]) -> String {
+    let mut data = format!("{} {}", priority, domain_name(target));
+
+    for parameter in parameters {
+        data.push(' ');
+        data.push_str(&parameter.to_string());
+    }
+
+    data
+}
+
+/// Formats the list of types in an NSEC or NSEC3 record, with a space
+/// before each one.
+fn type_bitmap(types: &[RecordType]) -> String {
+    let mut bitmap = String::new();
+
+    for record_type in types {
+        bitmap.push(' ');
+        bitmap.push_str(&record_type.to_string());
+    }
+
+    bitmap
+}
+
+/// Formats a domain name as an absolute name with a trailing dot, escaping
+/// any characters in its labels that would otherwise be read as syntax.
+fn domain_name(labels: &Labels) -> String {
+    if labels.len() == 0 {
+        String::from(".")
+    }
+    else {
+        labels.to_string()
+    }
+}
+
+/// Formats a character-string as a quoted string, escaping anything that
+/// cannot appear inside the quotes as-is.
+fn character_string(bytes: &[u8]) -> String {
+    format!("\"{}\"", escaped(bytes))
+}
+
+/// Escapes quotes and backslashes with a backslash, and writes every byte
+/// that is not printable ASCII as a three-digit decimal escape.
+fn escaped(bytes: &[u8]) -> String {
+    let mut string = String::new();
+
+    for byte in bytes.iter().copied() {
+        match byte {
+            b'"' | b'\\'   => { string.push('\\'); string.push(char::from(byte)); }
+            0x20 ..= 0x7E  => string.push(char::from(byte)),
+            _              => string.push_str(&format!("\\{:03}", byte)),
+        }
+    }
+
+    string
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use dns::{Wire, QClass};
+    use dns::record::*;
+    use std::net::Ipv4Addr;
+
+    fn answer(record: Record) -> Answer {
+        Answer::Standard {
+            qname: Labels::encode("dom.ain").unwrap(),
+            qclass: QClass::IN,
+            ttl: 300,
+            record,
+        }
+    }
+
+    #[test]
+    fn a_record() {
+        let record = Record::A(A { address: Ipv4Addr::LOCALHOST });
+        assert_eq!(zone_line(answer(record)),
+                   Some(String::from("dom.ain. 300 IN A 127.0.0.1")));
+    }
+
+    #[test]
+    fn txt_escapes() {
+        let record = Record::TXT(TXT { messages: vec![ b"say \"hi\"".to_vec().into(), b"a\\b\x07;".to_vec().into() ] });
+        assert_eq!(zone_line(answer(record)),
+                   Some(String::from("dom.ain. 300 IN TXT \"say \\\"hi\\\"\" \"a\\\\b\\007;\"")));
+    }
+
+    #[test]
+    fn caa_record() {
+        let record = Record::CAA(CAA { critical: true, tag: b"issue".to_vec().into(), value: b"ca.example; id=1".to_vec().into() });
+        assert_eq!(zone_line(answer(record)),
+                   Some(String::from("dom.ain. 300 IN CAA 128 issue \"ca.example; id=1\"")));
+    }
+
+    #[test]
+    fn mx_record() {
+        let record = Record::MX(MX { preference: 10, exchange: Labels::encode("mail.dom.ain").unwrap() });
+        assert_eq!(zone_line(answer(record)),
+                   Some(String::from("dom.ain. 300 IN MX 10 mail.dom.ain.")));
+    }
+
+    #[test]
+    fn sshfp_record() {
+        let record = Record::SSHFP(SSHFP { algorithm: 4, fingerprint_type: 2, fingerprint: vec![ 0xde, 0xad, 0xbe, 0xef ] });
+        assert_eq!(zone_line(answer(record)),
+                   Some(String::from("dom.ain. 300 IN SSHFP 4 2 deadbeef")));
+    }
+
+    #[test]
+    fn unknown_record() {
+        let record = Record::Other { type_number: UnknownQtype::UnheardOf(1234), bytes: vec![ 0x01, 0x02, 0x03 ] };
+        let answer = Answer::Standard { qname: Labels::root(), qclass: QClass::Other(99), ttl: 0, record };
+        assert_eq!(zone_line(answer),
+                   Some(String::from(". 0 CLASS99 TYPE1234 \\# 3 010203")));
+    }
+
+    #[test]
+    fn escaped_name() {
+        let name = Labels::encode("a b.c(d)").unwrap();
+        assert_eq!(domain_name(&name), "a\\032b.c\\(d\\).");
+    }
+
+    #[test]
+    fn out_of_range_location() {
+        let buf = &[
+            0x00,  // version
+            0x32,  // size,
+            0x00,  // horizontal precision
+            0x00,  // vertical precision
+            0xff, 0xff, 0xff, 0xff,  // latitude (out of range)
+            0x80, 0x00, 0x00, 0x00,  // longitude
+            0x00, 0x98, 0x96, 0x80,  // altitude
+        ];
+
+        let record = Record::LOC(LOC::read(buf.len() as _, &mut std::io::Cursor::new(&buf[..])).unwrap());
+        assert_eq!(zone_line(answer(record)),
+                   Some(String::from("; dom.ain. 300 IN LOC (unrepresentable data)")));
+    }
+
+    #[test]
+    fn pseudo_record() {
+        let answer = Answer::Pseudo { qname: Labels::root(), opt: dns::Request::additional_record() };
+        assert_eq!(zone_line(answer), None);
+    }
+}